@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::SyncProgressRecord;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "tauri", derive(specta::Type, tauri_specta::Event))]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -16,7 +18,13 @@ pub enum SyncEvent {
         error: Option<String>,
     },
     PuzzleBatchSynced,
+    Progress {
+        progress: SyncProgressRecord,
+    },
     CatInfo,
     DidInfo,
     NftData,
+    Reorg {
+        fork_height: u32,
+    },
 }
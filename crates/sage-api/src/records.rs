@@ -8,9 +8,11 @@ mod offer_summary;
 mod option;
 mod peer;
 mod pending_transaction;
+mod sync_progress;
 mod token;
 mod transaction;
 mod transaction_summary;
+mod vault;
 
 pub use coin::*;
 pub use derivation::*;
@@ -22,6 +24,8 @@ pub use offer_summary::*;
 pub use option::*;
 pub use peer::*;
 pub use pending_transaction::*;
+pub use sync_progress::*;
 pub use token::*;
 pub use transaction::*;
 pub use transaction_summary::*;
+pub use vault::*;
@@ -8,3 +8,19 @@ pub struct DerivationRecord {
     pub public_key: String,
     pub address: String,
 }
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DerivationGapRecord {
+    /// The number of derived addresses
+    pub derived: u32,
+    /// The number of addresses which have received coins
+    pub used: u32,
+    /// The index after the last used address
+    pub unused_index: u32,
+    /// The number of derived addresses after the last used address
+    pub gap: u32,
+    /// Whether more addresses are derived automatically as the gap is used up
+    pub auto_derive: bool,
+}
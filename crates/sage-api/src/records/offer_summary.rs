@@ -11,6 +11,9 @@ pub struct OfferSummary {
     pub taker: Vec<OfferAsset>,
     pub expiration_height: Option<u32>,
     pub expiration_timestamp: Option<u64>,
+    pub royalties: Vec<NftRoyaltyBreakdown>,
+    pub maker_royalties: Vec<RoyaltyAmount>,
+    pub taker_royalties: Vec<RoyaltyAmount>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,9 +32,30 @@ pub struct OfferAsset {
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct NftRoyalty {
     pub royalty_address: String,
+    pub royalty_puzzle_hash: String,
     pub royalty_basis_points: u16,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct NftRoyaltyBreakdown {
+    pub nft: Asset,
+    pub royalty_address: String,
+    pub royalty_puzzle_hash: String,
+    pub royalty_basis_points: u16,
+    pub paid_by_maker: bool,
+    pub payments: Vec<RoyaltyAmount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RoyaltyAmount {
+    pub asset: Asset,
+    pub amount: Amount,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "tauri", derive(specta::Type))]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
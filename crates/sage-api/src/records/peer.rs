@@ -8,4 +8,36 @@ pub struct PeerRecord {
     pub port: u16,
     pub peak_height: u32,
     pub user_managed: bool,
+    /// Reputation score from 0 to 100, based on latency, timeouts, peak lag and misbehavior
+    pub score: u32,
+}
+
+/// A peer that disagreed with other peers about the state of the wallet's coins
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PeerDiscrepancyRecord {
+    /// IP address of the peer
+    pub ip_addr: String,
+    /// What the peer disagreed about
+    pub reason: String,
+    /// When the discrepancy was found
+    pub timestamp: u64,
+}
+
+/// The status of the full node the wallet syncs against exclusively
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TrustedPeerRecord {
+    /// Hostname or IP address of the trusted peer
+    pub host: String,
+    /// Port of the trusted peer
+    pub port: u16,
+    /// Whether the wallet is currently connected to the trusted peer
+    pub connected: bool,
+    /// Why the last connection attempt failed, if it did
+    pub last_error: Option<String>,
+    /// Number of connection attempts that have failed in a row
+    pub failed_attempts: u32,
 }
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+/// Progress of a single phase of the sync
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SyncPhaseRecord {
+    /// Number of items that have been completed
+    pub completed: u32,
+    /// Total number of items known so far
+    pub total: u32,
+}
+
+/// Progress of each phase of the wallet sync
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SyncProgressRecord {
+    /// Derivations whose puzzle hashes have been subscribed to
+    pub derivations: SyncPhaseRecord,
+    /// Number of coin states received from peers
+    pub coin_states: u32,
+    /// Coins whose puzzles have been resolved
+    pub puzzles: SyncPhaseRecord,
+    /// NFT files that have been downloaded
+    pub nft_uris: SyncPhaseRecord,
+    /// Blocks whose timestamps have been filled in
+    pub block_timestamps: SyncPhaseRecord,
+    /// Estimated seconds until the sync is complete, based on the recent rate of progress
+    pub eta_seconds: Option<u64>,
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Amount;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct VaultRecord {
+    pub address: String,
+    pub name: String,
+    pub threshold: u32,
+    pub public_keys: Vec<String>,
+    pub balance: Amount,
+}
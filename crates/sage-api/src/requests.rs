@@ -4,7 +4,9 @@ mod data;
 mod keys;
 mod offers;
 mod settings;
+mod simulator;
 mod transactions;
+mod vaults;
 
 pub use action_system::*;
 pub use actions::*;
@@ -12,6 +14,8 @@ pub use data::*;
 pub use keys::*;
 pub use offers::*;
 pub use settings::*;
+pub use simulator::*;
 pub use transactions::*;
+pub use vaults::*;
 
 pub mod wallet_connect;
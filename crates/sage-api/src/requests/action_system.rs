@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{Amount, NftUriKind, TransactionResponse};
+use crate::{Amount, NftUriKind, OptionAsset, TransactionResponse};
 
 #[cfg_attr(
     feature = "openapi",
@@ -33,6 +33,12 @@ pub enum Action {
     Send(SendAction),
     MintNft(MintNftAction),
     UpdateNft(UpdateNftAction),
+    IssueCat(IssueCatAction),
+    RunTail(RunTailAction),
+    CreateDid(CreateDidAction),
+    MintOption(MintOptionAction),
+    ExerciseOption(ExerciseOptionAction),
+    SettleOffer(SettleOfferAction),
     Fee(FeeAction),
 }
 
@@ -114,6 +120,82 @@ pub struct UpdateNftAction {
     pub transfer: Option<NftTransfer>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct IssueCatAction {
+    /// The amount of the CAT to issue, in mojos
+    pub amount: Amount,
+    /// Whether the CAT can be revoked by the issuer
+    #[serde(default)]
+    pub revocable: bool,
+    /// Token name
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub name: Option<String>,
+    /// Token ticker symbol
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub ticker: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RunTailAction {
+    /// The id of the CAT to run the TAIL of
+    pub id: Id,
+    /// The TAIL program (encoded as hex)
+    pub tail_puzzle: String,
+    /// The solution to the TAIL program (encoded as hex)
+    pub tail_solution: String,
+    /// The amount of the CAT issued by the TAIL, in mojos
+    pub issue_amount: Amount,
+    /// The amount of the CAT melted by the TAIL, in mojos
+    pub melt_amount: Amount,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateDidAction {
+    /// The name of the DID
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MintOptionAction {
+    /// The id of the underlying asset to lock in the option
+    pub underlying_id: Id,
+    /// The amount of the underlying asset, in mojos
+    pub underlying_amount: Amount,
+    /// The asset and amount that must be paid to exercise the option
+    pub strike: OptionAsset,
+    /// Expiration time in seconds
+    pub expiration_seconds: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ExerciseOptionAction {
+    /// The id of the option to exercise
+    pub option_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SettleOfferAction {
+    /// The offer to take. Unless the transaction is submitted automatically, only the
+    /// taker's unsigned spends are returned
+    pub offer: String,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "tauri", derive(specta::Type))]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
@@ -131,7 +213,7 @@ pub enum Id {
     Xch,
     /// An existing asset by its asset id or launcher id
     Existing { asset_id: String },
-    /// A new asset by its index in the action list
+    /// A new asset by the index of the action that creates it
     New { index: usize },
 }
 
@@ -230,3 +230,35 @@ pub struct IncreaseDerivationIndex {
 #[cfg_attr(feature = "tauri", derive(specta::Type))]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct IncreaseDerivationIndexResponse {}
+
+/// Get a new address which hasn't been handed out before
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Addresses",
+        description = "Reserve the next unused address, so that it won't be returned again even if it never receives any coins."
+    )
+)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GetNewAddress {
+    /// Whether to use a hardened address (defaults to the wallet's receive setting)
+    #[serde(default)]
+    pub hardened: Option<bool>,
+}
+
+/// Response with the new address
+#[cfg_attr(feature = "openapi", crate::openapi_attr(tag = "Addresses"))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GetNewAddressResponse {
+    /// The new address
+    pub address: String,
+    /// The derivation index of the address
+    #[cfg_attr(feature = "openapi", schema(example = 12))]
+    pub index: u32,
+    /// Whether the address is hardened
+    pub hardened: bool,
+}
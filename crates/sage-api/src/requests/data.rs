@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    Amount, CoinRecord, DerivationRecord, DidRecord, NftCollectionRecord, NftData, NftRecord,
-    OptionRecord, PendingTransactionRecord, TokenRecord, TransactionRecord, Unit,
+    Amount, CoinRecord, DerivationGapRecord, DerivationRecord, DidRecord, NftCollectionRecord,
+    NftData, NftRecord, OptionRecord, PeerDiscrepancyRecord, PendingTransactionRecord,
+    SyncProgressRecord, TokenRecord, TransactionRecord, TrustedPeerRecord, Unit,
 };
 
 /// Validate and check an address
@@ -69,6 +70,31 @@ pub struct GetDerivationsResponse {
     pub total: u32,
 }
 
+/// Get the derivation gap of each branch
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Addresses",
+        description = "Get how many unhardened and hardened addresses have been derived and used, and how many unused addresses remain after the last used one."
+    )
+)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GetDerivationGap {}
+
+/// Response with the derivation gap of each branch
+#[cfg_attr(feature = "openapi", crate::openapi_attr(tag = "Addresses"))]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GetDerivationGapResponse {
+    /// Unhardened derivations
+    pub unhardened: DerivationGapRecord,
+    /// Hardened derivations
+    pub hardened: DerivationGapRecord,
+}
+
 /// Perform database maintenance operations
 #[cfg_attr(
     feature = "openapi",
@@ -182,6 +208,18 @@ pub struct GetSyncStatusResponse {
     pub total_files: u32,
     /// Database size in bytes
     pub database_size: u64,
+    /// Highest block whose header has been linked to the verified header chain
+    pub verified_height: Option<u32>,
+    /// Recent discrepancies found between peers while syncing in paranoid mode
+    pub peer_discrepancies: Vec<PeerDiscrepancyRecord>,
+    /// Status of the trusted peer, if the network syncs exclusively against one
+    pub trusted_peer: Option<TrustedPeerRecord>,
+    /// Progress of each phase of the wallet sync
+    pub sync_progress: SyncProgressRecord,
+    /// Whether the wallet is in offline mode
+    pub offline: bool,
+    /// Number of signed transactions waiting to be broadcast
+    pub queued_transactions: u32,
 }
 
 /// Get the wallet version
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{KeyInfo, SecretKeyInfo};
+use crate::{KeyInfo, KeyShareGroup, KeyShareInfo, SecretKeyInfo};
 
 /// Login to a wallet using a fingerprint
 #[cfg_attr(
@@ -10,13 +10,18 @@ use crate::{KeyInfo, SecretKeyInfo};
         description = "Authenticate and log into a wallet using its fingerprint. This must be called before most other endpoints."
     )
 )]
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "tauri", derive(specta::Type))]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Login {
     /// The unique fingerprint identifier of the wallet to authenticate with. This is a 32-bit unsigned integer that uniquely identifies each wallet key in the system.
     #[cfg_attr(feature = "openapi", schema(example = 1_234_567_890))]
     pub fingerprint: u32,
+    /// The password the key's secrets are encrypted with. It's kept until logging out, to
+    /// sign transactions with.
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub password: Option<String>,
 }
 
 /// Response from logging into a wallet
@@ -142,7 +147,7 @@ pub struct GenerateMnemonicResponse {
     feature = "openapi",
     crate::openapi_attr(
         tag = "Authentication & Keys",
-        description = "Import a wallet using a mnemonic phrase or private key. Optionally saves secrets and automatically logs in."
+        description = "Import a wallet using a mnemonic phrase, private key, or Shamir share phrases. Optionally saves secrets and automatically logs in."
     )
 )]
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -151,8 +156,12 @@ pub struct GenerateMnemonicResponse {
 pub struct ImportKey {
     /// Display name for the wallet
     pub name: String,
-    /// Mnemonic phrase or private key
+    /// Mnemonic phrase, private key, or Shamir share phrases with one per line
     pub key: String,
+    /// The passphrase the Shamir shares were created with
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub passphrase: Option<String>,
     /// Starting derivation index
     #[serde(default)]
     #[cfg_attr(feature = "openapi", schema(default = 0))]
@@ -177,6 +186,10 @@ pub struct ImportKey {
     #[serde(default)]
     #[cfg_attr(feature = "openapi", schema(nullable = true))]
     pub emoji: Option<String>,
+    /// The password to encrypt the saved secrets with
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub password: Option<String>,
 }
 
 fn yes() -> bool {
@@ -375,13 +388,17 @@ pub struct GetKeyResponse {
         description = "Retrieve the secret key (mnemonic) for a wallet. Requires authentication."
     )
 )]
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "tauri", derive(specta::Type))]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct GetSecretKey {
     /// Wallet fingerprint
     #[cfg_attr(feature = "openapi", schema(example = 1_234_567_890))]
     pub fingerprint: u32,
+    /// The password the key's secrets are encrypted with, if it's not the logged in key's
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub password: Option<String>,
 }
 
 /// Response with secret key information
@@ -398,6 +415,207 @@ pub struct GetSecretKeyResponse {
     pub secrets: Option<SecretKeyInfo>,
 }
 
+/// Change the password that wallet secrets are encrypted with
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Authentication & Keys",
+        description = "Re-encrypt the secrets of one wallet key, or of every key if no fingerprint is given, with a new password. Nothing is changed unless the old password decrypts all of them."
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ChangeKeyPassword {
+    /// Wallet fingerprint, or all keys if omitted
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(example = 1_234_567_890, nullable = true))]
+    pub fingerprint: Option<u32>,
+    /// The password the secrets are currently encrypted with
+    pub old_password: String,
+    /// The password to encrypt the secrets with
+    pub new_password: String,
+}
+
+/// Response for changing the key password
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(tag = "Authentication & Keys")
+)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ChangeKeyPasswordResponse {
+    /// The number of keys that were re-encrypted
+    pub changed: u32,
+}
+
+/// Check a wallet key password
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Authentication & Keys",
+        description = "Check whether a password decrypts the secrets of a wallet key, without returning them."
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct VerifyKeyPassword {
+    /// Wallet fingerprint
+    #[cfg_attr(feature = "openapi", schema(example = 1_234_567_890))]
+    pub fingerprint: u32,
+    /// The password to check
+    pub password: String,
+}
+
+/// Response with the result of the password check
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(tag = "Authentication & Keys")
+)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct VerifyKeyPasswordResponse {
+    /// Whether the password is correct
+    pub is_valid: bool,
+}
+
+/// Export an encrypted wallet backup
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Authentication & Keys",
+        description = "Export a password encrypted archive with the keychain entries, wallet settings, networks, themes, and the user data in each wallet database, such as offers and asset visibility."
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ExportBackup {
+    /// The password to encrypt the backup with
+    pub password: String,
+    /// The wallets to include, or all wallets if omitted
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub fingerprints: Option<Vec<u32>>,
+}
+
+/// Response with the encrypted backup
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(tag = "Authentication & Keys")
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ExportBackupResponse {
+    /// The base64 encoded backup archive
+    pub backup: String,
+}
+
+/// Restore an encrypted wallet backup
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Authentication & Keys",
+        description = "Restore a backup created by the export endpoint. Restoring the same backup again has no further effect, and existing keys are never overwritten."
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ImportBackup {
+    /// The base64 encoded backup archive
+    pub backup: String,
+    /// The password the backup was encrypted with
+    pub password: String,
+}
+
+/// Response with the restored wallets
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(tag = "Authentication & Keys")
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ImportBackupResponse {
+    /// The fingerprints of the wallets in the backup
+    pub fingerprints: Vec<u32>,
+}
+
+/// Split a wallet mnemonic into Shamir shares
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Authentication & Keys",
+        description = "Split the mnemonic of a wallet key into SLIP-39 share phrases. Any group threshold of the groups can recover it, once the threshold of shares within each of them are combined."
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ExportKeyShares {
+    /// Wallet fingerprint
+    #[cfg_attr(feature = "openapi", schema(example = 1_234_567_890))]
+    pub fingerprint: u32,
+    /// The number of groups needed to recover the mnemonic
+    pub group_threshold: u8,
+    /// The groups of shares to create
+    pub groups: Vec<KeyShareGroup>,
+    /// An optional passphrase that will also be required to recover the mnemonic
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub passphrase: Option<String>,
+    /// The password the key's secrets are encrypted with, if it's not the logged in key's
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub password: Option<String>,
+}
+
+/// Response with the share phrases of each group
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(tag = "Authentication & Keys")
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ExportKeySharesResponse {
+    pub groups: Vec<Vec<String>>,
+}
+
+/// Validate Shamir share phrases
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Authentication & Keys",
+        description = "Validate Shamir share phrases, and show which group each of them belongs to and whether there are enough to recover the mnemonic."
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct InspectKeyShares {
+    pub shares: Vec<String>,
+}
+
+/// Response with the details of each share
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(tag = "Authentication & Keys")
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct InspectKeySharesResponse {
+    pub shares: Vec<KeyShareInfo>,
+    /// Whether the shares are enough to recover the mnemonic
+    pub is_complete: bool,
+}
+
 /// Get the receive address for any wallet without switching sessions
 #[cfg_attr(
     feature = "openapi",
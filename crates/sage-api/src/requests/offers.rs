@@ -162,6 +162,81 @@ pub struct ViewOfferResponse {
     pub status: OfferRecordStatus,
 }
 
+/// Validate an offer against the current chain state
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Offers",
+        description = "Check whether an offer can still be taken by querying peers for the offered coins and running its spends."
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ValidateOffer {
+    /// Offer string to validate
+    pub offer: String,
+}
+
+/// Response with the problems found while validating an offer
+#[cfg_attr(feature = "openapi", crate::openapi_attr(tag = "Offers"))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ValidateOfferResponse {
+    /// Whether the offer can be taken right now
+    pub valid: bool,
+    /// Total estimated cost of the offered spends
+    pub cost: u64,
+    /// Peak height the offer was validated against
+    pub peak_height: Option<u32>,
+    /// Problems found with the offer
+    pub issues: Vec<OfferIssue>,
+}
+
+/// A problem found while validating an offer
+#[cfg_attr(feature = "openapi", crate::openapi_attr(tag = "Offers"))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct OfferIssue {
+    /// The kind of problem
+    pub kind: OfferIssueKind,
+    /// The coin the problem relates to, if any
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub coin_id: Option<String>,
+    /// Human readable description of the problem
+    pub message: String,
+}
+
+/// Kinds of problems that can be found with an offer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum OfferIssueKind {
+    /// An offered coin has already been spent
+    CoinSpent,
+    /// An offered coin does not exist on chain
+    CoinNotFound,
+    /// A spend failed to run
+    ClvmError,
+    /// The aggregated signature does not match the spends
+    InvalidSignature,
+    /// The spends exceed the maximum cost of a transaction
+    CostExceeded,
+    /// A height or time assertion is not yet satisfied
+    NotYetValid,
+    /// A height or time assertion has already passed
+    Expired,
+    /// A height or time assertion will pass soon
+    ExpiresSoon,
+    /// No peers were available to check the coin states
+    NoPeers,
+    /// A peer failed to respond, so some of the checks couldn't be completed
+    PeerError,
+}
+
 /// Import an offer
 #[cfg_attr(
     feature = "openapi",
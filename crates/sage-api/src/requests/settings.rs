@@ -83,6 +83,64 @@ pub struct SetDiscoverPeers {
     pub discover_peers: bool,
 }
 
+/// Enable or disable paranoid sync
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Peers",
+        description = "Enable or disable confirming coin states received during sync with a second peer.",
+        response_type = "EmptyResponse"
+    )
+)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SetParanoidSync {
+    /// Whether to confirm coin states with a second peer
+    #[cfg_attr(feature = "openapi", schema(example = true))]
+    pub paranoid_sync: bool,
+}
+
+/// Route connections through a SOCKS5 proxy
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Peers",
+        description = "Route peer connections, DNS lookups and HTTP requests through a SOCKS5 proxy such as Tor.",
+        response_type = "EmptyResponse"
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SetProxy {
+    /// Address of the proxy (null to connect directly)
+    #[cfg_attr(feature = "openapi", schema(example = "127.0.0.1:9050"))]
+    pub address: Option<String>,
+    /// Whether to isolate each peer connection onto its own Tor circuit
+    #[cfg_attr(feature = "openapi", schema(example = true))]
+    #[serde(default)]
+    pub isolate_streams: bool,
+}
+
+/// Switch between online and offline mode
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Peers",
+        description = "Disconnect from the network and queue signed transactions until the wallet goes back online.",
+        response_type = "EmptyResponse"
+    )
+)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SetOffline {
+    /// Whether the wallet should be offline
+    #[cfg_attr(feature = "openapi", schema(example = true))]
+    pub offline: bool,
+}
+
 /// Set target number of peers to maintain
 #[cfg_attr(
     feature = "openapi",
@@ -155,6 +213,74 @@ pub struct GetNetworks {}
 
 pub type GetNetworksResponse = NetworkList;
 
+/// Add a custom network
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Network Settings",
+        description = "Add a custom network definition and save it to the network list.",
+        response_type = "EmptyResponse"
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct AddNetwork {
+    /// Network definition, which must have a unique name
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    pub network: Network,
+}
+
+/// Update an existing network
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Network Settings",
+        description = "Replace the definition of the network with the same name. Takes effect immediately if the network is in use.",
+        response_type = "EmptyResponse"
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct UpdateNetwork {
+    /// New network definition, matched to an existing network by name
+    #[cfg_attr(feature = "openapi", schema(value_type = Object))]
+    pub network: Network,
+}
+
+/// Remove a network
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Network Settings",
+        description = "Remove a network which isn't the default network or used by any wallet.",
+        response_type = "EmptyResponse"
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RemoveNetwork {
+    /// Name of the network to remove
+    #[cfg_attr(feature = "openapi", schema(example = "testnet11"))]
+    pub name: String,
+}
+
+/// Reload the network list
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Network Settings",
+        description = "Reload networks.toml after it has been edited. Invalid networks are logged and left out, and the active network is reconnected if its definition changed.",
+        response_type = "EmptyResponse"
+    )
+)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ReloadNetworks {}
+
 /// Get current network information
 #[cfg_attr(
     feature = "openapi",
@@ -186,6 +312,7 @@ pub struct GetNetworkResponse {
 pub enum NetworkKind {
     Mainnet,
     Testnet,
+    Simulator,
     Unknown,
 }
 
@@ -248,6 +375,81 @@ pub struct SetChangeAddress {
     pub change_address: Option<String>,
 }
 
+/// Set whether the wallet receives to hardened addresses
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Addresses",
+        description = "Use hardened addresses instead of unhardened addresses for receiving and change.",
+        response_type = "EmptyResponse"
+    )
+)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SetReceiveHardened {
+    /// Wallet fingerprint
+    #[cfg_attr(feature = "openapi", schema(example = 1_234_567_890))]
+    pub fingerprint: u32,
+    /// Whether to receive to hardened addresses
+    #[cfg_attr(feature = "openapi", schema(example = true))]
+    pub receive_hardened: bool,
+}
+
+/// Set where the change from transactions is sent
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Addresses",
+        description = "Choose whether change reuses the current receive address, goes to a new address every time, or always goes to a fixed address.",
+        response_type = "EmptyResponse"
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SetChangePolicy {
+    /// Wallet fingerprint
+    #[cfg_attr(feature = "openapi", schema(example = 1_234_567_890))]
+    pub fingerprint: u32,
+    /// Where change is sent
+    pub change_policy: ChangePolicy,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChangePolicy {
+    /// Reuse the current receive address until it has received coins
+    Reuse,
+    /// Use a new address which hasn't been used before for every transaction
+    Fresh,
+    /// Always use the same address
+    Fixed { address: String },
+}
+
+/// Set the address gap limit of a wallet
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Addresses",
+        description = "Set how many unused addresses are derived and synced after the last used address.",
+        response_type = "EmptyResponse"
+    )
+)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SetGapLimit {
+    /// Wallet fingerprint
+    #[cfg_attr(feature = "openapi", schema(example = 1_234_567_890))]
+    pub fingerprint: u32,
+    /// Gap limit (null to use the default of 500)
+    #[cfg_attr(feature = "openapi", schema(example = 1000))]
+    pub gap_limit: Option<u32>,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[cfg_attr(feature = "tauri", derive(specta::Type))]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
@@ -256,9 +458,19 @@ pub struct EmptyResponse {}
 pub type AddPeerResponse = EmptyResponse;
 pub type RemovePeerResponse = EmptyResponse;
 pub type SetDiscoverPeersResponse = EmptyResponse;
+pub type SetParanoidSyncResponse = EmptyResponse;
+pub type SetProxyResponse = EmptyResponse;
+pub type SetOfflineResponse = EmptyResponse;
 pub type SetTargetPeersResponse = EmptyResponse;
 pub type SetNetworkResponse = EmptyResponse;
 pub type SetNetworkOverrideResponse = EmptyResponse;
 pub type SetDeltaSyncResponse = EmptyResponse;
 pub type SetDeltaSyncOverrideResponse = EmptyResponse;
 pub type SetChangeAddressResponse = EmptyResponse;
+pub type SetReceiveHardenedResponse = EmptyResponse;
+pub type SetChangePolicyResponse = EmptyResponse;
+pub type SetGapLimitResponse = EmptyResponse;
+pub type AddNetworkResponse = EmptyResponse;
+pub type UpdateNetworkResponse = EmptyResponse;
+pub type RemoveNetworkResponse = EmptyResponse;
+pub type ReloadNetworksResponse = EmptyResponse;
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Amount;
+
+/// Farm blocks on the simulator
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Simulator",
+        description = "Create new blocks on the in-process peer simulator. Only available while the active network is the simulator."
+    )
+)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FarmBlocks {
+    /// Number of blocks to create, up to 1000
+    #[cfg_attr(feature = "openapi", schema(example = 1, maximum = 1000))]
+    pub blocks: u32,
+}
+
+/// Response with the new peak height
+#[cfg_attr(feature = "openapi", crate::openapi_attr(tag = "Simulator"))]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FarmBlocksResponse {
+    /// Height of the simulator after the blocks were created
+    pub height: u32,
+}
+
+/// Mint a coin on the simulator
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Simulator",
+        description = "Create a new XCH coin out of thin air on the in-process peer simulator. Only available while the active network is the simulator."
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MintToAddress {
+    /// Address to send the coin to
+    pub address: String,
+    /// Amount of the coin in mojos
+    pub amount: Amount,
+}
+
+/// Response with the minted coin
+#[cfg_attr(feature = "openapi", crate::openapi_attr(tag = "Simulator"))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct MintToAddressResponse {
+    /// ID of the new coin
+    pub coin_id: String,
+}
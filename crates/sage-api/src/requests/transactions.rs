@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{Amount, CoinSpendJson, SpendBundleJson, TransactionSummary};
+use crate::{Amount, Asset, CoinJson, CoinSpendJson, SpendBundleJson, TransactionSummary};
 
 /// Send XCH to an address
 #[cfg_attr(
@@ -597,7 +597,7 @@ pub struct NormalizeDids {
 
 /// Asset specification for options
 #[cfg_attr(feature = "openapi", crate::openapi_attr(tag = "Options"))]
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "tauri", derive(specta::Type))]
 #[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct OptionAsset {
@@ -783,6 +783,241 @@ pub struct ViewCoinSpendsResponse {
     pub summary: TransactionSummary,
 }
 
+/// Simulate coin spends without submitting them
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Transactions",
+        description = "Run coin spends locally to check them against consensus rules and preview the resulting balance changes for this wallet."
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SimulateTransaction {
+    /// Coin spends to simulate
+    pub coin_spends: Vec<CoinSpendJson>,
+    /// Aggregated signature to verify, if the coin spends have already been signed
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub aggregated_signature: Option<String>,
+}
+
+/// Response with the outcome of a simulated transaction
+#[cfg_attr(feature = "openapi", crate::openapi_attr(tag = "Transactions"))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SimulateTransactionResponse {
+    /// Whether every spend ran and no errors were found
+    pub valid: bool,
+    /// Total CLVM cost of the transaction, as the mempool would compute it
+    pub cost: u64,
+    /// Fee paid by the transaction, or zero if it failed to run
+    pub fee: Amount,
+    /// Result of running each coin spend
+    pub spends: Vec<SimulatedSpend>,
+    /// Errors that apply to the transaction as a whole
+    pub errors: Vec<String>,
+    /// Changes to this wallet's balances if the transaction is confirmed
+    pub balance_changes: Vec<BalanceChange>,
+    /// Transaction summary, if every spend could be parsed
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub summary: Option<TransactionSummary>,
+}
+
+/// The result of running a single coin spend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SimulatedSpend {
+    /// Coin ID of the spent coin
+    pub coin_id: String,
+    /// Estimated CLVM cost of the spend on its own
+    pub cost: u64,
+    /// Conditions output by the puzzle
+    pub conditions: Vec<ConditionJson>,
+    /// Errors caused by this spend
+    pub errors: Vec<String>,
+}
+
+/// A condition output by a coin spend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ConditionJson {
+    /// Condition opcode
+    pub opcode: u16,
+    /// Name of the condition, if it's known
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub name: Option<String>,
+    /// Arguments of the condition (serialized CLVM encoded as hex)
+    pub args: Vec<String>,
+}
+
+/// The total amount of an asset this wallet gains and loses in a transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BalanceChange {
+    /// The asset being changed
+    pub asset: Asset,
+    /// Amount received by this wallet, including change
+    pub received: Amount,
+    /// Amount spent by this wallet
+    pub spent: Amount,
+}
+
+/// Decode coin spends into a human-readable description
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Transactions",
+        description = "Identify the puzzle layers of each coin spend and explain the conditions it outputs, without needing the coins to belong to this wallet."
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DecodeCoinSpends {
+    /// Coin spends to decode
+    pub coin_spends: Vec<CoinSpendJson>,
+}
+
+/// Response with the decoded coin spends
+#[cfg_attr(feature = "openapi", crate::openapi_attr(tag = "Transactions"))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DecodeCoinSpendsResponse {
+    /// Decoded coin spends, in the same order as the request
+    pub spends: Vec<DecodedSpend>,
+}
+
+/// A single coin spend broken down into its puzzle layers and conditions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DecodedSpend {
+    /// Coin ID of the spent coin
+    pub coin_id: String,
+    /// The spent coin
+    pub coin: CoinJson,
+    /// The kind of asset the coin holds
+    pub kind: DecodedCoinKind,
+    /// Asset ID of a CAT, or launcher ID of a singleton
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub asset_id: Option<String>,
+    /// Puzzle layers from outermost to innermost
+    pub layers: Vec<PuzzleLayer>,
+    /// Puzzle hash of the innermost puzzle, if it could be determined
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub p2_puzzle_hash: Option<String>,
+    /// Conditions output by the spend
+    pub conditions: Vec<DecodedCondition>,
+    /// Error encountered while running the spend
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub error: Option<String>,
+}
+
+/// The kind of asset a coin holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DecodedCoinKind {
+    /// An XCH coin, or a coin with an unrecognized puzzle
+    Unknown,
+    /// A singleton launcher
+    Launcher,
+    /// A clawback coin that hasn't been claimed or clawed back yet
+    Clawback,
+    /// A CAT coin
+    Cat,
+    /// A DID singleton
+    Did,
+    /// An NFT singleton
+    Nft,
+    /// An option contract singleton
+    Option,
+}
+
+/// A recognized layer of a puzzle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum PuzzleLayer {
+    /// The singleton launcher puzzle
+    SingletonLauncher,
+    /// The singleton top layer
+    Singleton,
+    /// The CAT layer
+    Cat,
+    /// The revocation layer of a revocable CAT
+    Revocation,
+    /// The NFT state layer, which holds the metadata
+    NftState,
+    /// The NFT ownership layer, which holds the owner and royalty info
+    NftOwnership,
+    /// The DID inner puzzle
+    Did,
+    /// The option contract inner puzzle
+    OptionContract,
+    /// The standard transaction puzzle
+    Standard,
+    /// The settlement payments puzzle used by offers
+    Settlement,
+    /// A clawback puzzle
+    Clawback,
+    /// A puzzle that wasn't recognized
+    Unknown,
+}
+
+/// A condition output by a coin spend, with an explanation of what it does
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DecodedCondition {
+    /// The raw condition
+    pub condition: ConditionJson,
+    /// Human-readable description of the condition
+    pub description: String,
+    /// The coin created by this condition, if it's a `CREATE_COIN`
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub output: Option<DecodedOutput>,
+}
+
+/// A coin created by a coin spend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DecodedOutput {
+    /// Coin ID of the created coin
+    pub coin_id: String,
+    /// Amount of the created coin
+    pub amount: Amount,
+    /// Puzzle hash of the created coin
+    pub puzzle_hash: String,
+    /// The kind of asset the created coin holds
+    pub kind: DecodedCoinKind,
+    /// Asset ID of a CAT, or launcher ID of a singleton
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub asset_id: Option<String>,
+    /// Address of the recipient, if it could be determined
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub address: Option<String>,
+    /// Address that can claw the coin back, if it was sent with clawback
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub clawback_address: Option<String>,
+    /// Timestamp after which the coin can no longer be clawed back
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub clawback_timestamp: Option<u64>,
+    /// Hint included in the memos
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub hint: Option<String>,
+}
+
 /// Submit a transaction to the network
 #[cfg_attr(
     feature = "openapi",
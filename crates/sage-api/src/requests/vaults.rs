@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Amount, CoinSpendJson, SpendBundleJson, TransactionSummary, VaultRecord};
+
+/// Create a multisig vault
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Vaults",
+        description = "Create an M of N multisig vault from the public keys of its members, and start syncing coins sent to its address."
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateVault {
+    /// Display name for the vault
+    pub name: String,
+    /// The number of members that have to sign each spend
+    pub threshold: u32,
+    /// BLS public keys of the members, in hex
+    pub public_keys: Vec<String>,
+}
+
+/// Response with the vault address
+#[cfg_attr(feature = "openapi", crate::openapi_attr(tag = "Vaults"))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateVaultResponse {
+    pub address: String,
+}
+
+/// List multisig vaults
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Vaults",
+        description = "List the multisig vaults of the current wallet, along with their balances."
+    )
+)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GetVaults {}
+
+/// Response with the vaults
+#[cfg_attr(feature = "openapi", crate::openapi_attr(tag = "Vaults"))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct GetVaultsResponse {
+    pub vaults: Vec<VaultRecord>,
+}
+
+/// Propose a spend from a multisig vault
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Vaults",
+        description = "Create unsigned coin spends that send XCH out of a vault. Each of the signers then signs them with SignCoinSpends and partial set to true, before they're combined with SubmitVaultSpend."
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProposeVaultSpend {
+    /// Vault address
+    pub vault_address: String,
+    /// Recipient address
+    pub address: String,
+    /// Amount to send
+    pub amount: Amount,
+    /// Transaction fee, paid by the vault
+    pub fee: Amount,
+    /// Public keys of the members that will sign, which must be exactly the threshold. Defaults
+    /// to the keys this wallet can sign with, followed by the other members in order.
+    #[serde(default)]
+    #[cfg_attr(feature = "openapi", schema(nullable = true))]
+    pub signers: Option<Vec<String>>,
+}
+
+/// Response with the proposed coin spends
+#[cfg_attr(feature = "openapi", crate::openapi_attr(tag = "Vaults"))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProposeVaultSpendResponse {
+    /// Transaction summary
+    pub summary: TransactionSummary,
+    /// Coin spends to be signed by each of the signers
+    pub coin_spends: Vec<CoinSpendJson>,
+    /// The public keys that have to sign
+    pub signers: Vec<String>,
+}
+
+/// Combine the signatures of a vault spend and submit it
+#[cfg_attr(
+    feature = "openapi",
+    crate::openapi_attr(
+        tag = "Vaults",
+        description = "Aggregate the partially signed spend bundles of each signer into one, and submit it to the network."
+    )
+)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SubmitVaultSpend {
+    /// The partially signed spend bundles, which must all have the same coin spends
+    pub spend_bundles: Vec<SpendBundleJson>,
+}
+
+/// Response with the aggregated spend bundle
+#[cfg_attr(feature = "openapi", crate::openapi_attr(tag = "Vaults"))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SubmitVaultSpendResponse {
+    pub spend_bundle: SpendBundleJson,
+}
@@ -28,3 +28,26 @@ pub struct SecretKeyInfo {
     pub mnemonic: Option<String>,
     pub secret_key: String,
 }
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct KeyShareGroup {
+    /// The number of shares needed to recover the group
+    pub threshold: u8,
+    /// The number of shares to create for the group
+    pub count: u8,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[cfg_attr(feature = "tauri", derive(specta::Type))]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct KeyShareInfo {
+    /// Shares with the same identifier belong to the same backup
+    pub identifier: u16,
+    pub group_index: u8,
+    pub group_threshold: u8,
+    pub group_count: u8,
+    pub member_index: u8,
+    pub member_threshold: u8,
+}
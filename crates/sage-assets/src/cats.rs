@@ -1,7 +1,7 @@
 use chia_wallet_sdk::prelude::*;
 use serde::Deserialize;
 
-use crate::UriError;
+use crate::{UriError, http_client};
 
 #[derive(Debug, Clone)]
 pub struct DexieCat {
@@ -19,13 +19,17 @@ impl DexieCat {
         let mut assets = Vec::new();
 
         loop {
-            let response = reqwest::get(format!(
+            let url = format!(
                 "{}/assets?page_size=100&page={page}&type=cat",
                 dexie_base_url(testnet)
-            ))
-            .await?
-            .json::<AssetResponse>()
-            .await?;
+            );
+
+            let response = http_client(&url)?
+                .get(url)
+                .send()
+                .await?
+                .json::<AssetResponse>()
+                .await?;
 
             if response.assets.is_empty() {
                 break;
@@ -53,13 +57,17 @@ impl DexieCat {
     }
 
     pub async fn fetch(asset_id: Bytes32, testnet: bool) -> Result<Self, UriError> {
-        let response = reqwest::get(format!(
+        let url = format!(
             "{}/assets?page_size=25&page=1&type=cat&code={asset_id}",
             dexie_base_url(testnet)
-        ))
-        .await?
-        .json::<AssetResponse>()
-        .await?;
+        );
+
+        let response = http_client(&url)?
+            .get(url)
+            .send()
+            .await?
+            .json::<AssetResponse>()
+            .await?;
 
         let asset = response.assets.first().cloned().unwrap_or_default();
         let icon_url = (!response.assets.is_empty()).then_some(format!(
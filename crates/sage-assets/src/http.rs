@@ -0,0 +1,127 @@
+use std::sync::{LazyLock, Mutex, PoisonError};
+
+use indexmap::IndexMap;
+use reqwest::{Client, ClientBuilder, Proxy, Url};
+
+use crate::UriError;
+
+#[derive(Debug, Clone)]
+struct ProxySettings {
+    address: String,
+    isolate_streams: bool,
+}
+
+/// The number of clients kept for reuse. With stream isolation every host gets its own client,
+/// so the least recently used ones are dropped once there are more than this.
+const MAX_CLIENTS: usize = 64;
+
+#[derive(Debug, Default)]
+struct HttpState {
+    /// The proxy that every request for asset data and prices is sent through, if any.
+    proxy: Option<ProxySettings>,
+    /// Clients that are reused between requests, keyed by the host they're isolated to, if
+    /// any. The most recently used client is last.
+    clients: IndexMap<String, Client>,
+}
+
+/// The proxy and its clients share a lock, so a client built with an old proxy can't be
+/// cached after the proxy has changed.
+static STATE: LazyLock<Mutex<HttpState>> = LazyLock::new(Mutex::default);
+
+/// Routes all HTTP requests through a SOCKS5 proxy at the given address, or sends them
+/// directly if there isn't one. Hostnames are resolved by the proxy. With stream isolation,
+/// requests to each host use different proxy credentials, so that Tor routes them through
+/// separate circuits, the same way as peer connections.
+pub fn set_http_proxy(address: Option<&str>, isolate_streams: bool) -> Result<(), UriError> {
+    let settings = address.map(|address| ProxySettings {
+        address: address.to_string(),
+        isolate_streams,
+    });
+
+    // Make sure the address is valid before replacing the current proxy
+    if let Some(settings) = &settings {
+        proxy(settings, "")?;
+    }
+
+    let mut state = STATE.lock().unwrap_or_else(PoisonError::into_inner);
+    state.proxy = settings;
+    state.clients.clear();
+
+    Ok(())
+}
+
+pub(crate) fn http_client(url: &str) -> Result<Client, UriError> {
+    let mut state = STATE.lock().unwrap_or_else(PoisonError::into_inner);
+    let key = isolation_key(state.proxy.as_ref(), url);
+
+    if let Some(index) = state.clients.get_index_of(&key) {
+        let last = state.clients.len() - 1;
+        state.clients.move_index(index, last);
+        return Ok(state.clients[last].clone());
+    }
+
+    let client = builder(state.proxy.as_ref(), &key)?.build()?;
+
+    if state.clients.len() >= MAX_CLIENTS {
+        state.clients.shift_remove_index(0);
+    }
+
+    state.clients.insert(key, client.clone());
+
+    Ok(client)
+}
+
+/// A builder for clients that need their own settings, which still uses the proxy.
+pub(crate) fn http_client_builder(url: &str) -> Result<ClientBuilder, UriError> {
+    let settings = STATE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .proxy
+        .clone();
+
+    let key = isolation_key(settings.as_ref(), url);
+
+    builder(settings.as_ref(), &key)
+}
+
+fn builder(settings: Option<&ProxySettings>, key: &str) -> Result<ClientBuilder, UriError> {
+    let builder = Client::builder();
+
+    match settings {
+        Some(settings) => Ok(builder.proxy(proxy(settings, key)?)),
+        None => Ok(builder),
+    }
+}
+
+fn proxy(settings: &ProxySettings, key: &str) -> Result<Proxy, UriError> {
+    let url = if settings.isolate_streams {
+        format!("socks5h://{}:sage@{}", key, settings.address)
+    } else {
+        format!("socks5h://{}", settings.address)
+    };
+
+    Ok(Proxy::all(url)?)
+}
+
+/// The host a request is sent to, if requests are isolated by host. It's used as the proxy
+/// username, so it's limited to characters that don't need to be escaped.
+fn isolation_key(settings: Option<&ProxySettings>, url: &str) -> String {
+    if !settings.is_some_and(|settings| settings.isolate_streams) {
+        return String::new();
+    }
+
+    let host = Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(ToString::to_string))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    host.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
@@ -1,9 +1,11 @@
 mod cats;
 mod error;
+mod http;
 mod nfts;
 mod price;
 
 pub use cats::*;
 pub use error::*;
+pub use http::*;
 pub use nfts::*;
 pub use price::*;
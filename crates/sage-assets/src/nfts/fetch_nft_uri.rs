@@ -8,7 +8,7 @@ use reqwest::{StatusCode, header::CONTENT_TYPE};
 use tokio::task::spawn_blocking;
 use tracing::debug;
 
-use crate::UriError;
+use crate::{UriError, http_client};
 
 use super::{Thumbnail, thumbnail as make_thumbnail};
 
@@ -21,7 +21,7 @@ pub struct Data {
 }
 
 pub async fn fetch_uri(uri: String, testnet: bool) -> Result<Data, UriError> {
-    let response = reqwest::get(&uri).await?;
+    let response = http_client(&uri)?.get(&uri).send().await?;
 
     let mime_type = match response.headers().get(CONTENT_TYPE) {
         Some(header) => Some(
@@ -159,7 +159,7 @@ pub async fn mintgarden_thumbnail(
         if testnet { "testnet" } else { "mainnet" }
     );
 
-    let response = reqwest::get(&url).await?;
+    let response = http_client(&url)?.get(&url).send().await?;
 
     if response.status() != StatusCode::OK {
         return Ok(None);
@@ -3,7 +3,9 @@ use std::time::Duration;
 use reqwest::Client;
 use serde::Deserialize;
 
-use crate::UriError;
+use crate::{UriError, http_client_builder};
+
+const PRICE_URL: &str = "https://api.coingecko.com/api/v3/simple/price";
 
 #[derive(Debug, Clone, Copy)]
 pub struct XchUsdPrice {
@@ -12,8 +14,8 @@ pub struct XchUsdPrice {
 
 impl XchUsdPrice {
     pub async fn fetch() -> Result<Self, UriError> {
-        let response = price_client()?
-            .get("https://api.coingecko.com/api/v3/simple/price")
+        let response = price_client(PRICE_URL)?
+            .get(PRICE_URL)
             .query(&[("ids", "chia"), ("vs_currencies", "usd")])
             .send()
             .await?
@@ -31,8 +33,8 @@ impl XchUsdPrice {
     }
 }
 
-fn price_client() -> Result<Client, UriError> {
-    Ok(Client::builder()
+fn price_client(url: &str) -> Result<Client, UriError> {
+    Ok(http_client_builder(url)?
         .timeout(Duration::from_secs(10))
         .user_agent(format!(
             "{}/{}",
@@ -13,7 +13,13 @@ impl_endpoints! {
     #[derive(Debug, Parser)]
     #[clap(rename_all = "snake_case")]
     pub enum RpcCommand {
-        Start,
+        Start {
+            /// Enable the built-in simulator network for local development, which can then be
+            /// selected with set_network
+            #[cfg(feature = "simulator")]
+            #[clap(long)]
+            simulator: bool,
+        },
         /// Generate OpenAPI specification to stdout or file
         GenerateOpenapi {
             /// Optional output file path (prints to stdout if not provided)
@@ -26,8 +32,13 @@ impl_endpoints! {
     impl RpcCommand {
         pub async fn handle(self, path: PathBuf) -> anyhow::Result<()> {
             match self {
-                Self::Start => {
+                Self::Start {
+                    #[cfg(feature = "simulator")]
+                    simulator,
+                } => {
                     let mut sage = Sage::new(&path, false);
+                    #[cfg(feature = "simulator")]
+                    sage.enable_simulator = simulator;
                     let mut receiver = sage.initialize().await?;
                     sage.switch_wallet().await?;
                     tokio::spawn(async move { while let Some(_message) = receiver.recv().await {} });
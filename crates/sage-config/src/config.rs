@@ -8,6 +8,7 @@ pub struct Config {
     pub global: GlobalConfig,
     pub network: NetworkConfig,
     pub rpc: RpcConfig,
+    pub keychain: KeychainConfig,
 }
 
 impl Default for Config {
@@ -17,6 +18,7 @@ impl Default for Config {
             global: GlobalConfig::default(),
             network: NetworkConfig::default(),
             rpc: RpcConfig::default(),
+            keychain: KeychainConfig::default(),
         }
     }
 }
@@ -43,6 +45,12 @@ pub struct NetworkConfig {
     pub default_network: String,
     pub target_peers: u32,
     pub discover_peers: bool,
+    pub paranoid_sync: bool,
+    pub proxy: Option<ProxyConfig>,
+    /// Whether to stay disconnected from the network, queueing transactions until the
+    /// wallet goes back online.
+    pub offline: bool,
+    pub rebroadcast: RebroadcastConfig,
 }
 
 impl Default for NetworkConfig {
@@ -51,10 +59,46 @@ impl Default for NetworkConfig {
             default_network: "mainnet".to_string(),
             target_peers: 5,
             discover_peers: true,
+            paranoid_sync: false,
+            proxy: None,
+            offline: false,
+            rebroadcast: RebroadcastConfig::default(),
         }
     }
 }
 
+/// When pending transactions are resubmitted to peers, and when to give up on them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct RebroadcastConfig {
+    /// How long to wait between resubmissions of a pending transaction.
+    pub interval_seconds: u64,
+    /// How long a transaction can stay pending before it's considered evicted and removed.
+    pub max_age_seconds: u64,
+    /// How many resubmissions no peer acknowledged before the transaction is removed.
+    pub max_retries: u32,
+}
+
+impl Default for RebroadcastConfig {
+    fn default() -> Self {
+        Self {
+            interval_seconds: 120,
+            max_age_seconds: 60 * 60 * 24,
+            max_retries: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct ProxyConfig {
+    /// The address of a SOCKS5 proxy, such as `127.0.0.1:9050` for Tor.
+    pub address: String,
+    /// Whether to connect to each peer with different proxy credentials, so that Tor
+    /// routes each connection through a separate circuit.
+    #[serde(default)]
+    pub isolate_streams: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Copy, Serialize, Deserialize, Type)]
 #[serde(default)]
 pub struct RpcConfig {
@@ -70,3 +114,23 @@ impl Default for RpcConfig {
         }
     }
 }
+
+/// The Argon2id cost parameters used when encrypting keys. Keys that are already encrypted
+/// keep the parameters they were encrypted with until their password is changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct KeychainConfig {
+    pub kdf_memory_kib: u32,
+    pub kdf_iterations: u32,
+    pub kdf_parallelism: u32,
+}
+
+impl Default for KeychainConfig {
+    fn default() -> Self {
+        Self {
+            kdf_memory_kib: 19 * 1024,
+            kdf_iterations: 2,
+            kdf_parallelism: 1,
+        }
+    }
+}
@@ -4,6 +4,7 @@ use chia_wallet_sdk::prelude::*;
 use serde::{Deserialize, Serialize};
 use serde_with::{hex::Hex, serde_as};
 use specta::Type;
+use thiserror::Error;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Type)]
 #[serde(default)]
@@ -38,10 +39,92 @@ impl NetworkList {
     pub fn by_name(&self, name: &str) -> Option<&Network> {
         self.networks.iter().find(|network| network.name == name)
     }
+
+    /// Checks that every network is valid on its own, that names are unique, and that networks
+    /// sharing a genesis challenge also share an address prefix.
+    pub fn validate(&self) -> Result<(), NetworkError> {
+        for (i, network) in self.networks.iter().enumerate() {
+            validate_against(network, &self.networks[..i])?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces each invalid network with the default network of the same name, or drops it if
+    /// there isn't one, so that a bad entry doesn't stop the wallet from starting. Returns the
+    /// errors for the networks that were replaced or dropped.
+    pub fn repair(&mut self) -> Vec<NetworkError> {
+        let defaults = Self::default();
+        let mut networks = Vec::new();
+        let mut errors = Vec::new();
+
+        for network in std::mem::take(&mut self.networks) {
+            if let Err(error) = validate_against(&network, &networks) {
+                errors.push(error);
+
+                if let Some(default) = defaults.by_name(&network.name)
+                    && validate_against(default, &networks).is_ok()
+                {
+                    networks.push(default.clone());
+                }
+
+                continue;
+            }
+
+            networks.push(network);
+        }
+
+        self.networks = networks;
+
+        errors
+    }
+}
+
+fn validate_against(network: &Network, others: &[Network]) -> Result<(), NetworkError> {
+    network.validate()?;
+
+    for other in others {
+        if other.name == network.name {
+            return Err(NetworkError::DuplicateName(network.name.clone()));
+        }
+
+        // Otherwise the same puzzle hash would have a different address on each network
+        if other.genesis_challenge == network.genesis_challenge
+            && other.prefix() != network.prefix()
+        {
+            return Err(NetworkError::PrefixMismatch {
+                name: network.name.clone(),
+                other: other.name.clone(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum NetworkError {
+    #[error("Network name cannot be empty")]
+    EmptyName,
+
+    #[error("Duplicate network name: {0}")]
+    DuplicateName(String),
+
+    #[error("Network {0} has an empty genesis challenge")]
+    InvalidGenesisChallenge(String),
+
+    #[error("Network {name} has an invalid address prefix: {prefix}")]
+    InvalidPrefix { name: String, prefix: String },
+
+    #[error("Network {name} has the same genesis challenge as {other}, but a different prefix")]
+    PrefixMismatch { name: String, other: String },
+
+    #[error("Network {name} inherits from {inherit}, but has a different genesis challenge")]
+    InheritMismatch { name: String, inherit: String },
 }
 
 #[serde_as]
-#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
 pub struct Network {
     pub name: String,
     pub ticker: String,
@@ -75,6 +158,12 @@ pub struct Network {
     pub additional_peer_introducers: Vec<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub inherit: Option<InheritedNetwork>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trusted_peer: Option<TrustedPeer>,
+    /// Whether the network is an in-process peer simulator for local development, rather
+    /// than a real network of full nodes.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub simulator: bool,
 }
 
 impl Network {
@@ -92,6 +181,45 @@ impl Network {
         self.agg_sig_me.unwrap_or(self.genesis_challenge)
     }
 
+    pub fn validate(&self) -> Result<(), NetworkError> {
+        if self.name.trim().is_empty() {
+            return Err(NetworkError::EmptyName);
+        }
+
+        if self.genesis_challenge == Bytes32::default() {
+            return Err(NetworkError::InvalidGenesisChallenge(self.name.clone()));
+        }
+
+        // Bech32 human readable parts are limited to 83 printable ASCII characters, and can't
+        // mix upper and lower case
+        let prefix = self.prefix();
+
+        if prefix.is_empty()
+            || prefix.len() > 83
+            || !prefix
+                .chars()
+                .all(|c| c.is_ascii_graphic() && !c.is_ascii_uppercase())
+        {
+            return Err(NetworkError::InvalidPrefix {
+                name: self.name.clone(),
+                prefix,
+            });
+        }
+
+        // Inheriting pulls in the introducers of the parent network, which would hand out
+        // peers for the wrong chain
+        if let Some(inherit) = self.inherit
+            && inherit.network().genesis_challenge != self.genesis_challenge
+        {
+            return Err(NetworkError::InheritMismatch {
+                name: self.name.clone(),
+                inherit: inherit.network().name.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
     pub fn dns_introducers(&self) -> Vec<String> {
         match self.inherit {
             Some(InheritedNetwork::Mainnet) => {
@@ -150,7 +278,21 @@ fn is_default_precision(precision: &u8) -> bool {
     *precision == 12
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Type)]
+/// A full node that the wallet syncs against exclusively, instead of discovering peers.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct TrustedPeer {
+    pub host: String,
+    pub port: u16,
+    /// The SHA-256 hash of the node's DER encoded TLS certificate. If set, the connection
+    /// is refused when the node presents any other certificate.
+    #[serde_as(as = "Option<Hex>")]
+    #[specta(type = Option<String>)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub certificate_pin: Option<Bytes32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
 pub enum InheritedNetwork {
     #[serde(rename = "mainnet")]
     Mainnet,
@@ -158,6 +300,15 @@ pub enum InheritedNetwork {
     Testnet11,
 }
 
+impl InheritedNetwork {
+    pub fn network(self) -> &'static Network {
+        match self {
+            Self::Mainnet => &MAINNET,
+            Self::Testnet11 => &TESTNET11,
+        }
+    }
+}
+
 pub static MAINNET: LazyLock<Network> = LazyLock::new(|| Network {
     name: "mainnet".to_string(),
     network_id: None,
@@ -175,6 +326,8 @@ pub static MAINNET: LazyLock<Network> = LazyLock::new(|| Network {
     ],
     additional_peer_introducers: vec!["introducer.chia.net".to_string()],
     inherit: Some(InheritedNetwork::Mainnet),
+    trusted_peer: None,
+    simulator: false,
 });
 
 pub static TESTNET11: LazyLock<Network> = LazyLock::new(|| Network {
@@ -189,4 +342,132 @@ pub static TESTNET11: LazyLock<Network> = LazyLock::new(|| Network {
     additional_dns_introducers: vec!["dns-introducer-testnet11.chia.net".to_string()],
     additional_peer_introducers: vec!["introducer-testnet11.chia.net".to_string()],
     inherit: Some(InheritedNetwork::Testnet11),
+    trusted_peer: None,
+    simulator: false,
 });
+
+/// The peer simulator uses the testnet11 consensus constants, but keeps its own database and
+/// peer list since its coins only exist for as long as the process is running. It isn't part
+/// of the default network list, and is only available when enabled for development.
+pub static SIMULATOR: LazyLock<Network> = LazyLock::new(|| Network {
+    name: "simulator".to_string(),
+    network_id: None,
+    default_port: 58444,
+    ticker: "TXCH".to_string(),
+    prefix: None,
+    precision: 12,
+    genesis_challenge: TESTNET11_CONSTANTS.genesis_challenge,
+    agg_sig_me: None,
+    additional_dns_introducers: Vec::new(),
+    additional_peer_introducers: Vec::new(),
+    inherit: None,
+    trusted_peer: None,
+    simulator: true,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom_network() -> Network {
+        Network {
+            name: "custom".to_string(),
+            ticker: "CXCH".to_string(),
+            prefix: None,
+            precision: 12,
+            network_id: None,
+            default_port: 58444,
+            genesis_challenge: Bytes32::new([1; 32]),
+            agg_sig_me: None,
+            additional_dns_introducers: Vec::new(),
+            additional_peer_introducers: Vec::new(),
+            inherit: None,
+            trusted_peer: None,
+            simulator: false,
+        }
+    }
+
+    #[test]
+    fn test_default_networks_are_valid() {
+        assert_eq!(NetworkList::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_network_validation() {
+        let mut network = custom_network();
+        assert_eq!(network.validate(), Ok(()));
+
+        network.ticker = "C XCH".to_string();
+        assert!(matches!(
+            network.validate(),
+            Err(NetworkError::InvalidPrefix { .. })
+        ));
+
+        network.prefix = Some("cxch".to_string());
+        assert_eq!(network.validate(), Ok(()));
+
+        network.inherit = Some(InheritedNetwork::Mainnet);
+        assert!(matches!(
+            network.validate(),
+            Err(NetworkError::InheritMismatch { .. })
+        ));
+
+        network.inherit = None;
+        network.genesis_challenge = Bytes32::default();
+        assert!(matches!(
+            network.validate(),
+            Err(NetworkError::InvalidGenesisChallenge(_))
+        ));
+    }
+
+    #[test]
+    fn test_network_list_validation() {
+        let mut list = NetworkList::default();
+        list.networks.push(custom_network());
+        assert_eq!(list.validate(), Ok(()));
+
+        list.networks.push(custom_network());
+        assert_eq!(
+            list.validate(),
+            Err(NetworkError::DuplicateName("custom".to_string()))
+        );
+
+        list.networks.pop();
+        list.networks.push(Network {
+            name: "custom2".to_string(),
+            prefix: Some("other".to_string()),
+            ..custom_network()
+        });
+        assert_eq!(
+            list.validate(),
+            Err(NetworkError::PrefixMismatch {
+                name: "custom2".to_string(),
+                other: "custom".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_network_list_repair() {
+        let mut list = NetworkList::default();
+
+        list.networks[0].genesis_challenge = Bytes32::default();
+        list.networks.push(Network {
+            ticker: String::new(),
+            ..custom_network()
+        });
+        list.networks.push(custom_network());
+        list.networks.push(custom_network());
+
+        let errors = list.repair();
+
+        assert_eq!(errors.len(), 3);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.networks[0], NetworkList::default().networks[0]);
+        assert_eq!(list.networks.last(), Some(&custom_network()));
+        assert_eq!(
+            list.networks.len(),
+            NetworkList::default().networks.len() + 1
+        );
+    }
+}
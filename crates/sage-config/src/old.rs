@@ -7,8 +7,8 @@ use serde_with::{hex::Hex, serde_as};
 use specta::Type;
 
 use crate::{
-    Config, GlobalConfig, InheritedNetwork, Network, NetworkConfig, NetworkList, RpcConfig, Wallet,
-    WalletConfig, WalletDefaults,
+    ChangePolicy, Config, GlobalConfig, InheritedNetwork, KeychainConfig, Network, NetworkConfig,
+    NetworkList, RebroadcastConfig, RpcConfig, SignerConfig, Wallet, WalletConfig, WalletDefaults,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Type)]
@@ -135,11 +135,16 @@ pub fn migrate_config(old: OldConfig) -> Result<(Config, WalletConfig), ParseInt
             default_network: old.network.network_id,
             target_peers: old.network.target_peers,
             discover_peers: old.network.discover_peers,
+            paranoid_sync: false,
+            proxy: None,
+            offline: false,
+            rebroadcast: RebroadcastConfig::default(),
         },
         rpc: RpcConfig {
             enabled: old.rpc.run_on_startup,
             port: old.rpc.server_port,
         },
+        keychain: KeychainConfig::default(),
     };
 
     let mut wallet_config = WalletConfig {
@@ -154,6 +159,10 @@ pub fn migrate_config(old: OldConfig) -> Result<(Config, WalletConfig), ParseInt
             network: None,
             delta_sync: None,
             emoji: None,
+            gap_limit: None,
+            change_policy: ChangePolicy::Reuse,
+            signer: SignerConfig::Keychain,
+            receive_hardened: false,
             change_address: None,
         });
     }
@@ -193,6 +202,8 @@ pub fn migrate_networks(old: IndexMap<String, OldNetwork>) -> NetworkList {
                     additional_dns_introducers: network.dns_introducers,
                     additional_peer_introducers: vec![],
                     inherit,
+                    trusted_peer: None,
+                    simulator: false,
                 }
             })
             .collect(),
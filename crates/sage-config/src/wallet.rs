@@ -30,13 +30,85 @@ pub struct Wallet {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub emoji: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub gap_limit: Option<u32>,
+    #[serde(skip_serializing_if = "ChangePolicy::is_reuse")]
+    pub change_policy: ChangePolicy,
+    #[serde(skip_serializing_if = "SignerConfig::is_keychain")]
+    pub signer: SignerConfig,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub receive_hardened: bool,
+    /// Replaced by [`ChangePolicy::Fixed`], but still read from older config files.
+    #[serde(skip_serializing)]
     pub change_address: Option<String>,
 }
 
+/// The number of unused addresses which are derived after the last used address by default.
+pub const DEFAULT_GAP_LIMIT: u32 = 500;
+
+/// Each unused address is subscribed to, so the gap limit can't be arbitrarily large.
+pub const MAX_GAP_LIMIT: u32 = 10_000;
+
+/// Where the change from a transaction is sent.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChangePolicy {
+    /// Change is sent to the current receive address, until it has received coins.
+    #[default]
+    Reuse,
+    /// Change is sent to a new address which hasn't been used before.
+    Fresh,
+    /// Change is always sent to the same address.
+    Fixed { address: String },
+}
+
+impl ChangePolicy {
+    pub fn is_reuse(&self) -> bool {
+        matches!(self, Self::Reuse)
+    }
+}
+
+/// Where the secret keys for a wallet are held when signing transactions.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SignerConfig {
+    /// The keys are stored in the local keychain.
+    #[default]
+    Keychain,
+    /// The keys are held by a separate signing process, listening on a loopback socket address.
+    Remote { address: String },
+}
+
+impl SignerConfig {
+    pub fn is_keychain(&self) -> bool {
+        matches!(self, Self::Keychain)
+    }
+}
+
+impl WalletConfig {
+    /// Moves the change address override from older config files into the change policy.
+    pub fn migrate_change_addresses(&mut self) {
+        for wallet in &mut self.wallets {
+            if let Some(address) = wallet.change_address.take()
+                && wallet.change_policy.is_reuse()
+            {
+                wallet.change_policy = ChangePolicy::Fixed { address };
+            }
+        }
+    }
+}
+
 impl Wallet {
     pub fn delta_sync(&self, defaults: &WalletDefaults) -> bool {
         self.delta_sync.unwrap_or(defaults.delta_sync)
     }
+
+    /// The gap limit is clamped, since a hand edited config could have a value of 0, which
+    /// would never derive anything, or one too large to subscribe to.
+    pub fn gap_limit(&self) -> u32 {
+        self.gap_limit
+            .unwrap_or(DEFAULT_GAP_LIMIT)
+            .clamp(1, MAX_GAP_LIMIT)
+    }
 }
 
 impl Default for Wallet {
@@ -47,6 +119,10 @@ impl Default for Wallet {
             network: None,
             delta_sync: None,
             emoji: None,
+            gap_limit: None,
+            change_policy: ChangePolicy::Reuse,
+            signer: SignerConfig::Keychain,
+            receive_hardened: false,
             change_address: None,
         }
     }
@@ -65,9 +141,14 @@ mod tests {
             network: None,
             delta_sync: None,
             emoji: None,
-            change_address: Some(
-                "xch1dtfukqqka3ftqtdlhmc5spc5vd44h7ejrtnjcewxlueam5yrnnqqyczg8t".to_string(),
-            ),
+            gap_limit: None,
+            change_policy: ChangePolicy::Fixed {
+                address: "xch1dtfukqqka3ftqtdlhmc5spc5vd44h7ejrtnjcewxlueam5yrnnqqyczg8t"
+                    .to_string(),
+            },
+            signer: SignerConfig::Keychain,
+            receive_hardened: false,
+            change_address: None,
         }
     }
 
@@ -94,7 +175,10 @@ mod tests {
                 [[wallets]]
                 name = "Main"
                 fingerprint = 1000000
-                change_address = "xch1dtfukqqka3ftqtdlhmc5spc5vd44h7ejrtnjcewxlueam5yrnnqqyczg8t"
+
+                [wallets.change_policy]
+                type = "fixed"
+                address = "xch1dtfukqqka3ftqtdlhmc5spc5vd44h7ejrtnjcewxlueam5yrnnqqyczg8t"
             "#]],
             &expect![[r#"
                 {
@@ -106,7 +190,10 @@ mod tests {
                       "name": "Main",
                       "fingerprint": 1000000,
                       "delta_sync": null,
-                      "change_address": "xch1dtfukqqka3ftqtdlhmc5spc5vd44h7ejrtnjcewxlueam5yrnnqqyczg8t"
+                      "change_policy": {
+                        "type": "fixed",
+                        "address": "xch1dtfukqqka3ftqtdlhmc5spc5vd44h7ejrtnjcewxlueam5yrnnqqyczg8t"
+                      }
                     }
                   ]
                 }"#]],
@@ -115,7 +202,13 @@ mod tests {
 
     #[test]
     fn test_wallet_config_override() {
-        let config = Wallet { ..default() };
+        let config = Wallet {
+            gap_limit: Some(1000),
+            signer: SignerConfig::Remote {
+                address: "127.0.0.1:9256".to_string(),
+            },
+            ..default()
+        };
         check(
             config,
             &expect![[r#"
@@ -125,7 +218,15 @@ mod tests {
                 [[wallets]]
                 name = "Main"
                 fingerprint = 1000000
-                change_address = "xch1dtfukqqka3ftqtdlhmc5spc5vd44h7ejrtnjcewxlueam5yrnnqqyczg8t"
+                gap_limit = 1000
+
+                [wallets.change_policy]
+                type = "fixed"
+                address = "xch1dtfukqqka3ftqtdlhmc5spc5vd44h7ejrtnjcewxlueam5yrnnqqyczg8t"
+
+                [wallets.signer]
+                type = "remote"
+                address = "127.0.0.1:9256"
             "#]],
             &expect![[r#"
                 {
@@ -137,10 +238,52 @@ mod tests {
                       "name": "Main",
                       "fingerprint": 1000000,
                       "delta_sync": null,
-                      "change_address": "xch1dtfukqqka3ftqtdlhmc5spc5vd44h7ejrtnjcewxlueam5yrnnqqyczg8t"
+                      "gap_limit": 1000,
+                      "change_policy": {
+                        "type": "fixed",
+                        "address": "xch1dtfukqqka3ftqtdlhmc5spc5vd44h7ejrtnjcewxlueam5yrnnqqyczg8t"
+                      },
+                      "signer": {
+                        "type": "remote",
+                        "address": "127.0.0.1:9256"
+                      }
                     }
                   ]
                 }"#]],
         );
     }
+
+    #[test]
+    fn test_wallet_config_change_address_migration() {
+        let mut config: WalletConfig = toml::from_str(
+            r#"
+            [[wallets]]
+            name = "Main"
+            fingerprint = 1000000
+            change_address = "xch1dtfukqqka3ftqtdlhmc5spc5vd44h7ejrtnjcewxlueam5yrnnqqyczg8t"
+            "#,
+        )
+        .expect("Failed to deserialize toml");
+
+        config.migrate_change_addresses();
+
+        assert_eq!(config.wallets[0].change_address, None);
+        assert_eq!(config.wallets[0].change_policy, default().change_policy);
+    }
+
+    #[test]
+    fn test_wallet_config_gap_limit_clamped() {
+        let gap_limit = |gap_limit| {
+            Wallet {
+                gap_limit,
+                ..default()
+            }
+            .gap_limit()
+        };
+
+        assert_eq!(gap_limit(None), DEFAULT_GAP_LIMIT);
+        assert_eq!(gap_limit(Some(0)), 1);
+        assert_eq!(gap_limit(Some(100)), 100);
+        assert_eq!(gap_limit(Some(u32::MAX)), MAX_GAP_LIMIT);
+    }
 }
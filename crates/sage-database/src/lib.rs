@@ -112,6 +112,9 @@ pub enum DatabaseError {
 
     #[error("Public key not found for puzzle hash")]
     PublicKeyNotFound,
+
+    #[error("Vault not found for puzzle hash")]
+    VaultNotFound,
 }
 
 pub(crate) type Result<T> = std::result::Result<T, DatabaseError>;
@@ -1,18 +1,25 @@
 mod assets;
 mod blocks;
+mod broadcast_queue;
 mod coins;
 mod collections;
 mod files;
 mod mempool_items;
 mod offers;
 mod p2_puzzles;
+mod sync_checkpoint;
 mod transactions;
+mod vaults;
 
 pub use assets::*;
+pub use blocks::*;
+pub use broadcast_queue::*;
 pub use coins::*;
 pub use collections::*;
 pub use files::*;
 pub use mempool_items::*;
 pub use offers::*;
 pub use p2_puzzles::*;
+pub use sync_checkpoint::*;
 pub use transactions::*;
+pub use vaults::*;
@@ -63,36 +63,12 @@ impl Database {
     }
 
     pub async fn update_asset(&self, asset: Asset) -> Result<()> {
-        let hash = asset.hash.as_ref();
-        let kind = asset.kind as i64;
-
-        query!(
-            "
-            UPDATE assets SET
-                kind = ?,
-                name = ?,
-                ticker = ?,
-                precision = ?,
-                icon_url = ?,
-                description = ?,
-                is_sensitive_content = ?,
-                is_visible = ?
-            WHERE hash = ?
-            ",
-            kind,
-            asset.name,
-            asset.ticker,
-            asset.precision,
-            asset.icon_url,
-            asset.description,
-            asset.is_sensitive_content,
-            asset.is_visible,
-            hash,
-        )
-        .execute(&self.pool)
-        .await?;
+        update_asset(&self.pool, asset).await
+    }
 
-        Ok(())
+    /// Every asset the wallet knows about, including hidden ones.
+    pub async fn all_assets(&self) -> Result<Vec<Asset>> {
+        all_assets(&self.pool).await
     }
 
     pub async fn asset_kind(&self, hash: Bytes32) -> Result<Option<AssetKind>> {
@@ -128,6 +104,10 @@ impl DatabaseTx<'_> {
         Ok(())
     }
 
+    pub async fn update_asset(&mut self, asset: Asset) -> Result<()> {
+        update_asset(&mut *self.tx, asset).await
+    }
+
     pub async fn update_hidden_puzzle_hash(
         &mut self,
         asset_hash: Bytes32,
@@ -206,6 +186,69 @@ async fn insert_asset(conn: impl SqliteExecutor<'_>, asset: Asset) -> Result<()>
     Ok(())
 }
 
+async fn update_asset(conn: impl SqliteExecutor<'_>, asset: Asset) -> Result<()> {
+    let hash = asset.hash.as_ref();
+    let kind = asset.kind as i64;
+
+    query!(
+        "
+        UPDATE assets SET
+            kind = ?,
+            name = ?,
+            ticker = ?,
+            precision = ?,
+            icon_url = ?,
+            description = ?,
+            is_sensitive_content = ?,
+            is_visible = ?
+        WHERE hash = ?
+        ",
+        kind,
+        asset.name,
+        asset.ticker,
+        asset.precision,
+        asset.icon_url,
+        asset.description,
+        asset.is_sensitive_content,
+        asset.is_visible,
+        hash,
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn all_assets(conn: impl SqliteExecutor<'_>) -> Result<Vec<Asset>> {
+    query!(
+        "
+        SELECT
+            hash, kind, name, ticker, precision, icon_url, description,
+            is_sensitive_content, is_visible, hidden_puzzle_hash
+        FROM assets
+        ORDER BY id ASC
+        "
+    )
+    .fetch_all(conn)
+    .await?
+    .into_iter()
+    .map(|row| {
+        Ok(Asset {
+            hash: row.hash.convert()?,
+            kind: row.kind.convert()?,
+            name: row.name,
+            ticker: row.ticker,
+            precision: row.precision.convert()?,
+            icon_url: row.icon_url,
+            description: row.description,
+            is_sensitive_content: row.is_sensitive_content,
+            is_visible: row.is_visible,
+            hidden_puzzle_hash: row.hidden_puzzle_hash.convert()?,
+        })
+    })
+    .collect()
+}
+
 async fn existing_hidden_puzzle_hash(
     conn: impl SqliteExecutor<'_>,
     asset_hash: Bytes32,
@@ -1,6 +1,13 @@
 use crate::{Convert, Database, DatabaseTx, Result};
 use chia_wallet_sdk::prelude::*;
-use sqlx::SqliteExecutor;
+use sqlx::{SqliteConnection, SqliteExecutor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerifiedHeader {
+    pub header_hash: Bytes32,
+    pub prev_header_hash: Bytes32,
+    pub timestamp: Option<i64>,
+}
 
 impl Database {
     pub async fn unsynced_blocks(&self, limit: u32) -> Result<Vec<u32>> {
@@ -20,12 +27,82 @@ impl Database {
     pub async fn latest_peak(&self) -> Result<Option<(u32, Bytes32)>> {
         latest_peak(&self.pool).await
     }
+
+    pub async fn recent_header_hashes(
+        &self,
+        max_height: u32,
+        limit: u32,
+    ) -> Result<Vec<(u32, Bytes32)>> {
+        recent_header_hashes(&self.pool, max_height, limit).await
+    }
+
+    /// The height of the highest block in the verified header chain.
+    pub async fn verified_height(&self) -> Result<Option<u32>> {
+        Ok(highest_verified_header(&self.pool)
+            .await?
+            .map(|(height, _)| height))
+    }
+
+    pub async fn highest_verified_header(&self) -> Result<Option<(u32, Bytes32)>> {
+        highest_verified_header(&self.pool).await
+    }
+
+    /// The height and previous header hash of the lowest block in the verified header chain.
+    pub async fn lowest_verified_header(&self) -> Result<Option<(u32, Bytes32)>> {
+        lowest_verified_header(&self.pool).await
+    }
+
+    /// The highest height below the given one in which a coin was created or spent.
+    pub async fn highest_coin_height_below(&self, height: u32) -> Result<Option<u32>> {
+        highest_coin_height_below(&self.pool, height).await
+    }
+
+    /// The lowest height a coin was created or spent in that has been verified to not be a
+    /// transaction block, which means the coin state doesn't match the chain.
+    pub async fn lowest_coin_height_in_non_transaction_block(&self) -> Result<Option<u32>> {
+        lowest_coin_height_in_non_transaction_block(&self.pool).await
+    }
+
+    /// The lowest height above the given one in which a coin was created or spent.
+    pub async fn lowest_coin_height_above(&self, height: u32) -> Result<Option<u32>> {
+        lowest_coin_height_above(&self.pool, height).await
+    }
+
+    pub async fn unverify_header(&self, height: u32) -> Result<()> {
+        unverify_header(&self.pool, height).await
+    }
+
+    /// How many of the blocks that coins were created or spent in have a timestamp, out of
+    /// the total number of such blocks.
+    pub async fn block_timestamp_progress(&self) -> Result<(u32, u32)> {
+        block_timestamp_progress(&self.pool).await
+    }
 }
 
 impl DatabaseTx<'_> {
     pub async fn insert_height(&mut self, height: u32) -> Result<()> {
         insert_height(&mut *self.tx, height).await
     }
+
+    pub async fn insert_verified_header(
+        &mut self,
+        height: u32,
+        header: VerifiedHeader,
+    ) -> Result<()> {
+        insert_verified_header(&mut *self.tx, height, header).await
+    }
+
+    /// Whether the block has been verified and is not a transaction block, in which case
+    /// no coins can have been created or spent in it.
+    pub async fn is_verified_non_transaction_block(&mut self, height: u32) -> Result<bool> {
+        is_verified_non_transaction_block(&mut *self.tx, height).await
+    }
+
+    /// Removes every block after the fork point. Coins created in those blocks are deleted
+    /// and coins spent in them become unspent again, which also rolls back the transaction history.
+    pub async fn rollback_to_height(&mut self, fork_height: u32) -> Result<()> {
+        rollback_to_height(&mut self.tx, fork_height).await
+    }
 }
 
 async fn insert_height(conn: impl SqliteExecutor<'_>, height: u32) -> Result<()> {
@@ -76,6 +153,7 @@ async fn insert_block(
         ON CONFLICT (height) DO UPDATE SET
             timestamp = COALESCE(excluded.timestamp, timestamp),
             header_hash = excluded.header_hash,
+            is_verified = (is_verified AND header_hash IS excluded.header_hash),
             is_peak = (excluded.is_peak OR is_peak)
         ",
         height,
@@ -107,3 +185,227 @@ async fn latest_peak(conn: impl SqliteExecutor<'_>) -> Result<Option<(u32, Bytes
     })
     .transpose()
 }
+
+async fn recent_header_hashes(
+    conn: impl SqliteExecutor<'_>,
+    max_height: u32,
+    limit: u32,
+) -> Result<Vec<(u32, Bytes32)>> {
+    sqlx::query!(
+        "
+        SELECT height, header_hash
+        FROM blocks
+        WHERE header_hash IS NOT NULL AND height <= ?
+        ORDER BY height DESC
+        LIMIT ?
+        ",
+        max_height,
+        limit
+    )
+    .fetch_all(conn)
+    .await?
+    .into_iter()
+    .filter_map(|row| {
+        row.header_hash
+            .map(|hash| Ok((row.height.convert()?, hash.convert()?)))
+    })
+    .collect()
+}
+
+async fn rollback_to_height(conn: &mut SqliteConnection, fork_height: u32) -> Result<()> {
+    sqlx::query!(
+        "UPDATE coins SET is_children_synced = FALSE WHERE spent_height > ?",
+        fork_height
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!("DELETE FROM blocks WHERE height > ?", fork_height)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+async fn highest_verified_header(conn: impl SqliteExecutor<'_>) -> Result<Option<(u32, Bytes32)>> {
+    sqlx::query!(
+        "
+        SELECT height, header_hash
+        FROM blocks
+        WHERE is_verified = TRUE
+        ORDER BY height DESC
+        LIMIT 1
+        "
+    )
+    .fetch_optional(conn)
+    .await?
+    .and_then(|row| {
+        row.header_hash
+            .map(|hash| Ok((row.height.convert()?, hash.convert()?)))
+    })
+    .transpose()
+}
+
+async fn lowest_verified_header(conn: impl SqliteExecutor<'_>) -> Result<Option<(u32, Bytes32)>> {
+    sqlx::query!(
+        "
+        SELECT height, prev_header_hash
+        FROM blocks
+        WHERE is_verified = TRUE
+        ORDER BY height ASC
+        LIMIT 1
+        "
+    )
+    .fetch_optional(conn)
+    .await?
+    .and_then(|row| {
+        row.prev_header_hash
+            .map(|hash| Ok((row.height.convert()?, hash.convert()?)))
+    })
+    .transpose()
+}
+
+async fn highest_coin_height_below(
+    conn: impl SqliteExecutor<'_>,
+    height: u32,
+) -> Result<Option<u32>> {
+    sqlx::query!(
+        "
+        SELECT MAX(height) AS \"height: i64\" FROM (
+            SELECT created_height AS height FROM coins WHERE created_height < ?
+            UNION ALL
+            SELECT spent_height AS height FROM coins WHERE spent_height < ?
+        )
+        ",
+        height,
+        height
+    )
+    .fetch_one(conn)
+    .await?
+    .height
+    .map(Convert::convert)
+    .transpose()
+}
+
+async fn block_timestamp_progress(conn: impl SqliteExecutor<'_>) -> Result<(u32, u32)> {
+    let row = sqlx::query!(
+        "
+        SELECT COUNT(blocks.timestamp) AS filled, COUNT(*) AS total FROM (
+            SELECT created_height AS height FROM coins
+            UNION
+            SELECT spent_height AS height FROM coins
+        ) AS heights
+        INNER JOIN blocks ON blocks.height = heights.height
+        "
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok((row.filled.convert()?, row.total.convert()?))
+}
+
+async fn unverify_header(conn: impl SqliteExecutor<'_>, height: u32) -> Result<()> {
+    sqlx::query!(
+        "UPDATE blocks SET is_verified = FALSE WHERE height = ?",
+        height
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn insert_verified_header(
+    conn: impl SqliteExecutor<'_>,
+    height: u32,
+    header: VerifiedHeader,
+) -> Result<()> {
+    let header_hash = header.header_hash.as_ref();
+    let prev_header_hash = header.prev_header_hash.as_ref();
+    let is_transaction_block = header.timestamp.is_some();
+
+    sqlx::query!(
+        "
+        INSERT INTO blocks (
+            height, header_hash, prev_header_hash, timestamp,
+            is_transaction_block, is_verified, is_peak
+        )
+        VALUES (?, ?, ?, ?, ?, TRUE, FALSE)
+        ON CONFLICT (height) DO UPDATE SET
+            header_hash = excluded.header_hash,
+            prev_header_hash = excluded.prev_header_hash,
+            timestamp = COALESCE(excluded.timestamp, timestamp),
+            is_transaction_block = excluded.is_transaction_block,
+            is_verified = TRUE
+        ",
+        height,
+        header_hash,
+        prev_header_hash,
+        header.timestamp,
+        is_transaction_block
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn is_verified_non_transaction_block(
+    conn: impl SqliteExecutor<'_>,
+    height: u32,
+) -> Result<bool> {
+    Ok(sqlx::query!(
+        "
+        SELECT COUNT(*) AS count FROM blocks
+        WHERE height = ? AND is_verified = TRUE AND is_transaction_block = FALSE
+        ",
+        height
+    )
+    .fetch_one(conn)
+    .await?
+    .count
+        > 0)
+}
+
+async fn lowest_coin_height_in_non_transaction_block(
+    conn: impl SqliteExecutor<'_>,
+) -> Result<Option<u32>> {
+    sqlx::query!(
+        "
+        SELECT MIN(heights.height) AS \"height: i64\" FROM (
+            SELECT created_height AS height FROM coins
+            UNION ALL
+            SELECT spent_height AS height FROM coins
+        ) AS heights
+        INNER JOIN blocks ON blocks.height = heights.height
+        WHERE blocks.is_verified = TRUE AND blocks.is_transaction_block = FALSE
+        "
+    )
+    .fetch_one(conn)
+    .await?
+    .height
+    .map(Convert::convert)
+    .transpose()
+}
+
+async fn lowest_coin_height_above(
+    conn: impl SqliteExecutor<'_>,
+    height: u32,
+) -> Result<Option<u32>> {
+    sqlx::query!(
+        "
+        SELECT MIN(height) AS \"height: i64\" FROM (
+            SELECT created_height AS height FROM coins WHERE created_height > ?
+            UNION ALL
+            SELECT spent_height AS height FROM coins WHERE spent_height > ?
+        )
+        ",
+        height,
+        height
+    )
+    .fetch_one(conn)
+    .await?
+    .height
+    .map(Convert::convert)
+    .transpose()
+}
@@ -0,0 +1,165 @@
+use chia_wallet_sdk::prelude::*;
+use sqlx::{Row, SqliteExecutor};
+
+use crate::{Convert, Database, DatabaseTx, Result};
+
+impl Database {
+    /// Signed transactions waiting to be broadcast, in the order they were queued.
+    pub async fn queued_broadcasts(&self) -> Result<Vec<SpendBundle>> {
+        queued_broadcasts(&self.pool).await
+    }
+
+    pub async fn queued_broadcast_count(&self) -> Result<u32> {
+        queued_broadcast_count(&self.pool).await
+    }
+
+    pub async fn remove_queued_broadcast(&self, hash: Bytes32) -> Result<()> {
+        remove_queued_broadcast(&self.pool, hash).await
+    }
+
+    /// Whether the coin is spent by a transaction that's still in the mempool.
+    pub async fn is_spent_in_mempool(&self, coin_id: Bytes32) -> Result<bool> {
+        is_spent_in_mempool(&self.pool, coin_id).await
+    }
+}
+
+impl DatabaseTx<'_> {
+    pub async fn queue_broadcast(&mut self, spend_bundle: &SpendBundle) -> Result<()> {
+        let hash = spend_bundle.name();
+
+        insert_queued_broadcast(&mut *self.tx, hash, &spend_bundle.aggregated_signature).await?;
+
+        for (seq, coin_spend) in spend_bundle.coin_spends.iter().enumerate() {
+            insert_queued_broadcast_spend(&mut *self.tx, hash, coin_spend, seq).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn remove_queued_broadcast(&mut self, hash: Bytes32) -> Result<()> {
+        remove_queued_broadcast(&mut *self.tx, hash).await
+    }
+}
+
+async fn insert_queued_broadcast(
+    conn: impl SqliteExecutor<'_>,
+    hash: Bytes32,
+    aggregated_signature: &Signature,
+) -> Result<()> {
+    sqlx::query(
+        "
+        INSERT OR IGNORE INTO broadcast_queue (hash, aggregated_signature, queued_timestamp)
+        VALUES (?, ?, unixepoch())
+        ",
+    )
+    .bind(hash.as_ref())
+    .bind(aggregated_signature.to_bytes().as_ref())
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn insert_queued_broadcast_spend(
+    conn: impl SqliteExecutor<'_>,
+    hash: Bytes32,
+    coin_spend: &CoinSpend,
+    seq: usize,
+) -> Result<()> {
+    let seq: i64 = seq.try_into()?;
+
+    sqlx::query(
+        "
+        INSERT OR IGNORE INTO broadcast_queue_spends (broadcast_id, coin_hash, parent_coin_hash, puzzle_hash, amount, puzzle_reveal, solution, seq)
+        VALUES ((SELECT id FROM broadcast_queue WHERE hash = ?), ?, ?, ?, ?, ?, ?, ?)
+        ",
+    )
+    .bind(hash.as_ref())
+    .bind(coin_spend.coin.coin_id().as_ref())
+    .bind(coin_spend.coin.parent_coin_info.as_ref())
+    .bind(coin_spend.coin.puzzle_hash.as_ref())
+    .bind(coin_spend.coin.amount.to_be_bytes().to_vec())
+    .bind(coin_spend.puzzle_reveal.clone().into_bytes())
+    .bind(coin_spend.solution.clone().into_bytes())
+    .bind(seq)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn queued_broadcasts(conn: impl SqliteExecutor<'_>) -> Result<Vec<SpendBundle>> {
+    let rows = sqlx::query(
+        "
+        SELECT broadcast_queue.hash, aggregated_signature, parent_coin_hash, puzzle_hash, amount, puzzle_reveal, solution
+        FROM broadcast_queue
+        INNER JOIN broadcast_queue_spends ON broadcast_queue_spends.broadcast_id = broadcast_queue.id
+        ORDER BY broadcast_queue.id ASC, seq ASC
+        ",
+    )
+    .fetch_all(conn)
+    .await?;
+
+    let mut spend_bundles: Vec<(Bytes32, SpendBundle)> = Vec::new();
+
+    for row in rows {
+        let hash: Bytes32 = row.get::<Vec<u8>, _>("hash").convert()?;
+
+        let coin_spend = CoinSpend::new(
+            Coin::new(
+                row.get::<Vec<u8>, _>("parent_coin_hash").convert()?,
+                row.get::<Vec<u8>, _>("puzzle_hash").convert()?,
+                row.get::<Vec<u8>, _>("amount").convert()?,
+            ),
+            row.get::<Vec<u8>, _>("puzzle_reveal").into(),
+            row.get::<Vec<u8>, _>("solution").into(),
+        );
+
+        match spend_bundles.last_mut() {
+            Some((last_hash, spend_bundle)) if *last_hash == hash => {
+                spend_bundle.coin_spends.push(coin_spend);
+            }
+            _ => {
+                let aggregated_signature =
+                    row.get::<Vec<u8>, _>("aggregated_signature").convert()?;
+
+                spend_bundles.push((
+                    hash,
+                    SpendBundle::new(vec![coin_spend], aggregated_signature),
+                ));
+            }
+        }
+    }
+
+    Ok(spend_bundles
+        .into_iter()
+        .map(|(_, spend_bundle)| spend_bundle)
+        .collect())
+}
+
+async fn queued_broadcast_count(conn: impl SqliteExecutor<'_>) -> Result<u32> {
+    sqlx::query("SELECT COUNT(*) AS count FROM broadcast_queue")
+        .fetch_one(conn)
+        .await?
+        .get::<i64, _>("count")
+        .convert()
+}
+
+async fn remove_queued_broadcast(conn: impl SqliteExecutor<'_>, hash: Bytes32) -> Result<()> {
+    sqlx::query("DELETE FROM broadcast_queue WHERE hash = ?")
+        .bind(hash.as_ref())
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+async fn is_spent_in_mempool(conn: impl SqliteExecutor<'_>, coin_id: Bytes32) -> Result<bool> {
+    Ok(
+        sqlx::query("SELECT 1 FROM mempool_spends WHERE coin_hash = ? LIMIT 1")
+            .bind(coin_id.as_ref())
+            .fetch_optional(conn)
+            .await?
+            .is_some(),
+    )
+}
@@ -12,6 +12,9 @@ pub struct MempoolItem {
     pub aggregated_signature: Signature,
     pub fee: u64,
     pub submitted_timestamp: Option<u64>,
+    pub created_timestamp: u64,
+    /// How many times the transaction was resubmitted without any peer acknowledging it.
+    pub failed_submissions: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -24,6 +27,8 @@ pub struct MempoolItemWithCoins {
 }
 
 impl Database {
+    /// Transactions that are due to be resubmitted. Ones that were queued while the wallet
+    /// was offline are left to the broadcast queue until they've been broadcast once.
     pub async fn mempool_items_to_submit(
         &self,
         check_every_seconds: i64,
@@ -40,6 +45,12 @@ impl Database {
         update_mempool_item_time(&self.pool, mempool_item_id).await
     }
 
+    /// Records a resubmission that no peer acknowledged, and resets the submission time so
+    /// that it isn't retried until the next interval.
+    pub async fn record_failed_submission(&self, mempool_item_id: Bytes32) -> Result<()> {
+        record_failed_submission(&self.pool, mempool_item_id).await
+    }
+
     pub async fn mempool_items(&self) -> Result<Vec<MempoolItem>> {
         mempool_items(&self.pool).await
     }
@@ -104,7 +115,8 @@ async fn insert_mempool_item(
 
     query!(
         "
-        INSERT OR IGNORE INTO mempool_items (hash, aggregated_signature, fee) VALUES (?, ?, ?)
+        INSERT OR IGNORE INTO mempool_items (hash, aggregated_signature, fee, created_timestamp)
+        VALUES (?, ?, ?, unixepoch())
         ",
         hash,
         aggregated_signature,
@@ -185,9 +197,11 @@ async fn mempool_items_to_submit(
 ) -> Result<Vec<MempoolItem>> {
     query!(
         "
-        SELECT hash, aggregated_signature, fee, submitted_timestamp
+        SELECT hash, aggregated_signature, fee, submitted_timestamp, created_timestamp, failed_submissions
         FROM mempool_items
-        WHERE submitted_timestamp IS NULL OR unixepoch() - submitted_timestamp >= ?
+        WHERE (submitted_timestamp IS NULL OR unixepoch() - submitted_timestamp >= ?)
+        AND hash NOT IN (SELECT hash FROM broadcast_queue)
+        ORDER BY id ASC
         LIMIT ?
         ",
         check_every_seconds,
@@ -202,6 +216,8 @@ async fn mempool_items_to_submit(
             aggregated_signature: row.aggregated_signature.convert()?,
             fee: row.fee.convert()?,
             submitted_timestamp: row.submitted_timestamp.map(|ts| ts as u64),
+            created_timestamp: row.created_timestamp.unwrap_or_default() as u64,
+            failed_submissions: row.failed_submissions.try_into()?,
         })
     })
     .collect()
@@ -325,13 +341,33 @@ async fn update_mempool_item_time(
     Ok(())
 }
 
+async fn record_failed_submission(
+    conn: impl SqliteExecutor<'_>,
+    mempool_item_id: Bytes32,
+) -> Result<()> {
+    let mempool_item_id = mempool_item_id.as_ref();
+
+    query!(
+        "
+        UPDATE mempool_items
+        SET failed_submissions = failed_submissions + 1, submitted_timestamp = unixepoch()
+        WHERE hash = ?
+        ",
+        mempool_item_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
 async fn mempool_items(conn: impl SqliteExecutor<'_>) -> Result<Vec<MempoolItem>> {
     query!(
         "
-        SELECT hash, aggregated_signature, fee, submitted_timestamp
+        SELECT hash, aggregated_signature, fee, submitted_timestamp, created_timestamp, failed_submissions
         FROM mempool_items
         ORDER BY submitted_timestamp DESC, hash ASC
-        ",
+        "
     )
     .fetch_all(conn)
     .await?
@@ -342,6 +378,8 @@ async fn mempool_items(conn: impl SqliteExecutor<'_>) -> Result<Vec<MempoolItem>
             aggregated_signature: row.aggregated_signature.convert()?,
             fee: row.fee.convert()?,
             submitted_timestamp: row.submitted_timestamp.map(|ts| ts as u64),
+            created_timestamp: row.created_timestamp.unwrap_or_default() as u64,
+            failed_submissions: row.failed_submissions.try_into()?,
         })
     })
     .collect()
@@ -41,6 +41,11 @@ impl Database {
         offer_assets(&self.pool, offer_id).await
     }
 
+    /// The wallet's coins that are locked up by the offer.
+    pub async fn offer_coins(&self, offer_id: Bytes32) -> Result<Vec<Coin>> {
+        offer_coins(&self.pool, offer_id).await
+    }
+
     pub async fn delete_offer(&self, offer_id: Bytes32) -> Result<()> {
         delete_offer(&self.pool, offer_id).await
     }
@@ -158,6 +163,32 @@ async fn offers_for_asset(
         .collect()
 }
 
+async fn offer_coins(conn: impl SqliteExecutor<'_>, offer_id: Bytes32) -> Result<Vec<Coin>> {
+    let offer_id = offer_id.as_ref();
+
+    sqlx::query!(
+        "
+        SELECT coins.parent_coin_hash, coins.puzzle_hash, coins.amount
+        FROM offer_coins
+        INNER JOIN offers ON offers.id = offer_coins.offer_id
+        INNER JOIN coins ON coins.id = offer_coins.coin_id
+        WHERE offers.hash = ?
+        ",
+        offer_id
+    )
+    .fetch_all(conn)
+    .await?
+    .into_iter()
+    .map(|row| {
+        Ok(Coin::new(
+            row.parent_coin_hash.convert()?,
+            row.puzzle_hash.convert()?,
+            row.amount.convert()?,
+        ))
+    })
+    .collect()
+}
+
 async fn offer_assets(
     conn: impl SqliteExecutor<'_>,
     offer_id: Bytes32,
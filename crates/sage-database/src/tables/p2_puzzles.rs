@@ -1,4 +1,7 @@
-use chia_wallet_sdk::{prelude::*, types::puzzles::P2DelegatedConditionsArgs};
+use chia_wallet_sdk::{
+    prelude::*,
+    types::puzzles::{P2DelegatedConditionsArgs, P2MOfNDelegateDirectArgs},
+};
 use sqlx::{SqliteExecutor, query};
 
 use crate::{Convert, Database, DatabaseError, DatabaseTx, Result};
@@ -9,14 +12,16 @@ pub enum P2PuzzleKind {
     Clawback,
     Option,
     Arbor,
+    Vault,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum P2Puzzle {
     PublicKey(PublicKey),
     Clawback(Clawback),
     Option(Underlying),
     Arbor(PublicKey),
+    Vault(Vault),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -37,7 +42,22 @@ pub struct Underlying {
     pub strike_type: OptionType,
 }
 
-#[derive(Debug, Clone, Copy)]
+/// An M of N multisig, where `threshold` of the public keys have to sign each spend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vault {
+    pub threshold: u32,
+    pub public_keys: Vec<PublicKey>,
+}
+
+impl Vault {
+    pub fn p2_puzzle_hash(&self) -> Bytes32 {
+        P2MOfNDelegateDirectArgs::new(self.threshold as usize, self.public_keys.clone())
+            .curry_tree_hash()
+            .into()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Derivation {
     pub derivation_index: u32,
     pub is_hardened: bool,
@@ -64,6 +84,12 @@ impl Database {
         is_custody_p2_puzzle_hash(&self.pool, puzzle_hash).await
     }
 
+    /// Whether coins with the puzzle hash are plain XCH held by the wallet, either in custody or
+    /// in one of its vaults.
+    pub async fn is_xch_p2_puzzle_hash(&self, puzzle_hash: Bytes32) -> Result<bool> {
+        is_xch_p2_puzzle_hash(&self.pool, puzzle_hash).await
+    }
+
     pub async fn is_p2_puzzle_hash(&self, puzzle_hash: Bytes32) -> Result<bool> {
         is_p2_puzzle_hash(&self.pool, puzzle_hash).await
     }
@@ -108,6 +134,13 @@ impl Database {
 
                 Ok(P2Puzzle::Arbor(key))
             }
+            P2PuzzleKind::Vault => {
+                let Some(vault) = self.vault(puzzle_hash).await? else {
+                    return Err(DatabaseError::VaultNotFound);
+                };
+
+                Ok(P2Puzzle::Vault(vault))
+            }
         }
     }
 
@@ -142,6 +175,10 @@ impl DatabaseTx<'_> {
         is_custody_p2_puzzle_hash(&mut *self.tx, puzzle_hash).await
     }
 
+    pub async fn is_xch_p2_puzzle_hash(&mut self, puzzle_hash: Bytes32) -> Result<bool> {
+        is_xch_p2_puzzle_hash(&mut *self.tx, puzzle_hash).await
+    }
+
     pub async fn is_p2_puzzle_hash(&mut self, puzzle_hash: Bytes32) -> Result<bool> {
         is_p2_puzzle_hash(&mut *self.tx, puzzle_hash).await
     }
@@ -154,6 +191,19 @@ impl DatabaseTx<'_> {
         unused_derivation_index(&mut *self.tx, is_hardened).await
     }
 
+    /// Marks the derivation as used, even though it hasn't received any coins yet.
+    pub async fn reserve_derivation(
+        &mut self,
+        derivation_index: u32,
+        is_hardened: bool,
+    ) -> Result<()> {
+        reserve_derivation(&mut *self.tx, derivation_index, is_hardened).await
+    }
+
+    pub async fn used_derivation_count(&mut self, is_hardened: bool) -> Result<u32> {
+        used_derivation_count(&mut *self.tx, is_hardened).await
+    }
+
     pub async fn insert_custody_p2_puzzle(
         &mut self,
         p2_puzzle_hash: Bytes32,
@@ -221,6 +271,22 @@ async fn is_custody_p2_puzzle_hash(
         > 0)
 }
 
+async fn is_xch_p2_puzzle_hash(
+    conn: impl SqliteExecutor<'_>,
+    puzzle_hash: Bytes32,
+) -> Result<bool> {
+    let puzzle_hash = puzzle_hash.as_ref();
+
+    Ok(query!(
+        "SELECT COUNT(*) AS count FROM p2_puzzles WHERE hash = ? AND kind IN (0, 3, 4)",
+        puzzle_hash
+    )
+    .fetch_one(conn)
+    .await?
+    .count
+        > 0)
+}
+
 async fn is_p2_puzzle_hash(conn: impl SqliteExecutor<'_>, puzzle_hash: Bytes32) -> Result<bool> {
     let puzzle_hash = puzzle_hash.as_ref();
 
@@ -316,8 +382,11 @@ async fn unused_derivation_index(conn: impl SqliteExecutor<'_>, is_hardened: boo
         "
         SELECT COALESCE(MAX(derivation_index) + 1, 0) AS derivation_index
         FROM public_keys
-        INNER JOIN coins ON coins.p2_puzzle_id = public_keys.p2_puzzle_id
         WHERE is_hardened = ?
+        AND (
+            is_reserved = TRUE
+            OR EXISTS (SELECT 1 FROM coins WHERE coins.p2_puzzle_id = public_keys.p2_puzzle_id)
+        )
         ",
         is_hardened
     )
@@ -327,6 +396,42 @@ async fn unused_derivation_index(conn: impl SqliteExecutor<'_>, is_hardened: boo
     .convert()
 }
 
+async fn reserve_derivation(
+    conn: impl SqliteExecutor<'_>,
+    derivation_index: u32,
+    is_hardened: bool,
+) -> Result<()> {
+    query!(
+        "
+        UPDATE public_keys SET is_reserved = TRUE
+        WHERE derivation_index = ? AND is_hardened = ?
+        ",
+        derivation_index,
+        is_hardened
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// The number of derivations which have received at least one coin.
+async fn used_derivation_count(conn: impl SqliteExecutor<'_>, is_hardened: bool) -> Result<u32> {
+    query!(
+        "
+        SELECT COUNT(DISTINCT public_keys.id) AS count
+        FROM public_keys
+        INNER JOIN coins ON coins.p2_puzzle_id = public_keys.p2_puzzle_id
+        WHERE is_hardened = ?
+        ",
+        is_hardened
+    )
+    .fetch_one(conn)
+    .await?
+    .count
+    .convert()
+}
+
 async fn insert_custody_p2_puzzle(
     conn: impl SqliteExecutor<'_>,
     p2_puzzle_hash: Bytes32,
@@ -456,6 +561,7 @@ async fn p2_puzzle_kind(
         1 => P2PuzzleKind::Clawback,
         2 => P2PuzzleKind::Option,
         3 => P2PuzzleKind::Arbor,
+        4 => P2PuzzleKind::Vault,
         _ => return Err(DatabaseError::InvalidEnumVariant),
     })
 }
@@ -0,0 +1,158 @@
+use chia_wallet_sdk::prelude::*;
+use sqlx::{Row, SqliteExecutor};
+
+use crate::{Convert, Database, DatabaseTx, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncCheckpoint {
+    pub height: u32,
+    pub header_hash: Bytes32,
+    /// The highest custody p2 puzzle id that was synced up to the checkpoint.
+    pub p2_puzzle_id: u32,
+}
+
+impl Database {
+    pub async fn sync_checkpoint(&self) -> Result<Option<SyncCheckpoint>> {
+        sync_checkpoint(&self.pool).await
+    }
+
+    pub async fn save_sync_checkpoint(&self, checkpoint: SyncCheckpoint) -> Result<()> {
+        save_sync_checkpoint(&self.pool, checkpoint).await
+    }
+
+    /// Moves the checkpoint forward to a new peak, once everything up to it has been synced.
+    pub async fn advance_sync_checkpoint(&self, height: u32, header_hash: Bytes32) -> Result<()> {
+        advance_sync_checkpoint(&self.pool, height, header_hash).await
+    }
+
+    /// Moves the checkpoint's p2 puzzle id forward, once the puzzles up to it have been synced.
+    pub async fn advance_sync_checkpoint_p2_puzzle_id(&self, p2_puzzle_id: u32) -> Result<()> {
+        advance_sync_checkpoint_p2_puzzle_id(&self.pool, p2_puzzle_id).await
+    }
+
+    pub async fn clear_sync_checkpoint(&self) -> Result<()> {
+        clear_sync_checkpoint(&self.pool).await
+    }
+
+    /// The custody and vault p2 puzzle hashes, whose coins are synced by subscribing to them,
+    /// along with their ids in the order they were inserted.
+    pub async fn synced_p2_puzzle_hashes_with_ids(&self) -> Result<Vec<(u32, Bytes32)>> {
+        synced_p2_puzzle_hashes_with_ids(&self.pool).await
+    }
+}
+
+impl DatabaseTx<'_> {
+    /// Moves the checkpoint back to the fork point if it's past it, or removes it if the fork
+    /// point isn't known.
+    pub async fn rewind_sync_checkpoint(
+        &mut self,
+        fork_height: u32,
+        header_hash: Option<Bytes32>,
+    ) -> Result<()> {
+        match header_hash {
+            Some(header_hash) => {
+                sqlx::query(
+                    "UPDATE sync_checkpoint SET height = ?, header_hash = ? WHERE height > ?",
+                )
+                .bind(fork_height)
+                .bind(header_hash.as_ref())
+                .bind(fork_height)
+                .execute(&mut *self.tx)
+                .await?;
+            }
+            None => {
+                clear_sync_checkpoint(&mut *self.tx).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+async fn sync_checkpoint(conn: impl SqliteExecutor<'_>) -> Result<Option<SyncCheckpoint>> {
+    sqlx::query("SELECT height, header_hash, p2_puzzle_id FROM sync_checkpoint WHERE id = 0")
+        .fetch_optional(conn)
+        .await?
+        .map(|row| {
+            Ok(SyncCheckpoint {
+                height: row.get::<i64, _>("height").convert()?,
+                header_hash: row.get::<Vec<u8>, _>("header_hash").convert()?,
+                p2_puzzle_id: row.get::<i64, _>("p2_puzzle_id").convert()?,
+            })
+        })
+        .transpose()
+}
+
+async fn save_sync_checkpoint(
+    conn: impl SqliteExecutor<'_>,
+    checkpoint: SyncCheckpoint,
+) -> Result<()> {
+    sqlx::query(
+        "
+        INSERT INTO sync_checkpoint (id, height, header_hash, p2_puzzle_id) VALUES (0, ?, ?, ?)
+        ON CONFLICT (id) DO UPDATE SET
+            height = excluded.height,
+            header_hash = excluded.header_hash,
+            p2_puzzle_id = excluded.p2_puzzle_id
+        ",
+    )
+    .bind(checkpoint.height)
+    .bind(checkpoint.header_hash.as_ref())
+    .bind(checkpoint.p2_puzzle_id)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn advance_sync_checkpoint(
+    conn: impl SqliteExecutor<'_>,
+    height: u32,
+    header_hash: Bytes32,
+) -> Result<()> {
+    sqlx::query("UPDATE sync_checkpoint SET height = ?, header_hash = ? WHERE height < ?")
+        .bind(height)
+        .bind(header_hash.as_ref())
+        .bind(height)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+async fn advance_sync_checkpoint_p2_puzzle_id(
+    conn: impl SqliteExecutor<'_>,
+    p2_puzzle_id: u32,
+) -> Result<()> {
+    sqlx::query("UPDATE sync_checkpoint SET p2_puzzle_id = ? WHERE p2_puzzle_id < ?")
+        .bind(p2_puzzle_id)
+        .bind(p2_puzzle_id)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+async fn clear_sync_checkpoint(conn: impl SqliteExecutor<'_>) -> Result<()> {
+    sqlx::query("DELETE FROM sync_checkpoint")
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+async fn synced_p2_puzzle_hashes_with_ids(
+    conn: impl SqliteExecutor<'_>,
+) -> Result<Vec<(u32, Bytes32)>> {
+    sqlx::query("SELECT id, hash FROM p2_puzzles WHERE kind IN (0, 3, 4) ORDER BY id ASC")
+        .fetch_all(conn)
+        .await?
+        .into_iter()
+        .map(|row| {
+            Ok((
+                row.get::<i64, _>("id").convert()?,
+                row.get::<Vec<u8>, _>("hash").convert()?,
+            ))
+        })
+        .collect()
+}
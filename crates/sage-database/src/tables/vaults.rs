@@ -0,0 +1,184 @@
+use chia_wallet_sdk::prelude::*;
+use sqlx::{Row, SqliteConnection, SqliteExecutor, query};
+
+use crate::{Convert, Database, DatabaseTx, Result, Vault};
+
+#[derive(Debug, Clone)]
+pub struct VaultRow {
+    pub p2_puzzle_hash: Bytes32,
+    pub name: String,
+    pub vault: Vault,
+}
+
+impl Database {
+    pub async fn vaults(&self) -> Result<Vec<VaultRow>> {
+        vaults(&self.pool).await
+    }
+
+    pub async fn vault(&self, p2_puzzle_hash: Bytes32) -> Result<Option<Vault>> {
+        vault(&self.pool, p2_puzzle_hash).await
+    }
+
+    /// Unspent XCH coins in the vault, which aren't already being spent or locked in an offer.
+    pub async fn selectable_vault_coins(&self, p2_puzzle_hash: Bytes32) -> Result<Vec<Coin>> {
+        selectable_vault_coins(&self.pool, p2_puzzle_hash).await
+    }
+}
+
+impl DatabaseTx<'_> {
+    pub async fn insert_vault_p2_puzzle(&mut self, name: &str, vault: &Vault) -> Result<Bytes32> {
+        insert_vault_p2_puzzle(&mut self.tx, name, vault).await
+    }
+}
+
+async fn vaults(conn: impl SqliteExecutor<'_>) -> Result<Vec<VaultRow>> {
+    let rows = sqlx::query(
+        "
+        SELECT p2_puzzles.hash, p2_vaults.name, p2_vaults.threshold, p2_vault_keys.key
+        FROM p2_vaults
+        INNER JOIN p2_puzzles ON p2_puzzles.id = p2_vaults.p2_puzzle_id
+        INNER JOIN p2_vault_keys ON p2_vault_keys.p2_vault_id = p2_vaults.id
+        ORDER BY p2_vaults.id ASC, p2_vault_keys.key_index ASC
+        ",
+    )
+    .fetch_all(conn)
+    .await?;
+
+    let mut vaults: Vec<VaultRow> = Vec::new();
+
+    for row in rows {
+        let p2_puzzle_hash: Bytes32 = row.get::<Vec<u8>, _>("hash").convert()?;
+        let key: PublicKey = row.get::<Vec<u8>, _>("key").convert()?;
+
+        if let Some(last) = vaults.last_mut()
+            && last.p2_puzzle_hash == p2_puzzle_hash
+        {
+            last.vault.public_keys.push(key);
+            continue;
+        }
+
+        vaults.push(VaultRow {
+            p2_puzzle_hash,
+            name: row.get("name"),
+            vault: Vault {
+                threshold: row.get::<i64, _>("threshold").convert()?,
+                public_keys: vec![key],
+            },
+        });
+    }
+
+    Ok(vaults)
+}
+
+async fn vault(conn: impl SqliteExecutor<'_>, p2_puzzle_hash: Bytes32) -> Result<Option<Vault>> {
+    let rows = sqlx::query(
+        "
+        SELECT p2_vaults.threshold, p2_vault_keys.key
+        FROM p2_vaults
+        INNER JOIN p2_puzzles ON p2_puzzles.id = p2_vaults.p2_puzzle_id
+        INNER JOIN p2_vault_keys ON p2_vault_keys.p2_vault_id = p2_vaults.id
+        WHERE p2_puzzles.hash = ?
+        ORDER BY p2_vault_keys.key_index ASC
+        ",
+    )
+    .bind(p2_puzzle_hash.as_ref())
+    .fetch_all(conn)
+    .await?;
+
+    let Some(first) = rows.first() else {
+        return Ok(None);
+    };
+
+    let threshold = first.get::<i64, _>("threshold").convert()?;
+
+    let public_keys = rows
+        .into_iter()
+        .map(|row| row.get::<Vec<u8>, _>("key").convert())
+        .collect::<Result<Vec<PublicKey>>>()?;
+
+    Ok(Some(Vault {
+        threshold,
+        public_keys,
+    }))
+}
+
+async fn selectable_vault_coins(
+    conn: impl SqliteExecutor<'_>,
+    p2_puzzle_hash: Bytes32,
+) -> Result<Vec<Coin>> {
+    sqlx::query(
+        "
+        SELECT parent_coin_hash, puzzle_hash, amount FROM wallet_coins
+        WHERE asset_id = 0
+        AND p2_puzzle_hash = ?
+        AND created_height IS NOT NULL
+        AND spent_height IS NULL
+        AND mempool_item_hash IS NULL
+        AND offer_hash IS NULL
+        AND NOT EXISTS (
+            SELECT 1 FROM mempool_coins
+            WHERE mempool_coins.coin_id = wallet_coins.coin_id
+        )
+        ",
+    )
+    .bind(p2_puzzle_hash.as_ref())
+    .fetch_all(conn)
+    .await?
+    .into_iter()
+    .map(|row| {
+        Ok(Coin::new(
+            row.get::<Vec<u8>, _>("parent_coin_hash").convert()?,
+            row.get::<Vec<u8>, _>("puzzle_hash").convert()?,
+            row.get::<Vec<u8>, _>("amount").convert()?,
+        ))
+    })
+    .collect()
+}
+
+async fn insert_vault_p2_puzzle(
+    conn: &mut SqliteConnection,
+    name: &str,
+    vault: &Vault,
+) -> Result<Bytes32> {
+    let p2_puzzle_hash = vault.p2_puzzle_hash();
+    let hash = p2_puzzle_hash.as_ref();
+
+    query!(
+        "
+        INSERT OR IGNORE INTO p2_puzzles (hash, kind) VALUES (?, 4);
+
+        INSERT OR IGNORE INTO p2_vaults (p2_puzzle_id, name, threshold)
+        VALUES ((SELECT id FROM p2_puzzles WHERE hash = ?), ?, ?);
+        ",
+        hash,
+        hash,
+        name,
+        vault.threshold,
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    for (index, key) in vault.public_keys.iter().enumerate() {
+        let index = u32::try_from(index)?;
+        let key = key.to_bytes();
+        let key = key.as_ref();
+
+        query!(
+            "
+            INSERT OR IGNORE INTO p2_vault_keys (p2_vault_id, key_index, key)
+            VALUES ((
+                SELECT p2_vaults.id FROM p2_vaults
+                INNER JOIN p2_puzzles ON p2_puzzles.id = p2_vaults.p2_puzzle_id
+                WHERE p2_puzzles.hash = ?
+            ), ?, ?)
+            ",
+            hash,
+            index,
+            key,
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(p2_puzzle_hash)
+}
@@ -1,5 +1,5 @@
 use aes_gcm::{AeadCore, Aes256Gcm, Key, KeyInit, Nonce, aead::Aead};
-use argon2::Argon2;
+use argon2::{Algorithm, Argon2, Params, Version};
 use rand::{CryptoRng, Rng};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_with::{Bytes, serde_as};
@@ -15,11 +15,62 @@ pub struct Encrypted {
     pub nonce: Vec<u8>,
     #[serde_as(as = "Bytes")]
     pub salt: [u8; 32],
+    pub kdf: KdfParams,
 }
 
-fn encryption_key(password: &[u8], salt: &[u8]) -> Result<Key<Aes256Gcm>, KeychainError> {
+/// The key derivation function used to turn a password into an encryption key.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum KdfAlgorithm {
+    #[default]
+    Argon2id,
+}
+
+/// The algorithm and cost parameters an entry was encrypted with, so that they can be
+/// changed later without breaking existing keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub algorithm: KdfAlgorithm,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            algorithm: KdfAlgorithm::Argon2id,
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Checks that the cost parameters are within the bounds the algorithm supports.
+    pub fn validate(&self) -> Result<(), KeychainError> {
+        self.argon2().map(|_| ())
+    }
+
+    fn argon2(&self) -> Result<Argon2<'static>, KeychainError> {
+        match self.algorithm {
+            KdfAlgorithm::Argon2id => {
+                let params = Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+                    .map_err(KeychainError::Argon2)?;
+                Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+            }
+        }
+    }
+}
+
+fn encryption_key(
+    password: &[u8],
+    salt: &[u8],
+    kdf: &KdfParams,
+) -> Result<Key<Aes256Gcm>, KeychainError> {
     let mut key_material = [0; 32];
-    Argon2::default()
+    kdf.argon2()?
         .hash_password_into(password, salt, &mut key_material)
         .map_err(KeychainError::Argon2)?;
     Ok(*Key::<Aes256Gcm>::from_slice(&key_material))
@@ -27,11 +78,12 @@ fn encryption_key(password: &[u8], salt: &[u8]) -> Result<Key<Aes256Gcm>, Keycha
 
 pub fn encrypt(
     password: &[u8],
+    kdf: KdfParams,
     rng: &mut (impl CryptoRng + Rng),
     data: &impl Serialize,
 ) -> Result<Encrypted, KeychainError> {
     let salt: [u8; 32] = rng.r#gen();
-    let key = encryption_key(password, &salt)?;
+    let key = encryption_key(password, &salt, &kdf)?;
     let cipher = Aes256Gcm::new(&key);
     let nonce = Aes256Gcm::generate_nonce(rng);
 
@@ -44,6 +96,7 @@ pub fn encrypt(
         ciphertext,
         nonce: nonce.to_vec(),
         salt,
+        kdf,
     })
 }
 
@@ -51,7 +104,7 @@ pub fn decrypt<T>(encrypted: &Encrypted, password: &[u8]) -> Result<T, KeychainE
 where
     T: DeserializeOwned,
 {
-    let key = encryption_key(password, &encrypted.salt)?;
+    let key = encryption_key(password, &encrypted.salt, &encrypted.kdf)?;
     let cipher = Aes256Gcm::new(&key);
 
     let nonce = Nonce::from_slice(&encrypted.nonce);
@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::ShamirError;
+
 #[derive(Debug, Error)]
 pub enum KeychainError {
     #[error("Encoding error: {0}")]
@@ -22,4 +24,25 @@ pub enum KeychainError {
 
     #[error("Key already exists")]
     KeyExists,
+
+    #[error("Key not found")]
+    KeyNotFound,
+
+    #[error("Key data doesn't match fingerprint {0}")]
+    FingerprintMismatch(u32),
+
+    #[error("Key has no secrets")]
+    NoSecrets,
+
+    #[error("Unsupported format version {0}")]
+    UnsupportedVersion(u32),
+
+    #[error("Not a valid archive")]
+    InvalidArchive,
+
+    #[error("Key has no mnemonic")]
+    NoMnemonic,
+
+    #[error("Shamir error: {0}")]
+    Shamir(#[from] ShamirError),
 }
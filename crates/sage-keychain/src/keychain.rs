@@ -6,15 +6,43 @@ use rand::SeedableRng;
 use rand_chacha::ChaCha20Rng;
 
 use crate::{
-    KeychainError,
+    DEFAULT_ITERATION_EXPONENT, KdfParams, KeychainError, Share, ShareGroup,
     encrypt::{decrypt, encrypt},
     key_data::{KeyData, SecretKeyData},
+    legacy::migrate_legacy,
+    shamir::split_secret,
 };
 
+/// Identifies a versioned keychain file. Files without it are in the legacy format.
+const MAGIC: &[u8; 8] = b"SAGEKEYC";
+
+/// The current version of the keychain file format.
+const VERSION: u32 = 1;
+
+/// Identifies a password encrypted archive, such as a wallet backup.
+const ARCHIVE_MAGIC: &[u8; 8] = b"SAGEARCH";
+
+/// The current version of the archive format.
+const ARCHIVE_VERSION: u32 = 1;
+
 #[derive(Debug)]
 pub struct Keychain {
     rng: ChaCha20Rng,
     keys: HashMap<u32, KeyData>,
+    kdf: KdfParams,
+    migrated: bool,
+}
+
+// The copy gets its own RNG, so that it never produces the same nonces as the original.
+impl Clone for Keychain {
+    fn clone(&self) -> Self {
+        Self {
+            rng: ChaCha20Rng::from_entropy(),
+            keys: self.keys.clone(),
+            kdf: self.kdf,
+            migrated: self.migrated,
+        }
+    }
 }
 
 impl Default for Keychain {
@@ -22,21 +50,124 @@ impl Default for Keychain {
         Self {
             rng: ChaCha20Rng::from_entropy(),
             keys: HashMap::default(),
+            kdf: KdfParams::default(),
+            migrated: false,
         }
     }
 }
 
 impl Keychain {
+    /// Parses a keychain file, upgrading it if it's in the legacy format.
     pub fn from_bytes(data: &[u8]) -> Result<Self, KeychainError> {
-        let keys = bincode::deserialize(data)?;
+        let (keys, migrated) = if let Some(data) = data.strip_prefix(MAGIC) {
+            let Some((version, body)) = data.split_first_chunk::<4>() else {
+                return Err(KeychainError::UnsupportedVersion(0));
+            };
+
+            let version = u32::from_le_bytes(*version);
+
+            if version != VERSION {
+                return Err(KeychainError::UnsupportedVersion(version));
+            }
+
+            (bincode::deserialize(body)?, false)
+        } else {
+            (migrate_legacy(data)?, true)
+        };
+
         Ok(Self {
-            rng: ChaCha20Rng::from_entropy(),
             keys,
+            migrated,
+            ..Default::default()
         })
     }
 
     pub fn to_bytes(&self) -> Result<Vec<u8>, KeychainError> {
-        Ok(bincode::serialize(&self.keys)?)
+        let mut data = MAGIC.to_vec();
+        data.extend_from_slice(&VERSION.to_le_bytes());
+        data.extend(bincode::serialize(&self.keys)?);
+        Ok(data)
+    }
+
+    /// Whether the keychain was read from an older file format, and should be saved again.
+    pub fn was_migrated(&self) -> bool {
+        self.migrated
+    }
+
+    /// Sets the cost parameters used to encrypt new secrets, and secrets that are re-encrypted
+    /// with a new password. Existing entries keep the parameters they were encrypted with.
+    pub fn set_kdf_params(&mut self, kdf: KdfParams) -> Result<(), KeychainError> {
+        kdf.validate()?;
+        self.kdf = kdf;
+        Ok(())
+    }
+
+    /// Serializes the entries for the given keys as they're stored, so secrets stay encrypted
+    /// with their own passwords.
+    pub fn export_keys(&self, fingerprints: &[u32]) -> Result<Vec<u8>, KeychainError> {
+        let keys: HashMap<u32, &KeyData> = fingerprints
+            .iter()
+            .filter_map(|fingerprint| Some((*fingerprint, self.keys.get(fingerprint)?)))
+            .collect();
+
+        Ok(bincode::serialize(&keys)?)
+    }
+
+    /// Adds the entries exported by [`Keychain::export_keys`], skipping keys that already exist.
+    /// Returns the fingerprints of every key in the export.
+    pub fn import_keys(&mut self, data: &[u8]) -> Result<Vec<u32>, KeychainError> {
+        let keys: HashMap<u32, KeyData> = bincode::deserialize(data)?;
+        let mut fingerprints = Vec::with_capacity(keys.len());
+
+        // The fingerprint is only a lookup key, so it's recomputed before anything is imported
+        for (&fingerprint, key_data) in &keys {
+            let (KeyData::Public { master_pk } | KeyData::Secret { master_pk, .. }) = key_data;
+
+            if PublicKey::from_bytes(master_pk)?.get_fingerprint() != fingerprint {
+                return Err(KeychainError::FingerprintMismatch(fingerprint));
+            }
+        }
+
+        for (fingerprint, key_data) in keys {
+            self.keys.entry(fingerprint).or_insert(key_data);
+            fingerprints.push(fingerprint);
+        }
+
+        fingerprints.sort_unstable();
+
+        Ok(fingerprints)
+    }
+
+    /// Encrypts arbitrary data with a password, using the current KDF parameters.
+    pub fn encrypt_archive(
+        &mut self,
+        password: &[u8],
+        data: &[u8],
+    ) -> Result<Vec<u8>, KeychainError> {
+        let encrypted = encrypt(password, self.kdf, &mut self.rng, &data)?;
+
+        let mut archive = ARCHIVE_MAGIC.to_vec();
+        archive.extend_from_slice(&ARCHIVE_VERSION.to_le_bytes());
+        archive.extend(bincode::serialize(&encrypted)?);
+        Ok(archive)
+    }
+
+    /// Decrypts an archive created by [`Keychain::encrypt_archive`].
+    pub fn decrypt_archive(archive: &[u8], password: &[u8]) -> Result<Vec<u8>, KeychainError> {
+        let Some((version, body)) = archive
+            .strip_prefix(ARCHIVE_MAGIC)
+            .and_then(<[u8]>::split_first_chunk::<4>)
+        else {
+            return Err(KeychainError::InvalidArchive);
+        };
+
+        let version = u32::from_le_bytes(*version);
+
+        if version != ARCHIVE_VERSION {
+            return Err(KeychainError::UnsupportedVersion(version));
+        }
+
+        decrypt(&bincode::deserialize(body)?, password)
     }
 
     pub fn contains(&self, fingerprint: u32) -> bool {
@@ -100,6 +231,109 @@ impl Keychain {
         }
     }
 
+    /// Checks whether the password decrypts the key's secrets, without returning them.
+    pub fn verify_password(
+        &self,
+        fingerprint: u32,
+        password: &[u8],
+    ) -> Result<bool, KeychainError> {
+        match self.keys.get(&fingerprint) {
+            None => Err(KeychainError::KeyNotFound),
+            Some(KeyData::Public { .. }) => Err(KeychainError::NoSecrets),
+            Some(KeyData::Secret { encrypted, .. }) => {
+                match decrypt::<SecretKeyData>(encrypted, password) {
+                    Ok(_) => Ok(true),
+                    Err(KeychainError::Decrypt) => Ok(false),
+                    Err(error) => Err(error),
+                }
+            }
+        }
+    }
+
+    /// Splits the mnemonic entropy of a key into Shamir share phrases, encrypted with the
+    /// passphrase. Returns the phrases of each group.
+    pub fn split_mnemonic(
+        &mut self,
+        fingerprint: u32,
+        password: &[u8],
+        passphrase: &[u8],
+        group_threshold: u8,
+        groups: &[ShareGroup],
+    ) -> Result<Vec<Vec<String>>, KeychainError> {
+        let entropy = match self.keys.get(&fingerprint) {
+            None => return Err(KeychainError::KeyNotFound),
+            Some(KeyData::Public { .. }) => return Err(KeychainError::NoSecrets),
+            Some(KeyData::Secret { entropy: false, .. }) => {
+                return Err(KeychainError::NoMnemonic);
+            }
+            Some(KeyData::Secret { encrypted, .. }) => {
+                decrypt::<SecretKeyData>(encrypted, password)?.0
+            }
+        };
+
+        let groups = split_secret(
+            &entropy,
+            passphrase,
+            group_threshold,
+            groups,
+            DEFAULT_ITERATION_EXPONENT,
+            &mut self.rng,
+        )?;
+
+        Ok(groups
+            .iter()
+            .map(|shares| shares.iter().map(Share::to_mnemonic).collect())
+            .collect())
+    }
+
+    /// Re-encrypts the secrets of a key with a new password, or of every key that has secrets
+    /// if no fingerprint is given. Nothing is changed unless all of them can be decrypted with
+    /// the old password. Returns the number of keys that were re-encrypted.
+    pub fn change_password(
+        &mut self,
+        fingerprint: Option<u32>,
+        old_password: &[u8],
+        new_password: &[u8],
+    ) -> Result<usize, KeychainError> {
+        let fingerprints: Vec<u32> = match fingerprint {
+            Some(fingerprint) => match self.keys.get(&fingerprint) {
+                None => return Err(KeychainError::KeyNotFound),
+                Some(KeyData::Public { .. }) => return Err(KeychainError::NoSecrets),
+                Some(KeyData::Secret { .. }) => vec![fingerprint],
+            },
+            None => self
+                .keys
+                .iter()
+                .filter(|(_, key_data)| matches!(key_data, KeyData::Secret { .. }))
+                .map(|(fingerprint, _)| *fingerprint)
+                .collect(),
+        };
+
+        let mut reencrypted = Vec::with_capacity(fingerprints.len());
+
+        for fingerprint in fingerprints {
+            let Some(KeyData::Secret { encrypted, .. }) = self.keys.get(&fingerprint) else {
+                continue;
+            };
+
+            let data = decrypt::<SecretKeyData>(encrypted, old_password)?;
+            reencrypted.push((
+                fingerprint,
+                encrypt(new_password, self.kdf, &mut self.rng, &data)?,
+            ));
+        }
+
+        let count = reencrypted.len();
+
+        for (fingerprint, new_encrypted) in reencrypted {
+            if let Some(KeyData::Secret { encrypted, .. }) = self.keys.get_mut(&fingerprint) {
+                *encrypted = new_encrypted;
+            }
+        }
+
+        Ok(count)
+    }
+
     pub fn add_public_key(&mut self, master_pk: &PublicKey) -> Result<u32, KeychainError> {
         let fingerprint = master_pk.get_fingerprint();
 
@@ -131,6 +365,7 @@ impl Keychain {
 
         let encrypted = encrypt(
             password,
+            self.kdf,
             &mut self.rng,
             &SecretKeyData(master_sk.to_bytes().to_vec()),
         )?;
@@ -162,7 +397,7 @@ impl Keychain {
             return Err(KeychainError::KeyExists);
         }
 
-        let encrypted = encrypt(password, &mut self.rng, &SecretKeyData(entropy))?;
+        let encrypted = encrypt(password, self.kdf, &mut self.rng, &SecretKeyData(entropy))?;
 
         self.keys.insert(
             fingerprint,
@@ -176,3 +411,170 @@ impl Keychain {
         Ok(fingerprint)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::mnemonic_from_shares;
+
+    use super::*;
+
+    #[test]
+    fn test_change_password() -> Result<(), KeychainError> {
+        let mut keychain = Keychain::default();
+
+        let first = keychain.add_secret_key(&SecretKey::from_seed(&[1; 32]), b"old")?;
+        let second = keychain.add_secret_key(&SecretKey::from_seed(&[2; 32]), b"other")?;
+
+        // The second key doesn't decrypt with the old password, so nothing is changed
+        assert!(matches!(
+            keychain.change_password(None, b"old", b"new"),
+            Err(KeychainError::Decrypt)
+        ));
+        assert!(keychain.verify_password(first, b"old")?);
+
+        assert_eq!(keychain.change_password(Some(first), b"old", b"new")?, 1);
+        assert!(!keychain.verify_password(first, b"old")?);
+        assert!(keychain.verify_password(first, b"new")?);
+
+        assert_eq!(keychain.change_password(Some(second), b"other", b"new")?, 1);
+        assert_eq!(keychain.change_password(None, b"new", b"newer")?, 2);
+
+        let (_, secret_key) = keychain.extract_secrets(second, b"newer")?;
+        assert_eq!(secret_key, Some(SecretKey::from_seed(&[2; 32])));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_kdf_params() -> Result<(), KeychainError> {
+        let kdf = KdfParams {
+            memory_kib: 8 * 1024,
+            iterations: 3,
+            ..Default::default()
+        };
+
+        let mut keychain = Keychain::default();
+        let old = keychain.add_secret_key(&SecretKey::from_seed(&[1; 32]), b"password")?;
+
+        keychain.set_kdf_params(kdf)?;
+        let new = keychain.add_secret_key(&SecretKey::from_seed(&[2; 32]), b"password")?;
+
+        let keychain = Keychain::from_bytes(&keychain.to_bytes()?)?;
+
+        let kdf_of = |fingerprint| match keychain.keys.get(&fingerprint) {
+            Some(KeyData::Secret { encrypted, .. }) => encrypted.kdf,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(kdf_of(old), KdfParams::default());
+        assert_eq!(kdf_of(new), kdf);
+        assert!(keychain.verify_password(old, b"password")?);
+        assert!(keychain.verify_password(new, b"password")?);
+
+        assert!(matches!(
+            Keychain::default().set_kdf_params(KdfParams {
+                parallelism: 0,
+                ..Default::default()
+            }),
+            Err(KeychainError::Argon2(..))
+        ));
+
+        let mut unsupported = keychain.to_bytes()?;
+        unsupported[8..12].copy_from_slice(&2_u32.to_le_bytes());
+        assert!(matches!(
+            Keychain::from_bytes(&unsupported),
+            Err(KeychainError::UnsupportedVersion(2))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_import_keys() -> Result<(), KeychainError> {
+        let mut keychain = Keychain::default();
+        let secret = keychain.add_secret_key(&SecretKey::from_seed(&[1; 32]), b"password")?;
+        let public = keychain.add_public_key(&SecretKey::from_seed(&[2; 32]).public_key())?;
+        keychain.add_public_key(&SecretKey::from_seed(&[3; 32]).public_key())?;
+
+        let archive =
+            keychain.encrypt_archive(b"backup", &keychain.export_keys(&[secret, public])?)?;
+
+        assert!(matches!(
+            Keychain::decrypt_archive(&archive, b"wrong"),
+            Err(KeychainError::Decrypt)
+        ));
+
+        let data = Keychain::decrypt_archive(&archive, b"backup")?;
+
+        let mut restored = Keychain::default();
+        let mut expected = vec![secret, public];
+        expected.sort_unstable();
+
+        // Importing twice doesn't change anything
+        assert_eq!(restored.import_keys(&data)?, expected);
+        assert_eq!(restored.import_keys(&data)?, expected);
+        assert_eq!(restored.fingerprints().count(), 2);
+
+        let (_, secret_key) = restored.extract_secrets(secret, b"password")?;
+        assert_eq!(secret_key, Some(SecretKey::from_seed(&[1; 32])));
+        assert!(!restored.has_secret_key(public));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_keys_with_wrong_fingerprint() -> Result<(), KeychainError> {
+        let master_pk = SecretKey::from_seed(&[1; 32]).public_key();
+        let fingerprint = master_pk.get_fingerprint().wrapping_add(1);
+
+        let data = bincode::serialize(&HashMap::from([(
+            fingerprint,
+            KeyData::Public {
+                master_pk: master_pk.to_bytes(),
+            },
+        )]))?;
+
+        let mut keychain = Keychain::default();
+
+        assert!(matches!(
+            keychain.import_keys(&data),
+            Err(KeychainError::FingerprintMismatch(mismatch)) if mismatch == fingerprint
+        ));
+        assert_eq!(keychain.fingerprints().count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_mnemonic() -> Result<(), KeychainError> {
+        let mnemonic = Mnemonic::from_entropy(&[3; 32])?;
+
+        let mut keychain = Keychain::default();
+        let fingerprint = keychain.add_mnemonic(&mnemonic, b"password")?;
+        let secret_key = keychain.add_secret_key(&SecretKey::from_seed(&[1; 32]), b"password")?;
+
+        let groups = keychain.split_mnemonic(
+            fingerprint,
+            b"password",
+            b"passphrase",
+            1,
+            &[ShareGroup {
+                threshold: 2,
+                count: 3,
+            }],
+        )?;
+
+        assert_eq!(groups[0].len(), 3);
+        assert_eq!(
+            mnemonic_from_shares(&groups[0][1..], b"passphrase")?,
+            mnemonic
+        );
+
+        assert!(matches!(
+            keychain.split_mnemonic(secret_key, b"password", b"", 1, &[]),
+            Err(KeychainError::NoMnemonic)
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_with::{Bytes, serde_as};
+
+use crate::{
+    encrypt::{Encrypted, KdfParams},
+    key_data::KeyData,
+};
+
+/// The unversioned format used before the file header was introduced, where every entry was
+/// encrypted with the default Argon2 parameters without recording them.
+#[serde_as]
+#[derive(Serialize, Deserialize)]
+#[repr(u8)]
+enum LegacyKeyData {
+    Public {
+        #[serde_as(as = "Bytes")]
+        master_pk: [u8; 48],
+    },
+    Secret {
+        #[serde_as(as = "Bytes")]
+        master_pk: [u8; 48],
+        entropy: bool,
+        encrypted: LegacyEncrypted,
+    },
+}
+
+#[serde_as]
+#[derive(Serialize, Deserialize)]
+struct LegacyEncrypted {
+    #[serde_as(as = "Bytes")]
+    ciphertext: Vec<u8>,
+    #[serde_as(as = "Bytes")]
+    nonce: Vec<u8>,
+    #[serde_as(as = "Bytes")]
+    salt: [u8; 32],
+}
+
+impl From<LegacyKeyData> for KeyData {
+    fn from(value: LegacyKeyData) -> Self {
+        match value {
+            LegacyKeyData::Public { master_pk } => Self::Public { master_pk },
+            LegacyKeyData::Secret {
+                master_pk,
+                entropy,
+                encrypted,
+            } => Self::Secret {
+                master_pk,
+                entropy,
+                encrypted: Encrypted {
+                    ciphertext: encrypted.ciphertext,
+                    nonce: encrypted.nonce,
+                    salt: encrypted.salt,
+                    kdf: KdfParams::default(),
+                },
+            },
+        }
+    }
+}
+
+pub fn migrate_legacy(data: &[u8]) -> Result<HashMap<u32, KeyData>, bincode::Error> {
+    let keys: HashMap<u32, LegacyKeyData> = bincode::deserialize(data)?;
+
+    Ok(keys
+        .into_iter()
+        .map(|(fingerprint, key_data)| (fingerprint, key_data.into()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_wallet_sdk::prelude::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::{Keychain, KeychainError, encrypt::encrypt, key_data::SecretKeyData};
+
+    use super::*;
+
+    #[test]
+    fn test_migrate_legacy_format() -> Result<(), KeychainError> {
+        let master_sk = SecretKey::from_seed(&[1; 32]);
+        let master_pk = master_sk.public_key();
+        let fingerprint = master_pk.get_fingerprint();
+
+        let encrypted = encrypt(
+            b"password",
+            KdfParams::default(),
+            &mut ChaCha20Rng::seed_from_u64(0),
+            &SecretKeyData(master_sk.to_bytes().to_vec()),
+        )?;
+
+        let legacy = bincode::serialize(&HashMap::from([(
+            fingerprint,
+            LegacyKeyData::Secret {
+                master_pk: master_pk.to_bytes(),
+                entropy: false,
+                encrypted: LegacyEncrypted {
+                    ciphertext: encrypted.ciphertext,
+                    nonce: encrypted.nonce,
+                    salt: encrypted.salt,
+                },
+            },
+        )]))?;
+
+        let keychain = Keychain::from_bytes(&legacy)?;
+        assert!(keychain.was_migrated());
+
+        let (_, secret_key) = keychain.extract_secrets(fingerprint, b"password")?;
+        assert_eq!(secret_key, Some(master_sk));
+
+        let keychain = Keychain::from_bytes(&keychain.to_bytes()?)?;
+        assert!(!keychain.was_migrated());
+        assert!(keychain.verify_password(fingerprint, b"password")?);
+
+        Ok(())
+    }
+}
@@ -2,6 +2,10 @@ mod encrypt;
 mod error;
 mod key_data;
 mod keychain;
+mod legacy;
+mod shamir;
 
+pub use encrypt::{KdfAlgorithm, KdfParams};
 pub use error::*;
 pub use keychain::*;
+pub use shamir::*;
@@ -0,0 +1,739 @@
+//! Shamir secret sharing of mnemonic entropy, following the SLIP-39 share format.
+
+mod wordlist;
+
+use std::collections::BTreeMap;
+
+use bip39::Mnemonic;
+use hmac::{Hmac, Mac};
+use pbkdf2::pbkdf2_hmac;
+use rand::{CryptoRng, Rng};
+use sha2::Sha256;
+use thiserror::Error;
+
+use wordlist::WORDS;
+
+const RADIX_BITS: usize = 10;
+const RADIX_MASK: u32 = (1 << RADIX_BITS) - 1;
+const CHECKSUM_WORDS: usize = 3;
+const METADATA_WORDS: usize = 4 + CHECKSUM_WORDS;
+const MIN_SECRET_BYTES: usize = 16;
+const MIN_MNEMONIC_WORDS: usize = METADATA_WORDS + (MIN_SECRET_BYTES * 8).div_ceil(RADIX_BITS);
+const MAX_SHARE_COUNT: u8 = 16;
+const DIGEST_LENGTH: usize = 4;
+const DIGEST_INDEX: u8 = 254;
+const SECRET_INDEX: u8 = 255;
+const BASE_ITERATION_COUNT: u32 = 10_000;
+const ROUND_COUNT: u8 = 4;
+const CUSTOMIZATION: &[u8] = b"shamir";
+const CUSTOMIZATION_EXTENDABLE: &[u8] = b"shamir_extendable";
+const CHECKSUM_GENERATOR: [u32; 10] = [
+    0x00E0_E040,
+    0x01C1_C080,
+    0x0383_8100,
+    0x0707_0200,
+    0x0E0E_0009,
+    0x1C0C_2412,
+    0x3808_6C24,
+    0x3090_FC48,
+    0x21B1_F890,
+    0x03F3_F120,
+];
+
+/// The iteration exponent used for new shares, which is what hardware wallets use by default.
+pub const DEFAULT_ITERATION_EXPONENT: u8 = 1;
+
+const GF256: ([u8; 255], [u8; 256]) = gf256_tables();
+
+#[derive(Debug, Error)]
+pub enum ShamirError {
+    #[error("Word {0} is not in the share word list")]
+    UnknownWord(String),
+
+    #[error("Share has {0} words, but at least {MIN_MNEMONIC_WORDS} are required")]
+    InvalidLength(usize),
+
+    #[error("Share checksum is invalid")]
+    InvalidChecksum,
+
+    #[error("Share has invalid padding")]
+    InvalidPadding,
+
+    #[error("The secret must be an even number of bytes, and at least {MIN_SECRET_BYTES}")]
+    InvalidSecretLength,
+
+    #[error("Invalid threshold of {threshold} for {count} shares")]
+    InvalidThreshold { threshold: u8, count: u8 },
+
+    #[error("Invalid iteration exponent {0}")]
+    InvalidIterationExponent(u8),
+
+    #[error("Shares are from different backups")]
+    MismatchedShares,
+
+    #[error("Share {member_index} of group {group_index} was given more than once")]
+    DuplicateShare { group_index: u8, member_index: u8 },
+
+    #[error("{needed} complete groups are required, but only {found} were given")]
+    NotEnoughShares { needed: u8, found: u8 },
+
+    #[error("Shares don't recover a valid secret")]
+    InvalidDigest,
+}
+
+/// How many shares to create for a group, and how many of them are needed to recover it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShareGroup {
+    pub threshold: u8,
+    pub count: u8,
+}
+
+/// A single parsed share, including the parameters needed to combine it with the others.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Share {
+    pub identifier: u16,
+    pub extendable: bool,
+    pub iteration_exponent: u8,
+    pub group_index: u8,
+    pub group_threshold: u8,
+    pub group_count: u8,
+    pub member_index: u8,
+    pub member_threshold: u8,
+    pub value: Vec<u8>,
+}
+
+impl Share {
+    /// Parses a share phrase, validating its checksum and parameters.
+    pub fn from_mnemonic(mnemonic: &str) -> Result<Self, ShamirError> {
+        let indices = mnemonic
+            .split_whitespace()
+            .map(|word| {
+                let lowercase = word.to_lowercase();
+                WORDS
+                    .binary_search(&lowercase.as_str())
+                    .map(|index| index as u32)
+                    .map_err(|_| ShamirError::UnknownWord(word.to_string()))
+            })
+            .collect::<Result<Vec<u32>, ShamirError>>()?;
+
+        if indices.len() < MIN_MNEMONIC_WORDS {
+            return Err(ShamirError::InvalidLength(indices.len()));
+        }
+
+        let id_exp = (indices[0] << RADIX_BITS) | indices[1];
+        let extendable = (id_exp >> 4) & 1 == 1;
+
+        if rs1024_polymod(customization(extendable), &indices) != 1 {
+            return Err(ShamirError::InvalidChecksum);
+        }
+
+        let params = (indices[2] << RADIX_BITS) | indices[3];
+        let group_threshold = ((params >> 12) & 15) as u8 + 1;
+        let group_count = ((params >> 8) & 15) as u8 + 1;
+
+        if group_threshold > group_count {
+            return Err(ShamirError::InvalidThreshold {
+                threshold: group_threshold,
+                count: group_count,
+            });
+        }
+
+        Ok(Self {
+            identifier: (id_exp >> 5) as u16,
+            extendable,
+            iteration_exponent: (id_exp & 15) as u8,
+            group_index: (params >> 16) as u8,
+            group_threshold,
+            group_count,
+            member_index: ((params >> 4) & 15) as u8,
+            member_threshold: (params & 15) as u8 + 1,
+            value: words_to_value(&indices[4..indices.len() - CHECKSUM_WORDS])?,
+        })
+    }
+
+    pub fn to_mnemonic(&self) -> String {
+        let id_exp = (u32::from(self.identifier) << 5)
+            | (u32::from(self.extendable) << 4)
+            | u32::from(self.iteration_exponent);
+
+        let params = (u32::from(self.group_index) << 16)
+            | (u32::from(self.group_threshold - 1) << 12)
+            | (u32::from(self.group_count - 1) << 8)
+            | (u32::from(self.member_index) << 4)
+            | u32::from(self.member_threshold - 1);
+
+        let mut indices = vec![
+            id_exp >> RADIX_BITS,
+            id_exp & RADIX_MASK,
+            params >> RADIX_BITS,
+            params & RADIX_MASK,
+        ];
+        indices.extend(value_to_words(&self.value));
+
+        let mut values = indices.clone();
+        values.extend([0; CHECKSUM_WORDS]);
+        let checksum = rs1024_polymod(customization(self.extendable), &values) ^ 1;
+
+        for i in (0..CHECKSUM_WORDS).rev() {
+            indices.push((checksum >> (RADIX_BITS * i)) & RADIX_MASK);
+        }
+
+        indices
+            .into_iter()
+            .map(|index| WORDS[index as usize])
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Encrypts the secret with the passphrase, and splits it into groups of shares. Any
+/// `group_threshold` groups can recover it, once enough shares of each are combined.
+pub fn split_secret(
+    secret: &[u8],
+    passphrase: &[u8],
+    group_threshold: u8,
+    groups: &[ShareGroup],
+    iteration_exponent: u8,
+    rng: &mut (impl CryptoRng + Rng),
+) -> Result<Vec<Vec<Share>>, ShamirError> {
+    if secret.len() < MIN_SECRET_BYTES || secret.len() % 2 != 0 {
+        return Err(ShamirError::InvalidSecretLength);
+    }
+
+    if iteration_exponent > 15 {
+        return Err(ShamirError::InvalidIterationExponent(iteration_exponent));
+    }
+
+    let group_count = u8::try_from(groups.len()).unwrap_or(u8::MAX);
+    check_threshold(group_threshold, group_count)?;
+
+    for group in groups {
+        check_threshold(group.threshold, group.count)?;
+
+        // A single share would be enough to recover the group, so it should just be 1-of-1
+        if group.threshold == 1 && group.count > 1 {
+            return Err(ShamirError::InvalidThreshold {
+                threshold: group.threshold,
+                count: group.count,
+            });
+        }
+    }
+
+    let identifier = rng.r#gen::<u16>() & 0x7FFF;
+    let encrypted = feistel(
+        secret,
+        passphrase,
+        iteration_exponent,
+        identifier,
+        true,
+        false,
+    );
+    let group_secrets = split_shares(group_threshold, group_count, &encrypted, rng);
+
+    Ok(group_secrets
+        .into_iter()
+        .zip(groups)
+        .map(|((group_index, group_secret), group)| {
+            split_shares(group.threshold, group.count, &group_secret, rng)
+                .into_iter()
+                .map(|(member_index, value)| Share {
+                    identifier,
+                    extendable: true,
+                    iteration_exponent,
+                    group_index,
+                    group_threshold,
+                    group_count,
+                    member_index,
+                    member_threshold: group.threshold,
+                    value,
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// Recovers the secret from enough shares of enough groups. Incomplete groups are ignored.
+pub fn combine_shares(shares: &[Share], passphrase: &[u8]) -> Result<Vec<u8>, ShamirError> {
+    let Some(first) = shares.first() else {
+        return Err(ShamirError::NotEnoughShares {
+            needed: 1,
+            found: 0,
+        });
+    };
+
+    let encrypted = recover_encrypted_secret(shares)?;
+
+    Ok(feistel(
+        &encrypted,
+        passphrase,
+        first.iteration_exponent,
+        first.identifier,
+        first.extendable,
+        true,
+    ))
+}
+
+/// Whether the shares are enough to recover the secret, without needing the passphrase.
+pub fn is_complete(shares: &[Share]) -> bool {
+    recover_encrypted_secret(shares).is_ok()
+}
+
+/// Parses the share phrases and recovers the mnemonic whose entropy they encode.
+pub fn mnemonic_from_shares(
+    mnemonics: &[impl AsRef<str>],
+    passphrase: &[u8],
+) -> Result<Mnemonic, crate::KeychainError> {
+    let shares = mnemonics
+        .iter()
+        .map(|mnemonic| Share::from_mnemonic(mnemonic.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let entropy = combine_shares(&shares, passphrase)?;
+
+    Ok(Mnemonic::from_entropy(&entropy)?)
+}
+
+fn check_threshold(threshold: u8, count: u8) -> Result<(), ShamirError> {
+    if threshold == 0 || threshold > count || count > MAX_SHARE_COUNT {
+        return Err(ShamirError::InvalidThreshold { threshold, count });
+    }
+
+    Ok(())
+}
+
+fn recover_encrypted_secret(shares: &[Share]) -> Result<Vec<u8>, ShamirError> {
+    let Some(first) = shares.first() else {
+        return Err(ShamirError::NotEnoughShares {
+            needed: 1,
+            found: 0,
+        });
+    };
+
+    if shares.iter().any(|share| {
+        share.identifier != first.identifier
+            || share.extendable != first.extendable
+            || share.iteration_exponent != first.iteration_exponent
+            || share.group_threshold != first.group_threshold
+            || share.group_count != first.group_count
+            || share.value.len() != first.value.len()
+    }) {
+        return Err(ShamirError::MismatchedShares);
+    }
+
+    let mut groups: BTreeMap<u8, Vec<&Share>> = BTreeMap::new();
+
+    for share in shares {
+        let members = groups.entry(share.group_index).or_default();
+
+        if members
+            .iter()
+            .any(|member| member.member_threshold != share.member_threshold)
+        {
+            return Err(ShamirError::MismatchedShares);
+        }
+
+        if members
+            .iter()
+            .any(|member| member.member_index == share.member_index)
+        {
+            return Err(ShamirError::DuplicateShare {
+                group_index: share.group_index,
+                member_index: share.member_index,
+            });
+        }
+
+        members.push(share);
+    }
+
+    let mut group_secrets = Vec::new();
+
+    for (group_index, members) in groups {
+        let threshold = usize::from(members[0].member_threshold);
+
+        if members.len() < threshold || group_secrets.len() == usize::from(first.group_threshold) {
+            continue;
+        }
+
+        let member_shares: Vec<(u8, Vec<u8>)> = members
+            .into_iter()
+            .take(threshold)
+            .map(|member| (member.member_index, member.value.clone()))
+            .collect();
+
+        group_secrets.push((group_index, recover_secret(&member_shares)?));
+    }
+
+    if group_secrets.len() < usize::from(first.group_threshold) {
+        return Err(ShamirError::NotEnoughShares {
+            needed: first.group_threshold,
+            found: group_secrets.len() as u8,
+        });
+    }
+
+    recover_secret(&group_secrets)
+}
+
+fn split_shares(
+    threshold: u8,
+    count: u8,
+    secret: &[u8],
+    rng: &mut (impl CryptoRng + Rng),
+) -> Vec<(u8, Vec<u8>)> {
+    if threshold == 1 {
+        return (0..count).map(|index| (index, secret.to_vec())).collect();
+    }
+
+    let random_count = threshold - 2;
+
+    let mut shares: Vec<(u8, Vec<u8>)> = (0..random_count)
+        .map(|index| (index, random_bytes(rng, secret.len())))
+        .collect();
+
+    let random_part = random_bytes(rng, secret.len() - DIGEST_LENGTH);
+    let mut digest_share = digest(&random_part, secret).to_vec();
+    digest_share.extend(&random_part);
+
+    let mut base_shares = shares.clone();
+    base_shares.push((DIGEST_INDEX, digest_share));
+    base_shares.push((SECRET_INDEX, secret.to_vec()));
+
+    for index in random_count..count {
+        shares.push((index, interpolate(&base_shares, index)));
+    }
+
+    shares
+}
+
+fn recover_secret(shares: &[(u8, Vec<u8>)]) -> Result<Vec<u8>, ShamirError> {
+    if let [(_, secret)] = shares {
+        return Ok(secret.clone());
+    }
+
+    let secret = interpolate(shares, SECRET_INDEX);
+    let digest_share = interpolate(shares, DIGEST_INDEX);
+    let (expected, random_part) = digest_share.split_at(DIGEST_LENGTH);
+
+    if digest(random_part, &secret) != expected {
+        return Err(ShamirError::InvalidDigest);
+    }
+
+    Ok(secret)
+}
+
+fn random_bytes(rng: &mut (impl CryptoRng + Rng), length: usize) -> Vec<u8> {
+    let mut bytes = vec![0; length];
+    rng.fill(&mut bytes[..]);
+    bytes
+}
+
+fn digest(random_part: &[u8], secret: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(random_part).expect("HMAC accepts keys of any length");
+    mac.update(secret);
+
+    let mut digest = [0; DIGEST_LENGTH];
+    digest.copy_from_slice(&mac.finalize().into_bytes()[..DIGEST_LENGTH]);
+    digest
+}
+
+/// Evaluates the polynomial that passes through the shares at `x`, over GF(256).
+fn interpolate(shares: &[(u8, Vec<u8>)], x: u8) -> Vec<u8> {
+    if let Some((_, value)) = shares.iter().find(|(index, _)| *index == x) {
+        return value.clone();
+    }
+
+    let (exp, log) = &GF256;
+
+    let log_product = shares
+        .iter()
+        .map(|(index, _)| u32::from(log[usize::from(index ^ x)]))
+        .sum::<u32>()
+        % 255;
+
+    let mut result = vec![0; shares[0].1.len()];
+
+    for (index, value) in shares {
+        let log_basis = (u32::from(log[usize::from(index ^ x)])
+            + shares
+                .iter()
+                .map(|(other, _)| u32::from(log[usize::from(index ^ other)]))
+                .sum::<u32>())
+            % 255;
+
+        for (output, &byte) in result.iter_mut().zip(value) {
+            if byte != 0 {
+                let power =
+                    (u32::from(log[usize::from(byte)]) + log_product + 255 - log_basis) % 255;
+                *output ^= exp[power as usize];
+            }
+        }
+    }
+
+    result
+}
+
+const fn gf256_tables() -> ([u8; 255], [u8; 256]) {
+    let mut exp = [0; 255];
+    let mut log = [0; 256];
+    let mut poly: u16 = 1;
+    let mut i = 0;
+
+    while i < 255 {
+        exp[i] = poly as u8;
+        log[poly as usize] = i as u8;
+
+        // Multiply by the generator 3, reducing by x^8 + x^4 + x^3 + x + 1
+        poly ^= poly << 1;
+
+        if poly & 0x100 != 0 {
+            poly ^= 0x11B;
+        }
+
+        i += 1;
+    }
+
+    (exp, log)
+}
+
+/// Encrypts or decrypts the secret with a four round Feistel network, using PBKDF2 as the
+/// round function.
+fn feistel(
+    secret: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+    decrypt: bool,
+) -> Vec<u8> {
+    let (left, right) = secret.split_at(secret.len() / 2);
+    let (mut left, mut right) = (left.to_vec(), right.to_vec());
+
+    let mut salt = Vec::new();
+
+    if !extendable {
+        salt.extend_from_slice(CUSTOMIZATION);
+        salt.extend_from_slice(&identifier.to_be_bytes());
+    }
+
+    let iterations = (BASE_ITERATION_COUNT << iteration_exponent) / u32::from(ROUND_COUNT);
+
+    let rounds: Vec<u8> = if decrypt {
+        (0..ROUND_COUNT).rev().collect()
+    } else {
+        (0..ROUND_COUNT).collect()
+    };
+
+    for round in rounds {
+        let mut password = vec![round];
+        password.extend_from_slice(passphrase);
+
+        let mut round_salt = salt.clone();
+        round_salt.extend_from_slice(&right);
+
+        let mut output = vec![0; right.len()];
+        pbkdf2_hmac::<Sha256>(&password, &round_salt, iterations, &mut output);
+
+        let next = left.iter().zip(output).map(|(a, b)| a ^ b).collect();
+        left = right;
+        right = next;
+    }
+
+    right.extend(left);
+    right
+}
+
+fn customization(extendable: bool) -> &'static [u8] {
+    if extendable {
+        CUSTOMIZATION_EXTENDABLE
+    } else {
+        CUSTOMIZATION
+    }
+}
+
+fn rs1024_polymod(customization: &[u8], values: &[u32]) -> u32 {
+    let mut checksum: u32 = 1;
+
+    for value in customization
+        .iter()
+        .map(|&byte| u32::from(byte))
+        .chain(values.iter().copied())
+    {
+        let top = checksum >> 20;
+        checksum = ((checksum & 0xF_FFFF) << RADIX_BITS) ^ value;
+
+        for (i, generator) in CHECKSUM_GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+
+    checksum
+}
+
+/// Packs the bytes into 10 bit words, with zero padding at the start.
+fn value_to_words(value: &[u8]) -> Vec<u32> {
+    let word_count = (value.len() * 8).div_ceil(RADIX_BITS);
+    let mut words = Vec::with_capacity(word_count);
+    let mut accumulator: u32 = 0;
+    let mut bits = word_count * RADIX_BITS - value.len() * 8;
+
+    for &byte in value {
+        accumulator = (accumulator << 8) | u32::from(byte);
+        bits += 8;
+
+        while bits >= RADIX_BITS {
+            bits -= RADIX_BITS;
+            words.push((accumulator >> bits) & RADIX_MASK);
+        }
+
+        accumulator &= (1 << bits) - 1;
+    }
+
+    words
+}
+
+fn words_to_value(words: &[u32]) -> Result<Vec<u8>, ShamirError> {
+    let total_bits = words.len() * RADIX_BITS;
+    let mut padding = total_bits % 16;
+
+    if padding > 8 {
+        return Err(ShamirError::InvalidPadding);
+    }
+
+    let mut bytes = Vec::with_capacity((total_bits - padding) / 8);
+    let mut accumulator: u32 = 0;
+    let mut bits = 0;
+
+    for &word in words {
+        accumulator = (accumulator << RADIX_BITS) | word;
+        bits += RADIX_BITS;
+
+        if padding > 0 {
+            if accumulator >> (bits - padding) != 0 {
+                return Err(ShamirError::InvalidPadding);
+            }
+
+            bits -= padding;
+            accumulator &= (1 << bits) - 1;
+            padding = 0;
+        }
+
+        while bits >= 8 {
+            bits -= 8;
+            bytes.push((accumulator >> bits) as u8);
+            accumulator &= (1 << bits) - 1;
+        }
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use super::*;
+
+    fn combine(mnemonics: &[&str], passphrase: &[u8]) -> Result<String, ShamirError> {
+        let shares = mnemonics
+            .iter()
+            .map(|mnemonic| Share::from_mnemonic(mnemonic))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(hex::encode(combine_shares(&shares, passphrase)?))
+    }
+
+    #[test]
+    fn test_slip39_vectors() -> Result<(), ShamirError> {
+        assert_eq!(
+            combine(
+                &[
+                    "duckling enlarge academic academic agency result length solution fridge kidney coal piece deal husband erode duke ajar critical decision keyboard"
+                ],
+                b"TREZOR"
+            )?,
+            "bb54aac4b89dc868ba37d9cc21b2cece"
+        );
+
+        let shares = [
+            "shadow pistol academic always adequate wildlife fancy gross oasis cylinder mustang wrist rescue view short owner flip making coding armed",
+            "shadow pistol academic acid actress prayer class unknown daughter sweater depict flip twice unkind craft early superior advocate guest smoking",
+        ];
+
+        assert_eq!(
+            combine(&shares, b"TREZOR")?,
+            "b43ceb7e57a0ea8766221624d01b0864"
+        );
+
+        assert!(matches!(
+            combine(&shares[..1], b"TREZOR"),
+            Err(ShamirError::NotEnoughShares { .. })
+        ));
+
+        let mut corrupted = shares[0].to_string();
+        corrupted.replace_range(..6, "sister");
+        assert!(matches!(
+            Share::from_mnemonic(&corrupted),
+            Err(ShamirError::InvalidChecksum)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_and_combine_groups() -> Result<(), ShamirError> {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let secret = [7; 32];
+
+        let groups = split_secret(
+            &secret,
+            b"passphrase",
+            2,
+            &[
+                ShareGroup {
+                    threshold: 1,
+                    count: 1,
+                },
+                ShareGroup {
+                    threshold: 2,
+                    count: 3,
+                },
+                ShareGroup {
+                    threshold: 3,
+                    count: 5,
+                },
+            ],
+            0,
+            &mut rng,
+        )?;
+
+        let parse = |group: usize, member: usize| {
+            Share::from_mnemonic(&groups[group][member].to_mnemonic())
+        };
+
+        let second = parse(1, 2)?;
+        assert_eq!(second.group_index, 1);
+        assert_eq!(second.member_index, 2);
+        assert_eq!(second.member_threshold, 2);
+        assert_eq!(second.group_threshold, 2);
+
+        let shares = vec![parse(0, 0)?, parse(2, 4)?, parse(2, 0)?, parse(2, 2)?];
+        assert_eq!(combine_shares(&shares, b"passphrase")?, secret);
+
+        // The incomplete third group is ignored
+        let shares = vec![parse(1, 0)?, parse(0, 0)?, parse(2, 3)?, parse(1, 1)?];
+        assert!(!is_complete(&shares[..3]));
+        assert!(is_complete(&shares));
+        assert_eq!(combine_shares(&shares, b"passphrase")?, secret);
+        assert_ne!(combine_shares(&shares, b"wrong")?, secret);
+
+        assert!(matches!(
+            combine_shares(&[parse(1, 0)?, parse(1, 0)?], b""),
+            Err(ShamirError::DuplicateShare { .. })
+        ));
+
+        Ok(())
+    }
+}
@@ -67,9 +67,17 @@ pub fn generate_openapi() -> OpenApi {
         .schema_from::<sage_api::TransactionRecord>()
         .schema_from::<sage_api::PendingTransactionRecord>()
         .schema_from::<sage_api::DerivationRecord>()
+        .schema_from::<sage_api::DerivationGapRecord>()
+        .schema_from::<sage_api::VaultRecord>()
         .schema_from::<sage_api::PeerRecord>()
+        .schema_from::<sage_api::PeerDiscrepancyRecord>()
+        .schema_from::<sage_api::TrustedPeerRecord>()
+        .schema_from::<sage_api::SyncPhaseRecord>()
+        .schema_from::<sage_api::SyncProgressRecord>()
         .schema_from::<sage_api::KeyInfo>()
         .schema_from::<sage_api::SecretKeyInfo>()
+        .schema_from::<sage_api::KeyShareGroup>()
+        .schema_from::<sage_api::KeyShareInfo>()
         .schema_from::<sage_api::KeyKind>()
         .schema_from::<sage_api::NftData>()
         .schema_from::<sage_api::NftSpecialUseType>()
@@ -85,6 +93,14 @@ pub fn generate_openapi() -> OpenApi {
         .schema_from::<sage_api::TransactionInput>()
         .schema_from::<sage_api::TransactionOutput>()
         .schema_from::<sage_api::TransactionCoinRecord>()
+        .schema_from::<sage_api::SimulatedSpend>()
+        .schema_from::<sage_api::ConditionJson>()
+        .schema_from::<sage_api::BalanceChange>()
+        .schema_from::<sage_api::DecodedSpend>()
+        .schema_from::<sage_api::DecodedCoinKind>()
+        .schema_from::<sage_api::PuzzleLayer>()
+        .schema_from::<sage_api::DecodedCondition>()
+        .schema_from::<sage_api::DecodedOutput>()
         .schema_from::<sage_api::CoinSpendJson>()
         .schema_from::<sage_api::SpendBundleJson>()
         .schema_from::<sage_api::CoinJson>()
@@ -92,18 +108,29 @@ pub fn generate_openapi() -> OpenApi {
         .schema_from::<sage_api::OfferRecordStatus>()
         .schema_from::<sage_api::OfferSummary>()
         .schema_from::<sage_api::OfferAsset>()
+        .schema_from::<sage_api::OfferIssue>()
+        .schema_from::<sage_api::OfferIssueKind>()
         .schema_from::<sage_api::NftRoyalty>()
+        .schema_from::<sage_api::NftRoyaltyBreakdown>()
+        .schema_from::<sage_api::RoyaltyAmount>()
         .schema_from::<sage_api::OptionAssets>()
         .schema_from::<sage_api::Payment>()
         .schema_from::<sage_api::NftMint>()
         .schema_from::<sage_api::OfferAmount>()
         .schema_from::<sage_api::OptionAsset>()
         .schema_from::<sage_api::NetworkKind>()
+        .schema_from::<sage_api::ChangePolicy>()
         .schema_from::<sage_api::Id>()
         .schema_from::<sage_api::Action>()
         .schema_from::<sage_api::SendAction>()
         .schema_from::<sage_api::MintNftAction>()
         .schema_from::<sage_api::UpdateNftAction>()
+        .schema_from::<sage_api::IssueCatAction>()
+        .schema_from::<sage_api::RunTailAction>()
+        .schema_from::<sage_api::CreateDidAction>()
+        .schema_from::<sage_api::MintOptionAction>()
+        .schema_from::<sage_api::ExerciseOptionAction>()
+        .schema_from::<sage_api::SettleOfferAction>()
         .schema_from::<sage_api::FeeAction>()
         .schema_from::<sage_api::NewNftUri>()
         .schema_from::<sage_api::NftTransfer>();
@@ -12,15 +12,24 @@ use chia_wallet_sdk::{
         bls::master_to_wallet_unhardened,
         puzzle_types::{DeriveSynthetic, standard::StandardArgs},
     },
+    driver::decode_offer,
     prelude::*,
     test::PeerSimulator,
 };
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
 use rustls::crypto::aws_lc_rs::default_provider;
-use sage::Sage;
-use sage_api::{Amount, GetKey, GetPeers, GetSyncStatus, GetVersion, ImportKey, Login, SendXch};
+use sage::{Sage, json_spend};
+use sage_api::{
+    Action, AddNetwork, Amount, CancelOffer, CreateDidAction, CreateTransaction, DecodeCoinSpends,
+    DecodedCoinKind, ExportBackup, FarmBlocks, FeeAction, GetCats, GetDids, GetKey, GetNetworks,
+    GetOffers, GetPeers, GetSecretKey, GetSyncStatus, GetVersion, Id, ImportBackup, ImportKey,
+    IssueCatAction, Login, MakeOffer, MakeOfferResponse, MintToAddress, OfferAmount,
+    OfferIssueKind, PuzzleLayer, ReloadNetworks, SendAction, SendXch, SetNetwork,
+    SettleOfferAction, SimulateTransaction, UpdateNetwork, ValidateOffer,
+};
 use sage_api_macro::impl_endpoints;
+use sage_config::{Network, SIMULATOR};
 use sage_wallet::{SyncCommand, SyncEvent};
 use serde::{Serialize, de::DeserializeOwned};
 use tempfile::TempDir;
@@ -37,7 +46,7 @@ struct TestApp {
     sage: Arc<Mutex<Sage>>,
     router: Router<()>,
     rng: ChaCha8Rng,
-    sim: PeerSimulator,
+    sim: Arc<PeerSimulator>,
     events: mpsc::Receiver<SyncEvent>,
     _dir: TempDir,
 }
@@ -66,7 +75,7 @@ impl TestApp {
             sage,
             router,
             rng,
-            sim,
+            sim: Arc::new(sim),
             events,
             _dir: dir,
         };
@@ -83,6 +92,36 @@ impl TestApp {
         Ok(app)
     }
 
+    /// Starts the app the same way as `sage rpc start --simulator`, with the simulator network
+    /// selected, so that the wallet syncs from its own in-process simulator.
+    pub async fn new_simulator() -> Result<Self> {
+        let _ = default_provider().install_default();
+
+        let dir = TempDir::new()?;
+        let rng = ChaCha8Rng::seed_from_u64(1337);
+
+        let mut sage = Sage::new(dir.path(), true);
+        sage.enable_simulator = true;
+        sage.config.network.default_network = SIMULATOR.name.clone();
+
+        let events = sage.initialize().await?;
+        sage.switch_wallet().await?;
+
+        let sim = sage.simulator.clone().expect("simulator wasn't started");
+
+        let sage = Arc::new(Mutex::new(sage));
+        let router = make_router(sage.clone());
+
+        Ok(Self {
+            sage,
+            router,
+            rng,
+            sim,
+            events,
+            _dir: dir,
+        })
+    }
+
     async fn call_rpc<T: Serialize, R: DeserializeOwned>(&self, path: &str, body: T) -> Result<R> {
         let req = Request::builder()
             .method("POST")
@@ -109,6 +148,14 @@ impl TestApp {
     }
 
     async fn setup_bls(&mut self, balance: u64) -> Result<u32> {
+        self.setup_bls_with_password(balance, None).await
+    }
+
+    async fn setup_bls_with_password(
+        &mut self,
+        balance: u64,
+        password: Option<&str>,
+    ) -> Result<u32> {
         let mnemonic = Mnemonic::from_entropy(&self.rng.r#gen::<[u8; 16]>())?;
 
         if balance > 0 {
@@ -131,12 +178,14 @@ impl TestApp {
             .import_key(ImportKey {
                 name: "Alice".to_string(),
                 key: mnemonic.to_string(),
+                passphrase: None,
                 derivation_index: 0,
                 hardened: None,
                 unhardened: None,
                 save_secrets: true,
                 login: true,
                 emoji: None,
+                password: password.map(str::to_string),
             })
             .await?
             .fingerprint;
@@ -172,6 +221,27 @@ impl TestApp {
         self.consume_until(|event| matches!(event, SyncEvent::PuzzleBatchSynced))
             .await;
     }
+
+    /// Logs into a new wallet with 1000 mojos, and offers 500 of them for 100.
+    async fn setup_offer(
+        &mut self,
+        expires_at_second: Option<u64>,
+        auto_import: bool,
+    ) -> Result<MakeOfferResponse> {
+        self.setup_bls(1000).await?;
+        self.wait_for_coins().await;
+
+        self.make_offer(MakeOffer {
+            requested_assets: vec![xch_offer_amount(100)],
+            offered_assets: vec![xch_offer_amount(500)],
+            fee: Amount::u64(0),
+            receive_address: None,
+            expires_at_second,
+            auto_import,
+            coin_ids: None,
+        })
+        .await
+    }
 }
 
 impl_endpoints! {
@@ -237,7 +307,11 @@ async fn test_send_xch() -> Result<()> {
     let bob = app.setup_bls(1000).await?;
     let bob_address = app.get_sync_status(GetSyncStatus {}).await?.receive_address;
 
-    app.login(Login { fingerprint: alice }).await?;
+    app.login(Login {
+        fingerprint: alice,
+        password: None,
+    })
+    .await?;
 
     let balance = app
         .get_sync_status(GetSyncStatus {})
@@ -267,7 +341,11 @@ async fn test_send_xch() -> Result<()> {
         .to_u64();
     assert_eq!(balance, Some(0));
 
-    app.login(Login { fingerprint: bob }).await?;
+    app.login(Login {
+        fingerprint: bob,
+        password: None,
+    })
+    .await?;
 
     app.wait_for_coins().await;
 
@@ -280,3 +358,868 @@ async fn test_send_xch() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_send_xch_with_password() -> Result<()> {
+    let mut app = TestApp::new().await?;
+
+    let alice = app.setup_bls_with_password(1000, Some("hunter2")).await?;
+
+    let bob = app.setup_bls(0).await?;
+    let bob_address = app.get_sync_status(GetSyncStatus {}).await?.receive_address;
+
+    assert!(
+        app.login(Login {
+            fingerprint: alice,
+            password: Some("wrong".to_string()),
+        })
+        .await
+        .is_err()
+    );
+
+    app.login(Login {
+        fingerprint: alice,
+        password: Some("hunter2".to_string()),
+    })
+    .await?;
+
+    app.wait_for_coins().await;
+
+    app.send_xch(SendXch {
+        address: bob_address,
+        amount: Amount::u64(1000),
+        fee: Amount::u64(0),
+        memos: vec![],
+        clawback: None,
+        auto_submit: true,
+    })
+    .await?;
+
+    app.wait_for_coins().await;
+
+    let balance = app
+        .get_sync_status(GetSyncStatus {})
+        .await?
+        .selectable_balance
+        .to_u64();
+    assert_eq!(balance, Some(0));
+
+    let secrets = app
+        .get_secret_key(GetSecretKey {
+            fingerprint: alice,
+            password: None,
+        })
+        .await?
+        .secrets;
+    assert!(secrets.is_some());
+
+    assert!(
+        app.get_secret_key(GetSecretKey {
+            fingerprint: bob,
+            password: Some("hunter2".to_string()),
+        })
+        .await
+        .is_err()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_backup_round_trip() -> Result<()> {
+    let mut app = TestApp::new().await?;
+
+    let fingerprint = app.setup_bls(1000).await?;
+    app.wait_for_coins().await;
+
+    let mut network = app
+        .get_networks(GetNetworks {})
+        .await?
+        .by_name("testnet11")
+        .cloned()
+        .expect("missing testnet11");
+    network.name = "backup".to_string();
+    app.add_network(AddNetwork { network }).await?;
+
+    let offer = app
+        .make_offer(MakeOffer {
+            requested_assets: vec![xch_offer_amount(100)],
+            offered_assets: vec![xch_offer_amount(500)],
+            fee: Amount::u64(0),
+            receive_address: None,
+            expires_at_second: None,
+            auto_import: true,
+            coin_ids: None,
+        })
+        .await?;
+
+    let backup = app
+        .export_backup(ExportBackup {
+            password: "hunter2".to_string(),
+            fingerprints: None,
+        })
+        .await?
+        .backup;
+
+    let restored = TestApp::new().await?;
+
+    // Importing the same backup twice shouldn't duplicate anything
+    for _ in 0..2 {
+        let fingerprints = restored
+            .import_backup(ImportBackup {
+                backup: backup.clone(),
+                password: "hunter2".to_string(),
+            })
+            .await?
+            .fingerprints;
+        assert_eq!(fingerprints, vec![fingerprint]);
+    }
+
+    let networks = restored.get_networks(GetNetworks {}).await?;
+    assert_eq!(
+        networks
+            .networks
+            .iter()
+            .filter(|network| network.name == "backup")
+            .count(),
+        1
+    );
+
+    restored
+        .login(Login {
+            fingerprint,
+            password: None,
+        })
+        .await?;
+
+    let offers = restored.get_offers(GetOffers {}).await?.offers;
+    assert_eq!(offers.len(), 1);
+    assert_eq!(offers[0].offer, offer.offer);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_network_changes() -> Result<()> {
+    let mut app = TestApp::new().await?;
+
+    app.setup_bls(0).await?;
+
+    let networks = app.get_networks(GetNetworks {}).await?;
+    let testnet11 = networks
+        .by_name("testnet11")
+        .cloned()
+        .expect("missing testnet11");
+
+    let custom = Network {
+        name: "custom".to_string(),
+        ticker: "CXCH".to_string(),
+        genesis_challenge: Bytes32::new([1; 32]),
+        inherit: None,
+        ..testnet11.clone()
+    };
+    app.add_network(AddNetwork {
+        network: custom.clone(),
+    })
+    .await?;
+
+    // Nothing has synced from the custom network yet, so it can be moved to another chain
+    app.update_network(UpdateNetwork {
+        network: Network {
+            genesis_challenge: Bytes32::new([2; 32]),
+            ..custom
+        },
+    })
+    .await?;
+
+    // But the wallet already has a database for testnet11
+    assert!(
+        app.update_network(UpdateNetwork {
+            network: Network {
+                prefix: Some("other".to_string()),
+                ..testnet11.clone()
+            },
+        })
+        .await
+        .is_err()
+    );
+
+    app.update_network(UpdateNetwork {
+        network: Network {
+            default_port: 1,
+            ..testnet11
+        },
+    })
+    .await?;
+
+    // Invalid networks added by hand are left out when reloading
+    let path = app.sage.lock().await.path.join("networks.toml");
+    let mut text = std::fs::read_to_string(&path)?;
+    text.push_str(&format!(
+        "\n[[networks]]\nname = \"broken\"\nticker = \"BRK\"\ndefault_port = 1\ngenesis_challenge = \"{}\"\n",
+        "00".repeat(32)
+    ));
+    std::fs::write(&path, text)?;
+
+    app.reload_networks(ReloadNetworks {}).await?;
+
+    let networks = app.get_networks(GetNetworks {}).await?;
+    assert!(networks.by_name("broken").is_none());
+    assert_eq!(
+        networks
+            .by_name("custom")
+            .map(|network| network.genesis_challenge),
+        Some(Bytes32::new([2; 32]))
+    );
+    assert_eq!(
+        networks
+            .by_name("testnet11")
+            .map(|network| network.default_port),
+        Some(1)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_simulator_network() -> Result<()> {
+    let mut app = TestApp::new_simulator().await?;
+
+    // The simulator is available, but isn't saved to the network list
+    let networks = app.get_networks(GetNetworks {}).await?;
+    assert!(networks.by_name(&SIMULATOR.name).is_some());
+    let path = app.sage.lock().await.path.join("networks.toml");
+    assert!(!std::fs::read_to_string(path)?.contains(&SIMULATOR.name));
+
+    app.setup_bls(1000).await?;
+    app.wait_for_coins().await;
+
+    let status = app.get_sync_status(GetSyncStatus {}).await?;
+    assert_eq!(status.selectable_balance.to_u64(), Some(1000));
+
+    let height = app.farm_blocks(FarmBlocks { blocks: 3 }).await?.height;
+    assert_eq!(
+        app.farm_blocks(FarmBlocks { blocks: 0 }).await?.height,
+        height
+    );
+    assert!(app.farm_blocks(FarmBlocks { blocks: 1001 }).await.is_err());
+
+    app.mint_to_address(MintToAddress {
+        address: status.receive_address,
+        amount: Amount::u64(500),
+    })
+    .await?;
+    app.wait_for_coins().await;
+
+    let status = app.get_sync_status(GetSyncStatus {}).await?;
+    assert_eq!(status.selectable_balance.to_u64(), Some(1500));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_simulator_disabled() -> Result<()> {
+    let app = TestApp::new().await?;
+
+    let networks = app.get_networks(GetNetworks {}).await?;
+    assert!(networks.by_name(&SIMULATOR.name).is_none());
+
+    assert!(
+        app.set_network(SetNetwork {
+            name: SIMULATOR.name.clone(),
+        })
+        .await
+        .is_err()
+    );
+
+    assert!(app.farm_blocks(FarmBlocks { blocks: 1 }).await.is_err());
+
+    Ok(())
+}
+
+fn xch_offer_amount(amount: u64) -> OfferAmount {
+    OfferAmount {
+        asset_id: None,
+        hidden_puzzle_hash: None,
+        amount: Amount::u64(amount),
+    }
+}
+
+#[tokio::test]
+async fn test_validate_offer() -> Result<()> {
+    let mut app = TestApp::new().await?;
+
+    let offer = app.setup_offer(None, true).await?;
+
+    let validation = app
+        .validate_offer(ValidateOffer { offer: offer.offer })
+        .await?;
+
+    assert!(validation.valid);
+    assert!(validation.issues.is_empty());
+    assert!(validation.cost > 0);
+    assert!(validation.peak_height.is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_validate_spent_offer() -> Result<()> {
+    let mut app = TestApp::new().await?;
+
+    let offer = app.setup_offer(None, true).await?;
+
+    app.cancel_offer(CancelOffer {
+        offer_id: offer.offer_id,
+        fee: Amount::u64(0),
+        auto_submit: true,
+    })
+    .await?;
+
+    app.wait_for_coins().await;
+
+    let validation = app
+        .validate_offer(ValidateOffer { offer: offer.offer })
+        .await?;
+
+    assert!(!validation.valid);
+    assert!(
+        validation
+            .issues
+            .iter()
+            .any(|issue| issue.kind == OfferIssueKind::CoinSpent)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_validate_expired_offer() -> Result<()> {
+    let mut app = TestApp::new().await?;
+
+    let offer = app.setup_offer(Some(1), false).await?;
+
+    let validation = app
+        .validate_offer(ValidateOffer { offer: offer.offer })
+        .await?;
+
+    assert!(!validation.valid);
+    assert!(
+        validation
+            .issues
+            .iter()
+            .any(|issue| issue.kind == OfferIssueKind::Expired)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_action_issue_cat() -> Result<()> {
+    let mut app = TestApp::new().await?;
+
+    app.setup_bls(1000).await?;
+    app.wait_for_coins().await;
+
+    app.create_transaction(CreateTransaction {
+        selected_coin_ids: vec![],
+        actions: vec![
+            Action::IssueCat(IssueCatAction {
+                amount: Amount::u64(500),
+                revocable: false,
+                name: Some("Test Token".to_string()),
+                ticker: Some("TEST".to_string()),
+            }),
+            Action::Fee(FeeAction {
+                amount: Amount::u64(100),
+            }),
+        ],
+        auto_submit: true,
+    })
+    .await?;
+
+    app.wait_for_coins().await;
+    app.wait_for_puzzles().await;
+
+    let cats = app.get_cats(GetCats {}).await?.cats;
+
+    assert_eq!(cats.len(), 1);
+    assert_eq!(cats[0].name.as_deref(), Some("Test Token"));
+    assert_eq!(cats[0].ticker.as_deref(), Some("TEST"));
+    assert_eq!(cats[0].balance.to_u64(), Some(500));
+
+    let balance = app
+        .get_sync_status(GetSyncStatus {})
+        .await?
+        .selectable_balance
+        .to_u64();
+    assert_eq!(balance, Some(400));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_action_create_did() -> Result<()> {
+    let mut app = TestApp::new().await?;
+
+    app.setup_bls(1000).await?;
+    app.wait_for_coins().await;
+
+    app.create_transaction(CreateTransaction {
+        selected_coin_ids: vec![],
+        actions: vec![Action::CreateDid(CreateDidAction {
+            name: Some("Profile".to_string()),
+        })],
+        auto_submit: true,
+    })
+    .await?;
+
+    app.wait_for_coins().await;
+    app.wait_for_puzzles().await;
+
+    let dids = app.get_dids(GetDids {}).await?.dids;
+
+    assert_eq!(dids.len(), 1);
+    assert_eq!(dids[0].name.as_deref(), Some("Profile"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_action_invalid_index() -> Result<()> {
+    let mut app = TestApp::new().await?;
+
+    app.setup_bls(1000).await?;
+    app.wait_for_coins().await;
+
+    let address = app.get_sync_status(GetSyncStatus {}).await?.receive_address;
+
+    let result = app
+        .create_transaction(CreateTransaction {
+            selected_coin_ids: vec![],
+            actions: vec![Action::Send(SendAction {
+                id: Id::New { index: 1 },
+                address,
+                amount: Amount::u64(1),
+                clawback: None,
+                memos: vec![],
+            })],
+            auto_submit: false,
+        })
+        .await;
+
+    let error = result.expect_err("the action index doesn't exist");
+    assert!(error.to_string().contains("Invalid action index: 1"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_action_settle_offer() -> Result<()> {
+    let mut app = TestApp::new().await?;
+
+    let offer = app.setup_offer(None, true).await?;
+
+    app.setup_bls(1000).await?;
+    app.wait_for_coins().await;
+
+    // Without auto submit, the taker's spends are only returned as a preview
+    let preview = app
+        .create_transaction(CreateTransaction {
+            selected_coin_ids: vec![],
+            actions: vec![Action::SettleOffer(SettleOfferAction {
+                offer: offer.offer.clone(),
+            })],
+            auto_submit: false,
+        })
+        .await?;
+    assert!(!preview.coin_spends.is_empty());
+
+    let result = app
+        .create_transaction(CreateTransaction {
+            selected_coin_ids: vec![],
+            actions: vec![
+                Action::SettleOffer(SettleOfferAction {
+                    offer: offer.offer.clone(),
+                }),
+                Action::SettleOffer(SettleOfferAction {
+                    offer: offer.offer.clone(),
+                }),
+            ],
+            auto_submit: true,
+        })
+        .await;
+
+    let error = result.expect_err("only one offer can be settled");
+    assert!(
+        error
+            .to_string()
+            .contains("Only one offer can be settled per transaction")
+    );
+
+    app.create_transaction(CreateTransaction {
+        selected_coin_ids: vec![],
+        actions: vec![Action::SettleOffer(SettleOfferAction {
+            offer: offer.offer,
+        })],
+        auto_submit: true,
+    })
+    .await?;
+
+    app.wait_for_coins().await;
+
+    let balance = app
+        .get_sync_status(GetSyncStatus {})
+        .await?
+        .selectable_balance
+        .to_u64();
+    assert_eq!(balance, Some(1400));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_action_new_asset_after_settle_offer() -> Result<()> {
+    let mut app = TestApp::new().await?;
+
+    let offer = app.setup_offer(None, true).await?;
+    let maker_address = app.get_sync_status(GetSyncStatus {}).await?.receive_address;
+
+    app.setup_bls(1000).await?;
+    app.wait_for_coins().await;
+
+    let actions = |id| {
+        vec![
+            Action::SettleOffer(SettleOfferAction {
+                offer: offer.offer.clone(),
+            }),
+            Action::IssueCat(IssueCatAction {
+                amount: Amount::u64(500),
+                revocable: false,
+                name: None,
+                ticker: None,
+            }),
+            Action::Send(SendAction {
+                id,
+                address: maker_address.clone(),
+                amount: Amount::u64(200),
+                clawback: None,
+                memos: vec![],
+            }),
+        ]
+    };
+
+    // Settling an offer doesn't create an asset of its own
+    let result = app
+        .create_transaction(CreateTransaction {
+            selected_coin_ids: vec![],
+            actions: actions(Id::New { index: 0 }),
+            auto_submit: false,
+        })
+        .await;
+
+    let error = result.expect_err("the offer isn't a new asset");
+    assert!(error.to_string().contains("Invalid action index: 0"));
+
+    app.create_transaction(CreateTransaction {
+        selected_coin_ids: vec![],
+        actions: actions(Id::New { index: 1 }),
+        auto_submit: true,
+    })
+    .await?;
+
+    app.wait_for_coins().await;
+    app.wait_for_puzzles().await;
+
+    let cats = app.get_cats(GetCats {}).await?.cats;
+    assert_eq!(cats.len(), 1);
+    assert_eq!(cats[0].balance.to_u64(), Some(300));
+
+    let balance = app
+        .get_sync_status(GetSyncStatus {})
+        .await?
+        .selectable_balance
+        .to_u64();
+    assert_eq!(balance, Some(900));
+
+    Ok(())
+}
+
+fn identity_puzzle_hash() -> Result<Bytes32> {
+    let mut ctx = SpendContext::new();
+    let puzzle = ctx.alloc(&1)?;
+    Ok(ctx.tree_hash(puzzle).into())
+}
+
+/// Spends a coin with the `1` puzzle, which outputs its solution as conditions.
+fn identity_spend(coin: Coin, conditions: Conditions) -> Result<CoinSpend> {
+    let mut ctx = SpendContext::new();
+
+    let puzzle = ctx.alloc(&1)?;
+    let solution = ctx.alloc(&conditions)?;
+
+    ctx.spend(coin, Spend::new(puzzle, solution))?;
+
+    Ok(ctx.take().remove(0))
+}
+
+#[tokio::test]
+async fn test_simulate_balance_changes() -> Result<()> {
+    let mut app = TestApp::new().await?;
+
+    app.setup_bls(0).await?;
+    let bob_address = app.get_sync_status(GetSyncStatus {}).await?.receive_address;
+
+    app.setup_bls(1000).await?;
+    app.wait_for_coins().await;
+
+    let transaction = app
+        .send_xch(SendXch {
+            address: bob_address,
+            amount: Amount::u64(300),
+            fee: Amount::u64(0),
+            memos: vec![],
+            clawback: None,
+            auto_submit: false,
+        })
+        .await?;
+
+    let simulation = app
+        .simulate_transaction(SimulateTransaction {
+            coin_spends: transaction.coin_spends,
+            aggregated_signature: None,
+        })
+        .await?;
+
+    assert!(simulation.valid, "{:?}", simulation.errors);
+    assert_eq!(simulation.fee.to_u64(), Some(0));
+    assert_eq!(simulation.balance_changes.len(), 1);
+    assert_eq!(simulation.balance_changes[0].spent.to_u64(), Some(1000));
+    assert_eq!(simulation.balance_changes[0].received.to_u64(), Some(700));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_simulate_missing_coin() -> Result<()> {
+    let mut app = TestApp::new().await?;
+
+    app.setup_bls(1000).await?;
+    app.wait_for_coins().await;
+
+    let coin = Coin::new(Bytes32::new([1; 32]), identity_puzzle_hash()?, 1);
+    let coin_spend = identity_spend(coin, Conditions::new())?;
+
+    let simulation = app
+        .simulate_transaction(SimulateTransaction {
+            coin_spends: vec![json_spend(&coin_spend)],
+            aggregated_signature: None,
+        })
+        .await?;
+
+    assert!(!simulation.valid);
+    assert_eq!(
+        simulation.spends[0].errors,
+        vec!["Coin does not exist on chain".to_string()]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_simulate_expired_assertion() -> Result<()> {
+    let mut app = TestApp::new().await?;
+
+    app.setup_bls(1000).await?;
+    app.wait_for_coins().await;
+
+    let coin = app.sim.lock().await.new_coin(identity_puzzle_hash()?, 1);
+    let coin_spend = identity_spend(coin, Conditions::new().assert_before_seconds_absolute(1))?;
+
+    let simulation = app
+        .simulate_transaction(SimulateTransaction {
+            coin_spends: vec![json_spend(&coin_spend)],
+            aggregated_signature: None,
+        })
+        .await?;
+
+    assert!(!simulation.valid);
+    assert!(
+        simulation
+            .errors
+            .iter()
+            .any(|error| error.starts_with("Transaction can't be spent at or after timestamp 1"))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_decode_standard_spend() -> Result<()> {
+    let mut app = TestApp::new().await?;
+
+    app.setup_bls(0).await?;
+    let bob_address = app.get_sync_status(GetSyncStatus {}).await?.receive_address;
+
+    app.setup_bls(1000).await?;
+    app.wait_for_coins().await;
+
+    let transaction = app
+        .send_xch(SendXch {
+            address: bob_address.clone(),
+            amount: Amount::u64(300),
+            fee: Amount::u64(0),
+            memos: vec![],
+            clawback: Some(4_000_000_000),
+            auto_submit: false,
+        })
+        .await?;
+
+    let spends = app
+        .decode_coin_spends(DecodeCoinSpends {
+            coin_spends: transaction.coin_spends,
+        })
+        .await?
+        .spends;
+
+    assert_eq!(spends.len(), 1);
+    assert_eq!(spends[0].kind, DecodedCoinKind::Unknown);
+    assert_eq!(spends[0].layers, vec![PuzzleLayer::Standard]);
+    assert!(spends[0].error.is_none());
+
+    let outputs = spends[0]
+        .conditions
+        .iter()
+        .filter_map(|condition| condition.output.as_ref())
+        .collect::<Vec<_>>();
+
+    assert_eq!(outputs.len(), 2);
+
+    let clawback = outputs
+        .iter()
+        .find(|output| output.kind == DecodedCoinKind::Clawback)
+        .expect("missing clawback output");
+
+    assert_eq!(clawback.amount.to_u64(), Some(300));
+    assert_eq!(clawback.address.as_deref(), Some(bob_address.as_str()));
+    assert_eq!(clawback.clawback_timestamp, Some(4_000_000_000));
+    assert!(clawback.clawback_address.is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_decode_cat_spend() -> Result<()> {
+    let mut app = TestApp::new().await?;
+
+    app.setup_bls(1000).await?;
+    app.wait_for_coins().await;
+
+    let transaction = app
+        .create_transaction(CreateTransaction {
+            selected_coin_ids: vec![],
+            actions: vec![Action::IssueCat(IssueCatAction {
+                amount: Amount::u64(500),
+                revocable: false,
+                name: None,
+                ticker: None,
+            })],
+            auto_submit: false,
+        })
+        .await?;
+
+    let spends = app
+        .decode_coin_spends(DecodeCoinSpends {
+            coin_spends: transaction.coin_spends,
+        })
+        .await?
+        .spends;
+
+    let cat = spends
+        .iter()
+        .find(|spend| spend.kind == DecodedCoinKind::Cat)
+        .expect("missing CAT spend");
+
+    assert!(cat.asset_id.is_some());
+    assert_eq!(cat.layers, vec![PuzzleLayer::Cat, PuzzleLayer::Standard]);
+
+    assert!(spends.iter().any(|spend| {
+        spend.conditions.iter().any(|condition| {
+            condition
+                .output
+                .as_ref()
+                .is_some_and(|output| output.kind == DecodedCoinKind::Cat)
+        })
+    }));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_decode_offer_spends() -> Result<()> {
+    let mut app = TestApp::new().await?;
+
+    let offer = app.setup_offer(None, false).await?;
+    let spend_bundle = decode_offer(&offer.offer)?;
+
+    let spends = app
+        .decode_coin_spends(DecodeCoinSpends {
+            coin_spends: spend_bundle.coin_spends.iter().map(json_spend).collect(),
+        })
+        .await?
+        .spends;
+
+    assert!(
+        spends
+            .iter()
+            .any(|spend| spend.layers == vec![PuzzleLayer::Settlement])
+    );
+
+    assert!(spends.iter().any(|spend| {
+        spend.conditions.iter().any(|condition| {
+            condition
+                .description
+                .ends_with("for the offer settlement puzzle")
+        })
+    }));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_decode_unknown_spend() -> Result<()> {
+    let mut app = TestApp::new().await?;
+
+    app.setup_bls(0).await?;
+
+    let coin = Coin::new(Bytes32::default(), identity_puzzle_hash()?, 1);
+    let coin_spend = identity_spend(
+        coin,
+        Conditions::new()
+            .reserve_fee(1)
+            .assert_before_height_absolute(100),
+    )?;
+
+    let spends = app
+        .decode_coin_spends(DecodeCoinSpends {
+            coin_spends: vec![json_spend(&coin_spend)],
+        })
+        .await?
+        .spends;
+
+    assert_eq!(spends[0].kind, DecodedCoinKind::Unknown);
+    assert_eq!(spends[0].layers, vec![PuzzleLayer::Unknown]);
+
+    let descriptions = spends[0]
+        .conditions
+        .iter()
+        .map(|condition| condition.description.as_str())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        descriptions,
+        vec!["Reserves a fee of 1 mojos", "Expires at height 100"]
+    );
+
+    Ok(())
+}
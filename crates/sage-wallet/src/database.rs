@@ -509,6 +509,49 @@ pub async fn insert_option(
     Ok(true)
 }
 
+/// Queues a transaction to be broadcast once the wallet is back online, and records it as
+/// pending so that the coins it spends can't be selected again in the meantime. The outputs
+/// are only inserted once it's broadcast, since parsing them requires a peer.
+pub async fn queue_transaction(
+    db: &Database,
+    spend_bundle: &SpendBundle,
+) -> Result<(), WalletError> {
+    let transaction_id = spend_bundle.name();
+    let transaction = Transaction::from_coin_spends(spend_bundle.coin_spends.clone())?;
+
+    let output_coin_ids: HashSet<Bytes32> = transaction
+        .inputs
+        .iter()
+        .flat_map(|input| input.outputs.iter().map(|output| output.coin.coin_id()))
+        .collect();
+
+    let mut tx = db.tx().await?;
+
+    tx.queue_broadcast(spend_bundle).await?;
+    tx.insert_mempool_item(
+        transaction_id,
+        spend_bundle.aggregated_signature.clone(),
+        transaction.fee,
+    )
+    .await?;
+
+    for (index, input) in transaction.inputs.iter().enumerate() {
+        let input_coin_id = input.coin_spend.coin.coin_id();
+
+        tx.insert_mempool_spend(transaction_id, input.coin_spend.clone(), index)
+            .await?;
+
+        if !output_coin_ids.contains(&input_coin_id) && tx.is_known_coin(input_coin_id).await? {
+            tx.insert_mempool_coin(transaction_id, input_coin_id, true, false)
+                .await?;
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
 pub async fn insert_transaction(
     db: &Database,
     peer: &WalletPeer,
@@ -574,10 +617,7 @@ pub async fn insert_transaction(
 
             // If it's an XCH coin, we can insert it and sync it immediately.
             // Attach it to the transaction as an output for display purposes.
-            if tx
-                .is_custody_p2_puzzle_hash(output.coin.puzzle_hash)
-                .await?
-            {
+            if tx.is_xch_p2_puzzle_hash(output.coin.puzzle_hash).await? {
                 tx.insert_coin(coin_state).await?;
 
                 tx.update_coin(coin_id, Bytes32::default(), output.coin.puzzle_hash)
@@ -48,12 +48,27 @@ pub enum WalletError {
     #[error("Peer misbehaved")]
     PeerMisbehaved,
 
+    #[error("Chain reorganization past the requested header hash")]
+    Reorg,
+
+    #[error("Peers disagree about the state of the wallet's coins")]
+    PeerDisagreement,
+
+    #[error("Could not resolve the address of trusted peer {0}")]
+    UnresolvedTrustedPeer(String),
+
+    #[error("Certificate pinning requires a TLS connection")]
+    CertificatePinUnsupported,
+
     #[error("Subscription limit reached")]
     SubscriptionLimitReached,
 
     #[error("System time error: {0}")]
     SystemTime(#[from] SystemTimeError),
 
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
     #[error("Join error: {0}")]
     Join(#[from] JoinError),
 
@@ -125,6 +140,21 @@ pub enum WalletError {
     #[error("Cannot find clawback info for coin with id {0}")]
     MissingClawbackInfo(Bytes32),
 
+    #[error("Vault spends need exactly {0} distinct signers from the vault's keys")]
+    InvalidVaultSigners(u32),
+
+    #[error("Vault spends must send an amount or pay a fee")]
+    EmptyVaultSpend,
+
+    #[error("Remote signer address must be a loopback socket address: {0}")]
+    InvalidSignerAddress(String),
+
+    #[error("Invalid response from signer: {0}")]
+    SignerResponse(String),
+
+    #[error("Hardened derivations require the secret key, which isn't available")]
+    HardenedKeyUnavailable,
+
     #[error("Try from int error: {0}")]
     TryFromInt(#[from] TryFromIntError),
 }
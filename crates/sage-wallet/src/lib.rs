@@ -4,6 +4,7 @@ mod database;
 mod error;
 mod puzzle_context;
 mod queues;
+mod signer;
 mod sync_manager;
 mod transaction;
 mod utils;
@@ -16,6 +17,7 @@ pub use database::*;
 pub use error::*;
 pub use puzzle_context::*;
 pub use queues::*;
+pub use signer::*;
 pub use sync_manager::*;
 pub use transaction::*;
 pub use utils::*;
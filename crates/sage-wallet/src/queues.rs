@@ -1,12 +1,16 @@
 mod blocktime_queue;
+mod broadcast_queue;
 mod cat_queue;
+mod header_queue;
 mod nft_uri_queue;
 mod offer_queue;
 mod puzzle_queue;
 mod transaction_queue;
 
 pub use blocktime_queue::*;
+pub use broadcast_queue::*;
 pub use cat_queue::*;
+pub use header_queue::*;
 pub use nft_uri_queue::*;
 pub use offer_queue::*;
 pub use puzzle_queue::*;
@@ -1,4 +1,4 @@
-use crate::{PeerState, SyncEvent, WalletError, WalletPeer};
+use crate::{PeerState, PhaseProgress, SyncEvent, SyncProgressTracker, WalletError, WalletPeer};
 
 use futures_util::{StreamExt, stream::FuturesUnordered};
 use sage_database::Database;
@@ -15,6 +15,7 @@ use tracing::{error, info};
 pub struct BlockTimeQueue {
     db: Database,
     state: Arc<Mutex<PeerState>>,
+    progress: Arc<Mutex<SyncProgressTracker>>,
     sync_sender: mpsc::Sender<SyncEvent>,
 }
 
@@ -22,11 +23,13 @@ impl BlockTimeQueue {
     pub fn new(
         db: Database,
         state: Arc<Mutex<PeerState>>,
+        progress: Arc<Mutex<SyncProgressTracker>>,
         sync_sender: mpsc::Sender<SyncEvent>,
     ) -> Self {
         Self {
             db,
             state,
+            progress,
             sync_sender,
         }
     }
@@ -39,6 +42,13 @@ impl BlockTimeQueue {
     }
 
     async fn process_batch(&mut self) -> Result<(), WalletError> {
+        let (filled, total) = self.db.block_timestamp_progress().await?;
+
+        self.progress
+            .lock()
+            .await
+            .set_block_timestamps(PhaseProgress::new(filled, total));
+
         // Look for missing created timestamps, ordered by height highest to lowest.
         // Followed by spent timestamps, ordered by height highest to lowest.
         let peers = self.state.lock().await.peers();
@@ -0,0 +1,253 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use chia_wallet_sdk::prelude::*;
+use sage_database::Database;
+use tokio::{
+    sync::{Mutex, mpsc},
+    time::sleep,
+};
+use tracing::{info, warn};
+
+use crate::{
+    PeerState, Status, SyncCommand, SyncEvent, Transaction, WalletError, insert_transaction,
+    submit_to_peers,
+};
+
+/// Broadcasts the transactions that were queued while the wallet was offline, once it has
+/// peers again. Transactions are submitted in the order they were queued, except that a
+/// transaction which spends coins created by another pending transaction waits until its
+/// parent has been confirmed, and fails along with it.
+#[derive(Debug)]
+pub struct BroadcastQueue {
+    db: Database,
+    genesis_challenge: Bytes32,
+    state: Arc<Mutex<PeerState>>,
+    sync_sender: mpsc::Sender<SyncEvent>,
+    command_sender: mpsc::Sender<SyncCommand>,
+}
+
+impl BroadcastQueue {
+    pub fn new(
+        db: Database,
+        genesis_challenge: Bytes32,
+        state: Arc<Mutex<PeerState>>,
+        sync_sender: mpsc::Sender<SyncEvent>,
+        command_sender: mpsc::Sender<SyncCommand>,
+    ) -> Self {
+        Self {
+            db,
+            genesis_challenge,
+            state,
+            sync_sender,
+            command_sender,
+        }
+    }
+
+    pub async fn start(mut self, delay: Duration) -> Result<(), WalletError> {
+        loop {
+            self.process_batch().await?;
+            sleep(delay).await;
+        }
+    }
+
+    async fn process_batch(&mut self) -> Result<(), WalletError> {
+        let peers = self.state.lock().await.peers();
+
+        if peers.is_empty() {
+            return Ok(());
+        }
+
+        let spend_bundles = self.db.queued_broadcasts().await?;
+
+        if spend_bundles.is_empty() {
+            return Ok(());
+        }
+
+        info!("Flushing {} queued transactions", spend_bundles.len());
+
+        // Coins spent by queued transactions that haven't been confirmed yet, or that failed
+        let mut pending_coin_ids = HashSet::new();
+        let mut failed_coin_ids = HashSet::new();
+
+        for spend_bundle in spend_bundles {
+            let transaction_id = spend_bundle.name();
+            let spent_coin_ids = spent_coin_ids(&spend_bundle);
+            let parent_coin_ids = parent_coin_ids(&spend_bundle);
+
+            if parent_coin_ids
+                .iter()
+                .any(|coin_id| failed_coin_ids.contains(coin_id))
+            {
+                warn!("Dropping queued transaction {transaction_id} because its parent failed");
+
+                self.remove_failed(transaction_id).await?;
+                failed_coin_ids.extend(spent_coin_ids);
+
+                self.sync_sender
+                    .send(SyncEvent::TransactionFailed {
+                        transaction_id,
+                        error: Some("A transaction it depends on failed".to_string()),
+                    })
+                    .await
+                    .ok();
+
+                continue;
+            }
+
+            let mut is_blocked = parent_coin_ids
+                .iter()
+                .any(|coin_id| pending_coin_ids.contains(coin_id));
+
+            for &coin_id in &parent_coin_ids {
+                if is_blocked {
+                    break;
+                }
+
+                is_blocked = self.db.is_spent_in_mempool(coin_id).await?;
+            }
+
+            if is_blocked {
+                pending_coin_ids.extend(spent_coin_ids);
+                continue;
+            }
+
+            match submit_to_peers(&peers, spend_bundle.clone()).await? {
+                Status::Pending => {
+                    info!("Queued transaction {transaction_id} was accepted into the mempool");
+
+                    let Some(peer) = self.state.lock().await.acquire_peer() else {
+                        return Ok(());
+                    };
+
+                    let subscriptions = insert_transaction(
+                        &self.db,
+                        &peer,
+                        self.genesis_challenge,
+                        transaction_id,
+                        Transaction::from_coin_spends(spend_bundle.coin_spends)?,
+                        spend_bundle.aggregated_signature,
+                    )
+                    .await?;
+
+                    self.db.update_mempool_item_time(transaction_id).await?;
+                    self.db.remove_queued_broadcast(transaction_id).await?;
+                    pending_coin_ids.extend(spent_coin_ids);
+
+                    self.command_sender
+                        .send(SyncCommand::SubscribeCoins {
+                            coin_ids: subscriptions,
+                        })
+                        .await
+                        .ok();
+
+                    self.sync_sender
+                        .send(SyncEvent::TransactionUpdated { transaction_id })
+                        .await
+                        .ok();
+                }
+                Status::Failed(status, error) => {
+                    info!(
+                        "Queued transaction {transaction_id} failed with status {status} and error {error:?}, removing it"
+                    );
+
+                    self.remove_failed(transaction_id).await?;
+                    failed_coin_ids.extend(spent_coin_ids);
+
+                    self.sync_sender
+                        .send(SyncEvent::TransactionFailed {
+                            transaction_id,
+                            error,
+                        })
+                        .await
+                        .ok();
+                }
+                Status::Unknown => {
+                    warn!("Queued transaction {transaction_id} status unknown, retrying later");
+                    pending_coin_ids.extend(spent_coin_ids);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes a transaction that won't be broadcast, along with the pending transaction
+    /// that was recorded when it was queued, so that its coins can be spent again.
+    async fn remove_failed(&self, transaction_id: Bytes32) -> Result<(), WalletError> {
+        let mut tx = self.db.tx().await?;
+        tx.remove_queued_broadcast(transaction_id).await?;
+        tx.remove_mempool_item(transaction_id).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+fn spent_coin_ids(spend_bundle: &SpendBundle) -> HashSet<Bytes32> {
+    spend_bundle
+        .coin_spends
+        .iter()
+        .map(|coin_spend| coin_spend.coin.coin_id())
+        .collect()
+}
+
+/// The parents of the coins spent by the transaction, excluding ephemeral coins that are
+/// created and spent within the transaction itself.
+fn parent_coin_ids(spend_bundle: &SpendBundle) -> HashSet<Bytes32> {
+    let spent_coin_ids = spent_coin_ids(spend_bundle);
+
+    spend_bundle
+        .coin_spends
+        .iter()
+        .map(|coin_spend| coin_spend.coin.parent_coin_info)
+        .filter(|coin_id| !spent_coin_ids.contains(coin_id))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use crate::{KeychainSigner, TestWallet, queue_transaction};
+
+    use super::*;
+
+    #[test(tokio::test)]
+    async fn test_flush_queued_broadcast() -> anyhow::Result<()> {
+        let mut test = TestWallet::new(1000).await?;
+
+        let coin_spends = test
+            .wallet
+            .send_xch(vec![(test.puzzle_hash, 250)], 250, vec![], None)
+            .await?;
+
+        let spend_bundle = test
+            .wallet
+            .sign_transaction(
+                SpendBundle::new(coin_spends, Signature::default()),
+                &test.agg_sig,
+                &KeychainSigner::new(test.master_sk.clone()),
+                false,
+            )
+            .await?;
+
+        let transaction_id = spend_bundle.name();
+
+        queue_transaction(&test.wallet.db, &spend_bundle).await?;
+
+        // The spent coin can't be selected again while the transaction is queued
+        assert_eq!(test.wallet.db.queued_broadcast_count().await?, 1);
+        assert_eq!(test.wallet.db.selectable_xch_balance().await?, 0);
+
+        test.consume_until(|event| {
+            matches!(event, SyncEvent::TransactionUpdated { transaction_id: id } if id == transaction_id)
+        })
+        .await;
+
+        assert_eq!(test.wallet.db.queued_broadcast_count().await?, 0);
+
+        test.wait_for_coins().await;
+        assert_eq!(test.wallet.db.xch_balance().await?, 750);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,457 @@
+use crate::{PeerState, SyncCommand, WalletError, WalletPeer};
+
+use chia_wallet_sdk::{chia::protocol::HeaderBlock, prelude::*};
+use itertools::Itertools;
+use sage_database::{Database, VerifiedHeader};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::{
+    sync::{Mutex, mpsc},
+    time::sleep,
+};
+use tracing::{debug, info, warn};
+
+/// The number of headers requested from a peer at once.
+const HEADER_BATCH_SIZE: u32 = 32;
+
+/// The number of peers that must agree on the first header before it's trusted.
+const ANCHOR_CONFIRMATIONS: usize = 2;
+
+/// The number of peers asked for the first header.
+const ANCHOR_PEERS: usize = 3;
+
+/// Tracks the linkage of a chain of headers in the database. The first header is taken from
+/// the current peak once enough peers agree on it, and the chain is then extended forward to
+/// each new peak and backward to cover the heights of coins that were synced before it. Every
+/// header must be at the expected height and build on the header before it.
+///
+/// Weight, proof of space and the challenge chains aren't checked, so this doesn't prove that
+/// the headers are part of the heaviest chain. Extending backward from a trusted header is
+/// safe, since each header hash commits to the one before it, but the tip of each forward batch
+/// is confirmed with a second peer when one is available. Coin states that claim a height the
+/// tracked chain rules out are rolled back, so they can be synced again.
+#[derive(Debug)]
+pub struct HeaderQueue {
+    db: Database,
+    state: Arc<Mutex<PeerState>>,
+    command_sender: mpsc::Sender<SyncCommand>,
+    requested_rollback: Option<u32>,
+}
+
+impl HeaderQueue {
+    pub fn new(
+        db: Database,
+        state: Arc<Mutex<PeerState>>,
+        command_sender: mpsc::Sender<SyncCommand>,
+    ) -> Self {
+        Self {
+            db,
+            state,
+            command_sender,
+            requested_rollback: None,
+        }
+    }
+
+    pub async fn start(mut self, delay: Duration) -> Result<(), WalletError> {
+        loop {
+            self.process_batch().await?;
+            sleep(delay).await;
+        }
+    }
+
+    async fn process_batch(&mut self) -> Result<(), WalletError> {
+        let Some((peak_height, _)) = self.db.latest_peak().await? else {
+            return Ok(());
+        };
+
+        let Some((verified_height, verified_hash)) = self.db.highest_verified_header().await?
+        else {
+            return self.verify_anchor(peak_height).await;
+        };
+
+        if self
+            .check_coin_heights(verified_height, peak_height)
+            .await?
+        {
+            return Ok(());
+        }
+
+        let Some(peer) = self.state.lock().await.acquire_peer() else {
+            return Ok(());
+        };
+
+        let result = if verified_height < peak_height {
+            self.extend_forward(&peer, verified_height, verified_hash, peak_height)
+                .await
+        } else {
+            self.extend_backward(&peer).await
+        };
+
+        match result {
+            Err(WalletError::PeerMisbehaved) => {
+                warn!(
+                    "Peer {} sent headers that don't form a valid chain",
+                    peer.socket_addr()
+                );
+
+                self.state.lock().await.ban(
+                    peer.socket_addr().ip(),
+                    Duration::from_hours(1),
+                    "invalid header chain",
+                );
+
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    /// Coins can only be created or spent in transaction blocks that are part of the chain, so
+    /// a coin state in a tracked non-transaction block, or past the peak once the chain has
+    /// been tracked up to it, doesn't match the chain. Returns whether a rollback is pending.
+    async fn check_coin_heights(
+        &mut self,
+        verified_height: u32,
+        peak_height: u32,
+    ) -> Result<bool, WalletError> {
+        let mut ruled_out_height = self
+            .db
+            .lowest_coin_height_in_non_transaction_block()
+            .await?;
+
+        if verified_height >= peak_height
+            && let Some(height) = self.db.lowest_coin_height_above(verified_height).await?
+        {
+            ruled_out_height = Some(ruled_out_height.map_or(height, |lowest| lowest.min(height)));
+        }
+
+        let Some(height) = ruled_out_height else {
+            self.requested_rollback = None;
+            return Ok(false);
+        };
+
+        let fork_height = height.saturating_sub(1);
+
+        // The sync manager applies the rollback asynchronously, so it isn't requested again
+        // while the same coin state is still waiting to be removed
+        if self.requested_rollback == Some(fork_height) {
+            return Ok(true);
+        }
+
+        warn!(
+            "A coin state claims to be in block {height}, which the verified header chain rules out"
+        );
+
+        self.command_sender
+            .send(SyncCommand::Rollback { fork_height })
+            .await
+            .ok();
+
+        self.requested_rollback = Some(fork_height);
+
+        Ok(true)
+    }
+
+    async fn verify_anchor(&self, height: u32) -> Result<(), WalletError> {
+        let peers = self.state.lock().await.peers();
+
+        if peers.is_empty() {
+            return Ok(());
+        }
+
+        let mut headers = Vec::new();
+
+        for peer in peers.iter().take(ANCHOR_PEERS) {
+            match peer.block_header(height).await {
+                Ok(header) if header.reward_chain_block.height == height => headers.push(header),
+                Ok(_) => warn!(
+                    "Peer {} sent a header at the wrong height",
+                    peer.socket_addr()
+                ),
+                Err(error) => warn!(
+                    "Failed to fetch header {height} from peer {}: {error}",
+                    peer.socket_addr()
+                ),
+            }
+        }
+
+        let Some((header, votes)) = headers
+            .iter()
+            .into_group_map_by(|header| header.header_hash())
+            .into_values()
+            .map(|group| (group[0], group.len()))
+            .max_by_key(|(_, votes)| *votes)
+        else {
+            return Ok(());
+        };
+
+        if votes < ANCHOR_CONFIRMATIONS.min(peers.len()) || votes * 2 <= headers.len() {
+            warn!("Not enough peers agree on header {height} to start verifying the header chain");
+            return Ok(());
+        }
+
+        info!("Verified header chain starting at height {height}");
+
+        let mut tx = self.db.tx().await?;
+        tx.insert_verified_header(height, verified_header(header)?)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn extend_forward(
+        &self,
+        peer: &WalletPeer,
+        verified_height: u32,
+        verified_hash: Bytes32,
+        peak_height: u32,
+    ) -> Result<(), WalletError> {
+        let start_height = verified_height + 1;
+        let end_height = peak_height.min(verified_height + HEADER_BATCH_SIZE);
+
+        let headers = peer.block_headers(start_height, end_height).await?;
+
+        let Some(first) = headers.first() else {
+            return Err(WalletError::PeerMisbehaved);
+        };
+
+        if first.foliage.prev_block_hash != verified_hash {
+            // The verified tip is no longer part of the chain, so step back until the chain
+            // links up again. Deep reorgs are rolled back by the sync manager instead.
+            warn!("Header {start_height} doesn't build on the verified header chain");
+            self.db.unverify_header(verified_height).await?;
+            return Ok(());
+        }
+
+        let linked = link_headers(start_height, verified_hash, &headers)?;
+
+        if linked.len() != (end_height - verified_height) as usize {
+            return Err(WalletError::PeerMisbehaved);
+        }
+
+        let Some((_, tip)) = linked.last() else {
+            return Ok(());
+        };
+
+        if !self.confirm_tip(peer, end_height, tip.header_hash).await {
+            return Ok(());
+        }
+
+        self.insert_headers(linked).await
+    }
+
+    /// Asks another peer for the header at the tip of a forward batch, since the linkage alone
+    /// doesn't stop a single peer from supplying a chain of its own. If no other peer can be
+    /// reached, the batch is accepted as is.
+    async fn confirm_tip(&self, peer: &WalletPeer, height: u32, header_hash: Bytes32) -> bool {
+        let ip = peer.socket_addr().ip();
+
+        let witnesses = self
+            .state
+            .lock()
+            .await
+            .peers()
+            .into_iter()
+            .filter(|witness| witness.socket_addr().ip() != ip)
+            .collect_vec();
+
+        for witness in witnesses {
+            match witness.block_header(height).await {
+                Ok(header) if header.header_hash() == header_hash => return true,
+                Ok(_) => {
+                    warn!(
+                        "Peer {} disagrees with peer {} about header {height}",
+                        witness.socket_addr(),
+                        peer.socket_addr()
+                    );
+                    return false;
+                }
+                Err(error) => debug!(
+                    "Failed to fetch header {height} from peer {}: {error}",
+                    witness.socket_addr()
+                ),
+            }
+        }
+
+        warn!("No other peers are available to confirm header {height} from peer {ip}");
+
+        true
+    }
+
+    async fn extend_backward(&self, peer: &WalletPeer) -> Result<(), WalletError> {
+        let Some((lowest_height, lowest_prev_hash)) = self.db.lowest_verified_header().await?
+        else {
+            return Ok(());
+        };
+
+        let Some(coin_height) = self.db.highest_coin_height_below(lowest_height).await? else {
+            return Ok(());
+        };
+
+        let start_height = coin_height.max(lowest_height.saturating_sub(HEADER_BATCH_SIZE));
+        let end_height = lowest_height - 1;
+
+        let headers = peer.block_headers(start_height, end_height).await?;
+
+        let Some(first) = headers.first() else {
+            return Err(WalletError::PeerMisbehaved);
+        };
+
+        let linked = link_headers(start_height, first.foliage.prev_block_hash, &headers)?;
+
+        if linked.len() != (lowest_height - start_height) as usize
+            || linked
+                .last()
+                .is_none_or(|(_, header)| header.header_hash != lowest_prev_hash)
+        {
+            return Err(WalletError::PeerMisbehaved);
+        }
+
+        self.insert_headers(linked).await
+    }
+
+    async fn insert_headers(&self, headers: Vec<(u32, VerifiedHeader)>) -> Result<(), WalletError> {
+        let mut tx = self.db.tx().await?;
+
+        for (height, header) in headers {
+            tx.insert_verified_header(height, header).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
+
+/// Checks that the headers are at consecutive heights and that each one builds on the one
+/// before it, starting with the given previous header hash.
+fn link_headers(
+    start_height: u32,
+    prev_header_hash: Bytes32,
+    headers: &[HeaderBlock],
+) -> Result<Vec<(u32, VerifiedHeader)>, WalletError> {
+    let mut prev_header_hash = prev_header_hash;
+    let mut linked = Vec::with_capacity(headers.len());
+
+    for (height, header) in (start_height..).zip(headers) {
+        if header.reward_chain_block.height != height
+            || header.foliage.prev_block_hash != prev_header_hash
+        {
+            return Err(WalletError::PeerMisbehaved);
+        }
+
+        let header = verified_header(header)?;
+        prev_header_hash = header.header_hash;
+        linked.push((height, header));
+    }
+
+    Ok(linked)
+}
+
+fn verified_header(header: &HeaderBlock) -> Result<VerifiedHeader, WalletError> {
+    Ok(VerifiedHeader {
+        header_hash: header.header_hash(),
+        prev_header_hash: header.foliage.prev_block_hash,
+        timestamp: header
+            .foliage_transaction_block
+            .as_ref()
+            .map(|block| block.timestamp.try_into())
+            .transpose()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use crate::TestWallet;
+
+    use super::*;
+
+    #[test(tokio::test)]
+    async fn test_forged_coin_height_is_rolled_back() -> anyhow::Result<()> {
+        let test = TestWallet::new(1000).await?;
+
+        let height = 1_000_000;
+
+        // The coin state is synced before its block is verified, so it isn't rejected yet
+        let coin_state = CoinState::new(
+            Coin::new(Bytes32::default(), test.puzzle_hash, 1),
+            None,
+            Some(height),
+        );
+
+        let mut tx = test.wallet.db.tx().await?;
+        tx.insert_height(height).await?;
+        tx.insert_coin(coin_state).await?;
+        tx.commit().await?;
+
+        let (command_sender, mut command_receiver) = mpsc::channel(10);
+
+        let mut queue =
+            HeaderQueue::new(test.wallet.db.clone(), test.state.clone(), command_sender);
+
+        assert!(!queue.check_coin_heights(height, height).await?);
+
+        let mut tx = test.wallet.db.tx().await?;
+        tx.insert_verified_header(
+            height,
+            VerifiedHeader {
+                header_hash: Bytes32::new([1; 32]),
+                prev_header_hash: Bytes32::new([2; 32]),
+                timestamp: None,
+            },
+        )
+        .await?;
+        tx.commit().await?;
+
+        assert!(queue.check_coin_heights(height, height).await?);
+
+        let Some(SyncCommand::Rollback { fork_height }) = command_receiver.recv().await else {
+            panic!("expected a rollback");
+        };
+
+        assert_eq!(fork_height, height - 1);
+
+        // The rollback hasn't been applied yet, so it isn't requested a second time
+        assert!(queue.check_coin_heights(height, height).await?);
+        assert!(command_receiver.try_recv().is_err());
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_coin_height_past_verified_peak_is_rolled_back() -> anyhow::Result<()> {
+        let test = TestWallet::new(1000).await?;
+
+        let coin_state = CoinState::new(
+            Coin::new(Bytes32::default(), test.puzzle_hash, 1),
+            None,
+            Some(500),
+        );
+
+        let mut tx = test.wallet.db.tx().await?;
+        tx.insert_height(500).await?;
+        tx.insert_coin(coin_state).await?;
+        tx.commit().await?;
+
+        let (command_sender, mut command_receiver) = mpsc::channel(10);
+
+        let mut queue =
+            HeaderQueue::new(test.wallet.db.clone(), test.state.clone(), command_sender);
+
+        // The verified chain hasn't caught up to the peak yet, so the coin may still be valid
+        assert!(!queue.check_coin_heights(100, 600).await?);
+
+        assert!(queue.check_coin_heights(100, 100).await?);
+
+        let Some(SyncCommand::Rollback { fork_height }) = command_receiver.recv().await else {
+            panic!("expected a rollback");
+        };
+
+        assert_eq!(fork_height, 499);
+
+        Ok(())
+    }
+}
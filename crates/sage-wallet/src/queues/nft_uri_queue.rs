@@ -1,29 +1,36 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
 use futures_lite::StreamExt;
 use futures_util::stream::FuturesUnordered;
 use sage_assets::{base64_data_uri, fetch_uri};
 use sage_database::{Database, NftMetadataInfo, ResizedImageKind};
 use tokio::{
-    sync::mpsc,
+    sync::{Mutex, mpsc},
     time::{sleep, timeout},
 };
 use tracing::{debug, info, warn};
 
-use crate::{SyncEvent, WalletError, compute_nft_info};
+use crate::{PhaseProgress, SyncEvent, SyncProgressTracker, WalletError, compute_nft_info};
 use chia_wallet_sdk::types::TESTNET11_CONSTANTS;
 use sage_config::Network;
 #[derive(Debug)]
 pub struct NftUriQueue {
     db: Database,
+    progress: Arc<Mutex<SyncProgressTracker>>,
     sync_sender: mpsc::Sender<SyncEvent>,
     network: Network,
 }
 
 impl NftUriQueue {
-    pub fn new(db: Database, sync_sender: mpsc::Sender<SyncEvent>, network: Network) -> Self {
+    pub fn new(
+        db: Database,
+        progress: Arc<Mutex<SyncProgressTracker>>,
+        sync_sender: mpsc::Sender<SyncEvent>,
+        network: Network,
+    ) -> Self {
         Self {
             db,
+            progress,
             sync_sender,
             network,
         }
@@ -37,6 +44,8 @@ impl NftUriQueue {
     }
 
     async fn process_batch(&self) -> Result<(), WalletError> {
+        self.update_progress().await?;
+
         let batch = self.db.candidates_for_download(60 * 60 * 24, 3, 25).await?;
 
         if batch.is_empty() {
@@ -134,6 +143,20 @@ impl NftUriQueue {
 
         self.sync_sender.send(SyncEvent::NftData).await.ok();
 
+        self.update_progress().await?;
+
+        Ok(())
+    }
+
+    async fn update_progress(&self) -> Result<(), WalletError> {
+        let checked = self.db.checked_files().await?;
+        let total = self.db.total_files().await?;
+
+        self.progress.lock().await.set_nft_uris(PhaseProgress::new(
+            checked.try_into().unwrap_or(u32::MAX),
+            total.try_into().unwrap_or(u32::MAX),
+        ));
+
         Ok(())
     }
 }
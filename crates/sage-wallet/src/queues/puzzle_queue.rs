@@ -1,4 +1,7 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use chia_wallet_sdk::prelude::*;
 use futures_util::{StreamExt, stream::FuturesUnordered};
@@ -10,16 +13,20 @@ use tokio::{
 use tracing::{debug, info, warn};
 
 use crate::{
-    ChildKind, PeerState, PuzzleContext, SyncCommand, SyncEvent, WalletError, WalletPeer,
-    database::insert_puzzle, validate_wallet_coin,
+    ChildKind, PeerState, PhaseProgress, PuzzleContext, SyncCommand, SyncEvent,
+    SyncProgressTracker, WalletError, WalletPeer, database::insert_puzzle, validate_wallet_coin,
 };
 
+/// The misbehavior penalty for a peer that fails to look up a puzzle.
+const FAILED_LOOKUP_PENALTY: f64 = 25.0;
+
 #[derive(Debug)]
 pub struct PuzzleQueue {
     db: Database,
     genesis_challenge: Bytes32,
     batch_size_per_peer: usize,
     state: Arc<Mutex<PeerState>>,
+    progress: Arc<Mutex<SyncProgressTracker>>,
     sync_sender: mpsc::Sender<SyncEvent>,
     command_sender: mpsc::Sender<SyncCommand>,
 }
@@ -30,6 +37,7 @@ impl PuzzleQueue {
         genesis_challenge: Bytes32,
         batch_size_per_peer: usize,
         state: Arc<Mutex<PeerState>>,
+        progress: Arc<Mutex<SyncProgressTracker>>,
         sync_sender: mpsc::Sender<SyncEvent>,
         command_sender: mpsc::Sender<SyncCommand>,
     ) -> Self {
@@ -38,6 +46,7 @@ impl PuzzleQueue {
             genesis_challenge,
             batch_size_per_peer,
             state,
+            progress,
             sync_sender,
             command_sender,
         }
@@ -51,7 +60,9 @@ impl PuzzleQueue {
     }
 
     async fn process_batch(&mut self) -> Result<(), WalletError> {
-        let peers = self.state.lock().await.peers();
+        self.update_progress().await?;
+
+        let peers = self.state.lock().await.peers_by_score();
 
         if peers.is_empty() {
             return Ok(());
@@ -71,27 +82,26 @@ impl PuzzleQueue {
             peers.len()
         );
 
+        let scores: Vec<u32> = peers.iter().map(|(_, score)| *score).collect();
+        let shares = batch_shares(&scores, coin_states.len(), self.batch_size_per_peer);
+
         let mut futures = FuturesUnordered::new();
         let mut remaining = coin_states.into_iter();
 
-        for peer in peers {
-            for _ in 0..self.batch_size_per_peer {
-                let Some(row) = remaining.next() else {
-                    break;
-                };
-
+        for ((peer, _), share) in peers.into_iter().zip(shares) {
+            for row in remaining.by_ref().take(share) {
                 let peer = peer.clone();
                 let genesis_challenge = self.genesis_challenge;
-                let is_custody_p2_puzzle_hash = self
+                let is_xch_p2_puzzle_hash = self
                     .db
-                    .is_custody_p2_puzzle_hash(row.coin_state.coin.puzzle_hash)
+                    .is_xch_p2_puzzle_hash(row.coin_state.coin.puzzle_hash)
                     .await?;
 
                 futures.push(async move {
+                    let start = Instant::now();
                     let result =
-                        fetch_puzzles(&peer, genesis_challenge, row, is_custody_p2_puzzle_hash)
-                            .await;
-                    (peer.socket_addr(), row, result)
+                        fetch_puzzles(&peer, genesis_challenge, row, is_xch_p2_puzzle_hash).await;
+                    (peer.socket_addr(), row, start.elapsed(), result)
                 });
             }
         }
@@ -99,9 +109,11 @@ impl PuzzleQueue {
         let mut subscriptions = Vec::new();
         let mut send_events = false;
 
-        while let Some((addr, root, synced_coins)) = futures.next().await {
+        while let Some((addr, root, latency, synced_coins)) = futures.next().await {
             match synced_coins {
                 Ok(synced_coins) => {
+                    self.state.lock().await.record_response(addr.ip(), latency);
+
                     let mut tx = self.db.tx().await?;
 
                     if root.is_children_unsynced {
@@ -219,17 +231,18 @@ impl PuzzleQueue {
                         error
                     );
 
-                    if matches!(
-                        error,
-                        WalletError::Elapsed(..)
-                            | WalletError::PeerMisbehaved
-                            | WalletError::Client(..)
-                    ) {
-                        self.state.lock().await.ban(
-                            addr.ip(),
-                            Duration::from_mins(5),
-                            "failed puzzle lookup",
-                        );
+                    match error {
+                        WalletError::Elapsed(..) => {
+                            self.state.lock().await.record_timeout(addr.ip());
+                        }
+                        WalletError::PeerMisbehaved | WalletError::Client(..) => {
+                            self.state.lock().await.record_misbehavior(
+                                addr.ip(),
+                                FAILED_LOOKUP_PENALTY,
+                                "failed puzzle lookup",
+                            );
+                        }
+                        _ => {}
                     }
                 }
             }
@@ -248,8 +261,56 @@ impl PuzzleQueue {
                 .await
                 .ok();
         }
+
+        self.update_progress().await?;
+
         Ok(())
     }
+
+    async fn update_progress(&self) -> Result<(), WalletError> {
+        let synced = self.db.synced_coin_count().await?;
+        let total = self.db.total_coin_count().await?;
+
+        self.progress
+            .lock()
+            .await
+            .set_puzzles(PhaseProgress::new(synced, total));
+
+        Ok(())
+    }
+}
+
+/// Peers with a score of zero still get a small weight, so that a batch can always be split.
+fn score_weight(score: u32) -> usize {
+    score.max(1) as usize
+}
+
+/// Splits a batch between peers in proportion to their scores, so the most reputable peers
+/// do most of the work. No peer gets more than its own batch size, and any coins left over
+/// after rounding are given to the peers that still have room, in order of score.
+fn batch_shares(scores: &[u32], batch_size: usize, batch_size_per_peer: usize) -> Vec<usize> {
+    let total_score: usize = scores.iter().map(|score| score_weight(*score)).sum();
+    let mut left = batch_size;
+
+    let mut shares: Vec<usize> = scores
+        .iter()
+        .map(|score| {
+            let share = (batch_size * score_weight(*score))
+                .div_ceil(total_score.max(1))
+                .min(batch_size_per_peer)
+                .min(left);
+            left -= share;
+            share
+        })
+        .collect();
+
+    for share in &mut shares {
+        let extra = (batch_size_per_peer - *share).min(left);
+        *share += extra;
+        left -= extra;
+    }
+
+    shares
 }
 
 #[derive(Debug, Clone)]
@@ -263,14 +324,14 @@ async fn fetch_puzzles(
     peer: &WalletPeer,
     genesis_challenge: Bytes32,
     unsynced_coin: UnsyncedCoin,
-    is_custody_p2_puzzle_hash: bool,
+    is_xch_p2_puzzle_hash: bool,
 ) -> Result<Vec<SyncedCoin>, WalletError> {
     let coin = unsynced_coin.coin_state.coin;
 
     let mut synced_coins = Vec::new();
 
     if unsynced_coin.is_asset_unsynced {
-        let parent_spend = if is_custody_p2_puzzle_hash {
+        let parent_spend = if is_xch_p2_puzzle_hash {
             None
         } else {
             peer.fetch_optional_coin_spend(coin.parent_coin_info, genesis_challenge)
@@ -337,3 +398,20 @@ async fn fetch_puzzles(
 
     Ok(synced_coins)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_shares_are_capped() {
+        // A single reputable peer would get the whole batch if it weren't capped
+        assert_eq!(batch_shares(&[1000, 1, 1], 30, 10), vec![10, 10, 10]);
+
+        // Rounding up never hands out more than the batch
+        assert_eq!(batch_shares(&[1, 1, 1], 4, 10), vec![2, 2, 0]);
+        assert_eq!(batch_shares(&[50, 30, 20], 7, 10).iter().sum::<usize>(), 7);
+
+        assert_eq!(batch_shares(&[], 0, 10), Vec::<usize>::new());
+    }
+}
@@ -1,32 +1,96 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::HashSet,
+    fmt,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use chia_wallet_sdk::prelude::*;
-use sage_database::Database;
+use sage_database::{Database, MempoolItem};
 use tokio::{
     sync::{Mutex, mpsc},
     time::sleep,
 };
 use tracing::{info, warn};
 
-use crate::{PeerState, Status, SyncEvent, WalletError, submit_to_peers};
+use crate::{
+    PeerState, RebroadcastPolicy, Status, SyncEvent, Transaction, WalletError, WalletPeer,
+    submit_to_peers,
+};
+
+/// Why a pending transaction was given up on and removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionFailure {
+    /// Every peer that responded rejected the transaction.
+    Rejected { status: u8, error: Option<String> },
+    /// One of the coins the transaction spends was spent by a different transaction.
+    DoubleSpent { coin_id: Bytes32 },
+    /// The transaction wasn't confirmed within the maximum age, so it was most likely
+    /// evicted from the mempool.
+    Expired { age: Duration },
+    /// No peer acknowledged the transaction after the maximum number of retries.
+    RetriesExhausted { retries: u32 },
+}
+
+impl fmt::Display for TransactionFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rejected {
+                error: Some(error), ..
+            } => write!(f, "{error}"),
+            Self::Rejected {
+                status,
+                error: None,
+            } => write!(f, "Rejected by peers with status {status}"),
+            Self::DoubleSpent { coin_id } => {
+                write!(f, "Coin {coin_id} was spent by another transaction")
+            }
+            Self::Expired { age } => write!(
+                f,
+                "Not confirmed after {} minutes, it was likely evicted from the mempool",
+                age.as_secs() / 60
+            ),
+            Self::RetriesExhausted { retries } => {
+                write!(
+                    f,
+                    "No peer acknowledged the transaction after {retries} retries"
+                )
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Decision {
+    Submit,
+    /// The transaction has already been confirmed, and will be removed by the wallet sync.
+    Skip,
+    Remove(TransactionFailure),
+}
 
 #[derive(Debug)]
 pub struct TransactionQueue {
     db: Database,
+    genesis_challenge: Bytes32,
     state: Arc<Mutex<PeerState>>,
     sync_sender: mpsc::Sender<SyncEvent>,
+    policy: RebroadcastPolicy,
 }
 
 impl TransactionQueue {
     pub fn new(
         db: Database,
+        genesis_challenge: Bytes32,
         state: Arc<Mutex<PeerState>>,
         sync_sender: mpsc::Sender<SyncEvent>,
+        policy: RebroadcastPolicy,
     ) -> Self {
         Self {
             db,
+            genesis_challenge,
             state,
             sync_sender,
+            policy,
         }
     }
 
@@ -44,20 +108,16 @@ impl TransactionQueue {
             return Ok(());
         }
 
-        let mut spend_bundles = Vec::new();
-
-        let rows = self.db.mempool_items_to_submit(120, 3).await?;
+        let rows = self
+            .db
+            .mempool_items_to_submit(self.policy.interval.as_secs().try_into()?, 3)
+            .await?;
 
         if rows.is_empty() {
             return Ok(());
         }
 
         for row in rows {
-            let coin_spends = self.db.mempool_coin_spends(row.hash).await?;
-            spend_bundles.push(SpendBundle::new(coin_spends, row.aggregated_signature));
-        }
-
-        for spend_bundle in spend_bundles {
             sleep(Duration::from_secs(1)).await;
 
             let peers = self.state.lock().await.peers();
@@ -66,8 +126,19 @@ impl TransactionQueue {
                 return Ok(());
             }
 
+            let coin_spends = self.db.mempool_coin_spends(row.hash).await?;
+            let spend_bundle = SpendBundle::new(coin_spends, row.aggregated_signature);
             let transaction_id = spend_bundle.name();
 
+            match self.check_policy(&row, &spend_bundle).await? {
+                Decision::Submit => {}
+                Decision::Skip => continue,
+                Decision::Remove(failure) => {
+                    self.remove_failed(transaction_id, failure).await?;
+                    continue;
+                }
+            }
+
             info!(
                 "Submitting transaction with id {transaction_id}: {:?}",
                 spend_bundle
@@ -93,27 +164,340 @@ impl TransactionQueue {
                         "Transaction inclusion in mempool failed for all peers with status {status} and error {error:?}, removing transaction"
                     );
 
-                    let mut tx = self.db.tx().await?;
-
-                    tx.set_transaction_children_unsynced(transaction_id).await?;
-                    tx.remove_mempool_item(transaction_id).await?;
-
-                    tx.commit().await?;
-
-                    self.sync_sender
-                        .send(SyncEvent::TransactionFailed {
-                            transaction_id,
-                            error,
-                        })
-                        .await
-                        .ok();
+                    self.remove_failed(
+                        transaction_id,
+                        TransactionFailure::Rejected { status, error },
+                    )
+                    .await?;
                 }
                 Status::Unknown => {
                     warn!("Transaction inclusion in mempool unknown, retrying later");
+
+                    self.db.record_failed_submission(transaction_id).await?;
                 }
             }
         }
 
         Ok(())
     }
+
+    /// Decides whether the transaction should be resubmitted, or given up on.
+    async fn check_policy(
+        &self,
+        row: &MempoolItem,
+        spend_bundle: &SpendBundle,
+    ) -> Result<Decision, WalletError> {
+        let decision = self.check_inputs(spend_bundle).await?;
+
+        if !matches!(decision, Decision::Submit) {
+            return Ok(decision);
+        }
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let age = Duration::from_secs(now.saturating_sub(row.created_timestamp));
+
+        if age > self.policy.max_age {
+            return Ok(Decision::Remove(TransactionFailure::Expired { age }));
+        }
+
+        if row.failed_submissions >= self.policy.max_retries {
+            return Ok(Decision::Remove(TransactionFailure::RetriesExhausted {
+                retries: row.failed_submissions,
+            }));
+        }
+
+        Ok(Decision::Submit)
+    }
+
+    /// Looks up the coins spent by the transaction. If some of them have been spent, but the
+    /// coins the transaction creates don't exist, then a different transaction spent them.
+    async fn check_inputs(&self, spend_bundle: &SpendBundle) -> Result<Decision, WalletError> {
+        let Some(peer) = self.state.lock().await.acquire_peer() else {
+            return Ok(Decision::Submit);
+        };
+
+        let spent_coin_ids: HashSet<Bytes32> = spend_bundle
+            .coin_spends
+            .iter()
+            .map(|coin_spend| coin_spend.coin.coin_id())
+            .collect();
+
+        // Ephemeral coins are created by the transaction itself, so they can't be on chain yet
+        let input_ids: Vec<Bytes32> = spend_bundle
+            .coin_spends
+            .iter()
+            .filter(|coin_spend| !spent_coin_ids.contains(&coin_spend.coin.parent_coin_info))
+            .map(|coin_spend| coin_spend.coin.coin_id())
+            .collect();
+
+        let Some(inputs) = self.fetch_coins(&peer, input_ids.clone()).await else {
+            return Ok(Decision::Submit);
+        };
+
+        let Some(spent_input) = inputs.iter().find(|input| input.spent_height.is_some()) else {
+            return Ok(Decision::Submit);
+        };
+
+        let output_ids: Vec<Bytes32> =
+            Transaction::from_coin_spends(spend_bundle.coin_spends.clone())?
+                .inputs
+                .into_iter()
+                .flat_map(|input| input.outputs)
+                .map(|output| output.coin.coin_id())
+                .filter(|coin_id| !spent_coin_ids.contains(coin_id))
+                .collect();
+
+        let is_confirmed = if output_ids.is_empty() {
+            inputs.len() == input_ids.len()
+                && inputs.iter().all(|input| input.spent_height.is_some())
+        } else {
+            let Some(outputs) = self.fetch_coins(&peer, output_ids).await else {
+                return Ok(Decision::Submit);
+            };
+            !outputs.is_empty()
+        };
+
+        if is_confirmed {
+            return Ok(Decision::Skip);
+        }
+
+        Ok(Decision::Remove(TransactionFailure::DoubleSpent {
+            coin_id: spent_input.coin.coin_id(),
+        }))
+    }
+
+    async fn fetch_coins(
+        &self,
+        peer: &WalletPeer,
+        coin_ids: Vec<Bytes32>,
+    ) -> Option<Vec<CoinState>> {
+        match peer.fetch_coins(coin_ids, self.genesis_challenge).await {
+            Ok(coin_states) => Some(coin_states),
+            Err(error) => {
+                warn!(
+                    "Failed to check coin states for pending transaction with {}: {error}",
+                    peer.socket_addr()
+                );
+                None
+            }
+        }
+    }
+
+    async fn remove_failed(
+        &self,
+        transaction_id: Bytes32,
+        failure: TransactionFailure,
+    ) -> Result<(), WalletError> {
+        info!("Removing transaction {transaction_id}: {failure}");
+
+        let mut tx = self.db.tx().await?;
+
+        tx.set_transaction_children_unsynced(transaction_id).await?;
+        tx.remove_mempool_item(transaction_id).await?;
+
+        tx.commit().await?;
+
+        self.sync_sender
+            .send(SyncEvent::TransactionFailed {
+                transaction_id,
+                error: Some(failure.to_string()),
+            })
+            .await
+            .ok();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use crate::{KeychainSigner, SyncOptions, TestWallet, default_test_options};
+
+    use super::*;
+
+    async fn sign(test: &TestWallet, coin_spends: Vec<CoinSpend>) -> anyhow::Result<SpendBundle> {
+        Ok(test
+            .wallet
+            .sign_transaction(
+                SpendBundle::new(coin_spends, Signature::default()),
+                &test.agg_sig,
+                &KeychainSigner::new(test.master_sk.clone()),
+                false,
+            )
+            .await?)
+    }
+
+    /// A queue that's driven by the test, since the wallet's own queue is kept idle.
+    async fn manual_queue(
+        test: &TestWallet,
+        policy: RebroadcastPolicy,
+    ) -> anyhow::Result<(TransactionQueue, SpendBundle)> {
+        let coin_spends = test
+            .wallet
+            .send_xch(vec![(test.puzzle_hash, 250)], 250, vec![], None)
+            .await?;
+
+        let spend_bundle = sign(test, coin_spends).await?;
+        test.push_bundle(spend_bundle.clone()).await?;
+
+        let (sync_sender, _) = mpsc::channel(32);
+
+        let queue = TransactionQueue::new(
+            test.wallet.db.clone(),
+            test.wallet.genesis_challenge,
+            test.state.clone(),
+            sync_sender,
+            policy,
+        );
+
+        Ok((queue, spend_bundle))
+    }
+
+    fn idle_options() -> SyncOptions {
+        let mut options = default_test_options();
+        options.timeouts.transaction_delay = Duration::from_hours(1);
+        options
+    }
+
+    #[test(tokio::test)]
+    async fn test_expired_transaction() -> anyhow::Result<()> {
+        let test = TestWallet::new_with_options(1000, idle_options()).await?;
+
+        let (queue, spend_bundle) = manual_queue(
+            &test,
+            RebroadcastPolicy {
+                max_age: Duration::from_mins(10),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        let mut row = test.wallet.db.mempool_items().await?.remove(0);
+        assert!(matches!(
+            queue.check_policy(&row, &spend_bundle).await?,
+            Decision::Submit
+        ));
+
+        row.created_timestamp -= 3600;
+        assert!(matches!(
+            queue.check_policy(&row, &spend_bundle).await?,
+            Decision::Remove(TransactionFailure::Expired { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_double_spent_input() -> anyhow::Result<()> {
+        let test = TestWallet::new_with_options(1000, idle_options()).await?;
+
+        // Both transactions spend the same coin, since neither has been inserted yet
+        let other_spends = test
+            .wallet
+            .send_xch(vec![(test.puzzle_hash, 100)], 0, vec![], None)
+            .await?;
+        let coin_id = other_spends[0].coin.coin_id();
+        let other_bundle = sign(&test, other_spends).await?;
+
+        let (queue, spend_bundle) = manual_queue(&test, RebroadcastPolicy::default()).await?;
+
+        let ack = test.peer.send_transaction(other_bundle.clone()).await?;
+        assert_eq!(ack.status, 1);
+
+        assert!(matches!(
+            queue.check_inputs(&spend_bundle).await?,
+            Decision::Remove(TransactionFailure::DoubleSpent { coin_id: id }) if id == coin_id
+        ));
+
+        // The transaction that was confirmed is left for the wallet sync to remove
+        assert!(matches!(
+            queue.check_inputs(&other_bundle).await?,
+            Decision::Skip
+        ));
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_failed_submission_backoff() -> anyhow::Result<()> {
+        let test = TestWallet::new_with_options(1000, idle_options()).await?;
+
+        let (queue, spend_bundle) = manual_queue(
+            &test,
+            RebroadcastPolicy {
+                max_retries: 2,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        let transaction_id = spend_bundle.name();
+        let db = &test.wallet.db;
+
+        assert_eq!(db.mempool_items_to_submit(60, 10).await?.len(), 1);
+
+        // A failed submission isn't retried until the next interval
+        db.record_failed_submission(transaction_id).await?;
+        assert!(db.mempool_items_to_submit(60, 10).await?.is_empty());
+
+        let row = db.mempool_items_to_submit(0, 10).await?.remove(0);
+        assert_eq!(row.failed_submissions, 1);
+        assert!(matches!(
+            queue.check_policy(&row, &spend_bundle).await?,
+            Decision::Submit
+        ));
+
+        db.record_failed_submission(transaction_id).await?;
+
+        let row = db.mempool_items_to_submit(0, 10).await?.remove(0);
+        assert!(matches!(
+            queue.check_policy(&row, &spend_bundle).await?,
+            Decision::Remove(TransactionFailure::RetriesExhausted { retries: 2 })
+        ));
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_give_up_after_max_retries() -> anyhow::Result<()> {
+        let mut options = default_test_options();
+        options.rebroadcast.max_retries = 0;
+
+        let mut test = TestWallet::new_with_options(1000, options).await?;
+
+        let coin_spends = test
+            .wallet
+            .send_xch(vec![(test.puzzle_hash, 250)], 250, vec![], None)
+            .await?;
+
+        let spend_bundle = test
+            .wallet
+            .sign_transaction(
+                SpendBundle::new(coin_spends, Signature::default()),
+                &test.agg_sig,
+                &KeychainSigner::new(test.master_sk.clone()),
+                false,
+            )
+            .await?;
+
+        let transaction_id = spend_bundle.name();
+
+        test.push_bundle(spend_bundle).await?;
+
+        test.consume_until(|event| {
+            matches!(
+                event,
+                SyncEvent::TransactionFailed { transaction_id: id, error: Some(error) }
+                    if id == transaction_id && error.contains("0 retries")
+            )
+        })
+        .await;
+
+        assert!(test.wallet.db.mempool_items().await?.is_empty());
+        assert_eq!(test.wallet.db.xch_balance().await?, 1000);
+
+        Ok(())
+    }
 }
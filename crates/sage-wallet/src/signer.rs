@@ -0,0 +1,385 @@
+use std::{fmt, net::SocketAddr, ops::Range, time::Duration};
+
+use async_trait::async_trait;
+use chia_wallet_sdk::{
+    chia::{
+        bls::{
+            DerivableKey, master_to_wallet_hardened_intermediate,
+            master_to_wallet_unhardened_intermediate, sign, verify,
+        },
+        puzzle_types::DeriveSynthetic,
+    },
+    prelude::*,
+};
+use sage_database::Derivation;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    time::timeout,
+};
+
+use crate::WalletError;
+
+/// A message which needs to be signed by the secret key for a given public key. The derivation
+/// is included if the key is one of the wallet's synthetic keys, so that the signer doesn't have
+/// to search for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureRequest {
+    pub public_key: PublicKey,
+    pub derivation: Option<Derivation>,
+    pub message: Vec<u8>,
+}
+
+/// Holds the keys for a wallet and produces signatures on its behalf.
+#[async_trait]
+pub trait Signer: fmt::Debug + Send + Sync {
+    /// Returns a signature for each request in the same order, or `None` if the signer
+    /// doesn't have the secret key for it.
+    async fn sign(
+        &self,
+        requests: &[SignatureRequest],
+    ) -> Result<Vec<Option<Signature>>, WalletError>;
+
+    /// Derives the synthetic public keys for a range of hardened derivations, which can't be
+    /// done from the intermediate public key alone. Returns `None` if the signer can't.
+    async fn hardened_public_keys(
+        &self,
+        _range: Range<u32>,
+    ) -> Result<Option<Vec<PublicKey>>, WalletError> {
+        Ok(None)
+    }
+}
+
+/// Signs with a master secret key that was extracted from the local keychain.
+#[derive(Debug, Clone)]
+pub struct KeychainSigner {
+    master_sk: SecretKey,
+}
+
+impl KeychainSigner {
+    pub fn new(master_sk: SecretKey) -> Self {
+        Self { master_sk }
+    }
+}
+
+#[async_trait]
+impl Signer for KeychainSigner {
+    async fn sign(
+        &self,
+        requests: &[SignatureRequest],
+    ) -> Result<Vec<Option<Signature>>, WalletError> {
+        let master_pk = self.master_sk.public_key();
+        let unhardened_intermediate_sk = master_to_wallet_unhardened_intermediate(&self.master_sk);
+        let hardened_intermediate_sk = master_to_wallet_hardened_intermediate(&self.master_sk);
+
+        Ok(requests
+            .iter()
+            .map(|request| {
+                let sk = if request.public_key == master_pk {
+                    self.master_sk.clone()
+                } else if let Some(derivation) = request.derivation {
+                    if derivation.is_hardened {
+                        hardened_intermediate_sk.derive_hardened(derivation.derivation_index)
+                    } else {
+                        unhardened_intermediate_sk.derive_unhardened(derivation.derivation_index)
+                    }
+                    .derive_synthetic()
+                } else {
+                    return None;
+                };
+
+                Some(sign(&sk, &request.message))
+            })
+            .collect())
+    }
+
+    async fn hardened_public_keys(
+        &self,
+        range: Range<u32>,
+    ) -> Result<Option<Vec<PublicKey>>, WalletError> {
+        let intermediate_sk = master_to_wallet_hardened_intermediate(&self.master_sk);
+
+        Ok(Some(
+            range
+                .map(|index| {
+                    intermediate_sk
+                        .derive_hardened(index)
+                        .derive_synthetic()
+                        .public_key()
+                })
+                .collect(),
+        ))
+    }
+}
+
+/// Delegates signing to a separate process listening on a loopback socket, such as a daemon in
+/// front of an HSM. Each request opens a connection, writes a single line of JSON and reads a
+/// single line of JSON back:
+///
+/// ```json
+/// {"fingerprint":1234,"requests":[{"public_key":"..","derivation_index":0,"hardened":false,"message":".."}]}
+/// {"signatures":["..",null]}
+/// ```
+///
+/// The signer can reply with `{"error":".."}` instead, for example if the user declined to sign.
+#[derive(Debug, Clone, Copy)]
+pub struct RemoteSigner {
+    address: SocketAddr,
+    fingerprint: u32,
+    timeout: Duration,
+}
+
+impl RemoteSigner {
+    pub fn new(address: &str, fingerprint: u32) -> Result<Self, WalletError> {
+        let Ok(parsed) = address.parse::<SocketAddr>() else {
+            return Err(WalletError::InvalidSignerAddress(address.to_string()));
+        };
+
+        // Signatures and key derivations shouldn't leave the machine unencrypted
+        if !parsed.ip().is_loopback() {
+            return Err(WalletError::InvalidSignerAddress(address.to_string()));
+        }
+
+        Ok(Self {
+            address: parsed,
+            fingerprint,
+            // The signer may be waiting on the user to confirm on a hardware device
+            timeout: Duration::from_secs(120),
+        })
+    }
+
+    async fn request(
+        &self,
+        request: &RemoteSignRequest,
+    ) -> Result<RemoteSignResponse, WalletError> {
+        let mut stream = TcpStream::connect(self.address).await?;
+
+        let mut line = serde_json::to_string(request)
+            .map_err(|error| WalletError::SignerResponse(error.to_string()))?;
+        line.push('\n');
+
+        stream.write_all(line.as_bytes()).await?;
+        stream.flush().await?;
+
+        let mut response = String::new();
+        BufReader::new(stream).read_line(&mut response).await?;
+
+        serde_json::from_str(&response)
+            .map_err(|error| WalletError::SignerResponse(error.to_string()))
+    }
+}
+
+#[derive(Serialize)]
+struct RemoteSignRequest {
+    fingerprint: u32,
+    requests: Vec<RemoteSignatureRequest>,
+}
+
+#[derive(Serialize)]
+struct RemoteSignatureRequest {
+    public_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    derivation_index: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hardened: Option<bool>,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct RemoteSignResponse {
+    #[serde(default)]
+    signatures: Vec<Option<String>>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    async fn sign(
+        &self,
+        requests: &[SignatureRequest],
+    ) -> Result<Vec<Option<Signature>>, WalletError> {
+        let request = RemoteSignRequest {
+            fingerprint: self.fingerprint,
+            requests: requests
+                .iter()
+                .map(|request| RemoteSignatureRequest {
+                    public_key: hex::encode(request.public_key.to_bytes()),
+                    derivation_index: request
+                        .derivation
+                        .map(|derivation| derivation.derivation_index),
+                    hardened: request.derivation.map(|derivation| derivation.is_hardened),
+                    message: hex::encode(&request.message),
+                })
+                .collect(),
+        };
+
+        let response = timeout(self.timeout, self.request(&request)).await??;
+
+        if let Some(error) = response.error {
+            return Err(WalletError::SignerResponse(error));
+        }
+
+        if response.signatures.len() != requests.len() {
+            return Err(WalletError::SignerResponse(format!(
+                "Expected {} signatures, got {}",
+                requests.len(),
+                response.signatures.len()
+            )));
+        }
+
+        let mut signatures = Vec::with_capacity(requests.len());
+
+        for (request, signature) in requests.iter().zip(response.signatures) {
+            let Some(signature) = signature else {
+                signatures.push(None);
+                continue;
+            };
+
+            let signature = hex::decode(signature)
+                .ok()
+                .and_then(|bytes| <[u8; 96]>::try_from(bytes).ok())
+                .and_then(|bytes| Signature::from_bytes(&bytes).ok())
+                .ok_or_else(|| WalletError::SignerResponse("Invalid signature".to_string()))?;
+
+            // A bad signature would only be caught when the transaction is rejected by the
+            // mempool, so it's checked up front instead
+            if !verify(&signature, &request.public_key, &request.message) {
+                return Err(WalletError::SignerResponse(format!(
+                    "Invalid signature for public key {}",
+                    hex::encode(request.public_key.to_bytes())
+                )));
+            }
+
+            signatures.push(Some(signature));
+        }
+
+        Ok(signatures)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_remote_signer() -> anyhow::Result<()> {
+        let master_sk = SecretKey::from_seed(&[1; 32]);
+        let derivation = Derivation {
+            derivation_index: 3,
+            is_hardened: false,
+        };
+        let synthetic_sk = master_to_wallet_unhardened_intermediate(&master_sk)
+            .derive_unhardened(3)
+            .derive_synthetic();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let address = listener.local_addr()?.to_string();
+
+        // A minimal signing daemon which holds the key and answers with the keychain signer
+        let daemon_sk = master_sk.clone();
+        let daemon = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await?;
+            let (reader, mut writer) = stream.into_split();
+            let mut line = String::new();
+            BufReader::new(reader).read_line(&mut line).await?;
+
+            let value: serde_json::Value = serde_json::from_str(&line)?;
+            let mut requests = Vec::new();
+
+            for request in value["requests"].as_array().into_iter().flatten() {
+                let public_key: [u8; 48] = hex::decode(request["public_key"].as_str().unwrap())?
+                    .try_into()
+                    .unwrap();
+                requests.push(SignatureRequest {
+                    public_key: PublicKey::from_bytes(&public_key)?,
+                    derivation: request["derivation_index"]
+                        .as_u64()
+                        .map(|index| Derivation {
+                            derivation_index: index as u32,
+                            is_hardened: request["hardened"].as_bool().unwrap(),
+                        }),
+                    message: hex::decode(request["message"].as_str().unwrap())?,
+                });
+            }
+
+            let signatures: Vec<Option<String>> = KeychainSigner::new(daemon_sk)
+                .sign(&requests)
+                .await?
+                .into_iter()
+                .map(|signature| signature.map(|signature| hex::encode(signature.to_bytes())))
+                .collect();
+
+            let response = serde_json::json!({ "signatures": signatures }).to_string();
+            writer.write_all(format!("{response}\n").as_bytes()).await?;
+
+            anyhow::Ok(())
+        });
+
+        let requests = [
+            SignatureRequest {
+                public_key: synthetic_sk.public_key(),
+                derivation: Some(derivation),
+                message: b"hello".to_vec(),
+            },
+            SignatureRequest {
+                public_key: SecretKey::from_seed(&[2; 32]).public_key(),
+                derivation: None,
+                message: b"hello".to_vec(),
+            },
+        ];
+
+        let signatures = RemoteSigner::new(&address, 1234)?.sign(&requests).await?;
+        daemon.await??;
+
+        assert_eq!(signatures, vec![Some(sign(&synthetic_sk, b"hello")), None]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_keychain_signer_hardened_keys() -> anyhow::Result<()> {
+        let master_sk = SecretKey::from_seed(&[1; 32]);
+        let synthetic_key = master_to_wallet_hardened_intermediate(&master_sk)
+            .derive_hardened(5)
+            .derive_synthetic()
+            .public_key();
+
+        let signer = KeychainSigner::new(master_sk.clone());
+
+        assert_eq!(
+            signer.hardened_public_keys(5..6).await?,
+            Some(vec![synthetic_key])
+        );
+
+        let requests = [SignatureRequest {
+            public_key: master_sk.public_key(),
+            derivation: None,
+            message: b"hello".to_vec(),
+        }];
+
+        assert_eq!(
+            signer.sign(&requests).await?,
+            vec![Some(sign(&master_sk, b"hello"))]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remote_signer_requires_loopback() {
+        assert!(RemoteSigner::new("127.0.0.1:9256", 0).is_ok());
+        assert!(RemoteSigner::new("[::1]:9256", 0).is_ok());
+        assert!(matches!(
+            RemoteSigner::new("192.168.1.10:9256", 0),
+            Err(WalletError::InvalidSignerAddress(_))
+        ));
+        assert!(matches!(
+            RemoteSigner::new("localhost", 0),
+            Err(WalletError::InvalidSignerAddress(_))
+        ));
+    }
+}
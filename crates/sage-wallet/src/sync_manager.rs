@@ -2,7 +2,7 @@ use std::{
     fmt,
     net::{IpAddr, SocketAddr},
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use chia_traits::Streamable;
@@ -11,6 +11,7 @@ use chia_wallet_sdk::{
     client::{ClientError, Connector},
     prelude::*,
 };
+use cross_validation::{StateQuery, confirm_coin_states};
 use futures_lite::future::poll_once;
 use itertools::Itertools;
 use sage_config::Network;
@@ -20,29 +21,43 @@ use tokio::{
     time::{sleep, timeout},
 };
 use tracing::{debug, info, warn};
-use wallet_sync::{add_new_subscriptions, incremental_sync, sync_wallet};
+use wallet_sync::{
+    add_new_subscriptions, find_fork_height, incremental_sync, rollback_to_fork, sync_wallet,
+};
 
 use crate::{
-    BlockTimeQueue, CatQueue, NftUriQueue, OfferQueue, PuzzleQueue, TransactionQueue, Wallet,
-    WalletError,
+    BlockTimeQueue, BroadcastQueue, CatQueue, HeaderQueue, NftUriQueue, OfferQueue, PuzzleQueue,
+    TransactionQueue, Wallet, WalletError, WalletPeer,
 };
 
+mod certificate_pin;
+mod cross_validation;
 mod dns;
 mod options;
 mod peer_discovery;
+mod peer_reputation;
 mod peer_state;
+#[cfg(feature = "simulator")]
+mod simulator;
+mod socks;
 mod sync_command;
 mod sync_event;
+mod sync_progress;
+mod trusted_peer;
 mod wallet_sync;
 
 pub use options::*;
+pub use peer_reputation::*;
 pub use peer_state::*;
+pub use socks::*;
 pub use sync_command::*;
 pub use sync_event::*;
+pub use sync_progress::*;
 
 pub struct SyncManager {
     options: SyncOptions,
     state: Arc<Mutex<PeerState>>,
+    progress: Arc<Mutex<SyncProgressTracker>>,
     wallet: Option<Arc<Wallet>>,
     network: Network,
     connector: Connector,
@@ -54,10 +69,16 @@ pub struct SyncManager {
     cat_queue_task: Option<JoinHandle<Result<(), WalletError>>>,
     nft_uri_queue_task: Option<JoinHandle<Result<(), WalletError>>>,
     transaction_queue_task: Option<JoinHandle<Result<(), WalletError>>>,
+    broadcast_queue_task: Option<JoinHandle<Result<(), WalletError>>>,
     offer_queue_task: Option<JoinHandle<Result<(), WalletError>>>,
     blocktime_queue_task: Option<JoinHandle<Result<(), WalletError>>>,
+    header_queue_task: Option<JoinHandle<Result<(), WalletError>>>,
+    deferred_commands: Vec<SyncCommand>,
     pending_coin_subscriptions: Vec<Bytes32>,
     pending_puzzle_subscriptions: Vec<Bytes32>,
+    trusted_peer_retry_at: Option<Instant>,
+    #[cfg(feature = "simulator")]
+    simulator: Option<Arc<chia_wallet_sdk::test::PeerSimulator>>,
 }
 
 impl fmt::Debug for SyncManager {
@@ -94,12 +115,18 @@ impl Drop for SyncManager {
         if let Some(task) = &mut self.transaction_queue_task {
             task.abort();
         }
+        if let Some(task) = &mut self.broadcast_queue_task {
+            task.abort();
+        }
         if let Some(task) = &mut self.offer_queue_task {
             task.abort();
         }
         if let Some(task) = &mut self.blocktime_queue_task {
             task.abort();
         }
+        if let Some(task) = &mut self.header_queue_task {
+            task.abort();
+        }
     }
 }
 
@@ -107,6 +134,7 @@ impl SyncManager {
     pub fn new(
         options: SyncOptions,
         state: Arc<Mutex<PeerState>>,
+        progress: Arc<Mutex<SyncProgressTracker>>,
         wallet: Option<Arc<Wallet>>,
         network: Network,
         connector: Connector,
@@ -117,6 +145,7 @@ impl SyncManager {
         let manager = Self {
             options,
             state,
+            progress,
             wallet,
             network,
             connector,
@@ -128,10 +157,16 @@ impl SyncManager {
             cat_queue_task: None,
             nft_uri_queue_task: None,
             transaction_queue_task: None,
+            broadcast_queue_task: None,
             offer_queue_task: None,
             blocktime_queue_task: None,
+            header_queue_task: None,
+            deferred_commands: Vec::new(),
             pending_coin_subscriptions: Vec::new(),
             pending_puzzle_subscriptions: Vec::new(),
+            trusted_peer_retry_at: None,
+            #[cfg(feature = "simulator")]
+            simulator: None,
         };
 
         (manager, command_sender, event_receiver)
@@ -142,16 +177,23 @@ impl SyncManager {
             self.process_commands().await;
             self.update().await;
             self.subscribe().await;
+            self.report_progress().await;
             sleep(self.options.timeouts.sync_delay).await;
         }
     }
 
     async fn process_commands(&mut self) {
-        while let Ok(command) = self.command_receiver.try_recv() {
+        while let Some(command) = self.next_command() {
+            if self.options.offline && command.requires_network() {
+                self.deferred_commands.push(command);
+                continue;
+            }
+
             match command {
                 SyncCommand::SwitchWallet { wallet, delta_sync } => {
                     self.clear_subscriptions().await;
                     self.abort_wallet_tasks();
+                    self.progress.lock().await.reset();
                     self.wallet = wallet;
                     self.options.delta_sync = delta_sync;
                 }
@@ -159,10 +201,13 @@ impl SyncManager {
                     if self.network.network_id() != network.network_id()
                         || self.network.genesis_challenge != network.genesis_challenge
                         || self.network.default_port != network.default_port
+                        || self.network.trusted_peer != network.trusted_peer
                     {
                         self.state.lock().await.reset();
+                        self.progress.lock().await.reset();
                         self.abort_wallet_tasks();
                         self.network = network;
+                        self.trusted_peer_retry_at = None;
                         let _ = self
                             .event_sender
                             .send(SyncEvent::NetworkChanged {
@@ -190,7 +235,8 @@ impl SyncManager {
                     .await;
                 }
                 SyncCommand::AddPeer { peer, receiver } => {
-                    self.try_add_peer(peer, receiver, true, false).await;
+                    self.try_add_peer(WalletPeer::new(peer), receiver, true, false)
+                        .await;
                 }
                 SyncCommand::SubscribeCoins { coin_ids } => {
                     self.pending_coin_subscriptions.extend(coin_ids);
@@ -208,10 +254,80 @@ impl SyncManager {
                 SyncCommand::SetDiscoverPeers(discover_peers) => {
                     self.options.discover_peers = discover_peers;
                 }
+                SyncCommand::SetParanoidSync(paranoid) => {
+                    self.options.paranoid = paranoid;
+                }
+                SyncCommand::SetProxy(proxy) => {
+                    if self.options.proxy != proxy {
+                        // Existing connections don't go through the new proxy
+                        self.state.lock().await.reset();
+                        self.progress.lock().await.reset();
+                        self.abort_wallet_tasks();
+                        self.options.proxy = proxy;
+                        self.trusted_peer_retry_at = None;
+                    }
+                }
+                #[cfg(feature = "simulator")]
+                SyncCommand::SetSimulator(simulator) => {
+                    self.simulator = Some(simulator);
+                }
+                SyncCommand::Rollback { fork_height } => {
+                    let Some(peer) = self.state.lock().await.acquire_peer() else {
+                        continue;
+                    };
+
+                    if let Err(error) = self.rollback(&peer, fork_height).await {
+                        warn!("Failed to roll back to height {fork_height}: {error}");
+                    }
+                }
+                SyncCommand::SetOffline(offline) => {
+                    if self.options.offline != offline {
+                        info!(
+                            "Switching to {} mode",
+                            if offline { "offline" } else { "online" }
+                        );
+
+                        self.options.offline = offline;
+                        self.trusted_peer_retry_at = None;
+
+                        if offline {
+                            let mut state = self.state.lock().await;
+
+                            for peer in state.peers() {
+                                state.remove_peer(peer.socket_addr().ip());
+                            }
+                        }
+                    }
+                }
             }
         }
     }
 
+    /// Commands that were deferred while offline are replayed before any new ones once the
+    /// wallet is back online.
+    fn next_command(&mut self) -> Option<SyncCommand> {
+        if !self.options.offline && !self.deferred_commands.is_empty() {
+            return Some(self.deferred_commands.remove(0));
+        }
+
+        self.command_receiver.try_recv().ok()
+    }
+
+    async fn report_progress(&self) {
+        if self.wallet.is_none() {
+            return;
+        }
+
+        let progress = self.progress.lock().await.sample(Instant::now());
+
+        if let Some(progress) = progress {
+            self.event_sender
+                .send(SyncEvent::Progress(progress))
+                .await
+                .ok();
+        }
+    }
+
     async fn subscribe(&mut self) {
         if self.pending_coin_subscriptions.is_empty()
             && self.pending_puzzle_subscriptions.is_empty()
@@ -240,6 +356,8 @@ impl SyncManager {
         if let Err(error) = add_new_subscriptions(
             wallet,
             &peer,
+            self.options.paranoid.then_some(&self.state),
+            &self.progress,
             self.pending_coin_subscriptions.clone(),
             self.pending_puzzle_subscriptions.clone(),
             self.event_sender.clone(),
@@ -277,15 +395,21 @@ impl SyncManager {
         if let Some(task) = &mut self.transaction_queue_task.take() {
             task.abort();
         }
+        if let Some(task) = &mut self.broadcast_queue_task.take() {
+            task.abort();
+        }
         if let Some(task) = &mut self.offer_queue_task.take() {
             task.abort();
         }
         if let Some(task) = &mut self.blocktime_queue_task.take() {
             task.abort();
         }
+        if let Some(task) = &mut self.header_queue_task.take() {
+            task.abort();
+        }
     }
 
-    async fn handle_message(&self, ip: IpAddr, message: Message) -> Result<(), WalletError> {
+    async fn handle_message(&mut self, ip: IpAddr, message: Message) -> Result<(), WalletError> {
         match message.msg_type {
             ProtocolMessageTypes::NewPeakWallet => {
                 let message =
@@ -294,6 +418,14 @@ impl SyncManager {
                     .lock()
                     .await
                     .update_peak(ip, message.height, message.header_hash);
+
+                if let InitialWalletSync::Subscribed(subscribed_ip) = self.initial_wallet_sync
+                    && subscribed_ip == ip
+                {
+                    self.check_reorg(ip, message.height).await?;
+                    self.advance_checkpoint(ip, message.height, message.header_hash)
+                        .await?;
+                }
             }
             ProtocolMessageTypes::CoinStateUpdate => {
                 let message =
@@ -319,6 +451,44 @@ impl SyncManager {
 
                     let spent_count = spent_coin_ids.len();
 
+                    // Pushed updates are confirmed the same way as the initial sync, so a
+                    // malicious peer can't slip in fake coin states after it's trusted
+                    if self.options.paranoid {
+                        let Some(peer) = self
+                            .state
+                            .lock()
+                            .await
+                            .peer(ip)
+                            .map(|info| info.peer.clone())
+                        else {
+                            return Ok(());
+                        };
+
+                        let query = StateQuery::Coins {
+                            coin_ids: message
+                                .items
+                                .iter()
+                                .map(|item| item.coin.coin_id())
+                                .collect(),
+                            previous_height: None,
+                            header_hash: wallet.genesis_challenge,
+                        };
+
+                        confirm_coin_states(
+                            &self.state,
+                            &peer,
+                            &query,
+                            &message.items,
+                            Some(message.height),
+                        )
+                        .await?;
+                    }
+
+                    self.progress
+                        .lock()
+                        .await
+                        .add_coin_states(message.items.len().try_into()?);
+
                     if !spent_coin_ids.is_empty()
                         && let InitialWalletSync::Subscribed(ip) = self.initial_wallet_sync
                         && let Some(info) = self.state.lock().await.peer(ip)
@@ -352,10 +522,103 @@ impl SyncManager {
         Ok(())
     }
 
+    /// Moves the wallet's sync checkpoint up to the new peak of the peer it's subscribed to, as
+    /// long as there are no subscriptions waiting to be added that could have missed updates.
+    async fn advance_checkpoint(
+        &self,
+        ip: IpAddr,
+        height: u32,
+        header_hash: Bytes32,
+    ) -> Result<(), WalletError> {
+        let Some(wallet) = self.wallet.as_ref() else {
+            return Ok(());
+        };
+
+        if !matches!(self.initial_wallet_sync, InitialWalletSync::Subscribed(subscribed_ip) if subscribed_ip == ip)
+            || !self.pending_coin_subscriptions.is_empty()
+            || !self.pending_puzzle_subscriptions.is_empty()
+        {
+            return Ok(());
+        }
+
+        wallet
+            .db
+            .advance_sync_checkpoint(height, header_hash)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Checks whether the wallet's recorded blocks are still part of the peer's chain, and if not,
+    /// rolls back to the fork point and restarts the wallet sync from there.
+    async fn check_reorg(&mut self, ip: IpAddr, peak_height: u32) -> Result<(), WalletError> {
+        let Some(wallet) = self.wallet.clone() else {
+            return Ok(());
+        };
+
+        let Some(peer) = self
+            .state
+            .lock()
+            .await
+            .peer(ip)
+            .map(|info| info.peer.clone())
+        else {
+            return Ok(());
+        };
+
+        let Some(fork_height) = find_fork_height(&wallet, &peer, peak_height).await? else {
+            return Ok(());
+        };
+
+        warn!("Chain reorganization detected, rolling back to height {fork_height}");
+
+        self.rollback(&peer, fork_height).await
+    }
+
+    /// Rolls the wallet back to the fork point and restarts the wallet sync from there.
+    async fn rollback(&mut self, peer: &WalletPeer, fork_height: u32) -> Result<(), WalletError> {
+        let Some(wallet) = self.wallet.clone() else {
+            return Ok(());
+        };
+
+        rollback_to_fork(&wallet, peer, fork_height).await?;
+
+        if let InitialWalletSync::Syncing { task, .. } =
+            std::mem::take(&mut self.initial_wallet_sync)
+        {
+            task.abort();
+        }
+
+        self.event_sender
+            .send(SyncEvent::Reorg { fork_height })
+            .await
+            .ok();
+        self.event_sender.send(SyncEvent::CoinsUpdated).await.ok();
+
+        Ok(())
+    }
+
     async fn update(&mut self) {
+        if !self.options.offline {
+            self.connect_peers().await;
+        }
+
+        self.update_tasks().await;
+        self.poll_tasks().await;
+    }
+
+    async fn connect_peers(&mut self) {
         let peer_count = self.state.lock().await.peer_count();
 
-        if peer_count < self.options.target_peers && self.options.discover_peers {
+        // Without the simulator feature there's nothing to connect to on a simulator network
+        if self.network.simulator {
+            #[cfg(feature = "simulator")]
+            if let Some(simulator) = self.simulator.clone() {
+                self.connect_simulator(&simulator).await;
+            }
+        } else if let Some(trusted_peer) = self.network.trusted_peer.clone() {
+            self.connect_trusted_peer(&trusted_peer).await;
+        } else if peer_count < self.options.target_peers && self.options.discover_peers {
             if peer_count > 0 {
                 if !self.peer_discovery().await && !self.dns_discovery().await {
                     self.introducer_discovery().await;
@@ -364,9 +627,6 @@ impl SyncManager {
                 self.introducer_discovery().await;
             }
         }
-
-        self.update_tasks().await;
-        self.poll_tasks().await;
     }
 
     async fn update_tasks(&mut self) {
@@ -382,9 +642,11 @@ impl SyncManager {
                         wallet.clone(),
                         peer,
                         self.state.clone(),
+                        self.progress.clone(),
                         self.event_sender.clone(),
                         self.command_sender.clone(),
                         self.options.delta_sync,
+                        self.options.paranoid,
                     ));
                     *sync = InitialWalletSync::Syncing { ip, task };
                     self.event_sender.send(SyncEvent::Start(ip)).await.ok();
@@ -414,6 +676,7 @@ impl SyncManager {
                         wallet.genesis_challenge,
                         self.options.puzzle_batch_size_per_peer,
                         self.state.clone(),
+                        self.progress.clone(),
                         self.event_sender.clone(),
                         self.command_sender.clone(),
                     )
@@ -440,6 +703,7 @@ impl SyncManager {
                 let task = tokio::spawn(
                     NftUriQueue::new(
                         wallet.db.clone(),
+                        self.progress.clone(),
                         self.event_sender.clone(),
                         self.network.clone(),
                     )
@@ -452,14 +716,30 @@ impl SyncManager {
                 let task = tokio::spawn(
                     TransactionQueue::new(
                         wallet.db.clone(),
+                        wallet.genesis_challenge,
                         self.state.clone(),
                         self.event_sender.clone(),
+                        self.options.rebroadcast,
                     )
                     .start(self.options.timeouts.transaction_delay),
                 );
                 self.transaction_queue_task = Some(task);
             }
 
+            if self.broadcast_queue_task.is_none() {
+                let task = tokio::spawn(
+                    BroadcastQueue::new(
+                        wallet.db.clone(),
+                        wallet.genesis_challenge,
+                        self.state.clone(),
+                        self.event_sender.clone(),
+                        self.command_sender.clone(),
+                    )
+                    .start(self.options.timeouts.broadcast_delay),
+                );
+                self.broadcast_queue_task = Some(task);
+            }
+
             if self.offer_queue_task.is_none() {
                 let task = tokio::spawn(
                     OfferQueue::new(
@@ -478,19 +758,34 @@ impl SyncManager {
                     BlockTimeQueue::new(
                         wallet.db.clone(),
                         self.state.clone(),
+                        self.progress.clone(),
                         self.event_sender.clone(),
                     )
                     .start(self.options.timeouts.blocktime_delay),
                 );
                 self.blocktime_queue_task = Some(task);
             }
+
+            if self.header_queue_task.is_none() && !self.options.testing {
+                let task = tokio::spawn(
+                    HeaderQueue::new(
+                        wallet.db.clone(),
+                        self.state.clone(),
+                        self.command_sender.clone(),
+                    )
+                    .start(self.options.timeouts.header_delay),
+                );
+                self.header_queue_task = Some(task);
+            }
         } else {
             self.puzzle_lookup_task = None;
             self.cat_queue_task = None;
             self.nft_uri_queue_task = None;
             self.transaction_queue_task = None;
+            self.broadcast_queue_task = None;
             self.offer_queue_task = None;
             self.blocktime_queue_task = None;
+            self.header_queue_task = None;
         }
     }
 
@@ -503,6 +798,25 @@ impl SyncManager {
                     self.initial_wallet_sync = InitialWalletSync::Subscribed(*ip);
                     self.event_sender.send(SyncEvent::Subscribed).await.ok();
                 }
+                Ok(Err(WalletError::Reorg)) => {
+                    let ip = *ip;
+                    self.initial_wallet_sync = InitialWalletSync::Idle;
+
+                    let peak = self.state.lock().await.peak_of(ip);
+
+                    if let Some((height, _)) = peak
+                        && let Err(error) = self.check_reorg(ip, height).await
+                    {
+                        warn!("Failed to roll back reorged blocks: {error}");
+                        self.state.lock().await.ban(
+                            ip,
+                            Duration::from_mins(5),
+                            "failed to roll back reorg",
+                        );
+                    }
+
+                    self.event_sender.send(SyncEvent::Stop).await.ok();
+                }
                 Ok(Err(error)) => {
                     warn!("Initial wallet sync failed: {error}");
                     self.state
@@ -514,11 +828,7 @@ impl SyncManager {
                 }
                 Err(_timeout) => {
                     warn!("Initial wallet sync timed out");
-                    self.state.lock().await.ban(
-                        *ip,
-                        Duration::from_mins(5),
-                        "wallet sync timed out",
-                    );
+                    self.state.lock().await.record_timeout(*ip);
                     self.initial_wallet_sync = InitialWalletSync::Idle;
                     self.event_sender.send(SyncEvent::Stop).await.ok();
                 }
@@ -593,6 +903,23 @@ impl SyncManager {
             }
         }
 
+        if let Some(task) = &mut self.broadcast_queue_task {
+            match poll_once(task).await {
+                Some(Err(error)) => {
+                    warn!("Broadcast queue failed with panic: {error}");
+                    self.broadcast_queue_task = None;
+                }
+                Some(Ok(Err(error))) => {
+                    warn!("Broadcast queue failed with error: {error}");
+                    self.broadcast_queue_task = None;
+                }
+                Some(Ok(Ok(()))) => {
+                    self.broadcast_queue_task = None;
+                }
+                None => {}
+            }
+        }
+
         if let Some(task) = &mut self.offer_queue_task {
             match poll_once(task).await {
                 Some(Err(error)) => {
@@ -626,5 +953,22 @@ impl SyncManager {
                 None => {}
             }
         }
+
+        if let Some(task) = &mut self.header_queue_task {
+            match poll_once(task).await {
+                Some(Err(error)) => {
+                    warn!("Header queue failed with panic: {error}");
+                    self.header_queue_task = None;
+                }
+                Some(Ok(Err(error))) => {
+                    warn!("Header queue failed with error: {error}");
+                    self.header_queue_task = None;
+                }
+                Some(Ok(Ok(()))) => {
+                    self.header_queue_task = None;
+                }
+                None => {}
+            }
+        }
     }
 }
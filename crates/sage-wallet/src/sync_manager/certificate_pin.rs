@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use chia_sha2::Sha256;
+use chia_wallet_sdk::{client::Connector, prelude::*};
+use rustls::{ClientConfig, crypto::aws_lc_rs::default_provider};
+
+/// Only accepts a server certificate whose SHA-256 hash matches the pin.
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    pin: Bytes32,
+}
+
+impl rustls::client::danger::ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls::pki_types::CertificateDer<'_>,
+        _intermediates: &[rustls::pki_types::CertificateDer<'_>],
+        _server_name: &rustls::pki_types::ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: rustls::pki_types::UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        let mut hasher = Sha256::new();
+        hasher.update(end_entity.as_ref());
+
+        if Bytes32::new(hasher.finalize()) == self.pin {
+            Ok(rustls::client::danger::ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(
+                "Server certificate doesn't match the pin".into(),
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &default_provider().signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &default_provider().signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        default_provider()
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// Copies the connector's TLS config, but only trusts the pinned certificate. Plain
+/// connections can't be pinned, so there's no connector for them.
+pub fn pinned_connector(connector: &Connector, pin: Bytes32) -> Option<Connector> {
+    let Connector::Rustls(config) = connector else {
+        return None;
+    };
+
+    let mut config = ClientConfig::clone(config);
+    config
+        .dangerous()
+        .set_certificate_verifier(Arc::new(PinnedCertVerifier { pin }));
+
+    Some(Connector::Rustls(Arc::new(config)))
+}
@@ -0,0 +1,290 @@
+use std::{cmp::Reverse, collections::HashMap, net::IpAddr, sync::Arc, time::Duration};
+
+use chia_wallet_sdk::{chia::protocol::CoinStateFilters, prelude::*};
+use itertools::Itertools;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+use crate::{WalletError, WalletPeer};
+
+use super::PeerState;
+
+/// A coin state request that can be repeated against other peers.
+#[derive(Debug, Clone)]
+pub enum StateQuery {
+    Coins {
+        coin_ids: Vec<Bytes32>,
+        previous_height: Option<u32>,
+        header_hash: Bytes32,
+    },
+    Puzzles {
+        puzzle_hashes: Vec<Bytes32>,
+        previous_height: Option<u32>,
+        header_hash: Bytes32,
+        filters: CoinStateFilters,
+    },
+}
+
+impl StateQuery {
+    /// Sends the request without subscribing, and returns the coin states along with the
+    /// height they're up to date with, if the peer reports it.
+    async fn send(&self, peer: &WalletPeer) -> Result<(Vec<CoinState>, Option<u32>), WalletError> {
+        match self {
+            Self::Coins {
+                coin_ids,
+                previous_height,
+                header_hash,
+            } => {
+                let coin_states = peer
+                    .request_coin_states(coin_ids.clone(), *previous_height, *header_hash)
+                    .await?;
+
+                Ok((coin_states, None))
+            }
+            Self::Puzzles {
+                puzzle_hashes,
+                previous_height,
+                header_hash,
+                filters,
+            } => {
+                let data = peer
+                    .request_puzzle_states(
+                        puzzle_hashes.clone(),
+                        *previous_height,
+                        *header_hash,
+                        *filters,
+                    )
+                    .await?;
+
+                Ok((data.coin_states, Some(data.height)))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Observation {
+    ip: IpAddr,
+    coin_states: Vec<CoinState>,
+    height: u32,
+}
+
+/// Repeats a request against other peers and compares their coin states with the ones received
+/// from the peer the wallet is syncing from. If they disagree, a third peer breaks the tie, and
+/// whichever peer is outvoted gets banned. If there's no majority, the discrepancy is recorded
+/// for both peers, but neither is banned, since there's no way to tell which one is lying.
+///
+/// Returns an error if the peer the wallet is syncing from can't be trusted.
+pub async fn confirm_coin_states(
+    state: &Arc<Mutex<PeerState>>,
+    peer: &WalletPeer,
+    query: &StateQuery,
+    coin_states: &[CoinState],
+    height: Option<u32>,
+) -> Result<(), WalletError> {
+    let ip = peer.socket_addr().ip();
+
+    let (witnesses, peak) = {
+        let state = state.lock().await;
+
+        let witnesses = state
+            .peers_with_heights()
+            .into_iter()
+            .filter(|(witness, _)| witness.socket_addr().ip() != ip)
+            .sorted_by_key(|(_, height)| Reverse(*height))
+            .collect_vec();
+
+        (witnesses, state.peak_of(ip).map(|(height, _)| height))
+    };
+
+    let primary = Observation {
+        ip,
+        coin_states: coin_states.to_vec(),
+        height: height.or(peak).unwrap_or(u32::MAX),
+    };
+
+    let mut dissent: Option<Observation> = None;
+
+    for (witness, witness_peak) in witnesses {
+        let witness_ip = witness.socket_addr().ip();
+
+        let (coin_states, height) = match query.send(&witness).await {
+            Ok(response) => response,
+            Err(error) => {
+                debug!("Failed to confirm coin states with peer {witness_ip}: {error}");
+                continue;
+            }
+        };
+
+        let observation = Observation {
+            ip: witness_ip,
+            coin_states,
+            height: height.unwrap_or(witness_peak),
+        };
+
+        let Some(first) = dissent.take() else {
+            if differences(&primary, &observation) == 0 {
+                return Ok(());
+            }
+
+            dissent = Some(observation);
+            continue;
+        };
+
+        if differences(&primary, &observation) == 0 {
+            reject(state, &first, &primary, true).await;
+            return Ok(());
+        }
+
+        if differences(&first, &observation) == 0 {
+            reject(state, &primary, &first, true).await;
+        } else {
+            reject(state, &first, &primary, false).await;
+            reject(state, &primary, &first, false).await;
+        }
+
+        return Err(WalletError::PeerDisagreement);
+    }
+
+    if let Some(first) = dissent {
+        // Without a third peer to break the tie, neither peer can be trusted
+        reject(state, &first, &primary, false).await;
+        reject(state, &primary, &first, false).await;
+        return Err(WalletError::PeerDisagreement);
+    }
+
+    warn!("No other peers are available to confirm coin states from peer {ip}");
+
+    Ok(())
+}
+
+/// Records that a peer disagreed with another, and bans it if it was outvoted by a majority.
+async fn reject(
+    state: &Arc<Mutex<PeerState>>,
+    rejected: &Observation,
+    other: &Observation,
+    outvoted: bool,
+) {
+    let count = differences(rejected, other);
+
+    warn!(
+        "Peer {} disagrees with peer {} about {count} coin states",
+        rejected.ip, other.ip
+    );
+
+    let mut state = state.lock().await;
+
+    state.record_discrepancy(
+        rejected.ip,
+        format!(
+            "Disagreed with peer {} about the state of {count} coins",
+            other.ip
+        ),
+    );
+
+    if outvoted {
+        state.ban(rejected.ip, Duration::from_hours(1), "coin states disagree");
+    }
+}
+
+/// Counts the coins whose state differs between two peers, ignoring anything that happened
+/// after the lower of their peaks, since peers are rarely at exactly the same height.
+fn differences(a: &Observation, b: &Observation) -> usize {
+    let height = a.height.min(b.height);
+
+    let a = confirmed_states(&a.coin_states, height);
+    let b = confirmed_states(&b.coin_states, height);
+
+    a.keys()
+        .chain(b.keys())
+        .unique()
+        .filter(|coin_id| a.get(coin_id) != b.get(coin_id))
+        .count()
+}
+
+fn confirmed_states(coin_states: &[CoinState], height: u32) -> HashMap<Bytes32, Option<u32>> {
+    coin_states
+        .iter()
+        .filter(|cs| cs.created_height.is_some_and(|created| created <= height))
+        .map(|cs| {
+            (
+                cs.coin.coin_id(),
+                cs.spent_height.filter(|&spent| spent <= height),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coin_state(amount: u64, created_height: u32, spent_height: Option<u32>) -> CoinState {
+        CoinState::new(
+            Coin::new(Bytes32::default(), Bytes32::default(), amount),
+            spent_height,
+            Some(created_height),
+        )
+    }
+
+    fn observation(coin_states: Vec<CoinState>, height: u32) -> Observation {
+        Observation {
+            ip: IpAddr::from([127, 0, 0, 1]),
+            coin_states,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_differences_ignore_blocks_past_lower_peak() {
+        let a = observation(vec![coin_state(1, 10, None)], 20);
+        let b = observation(
+            vec![coin_state(1, 10, Some(25)), coin_state(2, 22, None)],
+            30,
+        );
+
+        assert_eq!(differences(&a, &b), 0);
+    }
+
+    #[test]
+    fn test_differences_hidden_and_invented_coins() {
+        let a = observation(vec![coin_state(1, 10, None), coin_state(2, 12, None)], 20);
+        let b = observation(vec![coin_state(1, 10, Some(15))], 20);
+
+        assert_eq!(differences(&a, &b), 2);
+    }
+
+    #[tokio::test]
+    async fn test_reject_without_majority_keeps_peers() {
+        let state = Arc::new(Mutex::new(PeerState::default()));
+
+        let a = observation(vec![coin_state(1, 10, None)], 20);
+        let mut b = observation(vec![coin_state(1, 10, Some(15))], 20);
+        b.ip = IpAddr::from([127, 0, 0, 2]);
+
+        reject(&state, &a, &b, false).await;
+        reject(&state, &b, &a, false).await;
+
+        let state = state.lock().await;
+
+        assert_eq!(state.discrepancies().len(), 2);
+        assert!(!state.is_banned(a.ip));
+        assert!(!state.is_banned(b.ip));
+    }
+
+    #[tokio::test]
+    async fn test_reject_outvoted_peer_is_banned() {
+        let state = Arc::new(Mutex::new(PeerState::default()));
+
+        let a = observation(vec![coin_state(1, 10, None)], 20);
+        let mut b = observation(vec![coin_state(1, 10, Some(15))], 20);
+        b.ip = IpAddr::from([127, 0, 0, 2]);
+
+        reject(&state, &a, &b, true).await;
+
+        let state = state.lock().await;
+
+        assert!(state.is_banned(a.ip));
+        assert!(!state.is_banned(b.ip));
+    }
+}
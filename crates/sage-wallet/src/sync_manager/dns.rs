@@ -4,11 +4,17 @@ use futures_lite::StreamExt;
 use futures_util::stream::FuturesUnordered;
 use tracing::{info, warn};
 
+use super::SocksProxy;
+
+/// How many times a DNS introducer is resolved through the proxy, to collect several addresses.
+const PROXY_DNS_LOOKUPS: usize = 4;
+
 pub async fn lookup_all(
     hosts: &[String],
     port: u16,
     timeout: Duration,
     batch_size: usize,
+    proxy: Option<SocksProxy>,
 ) -> Vec<SocketAddr> {
     let mut result = Vec::new();
 
@@ -17,7 +23,8 @@ pub async fn lookup_all(
 
         for dns_introducer in batch {
             futures.push(async move {
-                match tokio::time::timeout(timeout, lookup_host(dns_introducer, port)).await {
+                match tokio::time::timeout(timeout, lookup_host(dns_introducer, port, proxy)).await
+                {
                     Ok(Ok(addrs)) => addrs,
                     Ok(Err(error)) => {
                         warn!("Failed to lookup DNS introducer {dns_introducer}: {error}");
@@ -39,8 +46,24 @@ pub async fn lookup_all(
     result
 }
 
-async fn lookup_host(dns_introducer: &str, port: u16) -> Result<Vec<SocketAddr>, Error> {
+async fn lookup_host(
+    dns_introducer: &str,
+    port: u16,
+    proxy: Option<SocksProxy>,
+) -> Result<Vec<SocketAddr>, Error> {
     info!("Looking up DNS introducer {dns_introducer}");
+
+    // The proxy only resolves a single address per lookup, but a local lookup would leak
+    // the request
+    if let Some(proxy) = proxy {
+        return Ok(proxy
+            .resolve_all(dns_introducer, PROXY_DNS_LOOKUPS)
+            .await?
+            .into_iter()
+            .map(|ip| SocketAddr::new(ip, port))
+            .collect());
+    }
+
     let mut result = Vec::new();
     for addr in tokio::net::lookup_host(format!("{dns_introducer}:80")).await? {
         result.push(SocketAddr::new(addr.ip(), port));
@@ -1,5 +1,7 @@
 use std::time::Duration;
 
+use super::SocksProxy;
+
 #[derive(Debug, Clone, Copy)]
 pub struct SyncOptions {
     pub target_peers: usize,
@@ -7,12 +9,41 @@ pub struct SyncOptions {
     pub dns_batch_size: usize,
     pub connection_batch_size: usize,
     pub max_peer_age_seconds: u64,
+    /// Trusts what changed since the sync checkpoint after confirming the first batch with other
+    /// peers. When disabled, every batch synced from the checkpoint is confirmed.
     pub delta_sync: bool,
+    pub paranoid: bool,
+    /// Doesn't connect to any peers, and defers commands that need the network until the
+    /// wallet goes back online.
+    pub offline: bool,
+    pub proxy: Option<SocksProxy>,
     pub puzzle_batch_size_per_peer: usize,
+    pub rebroadcast: RebroadcastPolicy,
     pub timeouts: Timeouts,
     pub testing: bool,
 }
 
+/// Controls how pending transactions are resubmitted, and when they are given up on.
+#[derive(Debug, Clone, Copy)]
+pub struct RebroadcastPolicy {
+    pub interval: Duration,
+    /// Transactions that haven't been confirmed within this time are assumed to have been
+    /// evicted from the mempool, and are removed.
+    pub max_age: Duration,
+    /// Transactions are removed after this many resubmissions that no peer acknowledged.
+    pub max_retries: u32,
+}
+
+impl Default for RebroadcastPolicy {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(120),
+            max_age: Duration::from_hours(24),
+            max_retries: 30,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Timeouts {
     pub sync_delay: Duration,
@@ -20,8 +51,10 @@ pub struct Timeouts {
     pub nft_uri_delay: Duration,
     pub puzzle_delay: Duration,
     pub transaction_delay: Duration,
+    pub broadcast_delay: Duration,
     pub offer_delay: Duration,
     pub blocktime_delay: Duration,
+    pub header_delay: Duration,
     pub connection: Duration,
     pub initial_peak: Duration,
     pub dns: Duration,
@@ -36,8 +69,10 @@ impl Default for Timeouts {
             nft_uri_delay: Duration::from_millis(500),
             puzzle_delay: Duration::from_secs(1),
             transaction_delay: Duration::from_secs(1),
+            broadcast_delay: Duration::from_secs(5),
             offer_delay: Duration::from_secs(5),
             blocktime_delay: Duration::from_secs(1),
+            header_delay: Duration::from_secs(1),
             connection: Duration::from_secs(3),
             initial_peak: Duration::from_secs(2),
             dns: Duration::from_secs(3),
@@ -13,7 +13,7 @@ use chia_wallet_sdk::{
         ChiaProtocolMessage, Handshake, Message, NewPeakWallet, NodeType, ProtocolMessageTypes,
         TimestampedPeerInfo,
     },
-    client::{ClientError, connect_peer},
+    client::{ClientError, Connector, connect_peer},
     prelude::*,
 };
 use futures_lite::StreamExt;
@@ -24,7 +24,7 @@ use tracing::{debug, info, warn};
 
 use crate::{SyncCommand, WalletError, WalletPeer};
 
-use super::{PeerInfo, SyncManager, dns::lookup_all};
+use super::{PeerInfo, SocksProxy, SocksTarget, SyncManager, dns::lookup_all};
 
 #[derive(Streamable)]
 struct RequestPeersIntroducer {}
@@ -71,6 +71,7 @@ impl SyncManager {
             self.network.default_port,
             self.options.timeouts.dns,
             self.options.dns_batch_size,
+            self.options.proxy,
         )
         .await;
 
@@ -100,59 +101,28 @@ impl SyncManager {
             let port = self.network.default_port;
             let connector = self.connector.clone();
             let network_id = self.network.network_id();
+            let proxy = self.options.proxy;
 
             futures.push(async move {
                 let host_clone = host.clone();
 
                 let result = timeout(introducer_timeout, async move {
-                    let (peer, mut receiver) = Peer::connect_full_uri(
-                        &format!("wss://{host_clone}:{port}/ws"),
-                        connector,
-                        PeerOptions::default(),
-                    )
-                    .await?;
-
-                    peer.send(Handshake {
-                        network_id: network_id.clone(),
-                        protocol_version: "0.0.37".to_string(),
-                        software_version: "0.0.0".to_string(),
-                        server_port: 0,
-                        node_type: NodeType::Wallet,
-                        capabilities: vec![
-                            (1, "1".to_string()),
-                            (2, "1".to_string()),
-                            (3, "1".to_string()),
-                        ],
-                    })
-                    .await?;
-
-                    let Some(message) = receiver.recv().await else {
-                        return Err(ClientError::MissingHandshake)?;
+                    let uri = format!("wss://{host_clone}:{port}/ws");
+
+                    let (peer, mut receiver) = if let Some(proxy) = proxy {
+                        proxy
+                            .connect_peer(
+                                &uri,
+                                SocksTarget::Domain(&host_clone, port),
+                                connector,
+                                PeerOptions::default(),
+                            )
+                            .await?
+                    } else {
+                        Peer::connect_full_uri(&uri, connector, PeerOptions::default()).await?
                     };
 
-                    if message.msg_type != ProtocolMessageTypes::Handshake {
-                        Err(ClientError::InvalidResponse(
-                            vec![ProtocolMessageTypes::Handshake],
-                            message.msg_type,
-                        ))?;
-                    }
-
-                    let handshake =
-                        Handshake::from_bytes(&message.data).map_err(ClientError::from)?;
-
-                    if handshake.node_type != NodeType::Introducer {
-                        Err(ClientError::WrongNodeType(
-                            NodeType::Introducer,
-                            handshake.node_type,
-                        ))?;
-                    }
-
-                    if handshake.network_id != network_id {
-                        Err(ClientError::WrongNetwork(
-                            network_id.clone(),
-                            handshake.network_id,
-                        ))?;
-                    }
+                    handshake(&peer, &mut receiver, network_id, NodeType::Introducer).await?;
 
                     let peer_list = peer
                         .request_infallible::<RespondPeersIntroducer, _>(RequestPeersIntroducer {})
@@ -284,6 +254,11 @@ impl SyncManager {
         force: bool,
         user_managed: bool,
     ) -> bool {
+        if self.network.trusted_peer.is_some() {
+            debug!("Not connecting to other peers while using a trusted peer");
+            return false;
+        }
+
         let mut futures = FuturesUnordered::new();
 
         for &socket_addr in addrs {
@@ -296,11 +271,12 @@ impl SyncManager {
             let network_id = self.network.network_id();
             let connector = self.connector.clone();
             let duration = self.options.timeouts.connection;
+            let proxy = self.options.proxy;
 
             futures.push(async move {
                 let result = timeout(
                     duration,
-                    connect_peer(network_id, connector, socket_addr, PeerOptions::default()),
+                    connect_full_node(network_id, connector, socket_addr, proxy),
                 )
                 .await;
                 (socket_addr, result)
@@ -354,7 +330,7 @@ impl SyncManager {
 
     pub(crate) async fn try_add_peer(
         &mut self,
-        peer: Peer,
+        peer: WalletPeer,
         mut receiver: mpsc::Receiver<Message>,
         force: bool,
         user_managed: bool,
@@ -396,21 +372,21 @@ impl SyncManager {
             );
             return false;
         } else if force && state.peer_count() >= self.options.target_peers {
-            let mut peers = state.peers_with_heights();
+            let mut peers = state.peers_by_score();
             let mut rng = rand::thread_rng();
 
-            // Sort so user managed are deprioritized, then by height, then randomly
+            // Sort so user managed are deprioritized, then by score, then randomly
             let mut peer_rng = HashMap::new();
 
             for (peer, _) in &peers {
                 peer_rng.insert(peer.socket_addr(), rng.gen_range(0..100));
             }
 
-            peers.sort_by_key(|(peer, height)| {
+            peers.sort_by_key(|(peer, score)| {
                 let peer_info = state.peer(peer.socket_addr().ip()).expect("peer not found");
                 (
                     peer_info.user_managed,
-                    *height,
+                    *score,
                     peer_rng[&peer.socket_addr()],
                 )
             });
@@ -441,7 +417,7 @@ impl SyncManager {
         }
 
         state.add_peer(PeerInfo {
-            peer: WalletPeer::new(peer),
+            peer,
             claimed_peak: message.height,
             header_hash: message.header_hash,
             user_managed,
@@ -465,3 +441,73 @@ impl SyncManager {
         true
     }
 }
+
+pub(super) async fn connect_full_node(
+    network_id: String,
+    connector: Connector,
+    socket_addr: SocketAddr,
+    proxy: Option<SocksProxy>,
+) -> Result<(WalletPeer, mpsc::Receiver<Message>), ClientError> {
+    let Some(proxy) = proxy else {
+        let (peer, receiver) =
+            connect_peer(network_id, connector, socket_addr, PeerOptions::default()).await?;
+        return Ok((WalletPeer::new(peer), receiver));
+    };
+
+    let (peer, mut receiver) = proxy
+        .connect_peer(
+            &format!("wss://{socket_addr}/ws"),
+            SocksTarget::Addr(socket_addr),
+            connector,
+            PeerOptions::default(),
+        )
+        .await?;
+
+    handshake(&peer, &mut receiver, network_id, NodeType::FullNode).await?;
+
+    Ok((WalletPeer::with_socket_addr(peer, socket_addr), receiver))
+}
+
+async fn handshake(
+    peer: &Peer,
+    receiver: &mut mpsc::Receiver<Message>,
+    network_id: String,
+    node_type: NodeType,
+) -> Result<(), ClientError> {
+    peer.send(Handshake {
+        network_id: network_id.clone(),
+        protocol_version: "0.0.37".to_string(),
+        software_version: "0.0.0".to_string(),
+        server_port: 0,
+        node_type: NodeType::Wallet,
+        capabilities: vec![
+            (1, "1".to_string()),
+            (2, "1".to_string()),
+            (3, "1".to_string()),
+        ],
+    })
+    .await?;
+
+    let Some(message) = receiver.recv().await else {
+        return Err(ClientError::MissingHandshake);
+    };
+
+    if message.msg_type != ProtocolMessageTypes::Handshake {
+        return Err(ClientError::InvalidResponse(
+            vec![ProtocolMessageTypes::Handshake],
+            message.msg_type,
+        ));
+    }
+
+    let handshake = Handshake::from_bytes(&message.data)?;
+
+    if handshake.node_type != node_type {
+        return Err(ClientError::WrongNodeType(node_type, handshake.node_type));
+    }
+
+    if handshake.network_id != network_id {
+        return Err(ClientError::WrongNetwork(network_id, handshake.network_id));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+/// How much weight the newest sample has in the latency and timeout moving averages.
+const SMOOTHING: f64 = 0.2;
+
+/// How many seconds it takes for half of a misbehavior penalty to be forgiven.
+const MISBEHAVIOR_HALF_LIFE: f64 = 3600.0;
+
+/// Running statistics about how well a peer has served the wallet, which are combined into a
+/// score that's used to prefer reliable peers over slow or misbehaving ones.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PeerReputation {
+    /// Moving average of the response latency in milliseconds, if it has been measured.
+    pub latency_ms: Option<f64>,
+    /// Moving average of the fraction of requests that timed out.
+    pub timeout_rate: f64,
+    /// How many blocks the peer's peak is behind the highest peak of any connected peer.
+    pub peak_lag: u32,
+    /// Penalty for misbehavior, as of the misbehavior timestamp.
+    pub misbehavior: f64,
+    pub misbehavior_timestamp: u64,
+}
+
+impl PeerReputation {
+    pub fn record_response(&mut self, latency: Duration) {
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+
+        self.latency_ms = Some(self.latency_ms.map_or(latency_ms, |average| {
+            average + SMOOTHING * (latency_ms - average)
+        }));
+
+        self.timeout_rate *= 1.0 - SMOOTHING;
+    }
+
+    pub fn record_timeout(&mut self) {
+        self.timeout_rate += SMOOTHING * (1.0 - self.timeout_rate);
+    }
+
+    pub fn record_misbehavior(&mut self, penalty: f64, timestamp: u64) {
+        self.misbehavior = self.current_misbehavior(timestamp) + penalty;
+        self.misbehavior_timestamp = timestamp;
+    }
+
+    /// The misbehavior penalty after it has decayed until the given time.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn current_misbehavior(&self, timestamp: u64) -> f64 {
+        let elapsed = timestamp.saturating_sub(self.misbehavior_timestamp);
+        self.misbehavior * 0.5_f64.powf(elapsed as f64 / MISBEHAVIOR_HALF_LIFE)
+    }
+
+    /// A score from 0 to 100, where peers that haven't done anything wrong yet start at 100.
+    #[allow(clippy::cast_sign_loss)]
+    pub fn score(&self, timestamp: u64) -> u32 {
+        let latency = self.latency_ms.map_or(0.0, |ms| (ms / 50.0).min(30.0));
+        let timeouts = self.timeout_rate * 40.0;
+        let peak_lag = f64::from(self.peak_lag.min(4)) * 5.0;
+        let misbehavior = self.current_misbehavior(timestamp);
+
+        (100.0 - latency - timeouts - peak_lag - misbehavior)
+            .clamp(0.0, 100.0)
+            .round() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_penalties() {
+        let mut reputation = PeerReputation::default();
+        assert_eq!(reputation.score(0), 100);
+
+        reputation.record_response(Duration::from_millis(500));
+        assert_eq!(reputation.score(0), 90);
+
+        reputation.record_timeout();
+        assert_eq!(reputation.score(0), 82);
+
+        reputation.peak_lag = 10;
+        assert_eq!(reputation.score(0), 62);
+
+        reputation.record_misbehavior(40.0, 0);
+        assert_eq!(reputation.score(0), 22);
+    }
+
+    #[test]
+    fn test_misbehavior_decays() {
+        let mut reputation = PeerReputation::default();
+
+        reputation.record_misbehavior(40.0, 1000);
+        assert_eq!(reputation.score(1000), 60);
+        assert_eq!(reputation.score(1000 + 3600), 80);
+
+        reputation.record_misbehavior(40.0, 1000 + 3600);
+        assert_eq!(reputation.score(1000 + 3600), 40);
+    }
+
+    #[test]
+    fn test_timeouts_recover() {
+        let mut reputation = PeerReputation::default();
+
+        for _ in 0..10 {
+            reputation.record_timeout();
+        }
+
+        let score = reputation.score(0);
+
+        for _ in 0..10 {
+            reputation.record_response(Duration::ZERO);
+        }
+
+        assert!(reputation.score(0) > score);
+    }
+}
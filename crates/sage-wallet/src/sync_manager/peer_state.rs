@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     net::IpAddr,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
@@ -11,6 +11,8 @@ use tracing::debug;
 
 use crate::WalletPeer;
 
+use super::PeerReputation;
+
 #[derive(Debug)]
 pub struct PeerInfo {
     pub peer: WalletPeer,
@@ -26,16 +28,47 @@ impl Drop for PeerInfo {
     }
 }
 
+/// Peers whose score drops below this are banned until their reputation recovers.
+const MIN_SCORE: u32 = 20;
+
+/// The misbehavior penalty that's applied to a peer's reputation when it's banned.
+const BAN_PENALTY: f64 = 40.0;
+
+/// How many of the most recent peer discrepancies are kept around.
+const MAX_DISCREPANCIES: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerDiscrepancy {
+    pub ip: IpAddr,
+    pub reason: String,
+    pub timestamp: u64,
+}
+
+/// The connection status of the network's trusted peer, if it has one.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TrustedPeerStatus {
+    /// The address the trusted peer's host resolved to.
+    pub ip: Option<IpAddr>,
+    pub last_error: Option<String>,
+    pub failed_attempts: u32,
+}
+
 #[derive(Debug, Default)]
 pub struct PeerState {
     peers: HashMap<IpAddr, PeerInfo>,
     banned_peers: HashMap<IpAddr, u64>,
+    discrepancies: VecDeque<PeerDiscrepancy>,
+    trusted_peer: Option<TrustedPeerStatus>,
+    reputations: HashMap<IpAddr, PeerReputation>,
 }
 
 impl PeerState {
     pub fn reset(&mut self) {
         self.peers.clear();
         self.banned_peers.clear();
+        self.discrepancies.clear();
+        self.trusted_peer = None;
+        self.reputations.clear();
     }
 
     pub fn peak(&self) -> Option<(u32, Bytes32)> {
@@ -87,39 +120,120 @@ impl PeerState {
         self.peers.contains_key(&ip)
     }
 
+    /// Connected peers along with their current scores, best first.
+    pub fn peers_by_score(&self) -> Vec<(WalletPeer, u32)> {
+        self.peers
+            .values()
+            .map(|info| (info.peer.clone(), self.score(info.peer.socket_addr().ip())))
+            .sorted_by_key(|(_, score)| std::cmp::Reverse(*score))
+            .collect()
+    }
+
     pub fn acquire_peer(&self) -> Option<WalletPeer> {
         self.peers
             .values()
-            .max_by_key(|info| info.claimed_peak)
+            .max_by_key(|info| (self.score(info.peer.socket_addr().ip()), info.claimed_peak))
             .map(|info| info.peer.clone())
     }
 
+    pub fn reputation(&self, ip: IpAddr) -> PeerReputation {
+        self.reputations.get(&ip).copied().unwrap_or_default()
+    }
+
+    pub fn reputations(&self) -> &HashMap<IpAddr, PeerReputation> {
+        &self.reputations
+    }
+
+    /// Restores a reputation that was persisted in a previous session.
+    pub fn restore_reputation(&mut self, ip: IpAddr, reputation: PeerReputation) {
+        self.reputations.insert(ip, reputation);
+    }
+
+    /// Restores a ban that was persisted in a previous session, without penalizing the peer
+    /// again, since the penalty is already part of its persisted reputation.
+    pub fn restore_ban(&mut self, ip: IpAddr, ban_until: u64) {
+        if self.is_trusted(ip) || ban_until <= unix_timestamp() {
+            return;
+        }
+
+        self.banned_peers.insert(ip, ban_until);
+        self.remove_peer(ip);
+    }
+
+    pub fn score(&self, ip: IpAddr) -> u32 {
+        self.reputation(ip).score(unix_timestamp())
+    }
+
+    pub fn record_response(&mut self, ip: IpAddr, latency: Duration) {
+        self.reputations
+            .entry(ip)
+            .or_default()
+            .record_response(latency);
+    }
+
+    /// Records that a request to the peer timed out, and disconnects it if that has happened
+    /// too often for it to be worth keeping around.
+    pub fn record_timeout(&mut self, ip: IpAddr) {
+        self.reputations.entry(ip).or_default().record_timeout();
+        self.ban_if_disreputable(ip, "too many timeouts");
+    }
+
+    /// Penalizes the peer for misbehaving, and disconnects it if its score drops too low.
+    pub fn record_misbehavior(&mut self, ip: IpAddr, penalty: f64, message: &str) {
+        self.reputations
+            .entry(ip)
+            .or_default()
+            .record_misbehavior(penalty, unix_timestamp());
+        self.ban_if_disreputable(ip, message);
+    }
+
+    fn ban_if_disreputable(&mut self, ip: IpAddr, message: &str) {
+        let score = self.score(ip);
+
+        if score < MIN_SCORE {
+            self.ban_without_penalty(
+                ip,
+                Duration::from_mins(5),
+                &format!("score dropped to {score} ({message})"),
+            );
+        }
+    }
+
     pub fn ban(&mut self, ip: IpAddr, duration: Duration, message: &str) {
-        debug!("Banning peer {ip} ({duration:?}): {message}");
+        if self.is_trusted(ip) {
+            debug!("Not banning trusted peer {ip}: {message}");
+            return;
+        }
 
-        let start = SystemTime::now();
-        let since_the_epoch = start
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards");
+        self.reputations
+            .entry(ip)
+            .or_default()
+            .record_misbehavior(BAN_PENALTY, unix_timestamp());
 
-        self.banned_peers
-            .insert(ip, since_the_epoch.as_secs() + duration.as_secs());
+        self.ban_without_penalty(ip, duration, message);
+    }
 
-        self.banned_peers
-            .retain(|_, ban_until| *ban_until > since_the_epoch.as_secs());
+    /// Bans a peer whose score has already been lowered by whatever it did.
+    fn ban_without_penalty(&mut self, ip: IpAddr, duration: Duration, message: &str) {
+        if self.is_trusted(ip) {
+            debug!("Not banning trusted peer {ip}: {message}");
+            return;
+        }
+
+        debug!("Banning peer {ip} ({duration:?}): {message}");
+
+        let now = unix_timestamp();
+
+        self.banned_peers.insert(ip, now + duration.as_secs());
+        self.banned_peers.retain(|_, ban_until| *ban_until > now);
 
         self.remove_peer(ip);
     }
 
     pub fn is_banned(&self, ip: IpAddr) -> bool {
-        self.banned_peers.get(&ip).is_some_and(|ban_until| {
-            let start = SystemTime::now();
-            let since_the_epoch = start
-                .duration_since(UNIX_EPOCH)
-                .expect("Time went backwards");
-
-            *ban_until > since_the_epoch.as_secs()
-        })
+        self.banned_peers
+            .get(&ip)
+            .is_some_and(|ban_until| *ban_until > unix_timestamp())
     }
 
     pub fn update_peak(&mut self, ip: IpAddr, height: u32, header_hash: Bytes32) {
@@ -127,6 +241,58 @@ impl PeerState {
             peer.claimed_peak = height;
             peer.header_hash = header_hash;
         }
+
+        self.update_peak_lag();
+    }
+
+    fn update_peak_lag(&mut self) {
+        let Some((best_peak, _)) = self.peak() else {
+            return;
+        };
+
+        for (ip, info) in &self.peers {
+            self.reputations.entry(*ip).or_default().peak_lag =
+                best_peak.saturating_sub(info.claimed_peak);
+        }
+    }
+
+    pub fn record_discrepancy(&mut self, ip: IpAddr, reason: String) {
+        let timestamp = unix_timestamp();
+
+        if self.discrepancies.len() == MAX_DISCREPANCIES {
+            self.discrepancies.pop_front();
+        }
+
+        self.discrepancies.push_back(PeerDiscrepancy {
+            ip,
+            reason,
+            timestamp,
+        });
+    }
+
+    pub fn discrepancies(&self) -> Vec<PeerDiscrepancy> {
+        self.discrepancies.iter().cloned().collect()
+    }
+
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted_peer
+            .as_ref()
+            .is_some_and(|status| status.ip == Some(ip))
+    }
+
+    pub fn trusted_peer(&self) -> Option<&TrustedPeerStatus> {
+        self.trusted_peer.as_ref()
+    }
+
+    pub fn is_trusted_peer_connected(&self) -> bool {
+        self.trusted_peer
+            .as_ref()
+            .and_then(|status| status.ip)
+            .is_some_and(|ip| self.is_connected(ip))
+    }
+
+    pub(super) fn trusted_peer_mut(&mut self) -> &mut TrustedPeerStatus {
+        self.trusted_peer.get_or_insert_default()
     }
 
     pub fn peer(&self, ip: IpAddr) -> Option<&PeerInfo> {
@@ -139,17 +305,19 @@ impl PeerState {
 
     pub(super) fn add_peer(&mut self, state: PeerInfo) {
         self.peers.insert(state.peer.socket_addr().ip(), state);
+        self.update_peak_lag();
     }
 
     pub fn banned_peers(&mut self) -> &HashMap<IpAddr, u64> {
-        self.banned_peers.retain(|_, ban_until| {
-            let start = SystemTime::now();
-            let since_the_epoch = start
-                .duration_since(UNIX_EPOCH)
-                .expect("Time went backwards");
-
-            *ban_until > since_the_epoch.as_secs()
-        });
+        let now = unix_timestamp();
+        self.banned_peers.retain(|_, ban_until| *ban_until > now);
         &self.banned_peers
     }
 }
+
+fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+}
@@ -0,0 +1,30 @@
+use chia_wallet_sdk::test::PeerSimulator;
+use tracing::{info, warn};
+
+use crate::WalletPeer;
+
+use super::SyncManager;
+
+impl SyncManager {
+    /// The simulator is the only peer on its network, so rather than discovering peers the
+    /// sync manager reconnects to it whenever there's no connection.
+    pub(super) async fn connect_simulator(&mut self, simulator: &PeerSimulator) {
+        if self.state.lock().await.peer_count() > 0 {
+            return;
+        }
+
+        match simulator.connect_raw().await {
+            Ok((peer, receiver)) => {
+                if self
+                    .try_add_peer(WalletPeer::new(peer), receiver, true, false)
+                    .await
+                {
+                    info!("Connected to peer simulator");
+                }
+            }
+            Err(error) => {
+                warn!("Failed to connect to peer simulator: {error}");
+            }
+        }
+    }
+}
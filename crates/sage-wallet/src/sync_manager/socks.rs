@@ -0,0 +1,331 @@
+use std::{
+    io::{Error, ErrorKind},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+};
+
+use chia_wallet_sdk::{
+    chia::protocol::Message,
+    client::{ClientError, Connector},
+    prelude::*,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::mpsc,
+};
+use tokio_tungstenite::{client_async_tls_with_config, tungstenite};
+
+const VERSION: u8 = 0x05;
+const NO_AUTHENTICATION: u8 = 0x00;
+const USERNAME_PASSWORD: u8 = 0x02;
+const CONNECT: u8 = 0x01;
+
+/// Tor's extension for resolving a hostname through the proxy.
+const RESOLVE: u8 = 0xF0;
+
+const IPV4: u8 = 0x01;
+const DOMAIN: u8 = 0x03;
+const IPV6: u8 = 0x04;
+
+/// A SOCKS5 proxy that peer connections and DNS lookups are routed through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SocksProxy {
+    pub addr: SocketAddr,
+    /// Authenticates with different credentials for every destination, which Tor uses to
+    /// isolate connections onto separate circuits.
+    pub isolate_streams: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SocksTarget<'a> {
+    Addr(SocketAddr),
+    Domain(&'a str, u16),
+}
+
+impl SocksTarget<'_> {
+    fn isolation_key(&self) -> String {
+        match self {
+            Self::Addr(addr) => addr.ip().to_string(),
+            Self::Domain(host, _) => (*host).to_string(),
+        }
+    }
+}
+
+impl SocksProxy {
+    /// Opens a TCP stream to the target through the proxy. The hostname of a domain target
+    /// is resolved by the proxy rather than locally.
+    pub async fn connect(&self, target: SocksTarget<'_>) -> Result<TcpStream, Error> {
+        let (stream, _) = self
+            .request(CONNECT, target, &target.isolation_key())
+            .await?;
+        Ok(stream)
+    }
+
+    /// Resolves a hostname through the proxy, using Tor's `RESOLVE` extension. The extension
+    /// only returns a single address, even if the hostname has several records.
+    pub async fn resolve(&self, host: &str) -> Result<IpAddr, Error> {
+        self.resolve_with_key(host, host).await
+    }
+
+    /// Resolves a hostname through the proxy up to the given number of times, to collect
+    /// more than the single address `RESOLVE` returns. This only helps with stream isolation,
+    /// since each lookup then goes through a separate circuit, whose exit relay may pick a
+    /// different record. Otherwise the hostname is only resolved once.
+    pub async fn resolve_all(&self, host: &str, lookups: usize) -> Result<Vec<IpAddr>, Error> {
+        if !self.isolate_streams {
+            return Ok(vec![self.resolve(host).await?]);
+        }
+
+        let mut ips = Vec::new();
+        let mut last_error = None;
+
+        for i in 0..lookups.max(1) {
+            match self.resolve_with_key(host, &format!("{host}#{i}")).await {
+                Ok(ip) => {
+                    if !ips.contains(&ip) {
+                        ips.push(ip);
+                    }
+                }
+                Err(error) => last_error = Some(error),
+            }
+        }
+
+        match last_error {
+            Some(error) if ips.is_empty() => Err(error),
+            _ => Ok(ips),
+        }
+    }
+
+    async fn resolve_with_key(&self, host: &str, isolation_key: &str) -> Result<IpAddr, Error> {
+        let (_, ip) = self
+            .request(RESOLVE, SocksTarget::Domain(host, 0), isolation_key)
+            .await?;
+        ip.ok_or_else(|| invalid_data("proxy didn't resolve the hostname to an address"))
+    }
+
+    /// Connects to a peer's websocket through the proxy. The peer's socket address will be
+    /// that of the proxy, so it needs to be tracked separately.
+    pub async fn connect_peer(
+        &self,
+        uri: &str,
+        target: SocksTarget<'_>,
+        connector: Connector,
+        options: PeerOptions,
+    ) -> Result<(Peer, mpsc::Receiver<Message>), ClientError> {
+        let stream = self.connect(target).await.map_err(tungstenite::Error::Io)?;
+
+        let (ws, _) = client_async_tls_with_config(uri, stream, None, Some(connector)).await?;
+
+        Peer::from_websocket(ws, options)
+    }
+
+    async fn request(
+        &self,
+        command: u8,
+        target: SocksTarget<'_>,
+        isolation_key: &str,
+    ) -> Result<(TcpStream, Option<IpAddr>), Error> {
+        let mut stream = TcpStream::connect(self.addr).await?;
+
+        let method = if self.isolate_streams {
+            USERNAME_PASSWORD
+        } else {
+            NO_AUTHENTICATION
+        };
+
+        stream.write_all(&[VERSION, 1, method]).await?;
+
+        let mut reply = [0; 2];
+        stream.read_exact(&mut reply).await?;
+
+        if reply[0] != VERSION {
+            return Err(invalid_data("unsupported SOCKS version"));
+        }
+
+        if reply[1] != method {
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "proxy rejected the authentication method",
+            ));
+        }
+
+        if method == USERNAME_PASSWORD {
+            let username = isolation_key.as_bytes();
+            let password = b"sage";
+
+            let mut auth = vec![0x01, length(username)?];
+            auth.extend_from_slice(username);
+            auth.push(length(password)?);
+            auth.extend_from_slice(password);
+            stream.write_all(&auth).await?;
+
+            stream.read_exact(&mut reply).await?;
+
+            if reply[1] != 0x00 {
+                return Err(Error::new(
+                    ErrorKind::PermissionDenied,
+                    "proxy rejected the credentials",
+                ));
+            }
+        }
+
+        let mut request = vec![VERSION, command, 0x00];
+
+        let port = match target {
+            SocksTarget::Addr(addr) => {
+                match addr.ip() {
+                    IpAddr::V4(ip) => {
+                        request.push(IPV4);
+                        request.extend_from_slice(&ip.octets());
+                    }
+                    IpAddr::V6(ip) => {
+                        request.push(IPV6);
+                        request.extend_from_slice(&ip.octets());
+                    }
+                }
+                addr.port()
+            }
+            SocksTarget::Domain(host, port) => {
+                request.push(DOMAIN);
+                request.push(length(host.as_bytes())?);
+                request.extend_from_slice(host.as_bytes());
+                port
+            }
+        };
+
+        request.extend_from_slice(&port.to_be_bytes());
+        stream.write_all(&request).await?;
+
+        let mut header = [0; 4];
+        stream.read_exact(&mut header).await?;
+
+        if header[1] != 0x00 {
+            return Err(Error::new(
+                ErrorKind::ConnectionRefused,
+                format!("proxy request failed with code {}", header[1]),
+            ));
+        }
+
+        let ip = match header[3] {
+            IPV4 => {
+                let mut octets = [0; 4];
+                stream.read_exact(&mut octets).await?;
+                Some(IpAddr::V4(Ipv4Addr::from(octets)))
+            }
+            IPV6 => {
+                let mut octets = [0; 16];
+                stream.read_exact(&mut octets).await?;
+                Some(IpAddr::V6(Ipv6Addr::from(octets)))
+            }
+            DOMAIN => {
+                let len = stream.read_u8().await?;
+                let mut domain = vec![0; len.into()];
+                stream.read_exact(&mut domain).await?;
+                None
+            }
+            _ => return Err(invalid_data("invalid address type")),
+        };
+
+        // The bound port isn't needed
+        stream.read_u16().await?;
+
+        Ok((stream, ip))
+    }
+}
+
+fn length(bytes: &[u8]) -> Result<u8, Error> {
+    u8::try_from(bytes.len()).map_err(|_| Error::new(ErrorKind::InvalidInput, "value too long"))
+}
+
+fn invalid_data(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resolve_with_isolation() -> anyhow::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let proxy = SocksProxy {
+            addr: listener.local_addr()?,
+            isolate_streams: true,
+        };
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await?;
+
+            let mut greeting = [0; 3];
+            stream.read_exact(&mut greeting).await?;
+            assert_eq!(greeting, [VERSION, 1, USERNAME_PASSWORD]);
+            stream.write_all(&[VERSION, USERNAME_PASSWORD]).await?;
+
+            let mut auth = [0; 2 + 11 + 1 + 4];
+            stream.read_exact(&mut auth).await?;
+            assert_eq!(&auth[2..13], b"example.com");
+            stream.write_all(&[0x01, 0x00]).await?;
+
+            let mut request = [0; 5 + 11 + 2];
+            stream.read_exact(&mut request).await?;
+            assert_eq!(request[..5], [VERSION, RESOLVE, 0x00, DOMAIN, 11]);
+            stream
+                .write_all(&[VERSION, 0x00, 0x00, IPV4, 1, 2, 3, 4, 0, 0])
+                .await?;
+
+            anyhow::Ok(())
+        });
+
+        let ip = proxy.resolve("example.com").await?;
+        server.await??;
+
+        assert_eq!(ip, IpAddr::from([1, 2, 3, 4]));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resolve_all_with_isolation() -> anyhow::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let proxy = SocksProxy {
+            addr: listener.local_addr()?,
+            isolate_streams: true,
+        };
+
+        let server = tokio::spawn(async move {
+            for i in 0..3u8 {
+                let (mut stream, _) = listener.accept().await?;
+
+                let mut greeting = [0; 3];
+                stream.read_exact(&mut greeting).await?;
+                stream.write_all(&[VERSION, USERNAME_PASSWORD]).await?;
+
+                // Each lookup uses a different username, so it gets its own circuit
+                let mut auth = [0; 2 + 13 + 1 + 4];
+                stream.read_exact(&mut auth).await?;
+                assert_eq!(&auth[2..15], format!("example.com#{i}").as_bytes());
+                stream.write_all(&[0x01, 0x00]).await?;
+
+                let mut request = [0; 5 + 11 + 2];
+                stream.read_exact(&mut request).await?;
+                stream
+                    .write_all(&[VERSION, 0x00, 0x00, IPV4, 1, 2, 3, i % 2, 0, 0])
+                    .await?;
+            }
+
+            anyhow::Ok(())
+        });
+
+        let ips = proxy.resolve_all("example.com", 3).await?;
+        server.await??;
+
+        assert_eq!(
+            ips,
+            vec![IpAddr::from([1, 2, 3, 0]), IpAddr::from([1, 2, 3, 1])]
+        );
+
+        Ok(())
+    }
+}
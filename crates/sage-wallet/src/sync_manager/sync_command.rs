@@ -4,7 +4,7 @@ use chia_wallet_sdk::{chia::protocol::Message, prelude::*};
 use sage_config::Network;
 use tokio::sync::mpsc;
 
-use crate::Wallet;
+use crate::{SocksProxy, Wallet};
 
 #[derive(Debug)]
 pub enum SyncCommand {
@@ -34,4 +34,29 @@ pub enum SyncCommand {
     ConnectionClosed(IpAddr),
     SetTargetPeers(usize),
     SetDiscoverPeers(bool),
+    SetParanoidSync(bool),
+    SetProxy(Option<SocksProxy>),
+    SetOffline(bool),
+    #[cfg(feature = "simulator")]
+    SetSimulator(Arc<chia_wallet_sdk::test::PeerSimulator>),
+    /// Rolls the wallet back to the given height and syncs again from there, for when coin
+    /// states past it turn out to be invalid.
+    Rollback {
+        fork_height: u32,
+    },
+}
+
+impl SyncCommand {
+    /// Whether the command can't do anything useful without peers, so it should be deferred
+    /// while the wallet is offline.
+    pub fn requires_network(&self) -> bool {
+        matches!(
+            self,
+            Self::ConnectPeer { .. }
+                | Self::AddPeer { .. }
+                | Self::SubscribeCoins { .. }
+                | Self::SubscribePuzzles { .. }
+                | Self::Rollback { .. }
+        )
+    }
 }
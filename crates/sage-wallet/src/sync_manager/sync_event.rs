@@ -2,6 +2,8 @@ use chia_wallet_sdk::prelude::*;
 use sage_database::OfferStatus;
 use std::net::IpAddr;
 
+use crate::SyncProgress;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SyncEvent {
     Start(IpAddr),
@@ -23,10 +25,14 @@ pub enum SyncEvent {
         status: OfferStatus,
     },
     PuzzleBatchSynced,
+    Progress(SyncProgress),
     CatInfo,
     DidInfo,
     NftData,
     NetworkChanged {
         network_id: String,
     },
+    Reorg {
+        fork_height: u32,
+    },
 }
@@ -0,0 +1,212 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// How far back samples are kept when estimating the rate of progress.
+const ETA_WINDOW: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PhaseProgress {
+    pub completed: u32,
+    pub total: u32,
+}
+
+impl PhaseProgress {
+    pub fn new(completed: u32, total: u32) -> Self {
+        Self { completed, total }
+    }
+
+    pub fn remaining(&self) -> u32 {
+        self.total.saturating_sub(self.completed)
+    }
+}
+
+/// How far each phase of syncing the wallet has come.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SyncProgress {
+    /// Derivations whose puzzle hashes have been subscribed to.
+    pub derivations: PhaseProgress,
+    /// Coin states received from peers, which has no known total ahead of time.
+    pub coin_states: u32,
+    /// Coins whose puzzles have been resolved by the puzzle queue.
+    pub puzzles: PhaseProgress,
+    /// NFT files that have been downloaded, or at least attempted.
+    pub nft_uris: PhaseProgress,
+    /// Blocks referenced by coins whose timestamps have been filled in.
+    pub block_timestamps: PhaseProgress,
+    /// Estimated time until every phase is complete, based on the recent rate of progress.
+    pub eta: Option<Duration>,
+}
+
+impl SyncProgress {
+    pub fn remaining(&self) -> u64 {
+        u64::from(self.derivations.remaining())
+            + u64::from(self.puzzles.remaining())
+            + u64::from(self.nft_uris.remaining())
+            + u64::from(self.block_timestamps.remaining())
+    }
+
+    pub fn completed(&self) -> u64 {
+        u64::from(self.derivations.completed)
+            + u64::from(self.puzzles.completed)
+            + u64::from(self.nft_uris.completed)
+            + u64::from(self.block_timestamps.completed)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.remaining() == 0
+    }
+}
+
+/// Collects progress from the sync tasks and queues, and keeps a rolling window of samples
+/// to estimate how long the rest of the sync will take.
+#[derive(Debug, Default)]
+pub struct SyncProgressTracker {
+    progress: SyncProgress,
+    samples: VecDeque<(Instant, u64)>,
+    is_changed: bool,
+}
+
+impl SyncProgressTracker {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn progress(&self) -> SyncProgress {
+        self.progress
+    }
+
+    pub fn set_derivations_total(&mut self, total: u32) {
+        self.update(|progress| progress.derivations.total = total);
+    }
+
+    pub fn add_derivations(&mut self, count: u32) {
+        self.update(|progress| {
+            progress.derivations.total = progress.derivations.total.saturating_add(count)
+        });
+    }
+
+    pub fn add_subscribed_derivations(&mut self, count: u32) {
+        self.update(|progress| {
+            progress.derivations.completed = progress
+                .derivations
+                .completed
+                .saturating_add(count)
+                .min(progress.derivations.total);
+        });
+    }
+
+    pub fn add_coin_states(&mut self, count: u32) {
+        self.update(|progress| progress.coin_states = progress.coin_states.saturating_add(count));
+    }
+
+    pub fn set_puzzles(&mut self, puzzles: PhaseProgress) {
+        self.update(|progress| progress.puzzles = puzzles);
+    }
+
+    pub fn set_nft_uris(&mut self, nft_uris: PhaseProgress) {
+        self.update(|progress| progress.nft_uris = nft_uris);
+    }
+
+    pub fn set_block_timestamps(&mut self, block_timestamps: PhaseProgress) {
+        self.update(|progress| progress.block_timestamps = block_timestamps);
+    }
+
+    /// Records a sample of the completed work and refreshes the ETA. Returns the progress if
+    /// it changed since the last time this was called, so that it can be reported.
+    ///
+    /// The rate is based on how much work was completed rather than how much is remaining,
+    /// since the totals keep growing while new coins are discovered.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn sample(&mut self, now: Instant) -> Option<SyncProgress> {
+        let remaining = self.progress.remaining();
+        let completed = self.progress.completed();
+
+        self.samples.push_back((now, completed));
+
+        while self
+            .samples
+            .front()
+            .is_some_and(|(time, _)| now.duration_since(*time) > ETA_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+
+        self.progress.eta = if remaining == 0 {
+            Some(Duration::ZERO)
+        } else {
+            self.samples.front().and_then(|&(time, oldest)| {
+                let elapsed = now.duration_since(time);
+                let done = completed.checked_sub(oldest).filter(|done| *done > 0)?;
+                Some(elapsed.mul_f64(remaining as f64 / done as f64))
+            })
+        };
+
+        std::mem::take(&mut self.is_changed).then_some(self.progress)
+    }
+
+    fn update(&mut self, f: impl FnOnce(&mut SyncProgress)) {
+        let previous = self.progress;
+        f(&mut self.progress);
+        self.is_changed |= self.progress != previous;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eta() {
+        let mut tracker = SyncProgressTracker::default();
+        let start = Instant::now();
+
+        tracker.set_puzzles(PhaseProgress::new(0, 100));
+        let progress = tracker.sample(start).expect("progress changed");
+        assert_eq!(progress.eta, None);
+
+        tracker.set_puzzles(PhaseProgress::new(25, 100));
+        let progress = tracker
+            .sample(start + Duration::from_secs(10))
+            .expect("progress changed");
+        assert_eq!(progress.eta, Some(Duration::from_secs(30)));
+
+        assert_eq!(tracker.sample(start + Duration::from_secs(20)), None);
+
+        tracker.set_puzzles(PhaseProgress::new(100, 100));
+        let progress = tracker
+            .sample(start + Duration::from_secs(30))
+            .expect("progress changed");
+        assert_eq!(progress.eta, Some(Duration::ZERO));
+        assert!(progress.is_complete());
+    }
+
+    #[test]
+    fn test_eta_with_growing_total() {
+        let mut tracker = SyncProgressTracker::default();
+        let start = Instant::now();
+
+        tracker.set_puzzles(PhaseProgress::new(0, 100));
+        tracker.sample(start);
+
+        // More coins were discovered, so there's more remaining than before, but the rate of
+        // completed work is still known
+        tracker.set_puzzles(PhaseProgress::new(50, 200));
+        let progress = tracker
+            .sample(start + Duration::from_secs(10))
+            .expect("progress changed");
+        assert_eq!(progress.eta, Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn test_remaining_does_not_overflow() {
+        let progress = SyncProgress {
+            derivations: PhaseProgress::new(0, u32::MAX),
+            puzzles: PhaseProgress::new(0, u32::MAX),
+            ..Default::default()
+        };
+
+        assert_eq!(progress.remaining(), 2 * u64::from(u32::MAX));
+    }
+}
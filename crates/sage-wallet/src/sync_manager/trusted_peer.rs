@@ -0,0 +1,114 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::{Duration, Instant},
+};
+
+use sage_config::TrustedPeer;
+use tokio::time::timeout;
+use tracing::{info, warn};
+
+use crate::WalletError;
+
+use super::{SyncManager, certificate_pin::pinned_connector, peer_discovery::connect_full_node};
+
+/// The longest the sync manager waits between attempts to reconnect to the trusted peer.
+const MAX_BACKOFF: Duration = Duration::from_mins(2);
+
+impl SyncManager {
+    /// Connects to the trusted peer if it isn't already connected, backing off exponentially
+    /// after each failed attempt.
+    pub(super) async fn connect_trusted_peer(&mut self, trusted_peer: &TrustedPeer) {
+        if self.state.lock().await.is_trusted_peer_connected() {
+            return;
+        }
+
+        if self
+            .trusted_peer_retry_at
+            .is_some_and(|retry_at| Instant::now() < retry_at)
+        {
+            return;
+        }
+
+        let result = self.try_connect_trusted_peer(trusted_peer).await;
+
+        let mut state = self.state.lock().await;
+        let status = state.trusted_peer_mut();
+
+        match result {
+            Ok(()) => {
+                info!(
+                    "Connected to trusted peer {}:{}",
+                    trusted_peer.host, trusted_peer.port
+                );
+                status.last_error = None;
+                status.failed_attempts = 0;
+                self.trusted_peer_retry_at = None;
+            }
+            Err(error) => {
+                status.failed_attempts += 1;
+                status.last_error = Some(error.to_string());
+
+                let backoff =
+                    Duration::from_secs(1 << status.failed_attempts.min(7)).min(MAX_BACKOFF);
+
+                warn!(
+                    "Failed to connect to trusted peer {}:{}, retrying in {backoff:?}: {error}",
+                    trusted_peer.host, trusted_peer.port
+                );
+
+                self.trusted_peer_retry_at = Some(Instant::now() + backoff);
+            }
+        }
+    }
+
+    async fn try_connect_trusted_peer(
+        &mut self,
+        trusted_peer: &TrustedPeer,
+    ) -> Result<(), WalletError> {
+        let socket_addr = self.resolve_trusted_peer(trusted_peer).await?;
+
+        self.state.lock().await.trusted_peer_mut().ip = Some(socket_addr.ip());
+
+        let connector = match trusted_peer.certificate_pin {
+            Some(pin) => pinned_connector(&self.connector, pin)
+                .ok_or(WalletError::CertificatePinUnsupported)?,
+            None => self.connector.clone(),
+        };
+
+        let (peer, receiver) = timeout(
+            self.options.timeouts.connection,
+            connect_full_node(
+                self.network.network_id(),
+                connector,
+                socket_addr,
+                self.options.proxy,
+            ),
+        )
+        .await??;
+
+        if !self.try_add_peer(peer, receiver, true, true).await {
+            return Err(WalletError::PeerMisbehaved);
+        }
+
+        Ok(())
+    }
+
+    async fn resolve_trusted_peer(
+        &self,
+        trusted_peer: &TrustedPeer,
+    ) -> Result<SocketAddr, WalletError> {
+        if let Ok(ip) = trusted_peer.host.parse::<IpAddr>() {
+            return Ok(SocketAddr::new(ip, trusted_peer.port));
+        }
+
+        if let Some(proxy) = self.options.proxy {
+            let ip = proxy.resolve(&trusted_peer.host).await?;
+            return Ok(SocketAddr::new(ip, trusted_peer.port));
+        }
+
+        tokio::net::lookup_host((trusted_peer.host.as_str(), trusted_peer.port))
+            .await?
+            .next()
+            .ok_or_else(|| WalletError::UnresolvedTrustedPeer(trusted_peer.host.clone()))
+    }
+}
@@ -1,7 +1,7 @@
 use std::{collections::HashSet, sync::Arc, time::Duration};
 
 use chia_wallet_sdk::{chia::protocol::CoinStateFilters, prelude::*};
-use sage_database::DatabaseTx;
+use sage_database::SyncCheckpoint;
 use tokio::{
     sync::{Mutex, mpsc},
     time::sleep,
@@ -10,35 +10,58 @@ use tracing::{info, warn};
 
 use crate::{SyncCommand, Wallet, WalletError, WalletPeer};
 
-use super::{PeerState, SyncEvent};
+use super::{
+    PeerState, SyncEvent, SyncProgressTracker,
+    cross_validation::{StateQuery, confirm_coin_states},
+};
 
+#[allow(clippy::too_many_arguments)]
 pub async fn sync_wallet(
     wallet: Arc<Wallet>,
     peer: WalletPeer,
     state: Arc<Mutex<PeerState>>,
+    progress: Arc<Mutex<SyncProgressTracker>>,
     sync_sender: mpsc::Sender<SyncEvent>,
     command_sender: mpsc::Sender<SyncCommand>,
     delta_sync: bool,
+    paranoid: bool,
 ) -> Result<(), WalletError> {
     info!("Starting sync against peer {}", peer.socket_addr());
 
-    let p2_puzzle_hashes = wallet.db.custody_p2_puzzle_hashes().await?;
+    let confirm_with = paranoid.then_some(&state);
 
-    let (start_height, start_header_hash) = if delta_sync {
-        wallet.db.latest_peak().await?
-    } else {
-        None
-    }
-    .map_or_else(
-        || (None, wallet.genesis_challenge),
-        |(peak, header_hash)| (Some(peak), header_hash),
-    );
+    let p2_puzzles = wallet.db.synced_p2_puzzle_hashes_with_ids().await?;
+
+    progress
+        .lock()
+        .await
+        .set_derivations_total(p2_puzzles.len().try_into()?);
+
+    let checkpoint = resume_checkpoint(&wallet, &peer, &state).await?;
+
+    let (start_height, start_header_hash) = checkpoint
+        .map_or((None, wallet.genesis_challenge), |checkpoint| {
+            (Some(checkpoint.height), checkpoint.header_hash)
+        });
+
+    // Every puzzle hash that's synced up to the peak, so that the checkpoint covers them
+    let mut synced_puzzle_hashes: HashSet<Bytes32> = p2_puzzles
+        .iter()
+        .map(|(_, puzzle_hash)| *puzzle_hash)
+        .collect();
+
+    // Puzzles that were added after the checkpoint was saved have to be synced from the start
+    let (resumed_puzzle_hashes, new_puzzle_hashes): (Vec<_>, Vec<_>) = p2_puzzles
+        .into_iter()
+        .partition(|(id, _)| checkpoint.is_some_and(|checkpoint| *id <= checkpoint.p2_puzzle_id));
 
     let coin_ids = wallet.db.subscription_coin_ids().await?;
 
     sync_coin_ids(
         &wallet,
         &peer,
+        confirm_with,
+        &progress,
         start_height,
         start_header_hash,
         coin_ids,
@@ -48,10 +71,25 @@ pub async fn sync_wallet(
     )
     .await?;
 
-    for batch in p2_puzzle_hashes.chunks(1000) {
+    let resumed_puzzle_hashes = resumed_puzzle_hashes
+        .into_iter()
+        .map(|(_, puzzle_hash)| puzzle_hash)
+        .collect::<Vec<_>>();
+
+    for (i, batch) in resumed_puzzle_hashes.chunks(1000).enumerate() {
+        // What changed since the checkpoint is always confirmed with other peers for the first
+        // batch, and for every batch if delta sync is disabled
+        let confirm_with = if i == 0 || !delta_sync {
+            Some(&state)
+        } else {
+            confirm_with
+        };
+
         sync_puzzle_hashes(
             &wallet,
             &peer,
+            confirm_with,
+            &progress,
             start_height,
             start_header_hash,
             batch,
@@ -59,11 +97,41 @@ pub async fn sync_wallet(
             command_sender.clone(),
         )
         .await?;
+
+        progress
+            .lock()
+            .await
+            .add_subscribed_derivations(batch.len().try_into()?);
+    }
+
+    let new_puzzle_hashes = new_puzzle_hashes
+        .into_iter()
+        .map(|(_, puzzle_hash)| puzzle_hash)
+        .collect::<Vec<_>>();
+
+    for batch in new_puzzle_hashes.chunks(1000) {
+        sync_puzzle_hashes(
+            &wallet,
+            &peer,
+            confirm_with,
+            &progress,
+            None,
+            wallet.genesis_challenge,
+            batch,
+            sync_sender.clone(),
+            command_sender.clone(),
+        )
+        .await?;
+
+        progress
+            .lock()
+            .await
+            .add_subscribed_derivations(batch.len().try_into()?);
     }
 
     loop {
         let mut tx = wallet.db.tx().await?;
-        let derivations = auto_insert_unhardened_derivations(&wallet, &mut tx).await?;
+        let derivations = wallet.auto_insert_derivations(&mut tx).await?;
         let next_index = tx.derivation_index(false).await?;
         tx.commit().await?;
 
@@ -73,6 +141,13 @@ pub async fn sync_wallet(
 
         info!("Inserted {} derivations", derivations.len());
 
+        synced_puzzle_hashes.extend(derivations.iter().copied());
+
+        progress
+            .lock()
+            .await
+            .add_derivations(derivations.len().try_into()?);
+
         sync_sender
             .send(SyncEvent::DerivationIndex { next_index })
             .await
@@ -82,6 +157,8 @@ pub async fn sync_wallet(
             sync_puzzle_hashes(
                 &wallet,
                 &peer,
+                confirm_with,
+                &progress,
                 None,
                 wallet.genesis_challenge,
                 batch,
@@ -89,32 +166,93 @@ pub async fn sync_wallet(
                 command_sender.clone(),
             )
             .await?;
+
+            progress
+                .lock()
+                .await
+                .add_subscribed_derivations(batch.len().try_into()?);
         }
     }
 
-    if delta_sync {
-        if let Some((height, header_hash)) = state.lock().await.peak_of(peer.socket_addr().ip()) {
-            info!(
-                "Updating peak from peer to {} with header hash {}",
-                height, header_hash
-            );
+    let p2_puzzle_id = synced_p2_puzzle_id(
+        &wallet.db.synced_p2_puzzle_hashes_with_ids().await?,
+        0,
+        &synced_puzzle_hashes,
+    );
 
-            wallet
-                .db
-                .insert_block(height, header_hash, None, true)
-                .await?;
-        } else {
-            warn!("No peak found");
-        }
+    let peak = state.lock().await.peak_of(peer.socket_addr().ip());
+
+    if let Some((height, header_hash)) = peak {
+        info!(
+            "Updating peak and sync checkpoint from peer to {} with header hash {}",
+            height, header_hash
+        );
+
+        wallet
+            .db
+            .insert_block(height, header_hash, None, true)
+            .await?;
+
+        wallet
+            .db
+            .save_sync_checkpoint(SyncCheckpoint {
+                height,
+                header_hash,
+                p2_puzzle_id,
+            })
+            .await?;
+    } else {
+        warn!("No peak found");
     }
 
     Ok(())
 }
 
+/// Loads the stored sync checkpoint, and makes sure that it's still part of the peer's chain
+/// before resuming from it.
+async fn resume_checkpoint(
+    wallet: &Wallet,
+    peer: &WalletPeer,
+    state: &Mutex<PeerState>,
+) -> Result<Option<SyncCheckpoint>, WalletError> {
+    let Some(checkpoint) = wallet.db.sync_checkpoint().await? else {
+        return Ok(None);
+    };
+
+    let peak = state.lock().await.peak_of(peer.socket_addr().ip());
+
+    if peak.is_none_or(|(height, _)| height < checkpoint.height) {
+        info!(
+            "Peer {} is behind the sync checkpoint at height {}, syncing from the start",
+            peer.socket_addr(),
+            checkpoint.height
+        );
+        return Ok(None);
+    }
+
+    if peer.block_header_hash(checkpoint.height).await? != checkpoint.header_hash {
+        warn!(
+            "Sync checkpoint at height {} is no longer part of the chain",
+            checkpoint.height
+        );
+        wallet.db.clear_sync_checkpoint().await?;
+        return Err(WalletError::Reorg);
+    }
+
+    info!(
+        "Resuming sync from checkpoint at height {}",
+        checkpoint.height
+    );
+
+    Ok(Some(checkpoint))
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn sync_coin_ids(
     wallet: &Wallet,
     peer: &WalletPeer,
+    confirm_with: Option<&Arc<Mutex<PeerState>>>,
+    progress: &Mutex<SyncProgressTracker>,
     start_height: Option<u32>,
     start_header_hash: Bytes32,
     coin_ids: Vec<Bytes32>,
@@ -139,6 +277,21 @@ async fn sync_coin_ids(
 
         info!("Received {} coin states", coin_states.len());
 
+        progress
+            .lock()
+            .await
+            .add_coin_states(coin_states.len().try_into()?);
+
+        if let Some(state) = confirm_with {
+            let query = StateQuery::Coins {
+                coin_ids: coin_ids.to_vec(),
+                previous_height: start_height,
+                header_hash: start_header_hash,
+            };
+
+            confirm_coin_states(state, peer, &query, &coin_states, None).await?;
+        }
+
         if coin_states
             .iter()
             .any(|cs| cs.spent_height.is_some() || !only_send_event_if_spent)
@@ -150,9 +303,12 @@ async fn sync_coin_ids(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn sync_puzzle_hashes(
     wallet: &Wallet,
     peer: &WalletPeer,
+    confirm_with: Option<&Arc<Mutex<PeerState>>>,
+    progress: &Mutex<SyncProgressTracker>,
     start_height: Option<u32>,
     start_header_hash: Bytes32,
     puzzle_hashes: &[Bytes32],
@@ -175,17 +331,35 @@ async fn sync_puzzle_hashes(
             peer.socket_addr()
         );
 
+        let filters = CoinStateFilters::new(true, true, true, 0);
+
         let data = peer
             .subscribe_puzzles(
                 puzzle_hashes.to_vec(),
                 prev_height,
                 prev_header_hash,
-                CoinStateFilters::new(true, true, true, 0),
+                filters,
             )
             .await?;
 
         info!("Received {} coin states", data.coin_states.len());
 
+        progress
+            .lock()
+            .await
+            .add_coin_states(data.coin_states.len().try_into()?);
+
+        if let Some(state) = confirm_with {
+            let query = StateQuery::Puzzles {
+                puzzle_hashes: puzzle_hashes.to_vec(),
+                previous_height: prev_height,
+                header_hash: prev_header_hash,
+                filters,
+            };
+
+            confirm_coin_states(state, peer, &query, &data.coin_states, Some(data.height)).await?;
+        }
+
         if !data.coin_states.is_empty() {
             incremental_sync(
                 wallet,
@@ -208,6 +382,63 @@ async fn sync_puzzle_hashes(
     Ok(())
 }
 
+/// How many recorded blocks to compare against the peer's chain before giving up on finding
+/// the fork point and rolling back everything.
+const REORG_SEARCH_LIMIT: u32 = 100;
+
+/// Compares the header hashes of recorded blocks against the peer's chain, starting from
+/// the most recent block at or below its peak. Returns the height of the most recent block
+/// both chains have in common, if any of the recorded blocks have been reorged out.
+pub async fn find_fork_height(
+    wallet: &Wallet,
+    peer: &WalletPeer,
+    peak_height: u32,
+) -> Result<Option<u32>, WalletError> {
+    let mut reorged = false;
+
+    for (height, header_hash) in wallet
+        .db
+        .recent_header_hashes(peak_height, REORG_SEARCH_LIMIT)
+        .await?
+    {
+        if peer.block_header_hash(height).await? == header_hash {
+            return Ok(reorged.then_some(height));
+        }
+
+        reorged = true;
+    }
+
+    Ok(reorged.then_some(0))
+}
+
+/// Rolls the database back to the fork point, and records the fork point as the peak
+/// so that the next sync only requests coin states past it.
+pub async fn rollback_to_fork(
+    wallet: &Wallet,
+    peer: &WalletPeer,
+    fork_height: u32,
+) -> Result<(), WalletError> {
+    let header_hash = if fork_height > 0 {
+        Some(peer.block_header_hash(fork_height).await?)
+    } else {
+        None
+    };
+
+    let mut tx = wallet.db.tx().await?;
+    tx.rollback_to_height(fork_height).await?;
+    tx.rewind_sync_checkpoint(fork_height, header_hash).await?;
+    tx.commit().await?;
+
+    if let Some(header_hash) = header_hash {
+        wallet
+            .db
+            .insert_block(fork_height, header_hash, None, true)
+            .await?;
+    }
+
+    Ok(())
+}
+
 pub async fn incremental_sync(
     wallet: &Wallet,
     coin_states: Vec<CoinState>,
@@ -219,18 +450,26 @@ pub async fn incremental_sync(
     let mut confirmed_transactions = HashSet::new();
 
     for &coin_state in &coin_states {
-        if let Some(height) = coin_state.created_height {
-            tx.insert_height(height).await?;
-        }
+        for height in [coin_state.created_height, coin_state.spent_height]
+            .into_iter()
+            .flatten()
+        {
+            // Coins can only be created or spent in transaction blocks
+            if tx.is_verified_non_transaction_block(height).await? {
+                warn!(
+                    "Coin {} claims to be in block {height}, which isn't a transaction block",
+                    coin_state.coin.coin_id()
+                );
+                return Err(WalletError::PeerMisbehaved);
+            }
 
-        if let Some(height) = coin_state.spent_height {
             tx.insert_height(height).await?;
         }
 
         tx.insert_coin(coin_state).await?;
 
         if tx
-            .is_custody_p2_puzzle_hash(coin_state.coin.puzzle_hash)
+            .is_xch_p2_puzzle_hash(coin_state.coin.puzzle_hash)
             .await?
         {
             tx.update_coin(
@@ -261,7 +500,7 @@ pub async fn incremental_sync(
     let mut new_derivations = Vec::new();
 
     if derive_automatically {
-        new_derivations = auto_insert_unhardened_derivations(wallet, &mut tx).await?;
+        new_derivations = wallet.auto_insert_derivations(&mut tx).await?;
     }
 
     let next_index = tx.derivation_index(false).await?;
@@ -289,31 +528,12 @@ pub async fn incremental_sync(
     Ok(())
 }
 
-async fn auto_insert_unhardened_derivations(
-    wallet: &Wallet,
-    tx: &mut DatabaseTx<'_>,
-) -> Result<Vec<Bytes32>, WalletError> {
-    let mut derivations = Vec::new();
-    let mut next_index = tx.derivation_index(false).await?;
-
-    let max_index = tx.unused_derivation_index(false).await?;
-
-    while max_index + 500 >= next_index {
-        derivations.extend(
-            wallet
-                .insert_unhardened_derivations(tx, next_index..next_index + 500)
-                .await?,
-        );
-
-        next_index += 500;
-    }
-
-    Ok(derivations)
-}
-
+#[allow(clippy::too_many_arguments)]
 pub async fn add_new_subscriptions(
     wallet: &Wallet,
     peer: &WalletPeer,
+    confirm_with: Option<&Arc<Mutex<PeerState>>>,
+    progress: &Mutex<SyncProgressTracker>,
     coin_ids: Vec<Bytes32>,
     puzzle_hashes: Vec<Bytes32>,
     sync_sender: mpsc::Sender<SyncEvent>,
@@ -323,6 +543,8 @@ pub async fn add_new_subscriptions(
         sync_coin_ids(
             wallet,
             peer,
+            confirm_with,
+            progress,
             None,
             wallet.genesis_challenge,
             batch.to_vec(),
@@ -337,6 +559,8 @@ pub async fn add_new_subscriptions(
         sync_puzzle_hashes(
             wallet,
             peer,
+            confirm_with,
+            progress,
             None,
             wallet.genesis_challenge,
             batch,
@@ -346,5 +570,242 @@ pub async fn add_new_subscriptions(
         .await?;
     }
 
+    // New puzzles have been synced from the start, so they don't have to be synced again when
+    // resuming from the checkpoint
+    if !puzzle_hashes.is_empty()
+        && let Some(checkpoint) = wallet.db.sync_checkpoint().await?
+    {
+        let p2_puzzle_id = synced_p2_puzzle_id(
+            &wallet.db.synced_p2_puzzle_hashes_with_ids().await?,
+            checkpoint.p2_puzzle_id,
+            &puzzle_hashes.into_iter().collect(),
+        );
+
+        wallet
+            .db
+            .advance_sync_checkpoint_p2_puzzle_id(p2_puzzle_id)
+            .await?;
+    }
+
     Ok(())
 }
+
+/// The highest p2 puzzle id such that every puzzle after `start` up to it has been synced.
+/// Puzzles that were inserted but not synced yet stop it short, so that they're synced from
+/// the start when resuming.
+fn synced_p2_puzzle_id(
+    p2_puzzles: &[(u32, Bytes32)],
+    start: u32,
+    synced_puzzle_hashes: &HashSet<Bytes32>,
+) -> u32 {
+    p2_puzzles
+        .iter()
+        .filter(|(id, _)| *id > start)
+        .take_while(|(_, puzzle_hash)| synced_puzzle_hashes.contains(puzzle_hash))
+        .last()
+        .map_or(start, |(id, _)| *id)
+}
+
+#[cfg(test)]
+mod tests {
+    use sage_database::VerifiedHeader;
+    use test_log::test;
+
+    use crate::TestWallet;
+
+    use super::*;
+
+    #[test(tokio::test)]
+    async fn test_rollback_to_fork() -> anyhow::Result<()> {
+        let mut test = TestWallet::new(1000).await?;
+
+        let coin_spends = test
+            .wallet
+            .send_xch(vec![(test.puzzle_hash, 250)], 250, vec![], None)
+            .await?;
+
+        test.transact(coin_spends).await?;
+        test.wait_for_coins().await;
+
+        assert_eq!(test.wallet.db.xch_balance().await?, 750);
+
+        let (transactions, _) = test.wallet.db.transactions(None, false, 1, 0).await?;
+        let height = transactions[0].height;
+
+        let mut tx = test.wallet.db.tx().await?;
+        tx.rollback_to_height(height - 1).await?;
+        tx.commit().await?;
+
+        assert_eq!(test.wallet.db.xch_balance().await?, 1000);
+        assert_eq!(test.wallet.db.selectable_xch_coins().await?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_resume_from_checkpoint() -> anyhow::Result<()> {
+        let mut test = TestWallet::new(1000).await?;
+
+        let checkpoint = test
+            .wallet
+            .db
+            .sync_checkpoint()
+            .await?
+            .expect("checkpoint should be saved after syncing");
+
+        let coin_spends = test
+            .wallet
+            .send_xch(vec![(test.puzzle_hash, 250)], 250, vec![], None)
+            .await?;
+
+        test.transact(coin_spends).await?;
+        test.wait_for_coins().await;
+
+        test.sender
+            .send(SyncCommand::SwitchWallet {
+                wallet: Some(test.wallet.clone()),
+                delta_sync: true,
+            })
+            .await?;
+
+        test.consume_until(|event| matches!(event, SyncEvent::Subscribed))
+            .await;
+
+        let resumed = test
+            .wallet
+            .db
+            .sync_checkpoint()
+            .await?
+            .expect("checkpoint should be saved after resuming");
+
+        assert!(resumed.height > checkpoint.height);
+        assert!(resumed.p2_puzzle_id >= checkpoint.p2_puzzle_id);
+        assert_eq!(test.wallet.db.xch_balance().await?, 750);
+
+        Ok(())
+    }
+
+    async fn last_p2_puzzle_id(test: &TestWallet) -> anyhow::Result<u32> {
+        Ok(test
+            .wallet
+            .db
+            .synced_p2_puzzle_hashes_with_ids()
+            .await?
+            .last()
+            .map_or(0, |(id, _)| *id))
+    }
+
+    #[test(tokio::test)]
+    async fn test_resume_after_new_derivations() -> anyhow::Result<()> {
+        let mut test = TestWallet::new(1000).await?;
+
+        // Derivations that were inserted during the initial sync are covered by the checkpoint
+        let checkpoint = test.wallet.db.sync_checkpoint().await?.expect("checkpoint");
+        let initial_p2_puzzle_id = last_p2_puzzle_id(&test).await?;
+        assert_eq!(checkpoint.p2_puzzle_id, initial_p2_puzzle_id);
+
+        // Receiving to the last derivation inserts more of them, which are synced separately
+        let mut tx = test.wallet.db.tx().await?;
+        let index = tx.derivation_index(false).await? - 1;
+        let puzzle_hash = tx.custody_p2_puzzle_hash(index, false).await?;
+        tx.commit().await?;
+
+        let coin_spends = test
+            .wallet
+            .send_xch(vec![(puzzle_hash, 250)], 0, vec![], None)
+            .await?;
+
+        test.transact(coin_spends).await?;
+        test.consume_until(|event| matches!(event, SyncEvent::DerivationIndex { .. }))
+            .await;
+
+        let new_p2_puzzle_id = last_p2_puzzle_id(&test).await?;
+        assert!(new_p2_puzzle_id > initial_p2_puzzle_id);
+
+        let mut checkpoint = test.wallet.db.sync_checkpoint().await?.expect("checkpoint");
+
+        for _ in 0..100 {
+            if checkpoint.p2_puzzle_id == new_p2_puzzle_id {
+                break;
+            }
+
+            sleep(Duration::from_millis(100)).await;
+            checkpoint = test.wallet.db.sync_checkpoint().await?.expect("checkpoint");
+        }
+
+        assert_eq!(checkpoint.p2_puzzle_id, new_p2_puzzle_id);
+
+        test.sender
+            .send(SyncCommand::SwitchWallet {
+                wallet: Some(test.wallet.clone()),
+                delta_sync: true,
+            })
+            .await?;
+
+        test.consume_until(|event| matches!(event, SyncEvent::Subscribed))
+            .await;
+
+        let resumed = test.wallet.db.sync_checkpoint().await?.expect("checkpoint");
+        assert_eq!(resumed.p2_puzzle_id, new_p2_puzzle_id);
+        assert_eq!(test.wallet.db.xch_balance().await?, 1000);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_synced_p2_puzzle_id() {
+        let p2_puzzles: Vec<(u32, Bytes32)> = (1..=4)
+            .map(|id| (id, Bytes32::new([id as u8; 32])))
+            .collect();
+
+        let synced = [1, 2, 4]
+            .into_iter()
+            .map(|id| Bytes32::new([id; 32]))
+            .collect();
+
+        assert_eq!(synced_p2_puzzle_id(&p2_puzzles, 0, &synced), 2);
+        assert_eq!(synced_p2_puzzle_id(&p2_puzzles, 3, &synced), 4);
+        assert_eq!(synced_p2_puzzle_id(&p2_puzzles, 4, &synced), 4);
+    }
+
+    #[test(tokio::test)]
+    async fn test_reject_coin_in_non_transaction_block() -> anyhow::Result<()> {
+        let test = TestWallet::new(1000).await?;
+
+        let height = 1_000_000;
+
+        let mut tx = test.wallet.db.tx().await?;
+        tx.insert_verified_header(
+            height,
+            VerifiedHeader {
+                header_hash: Bytes32::new([1; 32]),
+                prev_header_hash: Bytes32::new([2; 32]),
+                timestamp: None,
+            },
+        )
+        .await?;
+        tx.commit().await?;
+
+        let coin_state = CoinState::new(
+            Coin::new(Bytes32::default(), test.puzzle_hash, 1),
+            None,
+            Some(height),
+        );
+
+        let (sync_sender, _sync_receiver) = mpsc::channel(10);
+        let (command_sender, _command_receiver) = mpsc::channel(10);
+
+        let result = incremental_sync(
+            &test.wallet,
+            vec![coin_state],
+            false,
+            &sync_sender,
+            &command_sender,
+        )
+        .await;
+
+        assert!(matches!(result, Err(WalletError::PeerMisbehaved)));
+
+        Ok(())
+    }
+}
@@ -28,8 +28,8 @@ use tokio::{
 use tracing::debug;
 
 use crate::{
-    PeerState, SyncCommand, SyncEvent, SyncManager, SyncOptions, Timeouts, Transaction, Wallet,
-    insert_transaction,
+    KeychainSigner, PeerState, RebroadcastPolicy, SyncCommand, SyncEvent, SyncManager, SyncOptions,
+    SyncProgressTracker, Timeouts, Transaction, Wallet, WalletPeer, insert_transaction,
 };
 
 static INDEX: Mutex<u32> = Mutex::const_new(0);
@@ -146,6 +146,7 @@ impl TestWallet {
         let (mut sync_manager, sender, events) = SyncManager::new(
             options,
             state.clone(),
+            Arc::new(Mutex::new(SyncProgressTracker::default())),
             Some(wallet.clone()),
             TESTNET11.clone(),
             Connector::Plain,
@@ -155,7 +156,7 @@ impl TestWallet {
 
         assert!(
             sync_manager
-                .try_add_peer(peer.clone(), receiver, true, false)
+                .try_add_peer(WalletPeer::new(peer.clone()), receiver, true, false)
                 .await
         );
 
@@ -194,7 +195,7 @@ impl TestWallet {
             .sign_transaction(
                 SpendBundle::new(coin_spends, Signature::default()),
                 &self.agg_sig,
-                self.master_sk.clone(),
+                &KeychainSigner::new(self.master_sk.clone()),
                 false,
             )
             .await?;
@@ -268,13 +269,18 @@ pub fn default_test_options() -> SyncOptions {
         connection_batch_size: 0,
         max_peer_age_seconds: 0,
         delta_sync: true,
+        paranoid: false,
+        offline: false,
+        proxy: None,
         puzzle_batch_size_per_peer: 5,
+        rebroadcast: RebroadcastPolicy::default(),
         timeouts: Timeouts {
             sync_delay: Duration::from_millis(100),
             nft_uri_delay: Duration::from_millis(100),
             cat_delay: Duration::from_millis(100),
             puzzle_delay: Duration::from_millis(100),
             transaction_delay: Duration::from_millis(100),
+            broadcast_delay: Duration::from_millis(100),
             offer_delay: Duration::from_millis(100),
             ..Default::default()
         },
@@ -1,5 +1,6 @@
 use std::{
     collections::{HashMap, HashSet},
+    sync::Arc,
     time::{SystemTime, UNIX_EPOCH},
 };
 
@@ -23,6 +24,7 @@ mod nfts;
 mod offer;
 mod options;
 mod signing;
+mod vaults;
 mod xch;
 
 pub use memos::*;
@@ -31,7 +33,7 @@ pub use nfts::*;
 pub use offer::*;
 pub use options::*;
 
-use crate::WalletError;
+use crate::{Signer, WalletError};
 
 #[derive(Debug)]
 pub struct Wallet {
@@ -41,6 +43,14 @@ pub struct Wallet {
     pub genesis_challenge: Bytes32,
     pub agg_sig_constants: AggSigConstants,
     pub change_p2_puzzle_hash: Option<Bytes32>,
+    /// Derives hardened keys when they're needed, which can't be done from the public key alone.
+    pub hardened_keys: Option<Arc<dyn Signer>>,
+    /// Whether to receive to hardened addresses rather than unhardened addresses.
+    pub receive_hardened: bool,
+    /// How many unused derivations to keep after the last used one.
+    pub gap_limit: u32,
+    /// Whether change is sent to the current receive address rather than a new one each time.
+    pub reuse_change: bool,
 }
 
 impl Wallet {
@@ -59,6 +69,10 @@ impl Wallet {
             genesis_challenge,
             agg_sig_constants,
             change_p2_puzzle_hash,
+            hardened_keys: None,
+            receive_hardened: false,
+            gap_limit: 500,
+            reuse_change: true,
         }
     }
 
@@ -400,6 +414,13 @@ impl Wallet {
                                     spend.finish().into_iter().collect(),
                                 ),
                             )?,
+                        P2Puzzle::Vault(..) => {
+                            return Err(DriverError::Custom(
+                                "Vault coins can only be spent by proposing a vault spend"
+                                    .to_string(),
+                            )
+                            .into());
+                        }
                     }
                 }
                 SpendKind::Settlement(spend) => SettlementLayer
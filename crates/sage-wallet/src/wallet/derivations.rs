@@ -46,6 +46,131 @@ impl Wallet {
         Ok(puzzle_hashes)
     }
 
+    /// Inserts a range of hardened derivations to the database.
+    pub async fn insert_hardened_derivations(
+        &self,
+        tx: &mut DatabaseTx<'_>,
+        range: Range<u32>,
+    ) -> Result<Vec<Bytes32>, WalletError> {
+        let Some(hardened_keys) = &self.hardened_keys else {
+            return Err(WalletError::HardenedKeyUnavailable);
+        };
+
+        let Some(synthetic_keys) = hardened_keys.hardened_public_keys(range.clone()).await? else {
+            return Err(WalletError::HardenedKeyUnavailable);
+        };
+
+        let mut puzzle_hashes = Vec::new();
+
+        for (index, synthetic_key) in range.zip(synthetic_keys) {
+            let p2_puzzle_hash = StandardArgs::curry_tree_hash(synthetic_key).into();
+
+            tx.insert_custody_p2_puzzle(
+                p2_puzzle_hash,
+                synthetic_key,
+                Derivation {
+                    derivation_index: index,
+                    is_hardened: true,
+                },
+            )
+            .await?;
+
+            puzzle_hashes.push(p2_puzzle_hash);
+        }
+
+        Ok(puzzle_hashes)
+    }
+
+    /// Inserts derivations until there are at least as many unused derivations as the gap limit
+    /// after the last used one, and returns the new p2 puzzle hashes which need to be subscribed.
+    pub async fn auto_insert_derivations(
+        &self,
+        tx: &mut DatabaseTx<'_>,
+    ) -> Result<Vec<Bytes32>, WalletError> {
+        let mut derivations = self.auto_insert_branch(tx, false).await?;
+
+        // Hardened keys can only be derived if the wallet has access to its secret key
+        if self.hardened_keys.is_some() {
+            match self.auto_insert_branch(tx, true).await {
+                Ok(hardened) => derivations.extend(hardened),
+                // The secret key can't be unlocked, for example without the key's password
+                Err(WalletError::HardenedKeyUnavailable) => {}
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(derivations)
+    }
+
+    async fn auto_insert_branch(
+        &self,
+        tx: &mut DatabaseTx<'_>,
+        hardened: bool,
+    ) -> Result<Vec<Bytes32>, WalletError> {
+        let mut derivations = Vec::new();
+        let mut next_index = tx.derivation_index(hardened).await?;
+
+        // If the wallet was imported without hardened keys, they shouldn't show up later on
+        if hardened && next_index == 0 {
+            return Ok(derivations);
+        }
+
+        let max_index = tx.unused_derivation_index(hardened).await?;
+
+        // A gap limit of 0 would never advance the index
+        let gap_limit = self.gap_limit.max(1);
+
+        while max_index.saturating_add(gap_limit) >= next_index {
+            let end = next_index.saturating_add(gap_limit);
+
+            // There are no derivation indices left
+            if end == next_index {
+                break;
+            }
+
+            derivations.extend(if hardened {
+                self.insert_hardened_derivations(tx, next_index..end)
+                    .await?
+            } else {
+                self.insert_unhardened_derivations(tx, next_index..end)
+                    .await?
+            });
+
+            next_index = end;
+        }
+
+        Ok(derivations)
+    }
+
+    /// Reserves the next unused derivation, so that it won't be handed out again even if it never
+    /// receives any coins.
+    pub async fn reserve_p2_puzzle_hash(
+        &self,
+        tx: &mut DatabaseTx<'_>,
+        hardened: bool,
+    ) -> Result<(u32, Bytes32), WalletError> {
+        let (index, p2_puzzle_hash) = self.unused_p2_puzzle_hash(tx, hardened).await?;
+
+        tx.reserve_derivation(index, hardened).await?;
+
+        Ok((index, p2_puzzle_hash))
+    }
+
+    /// The first derivation after the last used or reserved one.
+    async fn unused_p2_puzzle_hash(
+        &self,
+        tx: &mut DatabaseTx<'_>,
+        hardened: bool,
+    ) -> Result<(u32, Bytes32), WalletError> {
+        let index = tx.unused_derivation_index(hardened).await?;
+
+        if index >= tx.derivation_index(hardened).await? {
+            return Err(WalletError::InsufficientDerivations);
+        }
+
+        Ok((index, tx.custody_p2_puzzle_hash(index, hardened).await?))
+    }
+
     pub async fn p2_puzzle_hashes(
         &self,
         count: u32,
@@ -81,10 +206,134 @@ impl Wallet {
         Ok(p2_puzzle_hashes)
     }
 
-    pub async fn change_p2_puzzle_hash(&self) -> Result<Bytes32, WalletError> {
+    /// The address to receive to, which is reused until it has received coins.
+    pub async fn receive_p2_puzzle_hash(&self) -> Result<Bytes32, WalletError> {
         if let Some(change_p2_puzzle_hash) = self.change_p2_puzzle_hash {
             return Ok(change_p2_puzzle_hash);
         }
+
+        // Wallets without any hardened derivations fall back to unhardened addresses
+        if self.receive_hardened {
+            match self.p2_puzzle_hashes(1, true, true).await {
+                Ok(p2_puzzle_hashes) => return Ok(p2_puzzle_hashes[0]),
+                Err(WalletError::InsufficientDerivations) => {}
+                Err(error) => return Err(error),
+            }
+        }
+
         Ok(self.p2_puzzle_hashes(1, false, true).await?[0])
     }
+
+    pub async fn change_p2_puzzle_hash(&self) -> Result<Bytes32, WalletError> {
+        if self.reuse_change || self.change_p2_puzzle_hash.is_some() {
+            return self.receive_p2_puzzle_hash().await;
+        }
+
+        let mut tx = self.db.tx().await?;
+
+        // Nothing is reserved here, since the transaction may never be submitted. Once it is, the
+        // change coin marks the derivation as used, and the next transaction moves on.
+        let hardened = self.receive_hardened && tx.derivation_index(true).await? > 0;
+        let (_, p2_puzzle_hash) = self.unused_p2_puzzle_hash(&mut tx, hardened).await?;
+
+        tx.commit().await?;
+
+        Ok(p2_puzzle_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use test_log::test;
+
+    use crate::{KeychainSigner, TestWallet};
+
+    use super::*;
+
+    #[test(tokio::test)]
+    async fn test_receive_hardened() -> anyhow::Result<()> {
+        let test = TestWallet::new(0).await?;
+
+        let mut wallet = Wallet::new(
+            test.wallet.db.clone(),
+            test.wallet.fingerprint,
+            test.wallet.intermediate_pk,
+            test.wallet.genesis_challenge,
+            test.agg_sig.clone(),
+            None,
+        );
+
+        assert_eq!(wallet.change_p2_puzzle_hash().await?, test.puzzle_hash);
+
+        wallet.receive_hardened = true;
+        assert_eq!(
+            wallet.change_p2_puzzle_hash().await?,
+            test.hardened_puzzle_hash
+        );
+
+        let mut tx = wallet.db.tx().await?;
+        assert!(matches!(
+            wallet.insert_hardened_derivations(&mut tx, 100..110).await,
+            Err(WalletError::HardenedKeyUnavailable)
+        ));
+
+        wallet.hardened_keys = Some(Arc::new(KeychainSigner::new(test.master_sk.clone())));
+
+        let p2_puzzle_hashes = wallet
+            .insert_hardened_derivations(&mut tx, 100..110)
+            .await?;
+        assert_eq!(p2_puzzle_hashes.len(), 10);
+        assert_eq!(tx.derivation_index(true).await?, 110);
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    #[test(tokio::test)]
+    async fn test_fresh_change() -> anyhow::Result<()> {
+        let mut test = TestWallet::new(1000).await?;
+
+        let mut wallet = Wallet::new(
+            test.wallet.db.clone(),
+            test.wallet.fingerprint,
+            test.wallet.intermediate_pk,
+            test.wallet.genesis_challenge,
+            test.agg_sig.clone(),
+            None,
+        );
+
+        // Reused change stays the same until it receives coins
+        assert_eq!(wallet.change_p2_puzzle_hash().await?, test.puzzle_hash);
+
+        wallet.reuse_change = false;
+
+        // Fresh change isn't used up until a transaction sending change to it is submitted
+        let first = wallet.change_p2_puzzle_hash().await?;
+        assert_ne!(first, test.puzzle_hash);
+        assert_eq!(wallet.change_p2_puzzle_hash().await?, first);
+
+        let coin_spends = wallet
+            .send_xch(vec![(test.puzzle_hash, 250)], 0, vec![], None)
+            .await?;
+        test.transact(coin_spends).await?;
+        test.wait_for_coins().await;
+
+        let second = wallet.change_p2_puzzle_hash().await?;
+        assert_ne!(first, second);
+
+        // Reserved addresses count towards the gap limit
+        let mut tx = wallet.db.tx().await?;
+        assert_eq!(tx.unused_derivation_index(false).await?, 2);
+        let (index, p2_puzzle_hash) = wallet.reserve_p2_puzzle_hash(&mut tx, false).await?;
+        assert_eq!(index, 2);
+        assert_eq!(p2_puzzle_hash, second);
+        tx.commit().await?;
+
+        assert_eq!(wallet.receive_p2_puzzle_hash().await?, p2_puzzle_hash);
+        assert_ne!(wallet.change_p2_puzzle_hash().await?, second);
+
+        Ok(())
+    }
 }
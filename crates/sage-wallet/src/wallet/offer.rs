@@ -16,7 +16,8 @@ mod tests {
     use test_log::test;
 
     use crate::{
-        Offered, Requested, RequestedCat, TakenOffer, TestWallet, WalletError, WalletNftMint,
+        KeychainSigner, Offered, Requested, RequestedCat, TakenOffer, TestWallet, WalletError,
+        WalletNftMint,
     };
 
     use super::aggregate_offers;
@@ -34,7 +35,7 @@ mod tests {
             .sign_transaction(
                 spend_bundle,
                 &wallet.agg_sig,
-                wallet.master_sk.clone(),
+                &KeychainSigner::new(wallet.master_sk.clone()),
                 false,
             )
             .await?;
@@ -70,7 +71,12 @@ mod tests {
             .await?;
         let offer = alice
             .wallet
-            .sign_transaction(offer, &alice.agg_sig, alice.master_sk.clone(), true)
+            .sign_transaction(
+                offer,
+                &alice.agg_sig,
+                &KeychainSigner::new(alice.master_sk.clone()),
+                true,
+            )
             .await?;
 
         // Take offer
@@ -144,7 +150,12 @@ mod tests {
             .await?;
         let offer = alice
             .wallet
-            .sign_transaction(offer, &alice.agg_sig, alice.master_sk.clone(), true)
+            .sign_transaction(
+                offer,
+                &alice.agg_sig,
+                &KeychainSigner::new(alice.master_sk.clone()),
+                true,
+            )
             .await?;
 
         // Take offer
@@ -207,7 +218,12 @@ mod tests {
             .await?;
         let offer = alice
             .wallet
-            .sign_transaction(offer, &alice.agg_sig, alice.master_sk.clone(), true)
+            .sign_transaction(
+                offer,
+                &alice.agg_sig,
+                &KeychainSigner::new(alice.master_sk.clone()),
+                true,
+            )
             .await?;
 
         // Take offer
@@ -282,7 +298,12 @@ mod tests {
             .await?;
         let offer = alice
             .wallet
-            .sign_transaction(offer, &alice.agg_sig, alice.master_sk.clone(), true)
+            .sign_transaction(
+                offer,
+                &alice.agg_sig,
+                &KeychainSigner::new(alice.master_sk.clone()),
+                true,
+            )
             .await?;
 
         // Take offer
@@ -369,7 +390,12 @@ mod tests {
             .await?;
         let offer = alice
             .wallet
-            .sign_transaction(offer, &alice.agg_sig, alice.master_sk.clone(), true)
+            .sign_transaction(
+                offer,
+                &alice.agg_sig,
+                &KeychainSigner::new(alice.master_sk.clone()),
+                true,
+            )
             .await?;
 
         // Take offer
@@ -451,7 +477,12 @@ mod tests {
             .await?;
         let offer = alice
             .wallet
-            .sign_transaction(offer, &alice.agg_sig, alice.master_sk.clone(), true)
+            .sign_transaction(
+                offer,
+                &alice.agg_sig,
+                &KeychainSigner::new(alice.master_sk.clone()),
+                true,
+            )
             .await?;
 
         // Take offer
@@ -530,7 +561,12 @@ mod tests {
             .await?;
         let first_offer = alice
             .wallet
-            .sign_transaction(first_offer, &alice.agg_sig, alice.master_sk.clone(), true)
+            .sign_transaction(
+                first_offer,
+                &alice.agg_sig,
+                &KeychainSigner::new(alice.master_sk.clone()),
+                true,
+            )
             .await?;
 
         // Create second offer
@@ -550,7 +586,12 @@ mod tests {
             .await?;
         let second_offer = alice
             .wallet
-            .sign_transaction(second_offer, &alice.agg_sig, alice.master_sk.clone(), true)
+            .sign_transaction(
+                second_offer,
+                &alice.agg_sig,
+                &KeychainSigner::new(alice.master_sk.clone()),
+                true,
+            )
             .await?;
 
         // Aggregate offers
@@ -629,7 +670,7 @@ mod tests {
             .sign_transaction(
                 unsigned_offer,
                 &alice.agg_sig,
-                alice.master_sk.clone(),
+                &KeychainSigner::new(alice.master_sk.clone()),
                 true,
             )
             .await?;
@@ -706,7 +747,7 @@ mod tests {
             .sign_transaction(
                 unsigned_offer,
                 &alice.agg_sig,
-                alice.master_sk.clone(),
+                &KeychainSigner::new(alice.master_sk.clone()),
                 true,
             )
             .await?;
@@ -775,7 +816,7 @@ mod tests {
             .sign_transaction(
                 unsigned_offer,
                 &alice.agg_sig,
-                alice.master_sk.clone(),
+                &KeychainSigner::new(alice.master_sk.clone()),
                 true,
             )
             .await?;
@@ -814,7 +855,12 @@ mod tests {
             .await?;
         let offer = alice
             .wallet
-            .sign_transaction(offer, &alice.agg_sig, alice.master_sk.clone(), true)
+            .sign_transaction(
+                offer,
+                &alice.agg_sig,
+                &KeychainSigner::new(alice.master_sk.clone()),
+                true,
+            )
             .await?;
 
         // Take offer
@@ -852,7 +898,12 @@ mod tests {
             .await?;
         let offer = alice
             .wallet
-            .sign_transaction(offer, &alice.agg_sig, alice.master_sk.clone(), true)
+            .sign_transaction(
+                offer,
+                &alice.agg_sig,
+                &KeychainSigner::new(alice.master_sk.clone()),
+                true,
+            )
             .await?;
 
         let result = alice.wallet.take_offer(offer, 0).await;
@@ -913,7 +964,12 @@ mod tests {
             .await?;
         let mut offer = alice
             .wallet
-            .sign_transaction(offer, &alice.agg_sig, alice.master_sk.clone(), true)
+            .sign_transaction(
+                offer,
+                &alice.agg_sig,
+                &KeychainSigner::new(alice.master_sk.clone()),
+                true,
+            )
             .await?;
 
         offer.coin_spends.extend(malicious_spends);
@@ -958,7 +1014,12 @@ mod tests {
             .await?;
         let offer = alice
             .wallet
-            .sign_transaction(offer, &alice.agg_sig, alice.master_sk.clone(), true)
+            .sign_transaction(
+                offer,
+                &alice.agg_sig,
+                &KeychainSigner::new(alice.master_sk.clone()),
+                true,
+            )
             .await?;
 
         // Take offer
@@ -1016,7 +1077,12 @@ mod tests {
             .await?;
         let offer = alice
             .wallet
-            .sign_transaction(offer, &alice.agg_sig, alice.master_sk.clone(), true)
+            .sign_transaction(
+                offer,
+                &alice.agg_sig,
+                &KeychainSigner::new(alice.master_sk.clone()),
+                true,
+            )
             .await?;
 
         // Take offer
@@ -1160,7 +1226,7 @@ mod tests {
             .sign_transaction(
                 unsigned_offer,
                 &alice.agg_sig,
-                alice.master_sk.clone(),
+                &KeychainSigner::new(alice.master_sk.clone()),
                 true,
             )
             .await?;
@@ -1266,7 +1332,7 @@ mod tests {
             .sign_transaction(
                 unsigned_offer,
                 &alice.agg_sig,
-                alice.master_sk.clone(),
+                &KeychainSigner::new(alice.master_sk.clone()),
                 true,
             )
             .await?;
@@ -1362,7 +1428,7 @@ mod tests {
             .sign_transaction(
                 unsigned_offer,
                 &alice.agg_sig,
-                alice.master_sk.clone(),
+                &KeychainSigner::new(alice.master_sk.clone()),
                 true,
             )
             .await?;
@@ -1461,7 +1527,7 @@ mod tests {
             .sign_transaction(
                 unsigned_offer,
                 &alice.agg_sig,
-                alice.master_sk.clone(),
+                &KeychainSigner::new(alice.master_sk.clone()),
                 true,
             )
             .await?;
@@ -1585,7 +1651,7 @@ mod tests {
             .sign_transaction(
                 unsigned_offer,
                 &alice.agg_sig,
-                alice.master_sk.clone(),
+                &KeychainSigner::new(alice.master_sk.clone()),
                 true,
             )
             .await?;
@@ -1698,7 +1764,7 @@ mod tests {
             .sign_transaction(
                 unsigned_offer,
                 &alice.agg_sig,
-                alice.master_sk.clone(),
+                &KeychainSigner::new(alice.master_sk.clone()),
                 true,
             )
             .await?;
@@ -26,6 +26,35 @@ impl Wallet {
         let mut ctx = SpendContext::new();
         let offer = Offer::from_spend_bundle(&mut ctx, &spend_bundle)?;
 
+        let change_puzzle_hash = self.change_p2_puzzle_hash().await?;
+
+        let mut spends = Spends::new(change_puzzle_hash);
+        let mut actions = vec![Action::fee(fee)];
+
+        self.settle_offer(&mut ctx, &offer, &mut spends, &mut actions)
+            .await?;
+
+        // Finish the spend
+        let deltas = spends.apply(&mut ctx, &actions)?;
+
+        self.complete_spends(&mut ctx, &deltas, spends).await?;
+
+        Ok(TakenOffer {
+            offer,
+            spend_bundle: SpendBundle::new(ctx.take(), Signature::default()),
+        })
+    }
+
+    /// Adds the offered coins to the spends along with the actions needed to pay for the offer,
+    /// then selects coins for every action. The new actions are appended after the existing ones,
+    /// so that ids of assets created by earlier actions remain valid.
+    pub async fn settle_offer(
+        &self,
+        ctx: &mut SpendContext,
+        offer: &Offer,
+        spends: &mut Spends,
+        actions: &mut Vec<Action>,
+    ) -> Result<(), WalletError> {
         let arbitrage = offer.arbitrage();
 
         let mut requested_nfts = IndexMap::new();
@@ -48,8 +77,6 @@ impl Wallet {
             );
         }
 
-        let change_puzzle_hash = self.change_p2_puzzle_hash().await?;
-
         let offer_amounts = OfferAmounts {
             xch: arbitrage.offered.xch,
             cats: arbitrage.offered.cats.clone(),
@@ -75,16 +102,14 @@ impl Wallet {
         let offer_trade_price_amounts =
             calculate_trade_price_amounts(&offer_amounts, offer_royalties.len());
 
-        // Make payments
-        let mut actions = vec![Action::fee(fee)];
+        let settle_index = actions.len();
 
         // Pay royalties
         let royalty_payments =
-            calculate_royalty_payments(&mut ctx, &offer_trade_price_amounts, &offer_royalties)?;
+            calculate_royalty_payments(ctx, &offer_trade_price_amounts, &offer_royalties)?;
         actions.extend(royalty_payments.actions());
 
         // Pay requested payments
-        let mut spends = Spends::new(change_puzzle_hash);
         spends.add(offer.offered_coins().clone());
         actions.extend(offer.requested_payments().actions());
 
@@ -95,16 +120,19 @@ impl Wallet {
             .iter()
             .map(|coin_spend| coin_spend.coin.coin_id())
             .collect_vec();
-        self.select_spends_excluding(&mut ctx, &mut spends, &actions, &offer_input_coin_ids)
+        self.select_spends_excluding(ctx, spends, actions, &offer_input_coin_ids)
             .await?;
 
         // Reset DIDs and reveal trade prices
+        let requested_nft_ids = &offer.requested_payments().nfts;
         let mut royalty_nft_count = 0;
 
         for nft in spends.nfts.values().rev() {
             let nft = nft.last()?;
 
-            if !nft.kind.is_conditions() {
+            if !nft.kind.is_conditions()
+                || !requested_nft_ids.contains_key(&nft.asset.info.launcher_id)
+            {
                 continue;
             }
 
@@ -121,12 +149,14 @@ impl Wallet {
         for nft in spends.nfts.values().rev() {
             let nft = nft.last()?;
 
-            if !nft.kind.is_conditions() {
+            if !nft.kind.is_conditions()
+                || !requested_nft_ids.contains_key(&nft.asset.info.launcher_id)
+            {
                 continue;
             }
 
             actions.insert(
-                0,
+                settle_index,
                 Action::update_nft(
                     Id::Existing(nft.asset.info.launcher_id),
                     vec![],
@@ -142,14 +172,6 @@ impl Wallet {
             );
         }
 
-        // Finish the spend
-        let deltas = spends.apply(&mut ctx, &actions)?;
-
-        self.complete_spends(&mut ctx, &deltas, spends).await?;
-
-        Ok(TakenOffer {
-            offer,
-            spend_bundle: SpendBundle::new(ctx.take(), Signature::default()),
-        })
+        Ok(())
     }
 }
@@ -19,6 +19,26 @@ pub struct WalletOptionMint {
     pub strike_type: OptionType,
 }
 
+/// Underlying coins unlocked by exercising options. These must be added to the spends after
+/// coin selection, since they can only be used to pay for the settlement.
+#[derive(Debug, Default, Clone)]
+pub struct OptionSettlements {
+    pub coins: Vec<Coin>,
+    pub cats: Vec<Cat>,
+}
+
+impl OptionSettlements {
+    pub fn add_to(self, spends: &mut Spends) {
+        for coin in self.coins {
+            spends.add(coin);
+        }
+
+        for cat in self.cats {
+            spends.add(cat);
+        }
+    }
+}
+
 impl Wallet {
     pub async fn mint_option(
         &self,
@@ -125,116 +145,122 @@ impl Wallet {
     ) -> Result<Vec<CoinSpend>, WalletError> {
         let mut ctx = SpendContext::new();
         let mut actions = vec![Action::fee(fee)];
-
-        let mut settlement_coins = Vec::new();
-        let mut settlement_cats = Vec::new();
+        let mut settlements = OptionSettlements::default();
 
         for option_id in option_ids {
-            let option = self
-                .db
-                .option(option_id)
-                .await?
-                .ok_or(WalletError::MissingOption(option_id))?;
+            self.exercise_option(&mut ctx, option_id, &mut actions, &mut settlements)
+                .await?;
+        }
 
-            let underlying = self
-                .db
-                .option_underlying(option_id)
-                .await?
-                .ok_or(WalletError::MissingOption(option_id))?;
+        let mut spends = self.prepare_spends(&mut ctx, vec![], &actions).await?;
 
-            let underlying_spend = underlying.exercise_spend(
-                &mut ctx,
-                option.info.inner_puzzle_hash().into(),
-                option.coin.amount,
-            )?;
+        settlements.add_to(&mut spends);
 
-            match self
-                .db
-                .underlying_coin_kind(option_id)
-                .await?
-                .ok_or(WalletError::MissingOption(option_id))?
-            {
-                CoinKind::Xch => {
-                    let Some(coin) = self.db.xch_coin(option.info.underlying_coin_id).await? else {
-                        return Err(WalletError::MissingCoin(option.info.underlying_coin_id));
-                    };
-
-                    ctx.spend(coin, underlying_spend)?;
-
-                    settlement_coins.push(Coin::new(
-                        coin.coin_id(),
-                        SETTLEMENT_PAYMENT_HASH.into(),
-                        coin.amount,
-                    ));
-                }
-                CoinKind::Cat => {
-                    let Some(cat) = self.db.cat_coin(option.info.underlying_coin_id).await? else {
-                        return Err(WalletError::MissingCatCoin(option.info.underlying_coin_id));
-                    };
-
-                    let children =
-                        Cat::spend_all(&mut ctx, &[CatSpend::new(cat, underlying_spend)])?;
-
-                    settlement_cats.push(children[0]);
-                }
-                kind => {
-                    return Err(WalletError::UnsupportedUnderlyingCoinKind(kind));
-                }
-            }
+        let deltas = spends.apply(&mut ctx, &actions)?;
+        self.complete_spends(&mut ctx, &deltas, spends).await?;
 
-            actions.push(Action::melt_singleton(
-                Id::Existing(option.info.launcher_id),
-                1,
-            ));
+        Ok(ctx.take())
+    }
 
-            match underlying.strike_type {
-                OptionType::Xch { amount } => {
-                    actions.push(Action::settle(
-                        Id::Xch,
-                        NotarizedPayment::new(
-                            option.info.launcher_id,
-                            vec![Payment::new(
-                                underlying.creator_puzzle_hash,
-                                amount,
-                                Memos::None,
-                            )],
-                        ),
-                    ));
-                }
-                OptionType::Cat { asset_id, amount }
-                | OptionType::RevocableCat {
-                    asset_id, amount, ..
-                } => {
-                    let hint = ctx.hint(underlying.creator_puzzle_hash)?;
-
-                    actions.push(Action::settle(
-                        Id::Existing(asset_id),
-                        NotarizedPayment::new(
-                            option.info.launcher_id,
-                            vec![Payment::new(underlying.creator_puzzle_hash, amount, hint)],
-                        ),
-                    ));
-                }
-                OptionType::Nft { .. } => {
-                    return Err(WalletError::NftOptionNotSupported);
-                }
+    /// Spends the underlying coin of an option and adds the actions needed to melt the option
+    /// and pay the strike price. The unlocked underlying coin is added to the settlements.
+    pub async fn exercise_option(
+        &self,
+        ctx: &mut SpendContext,
+        option_id: Bytes32,
+        actions: &mut Vec<Action>,
+        settlements: &mut OptionSettlements,
+    ) -> Result<(), WalletError> {
+        let option = self
+            .db
+            .option(option_id)
+            .await?
+            .ok_or(WalletError::MissingOption(option_id))?;
+
+        let underlying = self
+            .db
+            .option_underlying(option_id)
+            .await?
+            .ok_or(WalletError::MissingOption(option_id))?;
+
+        let underlying_spend = underlying.exercise_spend(
+            ctx,
+            option.info.inner_puzzle_hash().into(),
+            option.coin.amount,
+        )?;
+
+        match self
+            .db
+            .underlying_coin_kind(option_id)
+            .await?
+            .ok_or(WalletError::MissingOption(option_id))?
+        {
+            CoinKind::Xch => {
+                let Some(coin) = self.db.xch_coin(option.info.underlying_coin_id).await? else {
+                    return Err(WalletError::MissingCoin(option.info.underlying_coin_id));
+                };
+
+                ctx.spend(coin, underlying_spend)?;
+
+                settlements.coins.push(Coin::new(
+                    coin.coin_id(),
+                    SETTLEMENT_PAYMENT_HASH.into(),
+                    coin.amount,
+                ));
             }
-        }
+            CoinKind::Cat => {
+                let Some(cat) = self.db.cat_coin(option.info.underlying_coin_id).await? else {
+                    return Err(WalletError::MissingCatCoin(option.info.underlying_coin_id));
+                };
 
-        let mut spends = self.prepare_spends(&mut ctx, vec![], &actions).await?;
+                let children = Cat::spend_all(ctx, &[CatSpend::new(cat, underlying_spend)])?;
 
-        for coin in settlement_coins {
-            spends.add(coin);
+                settlements.cats.push(children[0]);
+            }
+            kind => {
+                return Err(WalletError::UnsupportedUnderlyingCoinKind(kind));
+            }
         }
 
-        for cat in settlement_cats {
-            spends.add(cat);
+        actions.push(Action::melt_singleton(
+            Id::Existing(option.info.launcher_id),
+            1,
+        ));
+
+        match underlying.strike_type {
+            OptionType::Xch { amount } => {
+                actions.push(Action::settle(
+                    Id::Xch,
+                    NotarizedPayment::new(
+                        option.info.launcher_id,
+                        vec![Payment::new(
+                            underlying.creator_puzzle_hash,
+                            amount,
+                            Memos::None,
+                        )],
+                    ),
+                ));
+            }
+            OptionType::Cat { asset_id, amount }
+            | OptionType::RevocableCat {
+                asset_id, amount, ..
+            } => {
+                let hint = ctx.hint(underlying.creator_puzzle_hash)?;
+
+                actions.push(Action::settle(
+                    Id::Existing(asset_id),
+                    NotarizedPayment::new(
+                        option.info.launcher_id,
+                        vec![Payment::new(underlying.creator_puzzle_hash, amount, hint)],
+                    ),
+                ));
+            }
+            OptionType::Nft { .. } => {
+                return Err(WalletError::NftOptionNotSupported);
+            }
         }
 
-        let deltas = spends.apply(&mut ctx, &actions)?;
-        self.complete_spends(&mut ctx, &deltas, spends).await?;
-
-        Ok(ctx.take())
+        Ok(())
     }
 }
 
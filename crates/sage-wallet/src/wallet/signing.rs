@@ -1,18 +1,7 @@
-use std::collections::HashMap;
-
-use chia_wallet_sdk::{
-    chia::{
-        bls::{
-            DerivableKey, master_to_wallet_hardened_intermediate,
-            master_to_wallet_unhardened_intermediate, sign,
-        },
-        puzzle_types::DeriveSynthetic,
-    },
-    prelude::*,
-};
+use chia_wallet_sdk::prelude::*;
 use itertools::Itertools;
 
-use crate::WalletError;
+use crate::{SignatureRequest, Signer, WalletError};
 
 use super::Wallet;
 
@@ -21,7 +10,7 @@ impl Wallet {
         &self,
         spend_bundle: SpendBundle,
         agg_sig_constants: &AggSigConstants,
-        master_sk: SecretKey,
+        signer: &dyn Signer,
         partial: bool,
     ) -> Result<SpendBundle, WalletError> {
         let input_coin_spends = spend_bundle
@@ -37,54 +26,48 @@ impl Wallet {
             agg_sig_constants,
         )?;
 
-        let mut derivations = HashMap::new();
+        let mut requests = Vec::new();
 
-        for required in &required_signatures {
+        for required in required_signatures {
             let RequiredSignature::Bls(required) = required else {
                 continue;
             };
-            let Some(derivation) = self.db.derivation(required.public_key).await? else {
-                continue;
-            };
-            derivations.insert(required.public_key, derivation);
-        }
 
-        let unhardened_intermediate_sk = master_to_wallet_unhardened_intermediate(&master_sk);
-        let hardened_intermediate_sk = master_to_wallet_hardened_intermediate(&master_sk);
+            requests.push(SignatureRequest {
+                public_key: required.public_key,
+                derivation: self.db.derivation(required.public_key).await?,
+                message: required.message(),
+            });
+        }
 
-        let secret_keys: HashMap<PublicKey, SecretKey> = derivations
-            .iter()
-            .map(|(public_key, derivation)| {
-                let secret_key = if derivation.is_hardened {
-                    hardened_intermediate_sk.derive_hardened(derivation.derivation_index)
-                } else {
-                    unhardened_intermediate_sk.derive_unhardened(derivation.derivation_index)
-                }
-                .derive_synthetic();
+        let mut aggregated_signature = spend_bundle.aggregated_signature;
 
-                (*public_key, secret_key)
-            })
-            .collect();
+        if requests.is_empty() {
+            return Ok(SpendBundle::new(
+                spend_bundle.coin_spends,
+                aggregated_signature,
+            ));
+        }
 
-        let mut aggregated_signature = spend_bundle.aggregated_signature;
+        let signatures = signer.sign(&requests).await?;
 
-        for required in required_signatures {
-            let RequiredSignature::Bls(required) = required else {
-                continue;
-            };
+        if signatures.len() != requests.len() {
+            return Err(WalletError::SignerResponse(format!(
+                "Expected {} signatures, got {}",
+                requests.len(),
+                signatures.len()
+            )));
+        }
 
-            let sk = if required.public_key == master_sk.public_key() {
-                master_sk.clone()
-            } else if let Some(sk) = secret_keys.get(&required.public_key).cloned() {
-                sk
-            } else {
+        for signature in signatures {
+            let Some(signature) = signature else {
                 if partial {
                     continue;
                 }
                 return Err(WalletError::UnknownPublicKey);
             };
 
-            aggregated_signature += &sign(&sk, required.message());
+            aggregated_signature += &signature;
         }
 
         Ok(SpendBundle::new(
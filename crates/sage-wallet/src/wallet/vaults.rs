@@ -0,0 +1,251 @@
+use chia_wallet_sdk::{
+    prelude::*,
+    types::puzzles::{P2MOfNDelegateDirectArgs, P2MOfNDelegateDirectSolution},
+};
+use sage_database::Vault;
+
+use crate::WalletError;
+
+use super::Wallet;
+
+impl Wallet {
+    /// Creates an unsigned spend of XCH from a vault, which has to be signed by each of the
+    /// signers before it can be submitted. Change is sent back to the vault.
+    pub async fn propose_vault_spend(
+        &self,
+        vault: &Vault,
+        signers: &[PublicKey],
+        amounts: Vec<(Bytes32, u64)>,
+        fee: u64,
+    ) -> Result<Vec<CoinSpend>, WalletError> {
+        let selectors = vault_selectors(vault, signers)?;
+        let p2_puzzle_hash = vault.p2_puzzle_hash();
+
+        let total: u128 = amounts
+            .iter()
+            .map(|(_, amount)| u128::from(*amount))
+            .sum::<u128>()
+            + u128::from(fee);
+
+        // Nothing would be selected, and there'd be no coin to attach the conditions to
+        if total == 0 {
+            return Err(WalletError::EmptyVaultSpend);
+        }
+
+        let coins = select_coins(
+            self.db.selectable_vault_coins(p2_puzzle_hash).await?,
+            total.try_into()?,
+        )?;
+
+        let selected: u128 = coins.iter().map(|coin| u128::from(coin.amount)).sum();
+        let change: u64 = (selected - total).try_into()?;
+
+        let mut conditions = Conditions::new();
+
+        for (puzzle_hash, amount) in amounts {
+            conditions = conditions.create_coin(puzzle_hash, amount, Memos::None);
+        }
+
+        if change > 0 {
+            conditions = conditions.create_coin(p2_puzzle_hash, change, Memos::None);
+        }
+
+        if fee > 0 {
+            conditions = conditions.reserve_fee(fee);
+        }
+
+        let mut ctx = SpendContext::new();
+        let first_coin_id = coins[0].coin_id();
+
+        for (i, &coin) in coins.iter().enumerate() {
+            let conditions = if i == 0 {
+                conditions.clone()
+            } else {
+                Conditions::new().assert_concurrent_spend(first_coin_id)
+            };
+
+            spend_vault_coin(&mut ctx, vault, &selectors, coin, conditions)?;
+        }
+
+        Ok(ctx.take())
+    }
+}
+
+/// Which of the vault's keys will sign, in the order they're curried into the puzzle.
+fn vault_selectors(vault: &Vault, signers: &[PublicKey]) -> Result<Vec<bool>, WalletError> {
+    let selectors: Vec<bool> = vault
+        .public_keys
+        .iter()
+        .map(|public_key| signers.contains(public_key))
+        .collect();
+
+    let selected = selectors.iter().filter(|selected| **selected).count();
+
+    if selected != signers.len() || selected != vault.threshold as usize {
+        return Err(WalletError::InvalidVaultSigners(vault.threshold));
+    }
+
+    Ok(selectors)
+}
+
+fn spend_vault_coin(
+    ctx: &mut SpendContext,
+    vault: &Vault,
+    selectors: &[bool],
+    coin: Coin,
+    conditions: Conditions,
+) -> Result<(), WalletError> {
+    let puzzle = ctx.curry(P2MOfNDelegateDirectArgs::new(
+        vault.threshold as usize,
+        vault.public_keys.clone(),
+    ))?;
+
+    // The delegated puzzle quotes the conditions, which is what each of the signers signs
+    let delegated_puzzle = ctx.alloc(&(1, conditions))?;
+
+    let solution = ctx.alloc(&P2MOfNDelegateDirectSolution::new(
+        selectors.to_vec(),
+        delegated_puzzle,
+        NodePtr::NIL,
+    ))?;
+
+    ctx.spend(coin, Spend::new(puzzle, solution))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use crate::{KeychainSigner, SyncCommand, TestWallet};
+
+    use super::*;
+
+    #[test(tokio::test)]
+    async fn test_vault_spend() -> anyhow::Result<()> {
+        let mut alice = TestWallet::new(1000).await?;
+        let bob = alice.next(0).await?;
+
+        let vault = Vault {
+            threshold: 2,
+            public_keys: vec![alice.master_sk.public_key(), bob.master_sk.public_key()],
+        };
+
+        let mut tx = alice.wallet.db.tx().await?;
+        let vault_puzzle_hash = tx.insert_vault_p2_puzzle("Vault", &vault).await?;
+        tx.commit().await?;
+
+        alice
+            .sender
+            .send(SyncCommand::SubscribePuzzles {
+                puzzle_hashes: vec![vault_puzzle_hash],
+            })
+            .await?;
+
+        let coin_spends = alice
+            .wallet
+            .send_xch(vec![(vault_puzzle_hash, 1000)], 0, vec![], None)
+            .await?;
+
+        alice.transact(coin_spends).await?;
+        alice.wait_for_coins().await;
+
+        assert_eq!(
+            alice
+                .wallet
+                .db
+                .selectable_vault_coins(vault_puzzle_hash)
+                .await?
+                .len(),
+            1
+        );
+        assert!(alice.wallet.db.selectable_xch_coins().await?.is_empty());
+
+        // The vault's coins can't be spent by the wallet alone, so they aren't in its balance
+        assert_eq!(alice.wallet.db.xch_balance().await?, 0);
+        assert!(
+            !alice
+                .wallet
+                .db
+                .is_custody_p2_puzzle_hash(vault_puzzle_hash)
+                .await?
+        );
+
+        assert!(matches!(
+            alice
+                .wallet
+                .propose_vault_spend(
+                    &vault,
+                    &[alice.master_sk.public_key(), bob.master_sk.public_key()],
+                    vec![],
+                    0,
+                )
+                .await,
+            Err(WalletError::EmptyVaultSpend)
+        ));
+
+        // Both keys have to sign, so one of them alone isn't enough
+        assert!(matches!(
+            alice
+                .wallet
+                .propose_vault_spend(
+                    &vault,
+                    &[alice.master_sk.public_key()],
+                    vec![(alice.puzzle_hash, 250)],
+                    0,
+                )
+                .await,
+            Err(WalletError::InvalidVaultSigners(2))
+        ));
+
+        let coin_spends = alice
+            .wallet
+            .propose_vault_spend(
+                &vault,
+                &[alice.master_sk.public_key(), bob.master_sk.public_key()],
+                vec![(alice.puzzle_hash, 250)],
+                100,
+            )
+            .await?;
+
+        let spend_bundle = alice
+            .wallet
+            .sign_transaction(
+                SpendBundle::new(coin_spends, Signature::default()),
+                &alice.agg_sig,
+                &KeychainSigner::new(alice.master_sk.clone()),
+                true,
+            )
+            .await?;
+
+        let spend_bundle = bob
+            .wallet
+            .sign_transaction(
+                spend_bundle,
+                &bob.agg_sig,
+                &KeychainSigner::new(bob.master_sk.clone()),
+                true,
+            )
+            .await?;
+
+        alice.push_bundle(spend_bundle).await?;
+        alice.wait_for_coins().await;
+
+        assert_eq!(alice.wallet.db.selectable_xch_balance().await?, 250);
+        assert_eq!(alice.wallet.db.xch_balance().await?, 250);
+        assert_eq!(
+            alice
+                .wallet
+                .db
+                .selectable_vault_coins(vault_puzzle_hash)
+                .await?
+                .iter()
+                .map(|coin| coin.amount)
+                .sum::<u64>(),
+            650
+        );
+
+        Ok(())
+    }
+}
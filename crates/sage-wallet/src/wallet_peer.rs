@@ -2,7 +2,8 @@ use std::{collections::HashMap, net::SocketAddr, time::Duration};
 
 use chia_wallet_sdk::{
     chia::protocol::{
-        CoinStateFilters, RejectStateReason, RequestBlockHeader, RespondBlockHeader, RespondPeers,
+        CoinStateFilters, HeaderBlock, RejectBlockHeaders, RejectStateReason, RequestBlockHeader,
+        RequestBlockHeaders, RespondBlockHeader, RespondBlockHeaders, RespondPeers,
         RespondPuzzleState, TransactionAck,
     },
     prelude::*,
@@ -14,14 +15,23 @@ use crate::WalletError;
 #[derive(Debug, Clone)]
 pub struct WalletPeer {
     peer: Peer,
+    socket_addr: SocketAddr,
     pending_coin_states: HashMap<Bytes32, CoinState>,
     pending_coin_spends: HashMap<Bytes32, CoinSpend>,
 }
 
 impl WalletPeer {
     pub fn new(peer: Peer) -> Self {
+        let socket_addr = peer.socket_addr();
+        Self::with_socket_addr(peer, socket_addr)
+    }
+
+    /// Wraps a peer whose connection goes through a proxy, in which case the socket address
+    /// of the connection is the proxy's rather than the peer's.
+    pub fn with_socket_addr(peer: Peer, socket_addr: SocketAddr) -> Self {
         Self {
             peer,
+            socket_addr,
             pending_coin_states: HashMap::new(),
             pending_coin_spends: HashMap::new(),
         }
@@ -35,13 +45,14 @@ impl WalletPeer {
     ) -> Self {
         Self {
             peer: self.peer.clone(),
+            socket_addr: self.socket_addr,
             pending_coin_states,
             pending_coin_spends,
         }
     }
 
     pub fn socket_addr(&self) -> SocketAddr {
-        self.peer.socket_addr()
+        self.socket_addr
     }
 
     pub async fn fetch_coin(
@@ -248,16 +259,38 @@ impl WalletPeer {
         coin_ids: Vec<Bytes32>,
         previous_height: Option<u32>,
         header_hash: Bytes32,
+    ) -> Result<Vec<CoinState>, WalletError> {
+        self.coin_states(coin_ids, previous_height, header_hash, true)
+            .await
+    }
+
+    /// Requests the same coin states as [`Self::subscribe_coins`], without subscribing to them.
+    pub async fn request_coin_states(
+        &self,
+        coin_ids: Vec<Bytes32>,
+        previous_height: Option<u32>,
+        header_hash: Bytes32,
+    ) -> Result<Vec<CoinState>, WalletError> {
+        self.coin_states(coin_ids, previous_height, header_hash, false)
+            .await
+    }
+
+    async fn coin_states(
+        &self,
+        coin_ids: Vec<Bytes32>,
+        previous_height: Option<u32>,
+        header_hash: Bytes32,
+        subscribe: bool,
     ) -> Result<Vec<CoinState>, WalletError> {
         let response = timeout(
             Duration::from_secs(15),
             self.peer
-                .request_coin_state(coin_ids, previous_height, header_hash, true),
+                .request_coin_state(coin_ids, previous_height, header_hash, subscribe),
         )
         .await??
         .map_err(|error| match error.reason {
             RejectStateReason::ExceededSubscriptionLimit => WalletError::SubscriptionLimitReached,
-            RejectStateReason::Reorg => WalletError::PeerMisbehaved,
+            RejectStateReason::Reorg => WalletError::Reorg,
         })?;
 
         Ok(response.coin_states)
@@ -269,6 +302,30 @@ impl WalletPeer {
         previous_height: Option<u32>,
         header_hash: Bytes32,
         filters: CoinStateFilters,
+    ) -> Result<RespondPuzzleState, WalletError> {
+        self.puzzle_states(puzzle_hashes, previous_height, header_hash, filters, true)
+            .await
+    }
+
+    /// Requests the same puzzle states as [`Self::subscribe_puzzles`], without subscribing to them.
+    pub async fn request_puzzle_states(
+        &self,
+        puzzle_hashes: Vec<Bytes32>,
+        previous_height: Option<u32>,
+        header_hash: Bytes32,
+        filters: CoinStateFilters,
+    ) -> Result<RespondPuzzleState, WalletError> {
+        self.puzzle_states(puzzle_hashes, previous_height, header_hash, filters, false)
+            .await
+    }
+
+    async fn puzzle_states(
+        &self,
+        puzzle_hashes: Vec<Bytes32>,
+        previous_height: Option<u32>,
+        header_hash: Bytes32,
+        filters: CoinStateFilters,
+        subscribe: bool,
     ) -> Result<RespondPuzzleState, WalletError> {
         timeout(
             Duration::from_secs(45),
@@ -277,13 +334,13 @@ impl WalletPeer {
                 previous_height,
                 header_hash,
                 filters,
-                true,
+                subscribe,
             ),
         )
         .await??
         .map_err(|error| match error.reason {
             RejectStateReason::ExceededSubscriptionLimit => WalletError::SubscriptionLimitReached,
-            RejectStateReason::Reorg => WalletError::PeerMisbehaved,
+            RejectStateReason::Reorg => WalletError::Reorg,
         })
     }
 
@@ -297,6 +354,39 @@ impl WalletPeer {
         Ok(())
     }
 
+    pub async fn block_header(&self, height: u32) -> Result<HeaderBlock, WalletError> {
+        Ok(timeout(
+            Duration::from_secs(5),
+            self.peer
+                .request_infallible::<RespondBlockHeader, _>(RequestBlockHeader::new(height)),
+        )
+        .await??
+        .header_block)
+    }
+
+    pub async fn block_header_hash(&self, height: u32) -> Result<Bytes32, WalletError> {
+        Ok(self.block_header(height).await?.header_hash())
+    }
+
+    /// Fetches a contiguous range of block headers, inclusive of both ends.
+    pub async fn block_headers(
+        &self,
+        start_height: u32,
+        end_height: u32,
+    ) -> Result<Vec<HeaderBlock>, WalletError> {
+        let response = timeout(
+            Duration::from_secs(15),
+            self.peer
+                .request_fallible::<RespondBlockHeaders, RejectBlockHeaders, _>(
+                    RequestBlockHeaders::new(start_height, end_height, false),
+                ),
+        )
+        .await??
+        .map_err(|_| WalletError::PeerMisbehaved)?;
+
+        Ok(response.header_blocks)
+    }
+
     pub async fn block_timestamp(&self, height: u32) -> Result<(Bytes32, u64), WalletError> {
         let header_block = timeout(
             Duration::from_secs(5),
@@ -1,9 +1,15 @@
 mod action_system;
 mod actions;
+mod backup;
 mod data;
 mod keys;
 mod offers;
 mod settings;
+#[cfg(feature = "simulator")]
+mod simulator;
+#[cfg(not(feature = "simulator"))]
+mod simulator_disabled;
 mod themes;
 mod transactions;
+mod vaults;
 mod wallet_connect;
@@ -1,18 +1,29 @@
 use chia_wallet_sdk::{
     driver::{
         FeeAction, MetadataUpdate, MintNftAction, SendAction, TransferNftById, UpdateNftAction,
-        UriKind,
+        UriKind, decode_offer,
     },
     prelude::*,
     puzzles::NFT_METADATA_UPDATER_DEFAULT_HASH,
 };
 use sage_api::{CreateTransaction, NftUriKind, TransactionResponse};
-use sage_wallet::{Hint, calculate_memos};
+use sage_database::{Asset, AssetKind};
+use sage_wallet::{Hint, OptionSettlements, calculate_memos};
 
 use crate::{
-    ConfirmationInfo, Result, Sage, parse_amount, parse_any_asset_id, parse_coin_ids, parse_memos,
+    ConfirmationInfo, Error, Result, Sage, json_spend, parse_amount, parse_any_asset_id,
+    parse_coin_ids, parse_memos, parse_option_id, parse_program,
 };
 
+#[derive(Debug, Clone)]
+struct NewAsset {
+    index: usize,
+    kind: AssetKind,
+    name: Option<String>,
+    ticker: Option<String>,
+    hidden_puzzle_hash: Option<Bytes32>,
+}
+
 impl Sage {
     pub async fn create_transaction(&self, req: CreateTransaction) -> Result<TransactionResponse> {
         let wallet = self.wallet()?;
@@ -21,15 +32,25 @@ impl Sage {
         let selected_coin_ids = parse_coin_ids(req.selected_coin_ids)?;
 
         let mut ctx = SpendContext::new();
+        let mut spends = wallet
+            .prepare_spends_for_selection(&mut ctx, &selected_coin_ids)
+            .await?;
         let mut actions = vec![];
+        let mut settlements = OptionSettlements::default();
+        let mut offer = None;
+        let mut new_assets = vec![];
         let mut info = ConfirmationInfo::default();
 
-        for action in req.actions {
+        // Some actions expand into several driver actions, or none at all, so this maps the
+        // index of each requested action to the driver action that creates its asset, if any
+        let mut indices = vec![None; req.actions.len()];
+
+        for (request_index, action) in req.actions.into_iter().enumerate() {
             match action {
                 sage_api::Action::Send(action) => {
                     let receiver_puzzle_hash = self.parse_address(action.address)?;
                     let amount = parse_amount(action.amount)?;
-                    let id = parse_id(action.id)?;
+                    let id = parse_id(action.id, &indices)?;
                     let hinted = id != Id::Xch;
                     let memos = parse_memos(action.memos)?;
 
@@ -69,7 +90,7 @@ impl Sage {
                     }));
                 }
                 sage_api::Action::MintNft(action) => {
-                    let parent_id = parse_id(action.parent_id)?;
+                    let parent_id = parse_id(action.parent_id, &indices)?;
 
                     let mint = self
                         .convert_nft_mint(
@@ -92,6 +113,7 @@ impl Sage {
 
                     let metadata = ctx.alloc_hashed(&mint.metadata)?;
 
+                    indices[request_index] = Some(actions.len());
                     actions.push(Action::MintNft(MintNftAction {
                         parent_id,
                         metadata,
@@ -102,7 +124,7 @@ impl Sage {
                     }));
                 }
                 sage_api::Action::UpdateNft(action) => {
-                    let id = parse_id(action.id)?;
+                    let id = parse_id(action.id, &indices)?;
 
                     let mut metadata_update_spends = vec![];
 
@@ -121,7 +143,10 @@ impl Sage {
                     }
 
                     let transfer = if let Some(transfer) = action.transfer {
-                        let did_id = transfer.did_id.map(parse_id).transpose()?;
+                        let did_id = transfer
+                            .did_id
+                            .map(|id| parse_id(id, &indices))
+                            .transpose()?;
 
                         Some(TransferNftById::new(did_id, vec![]))
                     } else {
@@ -134,6 +159,80 @@ impl Sage {
                         transfer,
                     }));
                 }
+                sage_api::Action::IssueCat(action) => {
+                    let hidden_puzzle_hash = action.revocable.then_some(sender_puzzle_hash);
+
+                    new_assets.push(NewAsset {
+                        index: actions.len(),
+                        kind: AssetKind::Token,
+                        name: action.name,
+                        ticker: action.ticker,
+                        hidden_puzzle_hash,
+                    });
+
+                    indices[request_index] = Some(actions.len());
+                    actions.push(Action::single_issue_cat(
+                        hidden_puzzle_hash,
+                        parse_amount(action.amount)?,
+                    ));
+                }
+                sage_api::Action::RunTail(action) => {
+                    let id = parse_id(action.id, &indices)?;
+                    let puzzle = ctx.alloc(&parse_program(action.tail_puzzle)?)?;
+                    let solution = ctx.alloc(&parse_program(action.tail_solution)?)?;
+
+                    actions.push(Action::run_tail(
+                        id,
+                        Spend::new(puzzle, solution),
+                        Delta::new(
+                            parse_amount(action.issue_amount)?,
+                            parse_amount(action.melt_amount)?,
+                        ),
+                    ));
+                }
+                sage_api::Action::CreateDid(action) => {
+                    new_assets.push(NewAsset {
+                        index: actions.len(),
+                        kind: AssetKind::Did,
+                        name: action.name,
+                        ticker: None,
+                        hidden_puzzle_hash: None,
+                    });
+
+                    indices[request_index] = Some(actions.len());
+                    actions.push(Action::create_empty_did());
+                }
+                sage_api::Action::MintOption(action) => {
+                    let underlying_id = parse_id(action.underlying_id, &indices)?;
+                    let strike_type = self.parse_option_asset(action.strike).await?;
+
+                    indices[request_index] = Some(actions.len());
+                    actions.push(Action::mint_option(
+                        sender_puzzle_hash,
+                        action.expiration_seconds,
+                        underlying_id,
+                        parse_amount(action.underlying_amount)?,
+                        strike_type,
+                        1,
+                    ));
+                }
+                sage_api::Action::ExerciseOption(action) => {
+                    let option_id = parse_option_id(action.option_id)?;
+
+                    wallet
+                        .exercise_option(&mut ctx, option_id, &mut actions, &mut settlements)
+                        .await?;
+                }
+                sage_api::Action::SettleOffer(action) => {
+                    if offer.is_some() {
+                        return Err(Error::MultipleOffersSettled);
+                    }
+
+                    offer = Some(Offer::from_spend_bundle(
+                        &mut ctx,
+                        &decode_offer(&action.offer)?,
+                    )?);
+                }
                 sage_api::Action::Fee(action) => {
                     actions.push(Action::Fee(FeeAction {
                         amount: parse_amount(action.amount)?,
@@ -143,18 +242,92 @@ impl Sage {
             }
         }
 
-        wallet.spend(&mut ctx, selected_coin_ids, &actions).await?;
+        // The offer is settled last, since it needs to select coins for every other action
+        if let Some(offer) = &offer {
+            wallet
+                .settle_offer(&mut ctx, offer, &mut spends, &mut actions)
+                .await?;
+        } else {
+            wallet
+                .select_spends(&mut ctx, &mut spends, &actions)
+                .await?;
+        }
+
+        settlements.add_to(&mut spends);
+
+        let deltas = spends.apply(&mut ctx, &actions)?;
+        let outputs = wallet.complete_spends(&mut ctx, &deltas, spends).await?;
+
+        if !new_assets.is_empty() {
+            let mut tx = wallet.db.tx().await?;
+
+            for asset in new_assets {
+                let id = Id::New(asset.index);
+
+                let (hash, precision) = match asset.kind {
+                    AssetKind::Token => {
+                        let Some(cat) = outputs.cats.get(&id).and_then(|cats| cats.first()) else {
+                            continue;
+                        };
+
+                        (cat.info.asset_id, 3)
+                    }
+                    AssetKind::Did => {
+                        let Some(did) = outputs.dids.get(&id) else {
+                            continue;
+                        };
+
+                        (did.info.launcher_id, 1)
+                    }
+                    _ => continue,
+                };
+
+                tx.insert_asset(Asset {
+                    hash,
+                    name: asset.name,
+                    ticker: asset.ticker,
+                    precision,
+                    icon_url: None,
+                    description: None,
+                    is_sensitive_content: false,
+                    is_visible: true,
+                    hidden_puzzle_hash: asset.hidden_puzzle_hash,
+                    kind: asset.kind,
+                })
+                .await?;
+            }
+
+            tx.commit().await?;
+        }
 
         let coin_spends = ctx.take();
 
-        self.transact_with(coin_spends, req.auto_submit, info).await
+        // The maker's spends are already signed, so a preview only includes the taker's
+        // unsigned spends
+        let Some(offer) = offer.filter(|_| req.auto_submit) else {
+            return self.transact_with(coin_spends, req.auto_submit, info).await;
+        };
+
+        let spend_bundle = offer.take(self.sign(coin_spends, false).await?);
+        self.submit(spend_bundle.clone()).await?;
+
+        Ok(TransactionResponse {
+            coin_spends: spend_bundle.coin_spends.iter().map(json_spend).collect(),
+            summary: self.summarize(spend_bundle.coin_spends, info).await?,
+        })
     }
 }
 
-fn parse_id(id: sage_api::Id) -> Result<Id> {
+fn parse_id(id: sage_api::Id, indices: &[Option<usize>]) -> Result<Id> {
     Ok(match id {
         sage_api::Id::Xch => Id::Xch,
         sage_api::Id::Existing { asset_id } => Id::Existing(parse_any_asset_id(asset_id)?),
-        sage_api::Id::New { index } => Id::New(index),
+        sage_api::Id::New { index } => Id::New(
+            indices
+                .get(index)
+                .copied()
+                .flatten()
+                .ok_or(Error::InvalidActionIndex(index))?,
+        ),
     })
 }
@@ -1,19 +1,13 @@
-use chia_wallet_sdk::{
-    chia::{
-        bls::master_to_wallet_hardened_intermediate,
-        puzzle_types::{DeriveSynthetic, nft::NftMetadata, standard::StandardArgs},
-    },
-    prelude::*,
-};
+use chia_wallet_sdk::{chia::puzzle_types::nft::NftMetadata, prelude::*};
 use sage_api::{
-    GetXchUsdPrice, GetXchUsdPriceResponse, IncreaseDerivationIndex,
-    IncreaseDerivationIndexResponse, RedownloadNft, RedownloadNftResponse, ResyncCat,
-    ResyncCatResponse, UpdateCat, UpdateCatResponse, UpdateDid, UpdateDidResponse, UpdateNft,
-    UpdateNftCollection, UpdateNftCollectionResponse, UpdateNftResponse, UpdateOption,
+    GetNewAddress, GetNewAddressResponse, GetXchUsdPrice, GetXchUsdPriceResponse,
+    IncreaseDerivationIndex, IncreaseDerivationIndexResponse, RedownloadNft, RedownloadNftResponse,
+    ResyncCat, ResyncCatResponse, UpdateCat, UpdateCatResponse, UpdateDid, UpdateDidResponse,
+    UpdateNft, UpdateNftCollection, UpdateNftCollectionResponse, UpdateNftResponse, UpdateOption,
     UpdateOptionResponse,
 };
 use sage_assets::{DexieCat, XchUsdPrice};
-use sage_database::{Asset, AssetKind, Derivation};
+use sage_database::{Asset, AssetKind};
 use sage_wallet::SyncCommand;
 
 use crate::{
@@ -26,7 +20,7 @@ impl Sage {
         let wallet = self.wallet()?;
 
         let asset_id = parse_asset_id(req.asset_id)?;
-        let testnet = self.network().genesis_challenge == TESTNET11_CONSTANTS.genesis_challenge;
+        let testnet = self.network()?.genesis_challenge == TESTNET11_CONSTANTS.genesis_challenge;
 
         let cat = DexieCat::fetch(asset_id, testnet).await?;
 
@@ -198,37 +192,19 @@ impl Sage {
         let mut derivations = Vec::new();
 
         if hardened {
-            let (_mnemonic, Some(master_sk)) =
-                self.keychain.extract_secrets(wallet.fingerprint, b"")?
-            else {
+            if wallet.hardened_keys.is_none() {
                 return Err(Error::NoSigningKey);
-            };
+            }
 
             let mut tx = wallet.db.tx().await?;
 
             let start = tx.derivation_index(true).await?;
-            let intermediate_sk = master_to_wallet_hardened_intermediate(&master_sk);
-
-            for index in start..req.index {
-                let synthetic_key = intermediate_sk
-                    .derive_hardened(index)
-                    .derive_synthetic()
-                    .public_key();
-
-                let p2_puzzle_hash = StandardArgs::curry_tree_hash(synthetic_key).into();
-
-                tx.insert_custody_p2_puzzle(
-                    p2_puzzle_hash,
-                    synthetic_key,
-                    Derivation {
-                        derivation_index: index,
-                        is_hardened: true,
-                    },
-                )
-                .await?;
 
-                derivations.push(p2_puzzle_hash);
-            }
+            derivations.extend(
+                wallet
+                    .insert_hardened_derivations(&mut tx, start..req.index)
+                    .await?,
+            );
 
             tx.commit().await?;
         }
@@ -256,6 +232,39 @@ impl Sage {
         Ok(IncreaseDerivationIndexResponse {})
     }
 
+    pub async fn get_new_address(&self, req: GetNewAddress) -> Result<GetNewAddressResponse> {
+        let wallet = self.wallet()?;
+
+        let mut tx = wallet.db.tx().await?;
+
+        let hardened = match req.hardened {
+            Some(hardened) => hardened,
+            // Wallets without any hardened derivations fall back to unhardened addresses
+            None => wallet.receive_hardened && tx.derivation_index(true).await? > 0,
+        };
+
+        // Make sure there's an unused derivation to reserve, then restore the gap afterward
+        let mut derivations = wallet.auto_insert_derivations(&mut tx).await?;
+        let (index, p2_puzzle_hash) = wallet.reserve_p2_puzzle_hash(&mut tx, hardened).await?;
+        derivations.extend(wallet.auto_insert_derivations(&mut tx).await?);
+
+        tx.commit().await?;
+
+        if !derivations.is_empty() {
+            self.command_sender
+                .send(SyncCommand::SubscribePuzzles {
+                    puzzle_hashes: derivations,
+                })
+                .await?;
+        }
+
+        Ok(GetNewAddressResponse {
+            address: Address::new(p2_puzzle_hash, self.network()?.prefix()).encode()?,
+            index,
+            hardened,
+        })
+    }
+
     pub async fn get_xch_usd_price(&self, _req: GetXchUsdPrice) -> Result<GetXchUsdPriceResponse> {
         let price = XchUsdPrice::fetch().await?;
 
@@ -0,0 +1,562 @@
+use std::fs;
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+use chia_wallet_sdk::{driver::decode_offer, prelude::*};
+use indexmap::IndexMap;
+use sage_api::{ExportBackup, ExportBackupResponse, ImportBackup, ImportBackupResponse};
+use sage_config::{Network, Wallet};
+use sage_database::{Asset, AssetKind, CollectionRow, Database, OfferRow, OfferStatus, Vault};
+use sage_keychain::Keychain;
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result, Sage};
+
+/// The current version of the backup contents, inside of the encrypted archive.
+const BACKUP_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Backup {
+    version: u32,
+    /// The keychain entries, with secrets still encrypted with their own passwords.
+    keys: String,
+    wallets: Vec<Wallet>,
+    networks: Vec<Network>,
+    /// The `theme.json` of each saved theme, by NFT id.
+    themes: IndexMap<String, String>,
+    databases: Vec<DatabaseBackup>,
+}
+
+/// The data in a wallet database that can't be recovered by syncing.
+#[derive(Serialize, Deserialize)]
+struct DatabaseBackup {
+    fingerprint: u32,
+    assets: Vec<AssetBackup>,
+    collections: Vec<CollectionBackup>,
+    offers: Vec<OfferBackup>,
+    #[serde(default)]
+    vaults: Vec<VaultBackup>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AssetBackup {
+    hash: String,
+    kind: u8,
+    name: Option<String>,
+    ticker: Option<String>,
+    precision: u8,
+    icon_url: Option<String>,
+    description: Option<String>,
+    is_sensitive_content: bool,
+    is_visible: bool,
+    hidden_puzzle_hash: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CollectionBackup {
+    hash: String,
+    uuid: String,
+    minter_hash: String,
+    name: Option<String>,
+    icon_url: Option<String>,
+    banner_url: Option<String>,
+    description: Option<String>,
+    is_visible: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OfferBackup {
+    encoded_offer: String,
+    expiration_height: Option<u32>,
+    expiration_timestamp: Option<u64>,
+    fee: u64,
+    status: u8,
+    inserted_timestamp: u64,
+    assets: Vec<OfferAssetBackup>,
+    /// The wallet's coins locked up by the offer, so they aren't selected for other spends.
+    #[serde(default)]
+    coins: Vec<CoinBackup>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CoinBackup {
+    parent_coin_info: String,
+    puzzle_hash: String,
+    amount: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VaultBackup {
+    name: String,
+    threshold: u32,
+    public_keys: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OfferAssetBackup {
+    asset_hash: String,
+    is_requested: bool,
+    amount: u64,
+    royalty: u64,
+}
+
+/// A [`DatabaseBackup`] that has been parsed, so it can be written without failing halfway.
+struct DatabaseImport {
+    fingerprint: u32,
+    assets: Vec<Asset>,
+    collections: Vec<CollectionRow>,
+    offers: Vec<OfferImport>,
+    vaults: Vec<(String, Vault)>,
+}
+
+struct OfferImport {
+    row: OfferRow,
+    /// The parsed asset hash of each offered or requested asset.
+    assets: Vec<(Bytes32, OfferAssetBackup)>,
+    coins: Vec<Coin>,
+}
+
+impl Sage {
+    pub async fn export_backup(&mut self, req: ExportBackup) -> Result<ExportBackupResponse> {
+        let wallets: Vec<Wallet> = self
+            .wallet_config
+            .wallets
+            .iter()
+            .filter(|wallet| {
+                self.keychain.contains(wallet.fingerprint)
+                    && req
+                        .fingerprints
+                        .as_ref()
+                        .is_none_or(|fingerprints| fingerprints.contains(&wallet.fingerprint))
+            })
+            .cloned()
+            .collect();
+
+        let fingerprints: Vec<u32> = wallets.iter().map(|wallet| wallet.fingerprint).collect();
+
+        let mut databases = Vec::new();
+
+        for &fingerprint in &fingerprints {
+            if !self.wallet_db_path(fingerprint)?.try_exists()? {
+                continue;
+            }
+
+            let db = Database::new(self.connect_to_database(fingerprint).await?);
+            databases.push(export_database(&db, fingerprint).await?);
+        }
+
+        let backup = Backup {
+            version: BACKUP_VERSION,
+            keys: hex::encode(self.keychain.export_keys(&fingerprints)?),
+            wallets,
+            networks: self.network_list.networks.clone(),
+            themes: self.read_themes()?,
+            databases,
+        };
+
+        let data = serde_json::to_vec(&backup).map_err(|_| Error::InvalidBackup)?;
+        let archive = self
+            .keychain
+            .encrypt_archive(req.password.as_bytes(), &data)?;
+
+        Ok(ExportBackupResponse {
+            backup: BASE64_STANDARD.encode(archive),
+        })
+    }
+
+    pub async fn import_backup(&mut self, req: ImportBackup) -> Result<ImportBackupResponse> {
+        let archive = BASE64_STANDARD
+            .decode(req.backup.trim())
+            .map_err(|_| Error::InvalidBackup)?;
+        let data = Keychain::decrypt_archive(&archive, req.password.as_bytes())?;
+        let backup: Backup = serde_json::from_slice(&data).map_err(|_| Error::InvalidBackup)?;
+
+        if backup.version != BACKUP_VERSION {
+            return Err(Error::InvalidBackup);
+        }
+
+        // Everything is validated on copies first, so a bad backup leaves the wallet untouched
+        let mut network_list = self.network_list.clone();
+
+        for network in backup.networks {
+            if network_list.by_name(&network.name).is_none() {
+                network_list.networks.push(network);
+            }
+        }
+
+        network_list.validate()?;
+
+        for wallet in &backup.wallets {
+            if let Some(network) = &wallet.network
+                && network_list.by_name(network).is_none()
+            {
+                return Err(Error::UnknownNetwork(network.clone()));
+            }
+        }
+
+        let mut keychain = self.keychain.clone();
+        let fingerprints = keychain.import_keys(&hex::decode(backup.keys)?)?;
+
+        let databases = backup
+            .databases
+            .into_iter()
+            .filter(|database| keychain.contains(database.fingerprint))
+            .map(parse_database)
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut wallet_config = self.wallet_config.clone();
+
+        for wallet in backup.wallets {
+            if let Some(existing) = wallet_config
+                .wallets
+                .iter_mut()
+                .find(|existing| existing.fingerprint == wallet.fingerprint)
+            {
+                *existing = wallet;
+            } else {
+                wallet_config.wallets.push(wallet);
+            }
+        }
+
+        self.replace_keychain(keychain)?;
+        self.network_list = network_list;
+        self.wallet_config = wallet_config;
+        self.save_config()?;
+
+        for (nft_id, theme) in backup.themes {
+            // The NFT id is used as a directory name, so it can't be allowed to escape it
+            if nft_id.is_empty() || !nft_id.chars().all(char::is_alphanumeric) {
+                continue;
+            }
+
+            let theme_dir = self.path.join("themes").join(nft_id);
+            fs::create_dir_all(&theme_dir)?;
+            fs::write(theme_dir.join("theme.json"), theme)?;
+        }
+
+        for database in databases {
+            let db = Database::new(self.connect_to_database(database.fingerprint).await?);
+            import_database(&db, database).await?;
+        }
+
+        Ok(ImportBackupResponse { fingerprints })
+    }
+
+    fn read_themes(&self) -> Result<IndexMap<String, String>> {
+        let themes_dir = self.path.join("themes");
+        let mut themes = IndexMap::new();
+
+        if !themes_dir.try_exists()? {
+            return Ok(themes);
+        }
+
+        for entry in fs::read_dir(&themes_dir)? {
+            let path = entry?.path();
+            let theme_json_path = path.join("theme.json");
+
+            let Some(nft_id) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+
+            if theme_json_path.try_exists()? {
+                themes.insert(nft_id.to_string(), fs::read_to_string(theme_json_path)?);
+            }
+        }
+
+        Ok(themes)
+    }
+}
+
+async fn export_database(db: &Database, fingerprint: u32) -> Result<DatabaseBackup> {
+    let assets = db
+        .all_assets()
+        .await?
+        .into_iter()
+        .map(|asset| AssetBackup {
+            hash: hex::encode(asset.hash),
+            kind: asset.kind as u8,
+            name: asset.name,
+            ticker: asset.ticker,
+            precision: asset.precision,
+            icon_url: asset.icon_url,
+            description: asset.description,
+            is_sensitive_content: asset.is_sensitive_content,
+            is_visible: asset.is_visible,
+            hidden_puzzle_hash: asset.hidden_puzzle_hash.map(hex::encode),
+        })
+        .collect();
+
+    let (collections, _) = db.collections(u32::MAX, 0, true).await?;
+
+    let collections = collections
+        .into_iter()
+        .map(|collection| CollectionBackup {
+            hash: hex::encode(collection.hash),
+            uuid: collection.uuid,
+            minter_hash: hex::encode(collection.minter_hash),
+            name: collection.name,
+            icon_url: collection.icon_url,
+            banner_url: collection.banner_url,
+            description: collection.description,
+            is_visible: collection.is_visible,
+        })
+        .collect();
+
+    let mut offers = Vec::new();
+
+    for offer in db.offers(None).await? {
+        let assets = db
+            .offer_assets(offer.offer_id)
+            .await?
+            .into_iter()
+            .map(|asset| OfferAssetBackup {
+                asset_hash: hex::encode(asset.asset.hash),
+                is_requested: asset.is_requested,
+                amount: asset.amount,
+                royalty: asset.royalty,
+            })
+            .collect();
+
+        let coins = db
+            .offer_coins(offer.offer_id)
+            .await?
+            .into_iter()
+            .map(|coin| CoinBackup {
+                parent_coin_info: hex::encode(coin.parent_coin_info),
+                puzzle_hash: hex::encode(coin.puzzle_hash),
+                amount: coin.amount,
+            })
+            .collect();
+
+        offers.push(OfferBackup {
+            encoded_offer: offer.encoded_offer,
+            expiration_height: offer.expiration_height,
+            expiration_timestamp: offer.expiration_timestamp,
+            fee: offer.fee,
+            status: offer.status as u8,
+            inserted_timestamp: offer.inserted_timestamp,
+            assets,
+            coins,
+        });
+    }
+
+    let vaults = db
+        .vaults()
+        .await?
+        .into_iter()
+        .map(|row| VaultBackup {
+            name: row.name,
+            threshold: row.vault.threshold,
+            public_keys: row
+                .vault
+                .public_keys
+                .iter()
+                .map(|public_key| hex::encode(public_key.to_bytes()))
+                .collect(),
+        })
+        .collect();
+
+    Ok(DatabaseBackup {
+        fingerprint,
+        assets,
+        collections,
+        offers,
+        vaults,
+    })
+}
+
+/// Parses everything in a database backup up front, so that an invalid backup is rejected
+/// before anything is written.
+fn parse_database(backup: DatabaseBackup) -> Result<DatabaseImport> {
+    let assets = backup
+        .assets
+        .into_iter()
+        .map(|asset| {
+            Ok(Asset {
+                hash: parse_hash(&asset.hash)?,
+                kind: asset_kind(asset.kind)?,
+                name: asset.name,
+                ticker: asset.ticker,
+                precision: asset.precision,
+                icon_url: asset.icon_url,
+                description: asset.description,
+                is_sensitive_content: asset.is_sensitive_content,
+                is_visible: asset.is_visible,
+                hidden_puzzle_hash: asset
+                    .hidden_puzzle_hash
+                    .as_deref()
+                    .map(parse_hash)
+                    .transpose()?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let collections = backup
+        .collections
+        .into_iter()
+        .map(|collection| {
+            Ok(CollectionRow {
+                hash: parse_hash(&collection.hash)?,
+                uuid: collection.uuid,
+                minter_hash: parse_hash(&collection.minter_hash)?,
+                name: collection.name,
+                icon_url: collection.icon_url,
+                banner_url: collection.banner_url,
+                description: collection.description,
+                is_visible: collection.is_visible,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let offers = backup
+        .offers
+        .into_iter()
+        .map(|offer| {
+            let assets = offer
+                .assets
+                .into_iter()
+                .map(|asset| Ok((parse_hash(&asset.asset_hash)?, asset)))
+                .collect::<Result<Vec<_>>>()?;
+
+            let coins = offer
+                .coins
+                .iter()
+                .map(|coin| {
+                    Ok(Coin::new(
+                        parse_hash(&coin.parent_coin_info)?,
+                        parse_hash(&coin.puzzle_hash)?,
+                        coin.amount,
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            Ok(OfferImport {
+                row: OfferRow {
+                    offer_id: decode_offer(&offer.encoded_offer)?.name(),
+                    encoded_offer: offer.encoded_offer,
+                    expiration_height: offer.expiration_height,
+                    expiration_timestamp: offer.expiration_timestamp,
+                    fee: offer.fee,
+                    status: offer_status(offer.status)?,
+                    inserted_timestamp: offer.inserted_timestamp,
+                },
+                assets,
+                coins,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let vaults = backup
+        .vaults
+        .into_iter()
+        .map(|vault| {
+            let public_keys = vault
+                .public_keys
+                .iter()
+                .map(|public_key| {
+                    let bytes: [u8; 48] = hex::decode(public_key)?
+                        .try_into()
+                        .map_err(|_| Error::InvalidBackup)?;
+                    Ok(PublicKey::from_bytes(&bytes)?)
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            Ok((
+                vault.name,
+                Vault {
+                    threshold: vault.threshold,
+                    public_keys,
+                },
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(DatabaseImport {
+        fingerprint: backup.fingerprint,
+        assets,
+        collections,
+        offers,
+        vaults,
+    })
+}
+
+/// Restores the user data of a wallet database. Everything is inserted if missing, and the
+/// user editable fields are overwritten, so restoring the same backup twice is harmless.
+async fn import_database(db: &Database, import: DatabaseImport) -> Result<()> {
+    let mut tx = db.tx().await?;
+
+    for asset in import.assets {
+        tx.insert_asset(asset.clone()).await?;
+        tx.update_asset(asset).await?;
+    }
+
+    for collection in import.collections {
+        let hash = collection.hash;
+        let is_visible = collection.is_visible;
+
+        tx.insert_collection(collection).await?;
+        tx.set_collection_visible(hash, is_visible).await?;
+    }
+
+    for offer in import.offers {
+        let offer_id = offer.row.offer_id;
+
+        tx.insert_offer(offer.row).await?;
+
+        for (asset_hash, asset) in offer.assets {
+            tx.insert_offer_asset(
+                offer_id,
+                asset_hash,
+                asset.amount,
+                asset.royalty,
+                asset.is_requested,
+            )
+            .await?;
+        }
+
+        for coin in offer.coins {
+            // The coin is filled in once it's synced, but has to exist to be locked by the offer
+            if !tx.is_known_coin(coin.coin_id()).await? {
+                tx.insert_coin(CoinState::new(coin, None, None)).await?;
+            }
+
+            tx.insert_offered_coin(offer_id, coin.coin_id()).await?;
+        }
+    }
+
+    for (name, vault) in import.vaults {
+        tx.insert_vault_p2_puzzle(&name, &vault).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+fn parse_hash(hash: &str) -> Result<Bytes32> {
+    Ok(Bytes32::new(
+        hex::decode(hash)?
+            .try_into()
+            .map_err(|_| Error::InvalidBackup)?,
+    ))
+}
+
+fn asset_kind(id: u8) -> Result<AssetKind> {
+    Ok(match id {
+        0 => AssetKind::Token,
+        1 => AssetKind::Nft,
+        2 => AssetKind::Did,
+        3 => AssetKind::Option,
+        _ => return Err(Error::InvalidBackup),
+    })
+}
+
+fn offer_status(id: u8) -> Result<OfferStatus> {
+    Ok(match id {
+        0 => OfferStatus::Pending,
+        1 => OfferStatus::Active,
+        2 => OfferStatus::Completed,
+        3 => OfferStatus::Cancelled,
+        4 => OfferStatus::Expired,
+        _ => return Err(Error::InvalidBackup),
+    })
+}
@@ -1,6 +1,6 @@
 use crate::{
-    Error, Result, Sage, address_kind, parse_any_asset_id, parse_asset_id, parse_collection_id,
-    parse_did_id, parse_nft_id, parse_option_id,
+    Error, Result, Sage, address_kind, encode_sync_progress, parse_any_asset_id, parse_asset_id,
+    parse_collection_id, parse_did_id, parse_nft_id, parse_option_id,
 };
 use base64::{Engine, prelude::BASE64_STANDARD};
 use chia_wallet_sdk::chia::puzzle_types::nft::NftMetadata;
@@ -8,22 +8,23 @@ use chia_wallet_sdk::prelude::*;
 use chia_wallet_sdk::{driver::BURN_PUZZLE_HASH, utils::Address};
 use sage_api::{
     Amount, CheckAddress, CheckAddressResponse, CoinFilterMode as ApiCoinFilterMode, CoinRecord,
-    CoinSortMode as ApiCoinSortMode, DerivationRecord, DidRecord, GetAllCats, GetAllCatsResponse,
-    GetAreCoinsSpendable, GetAreCoinsSpendableResponse, GetCats, GetCatsResponse, GetCoins,
-    GetCoinsByIds, GetCoinsByIdsResponse, GetCoinsResponse, GetDatabaseStats,
-    GetDatabaseStatsResponse, GetDerivations, GetDerivationsResponse, GetDids, GetDidsResponse,
-    GetMinterDidIds, GetMinterDidIdsResponse, GetNft, GetNftCollection, GetNftCollectionResponse,
-    GetNftCollections, GetNftCollectionsResponse, GetNftData, GetNftDataResponse, GetNftIcon,
-    GetNftIconResponse, GetNftResponse, GetNftThumbnail, GetNftThumbnailResponse, GetNfts,
-    GetNftsResponse, GetOption, GetOptionResponse, GetOptions, GetOptionsResponse,
-    GetPendingTransactions, GetPendingTransactionsResponse, GetSpendableCoinCount,
-    GetSpendableCoinCountResponse, GetSyncStatus, GetSyncStatusResponse, GetToken,
-    GetTokenResponse, GetTransaction, GetTransactionResponse, GetTransactions,
-    GetTransactionsResponse, GetVersion, GetVersionResponse, IsAssetOwned, IsAssetOwnedResponse,
-    NftCollectionRecord, NftData, NftRecord, NftSortMode as ApiNftSortMode, NftSpecialUseType,
-    OptionRecord, OptionSortMode as ApiOptionSortMode, PendingTransactionRecord,
+    CoinSortMode as ApiCoinSortMode, DerivationGapRecord, DerivationRecord, DidRecord, GetAllCats,
+    GetAllCatsResponse, GetAreCoinsSpendable, GetAreCoinsSpendableResponse, GetCats,
+    GetCatsResponse, GetCoins, GetCoinsByIds, GetCoinsByIdsResponse, GetCoinsResponse,
+    GetDatabaseStats, GetDatabaseStatsResponse, GetDerivationGap, GetDerivationGapResponse,
+    GetDerivations, GetDerivationsResponse, GetDids, GetDidsResponse, GetMinterDidIds,
+    GetMinterDidIdsResponse, GetNft, GetNftCollection, GetNftCollectionResponse, GetNftCollections,
+    GetNftCollectionsResponse, GetNftData, GetNftDataResponse, GetNftIcon, GetNftIconResponse,
+    GetNftResponse, GetNftThumbnail, GetNftThumbnailResponse, GetNfts, GetNftsResponse, GetOption,
+    GetOptionResponse, GetOptions, GetOptionsResponse, GetPendingTransactions,
+    GetPendingTransactionsResponse, GetSpendableCoinCount, GetSpendableCoinCountResponse,
+    GetSyncStatus, GetSyncStatusResponse, GetToken, GetTokenResponse, GetTransaction,
+    GetTransactionResponse, GetTransactions, GetTransactionsResponse, GetVersion,
+    GetVersionResponse, IsAssetOwned, IsAssetOwnedResponse, NftCollectionRecord, NftData,
+    NftRecord, NftSortMode as ApiNftSortMode, NftSpecialUseType, OptionRecord,
+    OptionSortMode as ApiOptionSortMode, PeerDiscrepancyRecord, PendingTransactionRecord,
     PerformDatabaseMaintenance, PerformDatabaseMaintenanceResponse, TokenRecord,
-    TransactionCoinRecord, TransactionRecord,
+    TransactionCoinRecord, TransactionRecord, TrustedPeerRecord,
 };
 use sage_database::{
     AssetFilter, CoinFilterMode, CoinSortMode, NftGroupSearch, NftRow, NftSortMode, OptionSortMode,
@@ -86,10 +87,10 @@ impl Sage {
         let total_coins = wallet.db.total_coin_count().await?;
         let synced_coins = wallet.db.synced_coin_count().await?;
 
-        let change_p2_puzzle_hash = wallet.change_p2_puzzle_hash().await?;
+        let receive_p2_puzzle_hash = wallet.receive_p2_puzzle_hash().await?;
 
         let receive_address =
-            Address::new(change_p2_puzzle_hash, self.network().prefix()).encode()?;
+            Address::new(receive_p2_puzzle_hash, self.network()?.prefix()).encode()?;
 
         let database_size = self
             .wallet_db_path(wallet.fingerprint)
@@ -97,13 +98,41 @@ impl Sage {
             .and_then(|path| path.metadata().ok())
             .map_or(0, |metadata| metadata.len());
 
+        let peer_state = self.peer_state.lock().await;
+
+        let peer_discrepancies = peer_state
+            .discrepancies()
+            .into_iter()
+            .map(|discrepancy| PeerDiscrepancyRecord {
+                ip_addr: discrepancy.ip.to_string(),
+                reason: discrepancy.reason,
+                timestamp: discrepancy.timestamp,
+            })
+            .collect();
+
+        let trusted_peer = self.network()?.trusted_peer.as_ref().map(|trusted_peer| {
+            let status = peer_state.trusted_peer().cloned().unwrap_or_default();
+
+            TrustedPeerRecord {
+                host: trusted_peer.host.clone(),
+                port: trusted_peer.port,
+                connected: peer_state.is_trusted_peer_connected(),
+                last_error: status.last_error,
+                failed_attempts: status.failed_attempts,
+            }
+        });
+
+        drop(peer_state);
+
+        let sync_progress = encode_sync_progress(self.sync_progress.lock().await.progress());
+
         Ok(GetSyncStatusResponse {
             selectable_balance: Amount::u128(selectable_balance),
             unit: self.unit.clone(),
             total_coins,
             synced_coins,
             receive_address,
-            burn_address: Address::new(BURN_PUZZLE_HASH, self.network().prefix()).encode()?,
+            burn_address: Address::new(BURN_PUZZLE_HASH, self.network()?.prefix()).encode()?,
             unhardened_derivation_index: wallet
                 .db
                 .max_derivation_index(false)
@@ -117,6 +146,12 @@ impl Sage {
             checked_files: wallet.db.checked_files().await?.try_into().unwrap_or(0),
             total_files: wallet.db.total_files().await?.try_into().unwrap_or(0),
             database_size,
+            verified_height: wallet.db.verified_height().await?,
+            peer_discrepancies,
+            trusted_peer,
+            sync_progress,
+            offline: self.config.network.offline,
+            queued_transactions: wallet.db.queued_broadcast_count().await?,
         })
     }
 
@@ -149,7 +184,7 @@ impl Sage {
                 Ok(DerivationRecord {
                     index: row.index,
                     public_key: hex::encode(row.synthetic_key.to_bytes()),
-                    address: Address::new(row.p2_puzzle_hash, self.network().prefix()).encode()?,
+                    address: Address::new(row.p2_puzzle_hash, self.network()?.prefix()).encode()?,
                 })
             })
             .collect::<Result<Vec<_>>>()?;
@@ -157,6 +192,38 @@ impl Sage {
         Ok(GetDerivationsResponse { derivations, total })
     }
 
+    pub async fn get_derivation_gap(
+        &self,
+        _req: GetDerivationGap,
+    ) -> Result<GetDerivationGapResponse> {
+        let wallet = self.wallet()?;
+
+        let mut tx = wallet.db.tx().await?;
+
+        let mut gaps = Vec::new();
+
+        for hardened in [false, true] {
+            let derived = tx.derivation_index(hardened).await?;
+            let unused_index = tx.unused_derivation_index(hardened).await?;
+
+            gaps.push(DerivationGapRecord {
+                derived,
+                used: tx.used_derivation_count(hardened).await?,
+                unused_index,
+                gap: derived.saturating_sub(unused_index),
+                // Hardened keys are only extended if some were derived to begin with
+                auto_derive: !hardened || (wallet.hardened_keys.is_some() && derived > 0),
+            });
+        }
+
+        tx.commit().await?;
+
+        Ok(GetDerivationGapResponse {
+            unhardened: gaps[0],
+            hardened: gaps[1],
+        })
+    }
+
     pub async fn get_are_coins_spendable(
         &self,
         req: GetAreCoinsSpendable,
@@ -191,7 +258,7 @@ impl Sage {
         for row in rows {
             coins.push(CoinRecord {
                 coin_id: hex::encode(row.coin.coin_id()),
-                address: Address::new(row.p2_puzzle_hash, self.network().prefix()).encode()?,
+                address: Address::new(row.p2_puzzle_hash, self.network()?.prefix()).encode()?,
                 amount: Amount::u64(row.coin.amount),
                 transaction_id: row.mempool_item_hash.map(hex::encode),
                 offer_id: row.offer_hash.map(hex::encode),
@@ -242,7 +309,7 @@ impl Sage {
         for row in rows {
             coins.push(CoinRecord {
                 coin_id: hex::encode(row.coin.coin_id()),
-                address: Address::new(row.p2_puzzle_hash, self.network().prefix()).encode()?,
+                address: Address::new(row.p2_puzzle_hash, self.network()?.prefix()).encode()?,
                 amount: Amount::u64(row.coin.amount),
                 transaction_id: row.mempool_item_hash.map(hex::encode),
                 offer_id: row.offer_hash.map(hex::encode),
@@ -261,6 +328,7 @@ impl Sage {
         let wallet = self.wallet()?;
 
         let cats = wallet.db.all_cats().await?;
+        let prefix = self.network()?.prefix();
         let mut records = Vec::with_capacity(cats.len());
 
         for cat in cats {
@@ -279,7 +347,7 @@ impl Sage {
                 selectable_balance: Amount::u128(selectable_balance),
                 revocation_address: cat
                     .hidden_puzzle_hash
-                    .map(|puzzle_hash| Address::new(puzzle_hash, self.network().prefix()).encode())
+                    .map(|puzzle_hash| Address::new(puzzle_hash, prefix.clone()).encode())
                     .transpose()?,
             });
         }
@@ -291,6 +359,7 @@ impl Sage {
         let wallet = self.wallet()?;
 
         let cats = wallet.db.owned_cats().await?;
+        let prefix = self.network()?.prefix();
 
         let mut records = Vec::with_capacity(cats.len());
 
@@ -310,7 +379,7 @@ impl Sage {
                 selectable_balance: Amount::u128(selectable_balance),
                 revocation_address: cat
                     .hidden_puzzle_hash
-                    .map(|puzzle_hash| Address::new(puzzle_hash, self.network().prefix()).encode())
+                    .map(|puzzle_hash| Address::new(puzzle_hash, prefix.clone()).encode())
                     .transpose()?,
             });
         }
@@ -331,6 +400,7 @@ impl Sage {
         // selectable_cat_balance works for any token including xch
         // holde over from when cats and xch were distinct entities
         let selectable_balance = wallet.db.selectable_cat_balance(asset_id).await?;
+        let prefix = self.network()?.prefix();
 
         let token = token
             .map(|cat| {
@@ -346,9 +416,7 @@ impl Sage {
                     selectable_balance: Amount::u128(selectable_balance),
                     revocation_address: cat
                         .hidden_puzzle_hash
-                        .map(|puzzle_hash| {
-                            Address::new(puzzle_hash, self.network().prefix()).encode()
-                        })
+                        .map(|puzzle_hash| Address::new(puzzle_hash, prefix).encode())
                         .transpose()?,
                 })
             })
@@ -368,7 +436,7 @@ impl Sage {
                 name: row.asset.name,
                 visible: row.asset.is_visible,
                 coin_id: hex::encode(row.coin_row.coin.coin_id()),
-                address: Address::new(row.coin_row.p2_puzzle_hash, self.network().prefix())
+                address: Address::new(row.coin_row.p2_puzzle_hash, self.network()?.prefix())
                     .encode()?,
                 amount: Amount::u64(row.coin_row.coin.amount),
                 recovery_hash: row.did_info.recovery_list_hash.map(hex::encode),
@@ -436,7 +504,7 @@ impl Sage {
                 name: row.asset.name,
                 visible: row.asset.is_visible,
                 coin_id: hex::encode(row.coin_row.coin.coin_id()),
-                address: Address::new(row.coin_row.p2_puzzle_hash, self.network().prefix())
+                address: Address::new(row.coin_row.p2_puzzle_hash, self.network()?.prefix())
                     .encode()?,
                 amount: Amount::u64(row.coin_row.coin.amount),
                 underlying_asset: self.encode_asset(row.underlying_asset)?,
@@ -469,7 +537,8 @@ impl Sage {
             name: row.asset.name,
             visible: row.asset.is_visible,
             coin_id: hex::encode(row.coin_row.coin.coin_id()),
-            address: Address::new(row.coin_row.p2_puzzle_hash, self.network().prefix()).encode()?,
+            address: Address::new(row.coin_row.p2_puzzle_hash, self.network()?.prefix())
+                .encode()?,
             amount: Amount::u64(row.coin_row.coin.amount),
             underlying_asset: self.encode_asset(row.underlying_asset)?,
             underlying_amount: Amount::u64(row.underlying_amount),
@@ -844,10 +913,11 @@ impl Sage {
             name: row.asset.name,
             sensitive_content: row.asset.is_sensitive_content,
             coin_id: hex::encode(row.coin_row.coin.coin_id()),
-            address: Address::new(row.coin_row.p2_puzzle_hash, self.network().prefix()).encode()?,
+            address: Address::new(row.coin_row.p2_puzzle_hash, self.network()?.prefix())
+                .encode()?,
             royalty_address: Address::new(
                 row.nft_info.royalty_puzzle_hash,
-                self.network().prefix(),
+                self.network()?.prefix(),
             )
             .encode()?,
             royalty_ten_thousandths: row.nft_info.royalty_basis_points,
@@ -876,13 +946,13 @@ impl Sage {
     }
 
     fn transaction_coin(&self, transaction_coin: TransactionCoin) -> Result<TransactionCoinRecord> {
+        let prefix = self.network()?.prefix();
+
         Ok(TransactionCoinRecord {
             coin_id: hex::encode(transaction_coin.coin.coin_id()),
             address: transaction_coin
                 .p2_puzzle_hash
-                .map(|p2_puzzle_hash| {
-                    Address::new(p2_puzzle_hash, self.network().prefix()).encode()
-                })
+                .map(|p2_puzzle_hash| Address::new(p2_puzzle_hash, prefix).encode())
                 .transpose()?,
             address_kind: address_kind(transaction_coin.p2_puzzle_hash),
             amount: Amount::u64(transaction_coin.coin.amount),
@@ -14,20 +14,35 @@ use chia_wallet_sdk::{
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha20Rng;
 use sage_api::{
-    DeleteDatabase, DeleteDatabaseResponse, DeleteKey, DeleteKeyResponse, GenerateMnemonic,
+    ChangeKeyPassword, ChangeKeyPasswordResponse, DeleteDatabase, DeleteDatabaseResponse,
+    DeleteKey, DeleteKeyResponse, ExportKeyShares, ExportKeySharesResponse, GenerateMnemonic,
     GenerateMnemonicResponse, GetKey, GetKeyResponse, GetKeys, GetKeysResponse, GetSecretKey,
     GetSecretKeyResponse, GetWalletAddress, GetWalletAddressResponse, ImportKey, ImportKeyResponse,
-    KeyInfo, KeyKind, Login, LoginResponse, Logout, LogoutResponse, RenameKey, RenameKeyResponse,
-    Resync, ResyncResponse, SecretKeyInfo, SetWalletEmoji, SetWalletEmojiResponse,
+    InspectKeyShares, InspectKeySharesResponse, KeyInfo, KeyKind, KeyShareInfo, Login,
+    LoginResponse, Logout, LogoutResponse, RenameKey, RenameKeyResponse, Resync, ResyncResponse,
+    SecretKeyInfo, SetWalletEmoji, SetWalletEmojiResponse, VerifyKeyPassword,
+    VerifyKeyPasswordResponse,
 };
-use sage_config::Wallet;
+use sage_config::{ChangePolicy, Wallet};
 use sage_database::{Database, Derivation};
+use sage_keychain::{KeychainError, Share, ShareGroup, is_complete, mnemonic_from_shares};
 use sqlx::query;
 
 use crate::{Error, Result, Sage};
 
 impl Sage {
     pub async fn login(&mut self, req: Login) -> Result<LoginResponse> {
+        let password = req.password.unwrap_or_default();
+
+        if self.keychain.has_secret_key(req.fingerprint)
+            && !self
+                .keychain
+                .verify_password(req.fingerprint, password.as_bytes())?
+        {
+            return Err(KeychainError::Decrypt.into());
+        }
+
+        self.session_password = Some((req.fingerprint, password));
         self.config.global.fingerprint = Some(req.fingerprint);
         self.save_config()?;
         self.switch_wallet().await?;
@@ -35,6 +50,7 @@ impl Sage {
     }
 
     pub async fn logout(&mut self, _req: Logout) -> Result<LogoutResponse> {
+        self.session_password = None;
         self.config.global.fingerprint = None;
         self.save_config()?;
         self.switch_wallet().await?;
@@ -128,6 +144,8 @@ impl Sage {
             key_hex = &key_hex[2..];
         }
 
+        let password = req.password.unwrap_or_default();
+
         let (fingerprint, master_sk, master_pk) = if let Ok(bytes) = hex::decode(key_hex) {
             if let Ok(master_pk) = bytes.clone().try_into() {
                 let master_pk = PublicKey::from_bytes(&master_pk)?;
@@ -138,7 +156,8 @@ impl Sage {
                 let master_pk = master_sk.public_key();
 
                 let fingerprint = if req.save_secrets {
-                    self.keychain.add_secret_key(&master_sk, b"")?
+                    self.keychain
+                        .add_secret_key(&master_sk, password.as_bytes())?
                 } else {
                     self.keychain.add_public_key(&master_pk)?
                 };
@@ -150,14 +169,26 @@ impl Sage {
         } else {
             let words: Vec<&str> = req.key.split_whitespace().collect();
             let word_count = words.len();
+            let shares = split_shares(&req.key);
+
+            let mnemonic = if word_count != 12 && word_count != 24 {
+                // SLIP-39 shares are 20 or 33 words each, so they can't be mistaken for a mnemonic
+                if !shares
+                    .first()
+                    .is_some_and(|share| Share::from_mnemonic(share).is_ok())
+                {
+                    return Err(Error::InvalidMnemonic(format!(
+                        "Expected 12 or 24 words, but got {word_count}."
+                    )));
+                }
 
-            if word_count != 12 && word_count != 24 {
-                return Err(Error::InvalidMnemonic(format!(
-                    "Expected 12 or 24 words, but got {word_count}."
-                )));
-            }
-
-            let mnemonic = Mnemonic::from_str(&req.key).map_err(|e| match e {
+                mnemonic_from_shares(
+                    &shares,
+                    req.passphrase.as_deref().unwrap_or_default().as_bytes(),
+                )?
+            } else {
+                Mnemonic::from_str(&req.key).map_err(|e| {
+                    match e {
                 bip39::Error::BadWordCount(count) => {
                     Error::InvalidMnemonic(format!("Expected 12 or 24 words, but got {count}."))
                 }
@@ -171,11 +202,14 @@ impl Sage {
                         .to_string(),
                 ),
                 _ => Error::InvalidMnemonic(format!("Invalid mnemonic: {e}")),
-            })?;
+                }
+                })?
+            };
+
             let master_sk = SecretKey::from_seed(&mnemonic.to_seed(""));
             let master_pk = master_sk.public_key();
             let fingerprint = if req.save_secrets {
-                self.keychain.add_mnemonic(&mnemonic, b"")?
+                self.keychain.add_mnemonic(&mnemonic, password.as_bytes())?
             } else {
                 self.keychain.add_public_key(&master_pk)?
             };
@@ -190,6 +224,7 @@ impl Sage {
             ..Default::default()
         });
         self.config.global.fingerprint = Some(fingerprint);
+        self.session_password = Some((fingerprint, password));
 
         self.save_keychain()?;
         self.save_config()?;
@@ -278,6 +313,14 @@ impl Sage {
             self.config.global.fingerprint = None;
         }
 
+        if self
+            .session_password
+            .as_ref()
+            .is_some_and(|(fingerprint, _)| *fingerprint == req.fingerprint)
+        {
+            self.session_password = None;
+        }
+
         self.save_keychain()?;
         self.save_config()?;
 
@@ -330,7 +373,10 @@ impl Sage {
 
         let wallet_config = self.wallet_config().cloned().unwrap_or_default();
 
-        let network_id = wallet_config.network.unwrap_or_else(|| self.network_id());
+        let network_id = match wallet_config.network {
+            Some(network_id) => network_id,
+            None => self.network_id()?,
+        };
 
         let Some(master_pk) = self.keychain.extract_public_key(fingerprint)? else {
             return Ok(GetKeyResponse { key: None });
@@ -349,8 +395,104 @@ impl Sage {
         })
     }
 
+    pub async fn change_key_password(
+        &mut self,
+        req: ChangeKeyPassword,
+    ) -> Result<ChangeKeyPasswordResponse> {
+        // The keychain is only replaced once the re-encrypted copy has been saved
+        let mut keychain = self.keychain.clone();
+
+        let changed = keychain.change_password(
+            req.fingerprint,
+            req.old_password.as_bytes(),
+            req.new_password.as_bytes(),
+        )?;
+
+        self.replace_keychain(keychain)?;
+
+        if let Some((fingerprint, password)) = &mut self.session_password
+            && req
+                .fingerprint
+                .is_none_or(|changed| changed == *fingerprint)
+        {
+            *password = req.new_password;
+        }
+
+        // The open wallet's secret key was decrypted with the old password, so it's decrypted
+        // again, and dropped if the session no longer has the password for it
+        if let Some(fingerprint) = self.config.global.fingerprint
+            && req.fingerprint.is_none_or(|changed| changed == fingerprint)
+        {
+            self.switch_wallet().await?;
+        }
+
+        Ok(ChangeKeyPasswordResponse {
+            changed: changed.try_into()?,
+        })
+    }
+
+    pub fn verify_key_password(&self, req: VerifyKeyPassword) -> Result<VerifyKeyPasswordResponse> {
+        Ok(VerifyKeyPasswordResponse {
+            is_valid: self
+                .keychain
+                .verify_password(req.fingerprint, req.password.as_bytes())?,
+        })
+    }
+
+    pub fn export_key_shares(&mut self, req: ExportKeyShares) -> Result<ExportKeySharesResponse> {
+        let groups: Vec<ShareGroup> = req
+            .groups
+            .iter()
+            .map(|group| ShareGroup {
+                threshold: group.threshold,
+                count: group.count,
+            })
+            .collect();
+
+        let password = self
+            .key_password(req.fingerprint, req.password.as_deref())
+            .to_vec();
+
+        let groups = self.keychain.split_mnemonic(
+            req.fingerprint,
+            &password,
+            req.passphrase.as_deref().unwrap_or_default().as_bytes(),
+            req.group_threshold,
+            &groups,
+        )?;
+
+        Ok(ExportKeySharesResponse { groups })
+    }
+
+    pub fn inspect_key_shares(&self, req: InspectKeyShares) -> Result<InspectKeySharesResponse> {
+        let shares = req
+            .shares
+            .iter()
+            .map(|share| Share::from_mnemonic(share.trim()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(sage_keychain::KeychainError::from)?;
+
+        Ok(InspectKeySharesResponse {
+            is_complete: is_complete(&shares),
+            shares: shares
+                .into_iter()
+                .map(|share| KeyShareInfo {
+                    identifier: share.identifier,
+                    group_index: share.group_index,
+                    group_threshold: share.group_threshold,
+                    group_count: share.group_count,
+                    member_index: share.member_index,
+                    member_threshold: share.member_threshold,
+                })
+                .collect(),
+        })
+    }
+
     pub fn get_secret_key(&self, req: GetSecretKey) -> Result<GetSecretKeyResponse> {
-        let (mnemonic, Some(secret_key)) = self.keychain.extract_secrets(req.fingerprint, b"")?
+        let (mnemonic, Some(secret_key)) = self.keychain.extract_secrets(
+            req.fingerprint,
+            self.key_password(req.fingerprint, req.password.as_deref()),
+        )?
         else {
             return Ok(GetSecretKeyResponse { secrets: None });
         };
@@ -377,7 +519,10 @@ impl Sage {
                 public_key: hex::encode(master_pk.to_bytes()),
                 kind: KeyKind::Bls,
                 has_secrets: self.keychain.has_secret_key(wallet.fingerprint),
-                network_id: wallet.network.clone().unwrap_or_else(|| self.network_id()),
+                network_id: match &wallet.network {
+                    Some(network_id) => network_id.clone(),
+                    None => self.network_id()?,
+                },
                 emoji: wallet.emoji.clone(),
             });
         }
@@ -393,7 +538,7 @@ impl Sage {
             return Err(Error::UnknownFingerprint);
         };
 
-        // Return the change_address override directly if one is configured
+        // Return the fixed change address directly if one is configured
         let wallet_cfg = self
             .wallet_config
             .wallets
@@ -401,27 +546,38 @@ impl Sage {
             .find(|w| w.fingerprint == req.fingerprint);
 
         if let Some(cfg) = wallet_cfg
-            && let Some(change_address) = &cfg.change_address
+            && let ChangePolicy::Fixed { address } = &cfg.change_policy
         {
             return Ok(GetWalletAddressResponse {
-                address: change_address.clone(),
+                address: address.clone(),
             });
         }
 
         let network = self
-            .network_list
-            .by_name(&req.network_id)
+            .network_by_name(&req.network_id)
             .ok_or(Error::UnknownFingerprint)?;
 
         let prefix = network.prefix();
         let intermediate_pk = master_to_wallet_unhardened_intermediate(&master_pk);
 
         // Try to read the current receive address from the wallet's DB
-        let p2_puzzle_hash = self
-            .address_from_db(req.fingerprint, &req.network_id, false)
-            .await
-            .ok()
-            .flatten();
+        let mut p2_puzzle_hash = None;
+
+        if wallet_cfg.is_some_and(|cfg| cfg.receive_hardened) {
+            p2_puzzle_hash = self
+                .address_from_db(req.fingerprint, &req.network_id, true)
+                .await
+                .ok()
+                .flatten();
+        }
+
+        if p2_puzzle_hash.is_none() {
+            p2_puzzle_hash = self
+                .address_from_db(req.fingerprint, &req.network_id, false)
+                .await
+                .ok()
+                .flatten();
+        }
 
         // Fall back to deriving index 0 from the master public key
         let p2_puzzle_hash = p2_puzzle_hash.unwrap_or_else(|| {
@@ -457,3 +613,28 @@ impl Sage {
         Ok(tx.custody_p2_puzzle_hash(index, hardened).await.ok())
     }
 }
+
+/// Splits the share phrases by line, or by their word count if they were pasted on a single line.
+fn split_shares(key: &str) -> Vec<String> {
+    let lines: Vec<&str> = key
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    if lines.len() != 1 {
+        return lines.into_iter().map(ToString::to_string).collect();
+    }
+
+    let words: Vec<&str> = lines[0].split_whitespace().collect();
+
+    // Shares of 128-bit and 256-bit secrets have 20 and 33 words respectively
+    let Some(share_len) = [20, 33].into_iter().find(|len| words.len() % len == 0) else {
+        return vec![lines[0].to_string()];
+    };
+
+    words
+        .chunks(share_len)
+        .map(|chunk| chunk.join(" "))
+        .collect()
+}
@@ -10,7 +10,7 @@ use sage_api::{
     GetOffersForAsset, GetOffersForAssetResponse, GetOffersResponse, ImportOffer,
     ImportOfferResponse, MakeOffer, MakeOfferResponse, NftRoyalty, OfferAmount, OfferAsset,
     OfferRecord, OfferRecordStatus, OfferSummary, OptionAssets, TakeOffer, TakeOfferResponse,
-    ViewOffer, ViewOfferResponse,
+    ValidateOffer, ValidateOfferResponse, ViewOffer, ViewOfferResponse,
 };
 use sage_assets::fetch_uris_with_hash;
 use sage_database::{AssetKind, OfferRow, OfferStatus, OfferedAsset};
@@ -23,9 +23,9 @@ use tokio::time::timeout;
 use tracing::debug;
 
 use crate::{
-    ConfirmationInfo, Error, ExtractedNftData, Result, Sage, extract_nft_data, json_bundle,
-    offer_expiration, parse_amount, parse_asset_id, parse_coin_ids, parse_hash, parse_nft_id,
-    parse_offer_id, parse_option_id,
+    ConfirmationInfo, Error, ExtractedNftData, OfferRoyalties, Result, Sage, extract_nft_data,
+    json_bundle, offer_expiration, offer_royalties, parse_amount, parse_asset_id, parse_coin_ids,
+    parse_hash, parse_nft_id, parse_offer_id, parse_option_id,
 };
 
 #[derive(Debug, Clone)]
@@ -166,17 +166,11 @@ impl Sage {
             .make_offer(offered, requested, req.expires_at_second)
             .await?;
 
-        let (_mnemonic, Some(master_sk)) =
-            self.keychain.extract_secrets(wallet.fingerprint, b"")?
-        else {
-            return Err(Error::NoSigningKey);
-        };
-
         let offer = wallet
             .sign_transaction(
                 unsigned,
-                &AggSigConstants::new(self.network().agg_sig_me()),
-                master_sk,
+                &AggSigConstants::new(self.network()?.agg_sig_me()),
+                &*self.signer(wallet.fingerprint)?,
                 false,
             )
             .await?;
@@ -204,12 +198,6 @@ impl Sage {
 
         let taken = wallet.take_offer(offer, fee).await?;
 
-        let (_mnemonic, Some(master_sk)) =
-            self.keychain.extract_secrets(wallet.fingerprint, b"")?
-        else {
-            return Err(Error::NoSigningKey);
-        };
-
         let TakenOffer {
             offer,
             spend_bundle,
@@ -217,8 +205,8 @@ impl Sage {
         let spend_bundle = wallet
             .sign_transaction(
                 spend_bundle,
-                &AggSigConstants::new(self.network().agg_sig_me()),
-                master_sk,
+                &AggSigConstants::new(self.network()?.agg_sig_me()),
+                &*self.signer(wallet.fingerprint)?,
                 false,
             )
             .await?;
@@ -281,6 +269,10 @@ impl Sage {
         })
     }
 
+    pub async fn validate_offer(&self, req: ValidateOffer) -> Result<ValidateOfferResponse> {
+        self.validate_offer_bundle(decode_offer(&req.offer)?).await
+    }
+
     pub async fn import_offer(&self, req: ImportOffer) -> Result<ImportOfferResponse> {
         let wallet = self.wallet()?;
         let spend_bundle = sort_offer(decode_offer(&req.offer)?);
@@ -321,7 +313,7 @@ impl Sage {
             });
         }
 
-        let testnet = self.network().genesis_challenge == TESTNET11_CONSTANTS.genesis_challenge;
+        let testnet = self.network()?.genesis_challenge == TESTNET11_CONSTANTS.genesis_challenge;
 
         for nft in offer.offered_coins().nfts.values() {
             let _info = if let Ok(metadata) = ctx.extract::<NftMetadata>(nft.info.metadata.ptr()) {
@@ -640,9 +632,10 @@ impl Sage {
                     Result::Ok(NftRoyalty {
                         royalty_address: Address::new(
                             info.royalty_puzzle_hash,
-                            self.network().prefix(),
+                            self.network()?.prefix(),
                         )
                         .encode()?,
+                        royalty_puzzle_hash: hex::encode(info.royalty_puzzle_hash),
                         royalty_basis_points: info.royalty_basis_points,
                     })
                 })
@@ -682,6 +675,12 @@ impl Sage {
             }
         }
 
+        let OfferRoyalties {
+            royalties,
+            maker_royalties,
+            taker_royalties,
+        } = offer_royalties(&maker, &taker);
+
         Ok(OfferRecord {
             offer_id: hex::encode(offer.offer_id),
             offer: offer.encoded_offer,
@@ -699,6 +698,9 @@ impl Sage {
                 fee: Amount::u64(offer.fee),
                 expiration_height: offer.expiration_height,
                 expiration_timestamp: offer.expiration_timestamp,
+                royalties,
+                maker_royalties,
+                taker_royalties,
             },
         })
     }
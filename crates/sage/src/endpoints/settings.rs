@@ -1,16 +1,24 @@
-use std::time::Duration;
+use std::{net::SocketAddr, time::Duration};
 
+use chia_wallet_sdk::utils::Address;
 use itertools::Itertools;
 use sage_api::{
-    AddPeer, AddPeerResponse, GetNetwork, GetNetworkResponse, GetNetworks, GetNetworksResponse,
-    GetPeers, GetPeersResponse, NetworkKind, PeerRecord, RemovePeer, RemovePeerResponse,
-    SetChangeAddress, SetChangeAddressResponse, SetDeltaSync, SetDeltaSyncOverride,
-    SetDeltaSyncOverrideResponse, SetDeltaSyncResponse, SetDiscoverPeers, SetDiscoverPeersResponse,
-    SetNetwork, SetNetworkOverride, SetNetworkOverrideResponse, SetNetworkResponse, SetTargetPeers,
-    SetTargetPeersResponse,
+    AddNetwork, AddNetworkResponse, AddPeer, AddPeerResponse, GetNetwork, GetNetworkResponse,
+    GetNetworks, GetNetworksResponse, GetPeers, GetPeersResponse, NetworkKind, PeerRecord,
+    ReloadNetworks, ReloadNetworksResponse, RemoveNetwork, RemoveNetworkResponse, RemovePeer,
+    RemovePeerResponse, SetChangeAddress, SetChangeAddressResponse, SetChangePolicy,
+    SetChangePolicyResponse, SetDeltaSync, SetDeltaSyncOverride, SetDeltaSyncOverrideResponse,
+    SetDeltaSyncResponse, SetDiscoverPeers, SetDiscoverPeersResponse, SetGapLimit,
+    SetGapLimitResponse, SetNetwork, SetNetworkOverride, SetNetworkOverrideResponse,
+    SetNetworkResponse, SetOffline, SetOfflineResponse, SetParanoidSync, SetParanoidSyncResponse,
+    SetProxy, SetProxyResponse, SetReceiveHardened, SetReceiveHardenedResponse, SetTargetPeers,
+    SetTargetPeersResponse, UpdateNetwork, UpdateNetworkResponse,
 };
-use sage_config::{MAINNET, TESTNET11};
+#[cfg(feature = "simulator")]
+use sage_config::SIMULATOR;
+use sage_config::{ChangePolicy, MAINNET, MAX_GAP_LIMIT, NetworkError, ProxyConfig, TESTNET11};
 use sage_wallet::SyncCommand;
+use tracing::warn;
 
 use crate::{Error, Result, Sage};
 
@@ -30,6 +38,7 @@ impl Sage {
                         port: info.0.socket_addr().port(),
                         peak_height: info.1,
                         user_managed: peer_state.peer(ip).is_some_and(|p| p.user_managed),
+                        score: peer_state.score(ip),
                     }
                 })
                 .collect(),
@@ -76,6 +85,62 @@ impl Sage {
         Ok(SetDiscoverPeersResponse {})
     }
 
+    pub async fn set_paranoid_sync(
+        &mut self,
+        req: SetParanoidSync,
+    ) -> Result<SetParanoidSyncResponse> {
+        if self.config.network.paranoid_sync != req.paranoid_sync {
+            self.config.network.paranoid_sync = req.paranoid_sync;
+            self.save_config()?;
+            self.command_sender
+                .send(SyncCommand::SetParanoidSync(req.paranoid_sync))
+                .await?;
+        }
+
+        Ok(SetParanoidSyncResponse {})
+    }
+
+    pub async fn set_proxy(&mut self, req: SetProxy) -> Result<SetProxyResponse> {
+        let proxy = req.address.map(|address| ProxyConfig {
+            address,
+            isolate_streams: req.isolate_streams,
+        });
+
+        if self.config.network.proxy == proxy {
+            return Ok(SetProxyResponse {});
+        }
+
+        if let Some(proxy) = &proxy {
+            proxy.address.parse::<SocketAddr>()?;
+        }
+
+        // The config is only changed once the proxy has been applied successfully
+        sage_assets::set_http_proxy(
+            proxy.as_ref().map(|proxy| proxy.address.as_str()),
+            proxy.as_ref().is_some_and(|proxy| proxy.isolate_streams),
+        )?;
+
+        self.config.network.proxy = proxy;
+        self.save_config()?;
+        self.command_sender
+            .send(SyncCommand::SetProxy(self.proxy()?))
+            .await?;
+
+        Ok(SetProxyResponse {})
+    }
+
+    pub async fn set_offline(&mut self, req: SetOffline) -> Result<SetOfflineResponse> {
+        if self.config.network.offline != req.offline {
+            self.config.network.offline = req.offline;
+            self.save_config()?;
+            self.command_sender
+                .send(SyncCommand::SetOffline(req.offline))
+                .await?;
+        }
+
+        Ok(SetOfflineResponse {})
+    }
+
     pub async fn set_target_peers(
         &mut self,
         req: SetTargetPeers,
@@ -90,6 +155,10 @@ impl Sage {
     }
 
     pub async fn set_network(&mut self, req: SetNetwork) -> Result<SetNetworkResponse> {
+        if self.network_by_name(&req.name).is_none() {
+            return Err(Error::UnknownNetwork(req.name));
+        }
+
         self.config.network.default_network.clone_from(&req.name);
         self.save_config()?;
         self.switch_wallet().await?;
@@ -101,6 +170,12 @@ impl Sage {
         &mut self,
         req: SetNetworkOverride,
     ) -> Result<SetNetworkOverrideResponse> {
+        if let Some(name) = &req.name
+            && self.network_by_name(name).is_none()
+        {
+            return Err(Error::UnknownNetwork(name.clone()));
+        }
+
         let config = self
             .wallet_config
             .wallets
@@ -118,15 +193,125 @@ impl Sage {
     }
 
     pub fn get_networks(&mut self, _req: GetNetworks) -> Result<GetNetworksResponse> {
+        #[cfg(feature = "simulator")]
+        if self.enable_simulator && self.network_list.by_name(&SIMULATOR.name).is_none() {
+            let mut network_list = self.network_list.clone();
+            network_list.networks.push(SIMULATOR.clone());
+            return Ok(network_list);
+        }
+
         Ok(self.network_list.clone())
     }
 
+    pub fn add_network(&mut self, req: AddNetwork) -> Result<AddNetworkResponse> {
+        if self.network_list.by_name(&req.network.name).is_some() {
+            return Err(NetworkError::DuplicateName(req.network.name).into());
+        }
+
+        let mut network_list = self.network_list.clone();
+        network_list.networks.push(req.network);
+        network_list.validate()?;
+
+        self.network_list = network_list;
+        self.save_config()?;
+
+        Ok(AddNetworkResponse {})
+    }
+
+    pub async fn update_network(&mut self, req: UpdateNetwork) -> Result<UpdateNetworkResponse> {
+        let mut network_list = self.network_list.clone();
+
+        let network = network_list
+            .networks
+            .iter_mut()
+            .find(|network| network.name == req.network.name)
+            .ok_or_else(|| Error::UnknownNetwork(req.network.name.clone()))?;
+
+        self.check_network_change(network, &req.network)?;
+
+        let name = req.network.name.clone();
+        *network = req.network;
+        network_list.validate()?;
+
+        let is_active = self.network()?.name == name;
+
+        self.network_list = network_list;
+        self.save_config()?;
+
+        // Reconnect with the new definition, rather than waiting for a restart
+        if is_active {
+            self.switch_wallet().await?;
+            self.setup_peers().await?;
+        }
+
+        Ok(UpdateNetworkResponse {})
+    }
+
+    pub fn remove_network(&mut self, req: RemoveNetwork) -> Result<RemoveNetworkResponse> {
+        if self.network_list.by_name(&req.name).is_none() {
+            return Err(Error::UnknownNetwork(req.name));
+        }
+
+        if self.config.network.default_network == req.name
+            || self
+                .wallet_config
+                .wallets
+                .iter()
+                .any(|wallet| wallet.network.as_ref() == Some(&req.name))
+        {
+            return Err(Error::NetworkInUse(req.name));
+        }
+
+        self.network_list
+            .networks
+            .retain(|network| network.name != req.name);
+        self.save_config()?;
+
+        Ok(RemoveNetworkResponse {})
+    }
+
+    pub async fn reload_networks(
+        &mut self,
+        _req: ReloadNetworks,
+    ) -> Result<ReloadNetworksResponse> {
+        let (mut network_list, errors) = self.load_network_list()?;
+
+        for error in errors {
+            warn!("Ignoring invalid network: {error}");
+        }
+
+        // Networks which wallets have already synced from keep their previous definition
+        for network in &mut network_list.networks {
+            if let Some(old) = self.network_list.by_name(&network.name)
+                && let Err(error) = self.check_network_change(old, network)
+            {
+                warn!("Ignoring change to network: {error}");
+                network.clone_from(old);
+            }
+        }
+
+        network_list.validate()?;
+
+        let previous = self.network().ok().cloned();
+
+        self.network_list = network_list;
+
+        if self.network().ok() != previous.as_ref() {
+            self.switch_wallet().await?;
+            self.setup_peers().await?;
+        }
+
+        Ok(ReloadNetworksResponse {})
+    }
+
     pub fn get_network(&mut self, _req: GetNetwork) -> Result<GetNetworkResponse> {
-        let network = self.network();
+        let network = self.network()?;
 
         Ok(GetNetworkResponse {
             network: network.clone(),
-            kind: if network.genesis_challenge == MAINNET.genesis_challenge {
+            kind: if cfg!(feature = "simulator") && network.simulator {
+                NetworkKind::Simulator
+            } else if network.genesis_challenge == MAINNET.genesis_challenge {
                 NetworkKind::Mainnet
             } else if network.genesis_challenge == TESTNET11.genesis_challenge {
                 NetworkKind::Testnet
@@ -171,9 +356,79 @@ impl Sage {
         else {
             return Err(Error::UnknownFingerprint);
         };
-        wallet_config.change_address = req.change_address;
+        wallet_config.change_policy = match req.change_address {
+            Some(address) => ChangePolicy::Fixed { address },
+            None => ChangePolicy::Reuse,
+        };
         self.save_config()?;
         self.switch_wallet().await?;
         Ok(SetChangeAddressResponse {})
     }
+
+    pub async fn set_receive_hardened(
+        &mut self,
+        req: SetReceiveHardened,
+    ) -> Result<SetReceiveHardenedResponse> {
+        let Some(wallet_config) = self
+            .wallet_config
+            .wallets
+            .iter_mut()
+            .find(|w| w.fingerprint == req.fingerprint)
+        else {
+            return Err(Error::UnknownFingerprint);
+        };
+        wallet_config.receive_hardened = req.receive_hardened;
+        self.save_config()?;
+        self.switch_wallet().await?;
+        Ok(SetReceiveHardenedResponse {})
+    }
+
+    pub async fn set_change_policy(
+        &mut self,
+        req: SetChangePolicy,
+    ) -> Result<SetChangePolicyResponse> {
+        let change_policy = match req.change_policy {
+            sage_api::ChangePolicy::Reuse => ChangePolicy::Reuse,
+            sage_api::ChangePolicy::Fresh => ChangePolicy::Fresh,
+            sage_api::ChangePolicy::Fixed { address } => {
+                Address::decode(&address)?;
+                ChangePolicy::Fixed { address }
+            }
+        };
+
+        let Some(wallet_config) = self
+            .wallet_config
+            .wallets
+            .iter_mut()
+            .find(|w| w.fingerprint == req.fingerprint)
+        else {
+            return Err(Error::UnknownFingerprint);
+        };
+        wallet_config.change_policy = change_policy;
+        self.save_config()?;
+        self.switch_wallet().await?;
+        Ok(SetChangePolicyResponse {})
+    }
+
+    pub async fn set_gap_limit(&mut self, req: SetGapLimit) -> Result<SetGapLimitResponse> {
+        if req
+            .gap_limit
+            .is_some_and(|gap_limit| gap_limit == 0 || gap_limit > MAX_GAP_LIMIT)
+        {
+            return Err(Error::InvalidGapLimit(MAX_GAP_LIMIT));
+        }
+
+        let Some(wallet_config) = self
+            .wallet_config
+            .wallets
+            .iter_mut()
+            .find(|w| w.fingerprint == req.fingerprint)
+        else {
+            return Err(Error::UnknownFingerprint);
+        };
+        wallet_config.gap_limit = req.gap_limit;
+        self.save_config()?;
+        self.switch_wallet().await?;
+        Ok(SetGapLimitResponse {})
+    }
 }
@@ -0,0 +1,103 @@
+use std::{fs, path::PathBuf, sync::Arc};
+
+use chia_wallet_sdk::{prelude::*, test::PeerSimulator};
+use sage_api::{FarmBlocks, FarmBlocksResponse, MintToAddress, MintToAddressResponse};
+use sage_config::Network;
+use sage_wallet::SyncCommand;
+use tracing::info;
+
+use crate::{Error, Result, Sage, parse_amount};
+
+const MAX_FARM_BLOCKS: u32 = 1000;
+
+impl Sage {
+    pub async fn farm_blocks(&self, req: FarmBlocks) -> Result<FarmBlocksResponse> {
+        if req.blocks > MAX_FARM_BLOCKS {
+            return Err(Error::TooManyBlocks(MAX_FARM_BLOCKS));
+        }
+
+        let simulator = self.active_simulator()?;
+
+        // The simulator is unlocked between blocks so that the wallet can keep syncing
+        for _ in 0..req.blocks {
+            simulator.lock().await.create_block();
+            tokio::task::yield_now().await;
+        }
+
+        Ok(FarmBlocksResponse {
+            height: simulator.lock().await.height(),
+        })
+    }
+
+    pub async fn mint_to_address(&self, req: MintToAddress) -> Result<MintToAddressResponse> {
+        let simulator = self.active_simulator()?;
+        let puzzle_hash = self.parse_address(req.address)?;
+        let amount = parse_amount(req.amount)?;
+
+        let coin = simulator.lock().await.new_coin(puzzle_hash, amount);
+
+        // The coin doesn't go through the mempool, so the wallet is asked to subscribe to the
+        // puzzle hash again to make sure it picks up the new coin state
+        if let Ok(wallet) = self.wallet()
+            && wallet.db.is_custody_p2_puzzle_hash(puzzle_hash).await?
+        {
+            self.command_sender
+                .send(SyncCommand::SubscribePuzzles {
+                    puzzle_hashes: vec![puzzle_hash],
+                })
+                .await?;
+        }
+
+        Ok(MintToAddressResponse {
+            coin_id: hex::encode(coin.coin_id()),
+        })
+    }
+
+    /// The simulator is only started once it's needed, and is kept around afterward so that
+    /// its coins aren't lost when switching back and forth between networks.
+    pub(crate) async fn start_simulator(&mut self, network: &Network) -> Result<()> {
+        if !self.enable_simulator {
+            return Err(Error::SimulatorDisabled);
+        }
+
+        if self.simulator.is_none() {
+            // Wallets synced against a previous simulator have coins and a sync checkpoint that
+            // don't exist on the new one
+            for path in self.wallet_data_paths(network)? {
+                info!("Removing stale simulator database {}", path.display());
+
+                for suffix in ["", "-wal", "-shm"] {
+                    let mut file = path.clone().into_os_string();
+                    file.push(suffix);
+                    let file = PathBuf::from(file);
+
+                    if file.try_exists()? {
+                        fs::remove_file(&file)?;
+                    }
+                }
+            }
+
+            let simulator = PeerSimulator::new()
+                .await
+                .map_err(|error| Error::Simulator(error.to_string()))?;
+            self.simulator = Some(Arc::new(simulator));
+            info!("Started peer simulator");
+        }
+
+        if let Some(simulator) = self.simulator.clone() {
+            self.command_sender
+                .send(SyncCommand::SetSimulator(simulator))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    fn active_simulator(&self) -> Result<Arc<PeerSimulator>> {
+        if !self.network()?.simulator {
+            return Err(Error::NotSimulator);
+        }
+
+        self.simulator.clone().ok_or(Error::NotSimulator)
+    }
+}
@@ -0,0 +1,21 @@
+use sage_api::{FarmBlocks, FarmBlocksResponse, MintToAddress, MintToAddressResponse};
+use sage_config::Network;
+
+use crate::{Error, Result, Sage};
+
+/// Builds without the simulator feature still expose the simulator endpoints so that the API
+/// is the same everywhere, but the simulator network can't be used.
+#[allow(clippy::unused_async)]
+impl Sage {
+    pub async fn farm_blocks(&self, _req: FarmBlocks) -> Result<FarmBlocksResponse> {
+        Err(Error::SimulatorDisabled)
+    }
+
+    pub async fn mint_to_address(&self, _req: MintToAddress) -> Result<MintToAddressResponse> {
+        Err(Error::SimulatorDisabled)
+    }
+
+    pub(crate) async fn start_simulator(&mut self, _network: &Network) -> Result<()> {
+        Err(Error::SimulatorDisabled)
+    }
+}
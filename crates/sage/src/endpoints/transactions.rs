@@ -9,9 +9,10 @@ use itertools::Itertools;
 use sage_api::{
     AddNftUri, AssignNftsToDid, AutoCombineCat, AutoCombineCatResponse, AutoCombineXch,
     AutoCombineXchResponse, BulkMintNfts, BulkMintNftsResponse, BulkSendCat, BulkSendXch, Combine,
-    CreateDid, ExerciseOptions, FinalizeClawback, IssueCat, MintOption, MintOptionResponse,
-    MultiSend, NftUriKind, NormalizeDids, OptionAsset, SendCat, SendXch, SignCoinSpends,
-    SignCoinSpendsResponse, Split, SubmitTransaction, SubmitTransactionResponse,
+    CreateDid, DecodeCoinSpends, DecodeCoinSpendsResponse, ExerciseOptions, FinalizeClawback,
+    IssueCat, MintOption, MintOptionResponse, MultiSend, NftUriKind, NormalizeDids, OptionAsset,
+    SendCat, SendXch, SignCoinSpends, SignCoinSpendsResponse, SimulateTransaction,
+    SimulateTransactionResponse, Split, SubmitTransaction, SubmitTransactionResponse,
     TransactionResponse, TransferDids, TransferNfts, TransferOptions, ViewCoinSpends,
     ViewCoinSpendsResponse,
 };
@@ -23,7 +24,7 @@ use tokio::time::timeout;
 use crate::{
     ConfirmationInfo, Error, Result, Sage, json_bundle, json_spend, parse_amount, parse_asset_id,
     parse_coin_ids, parse_did_id, parse_hash, parse_memos, parse_nft_id, parse_option_id,
-    rust_bundle, rust_spend,
+    parse_signature, rust_bundle, rust_spend,
 };
 
 impl Sage {
@@ -424,7 +425,7 @@ impl Sage {
         })
     }
 
-    async fn parse_option_asset(&self, asset: OptionAsset) -> Result<OptionType> {
+    pub(crate) async fn parse_option_asset(&self, asset: OptionAsset) -> Result<OptionType> {
         let amount = asset
             .amount
             .to_u64()
@@ -527,6 +528,40 @@ impl Sage {
         })
     }
 
+    pub async fn simulate_transaction(
+        &self,
+        req: SimulateTransaction,
+    ) -> Result<SimulateTransactionResponse> {
+        let coin_spends = req
+            .coin_spends
+            .into_iter()
+            .map(rust_spend)
+            .collect::<Result<Vec<_>>>()?;
+
+        let signature = req.aggregated_signature.map(parse_signature).transpose()?;
+
+        self.simulate_coin_spends(coin_spends, signature).await
+    }
+
+    pub async fn decode_coin_spends(
+        &self,
+        req: DecodeCoinSpends,
+    ) -> Result<DecodeCoinSpendsResponse> {
+        let coin_spends = req
+            .coin_spends
+            .into_iter()
+            .map(rust_spend)
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut spends = Vec::with_capacity(coin_spends.len());
+
+        for coin_spend in &coin_spends {
+            spends.push(self.decode_coin_spend(coin_spend).await?);
+        }
+
+        Ok(DecodeCoinSpendsResponse { spends })
+    }
+
     pub async fn submit_transaction(
         &self,
         req: SubmitTransaction,
@@ -570,7 +605,7 @@ impl Sage {
         item: sage_api::NftMint,
         info: &mut ConfirmationInfo,
     ) -> Result<WalletNftMint> {
-        let testnet = self.network().genesis_challenge == TESTNET11_CONSTANTS.genesis_challenge;
+        let testnet = self.network()?.genesis_challenge == TESTNET11_CONSTANTS.genesis_challenge;
 
         let royalty_puzzle_hash = item
             .royalty_address
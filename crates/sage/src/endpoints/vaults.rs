@@ -0,0 +1,191 @@
+use chia_wallet_sdk::prelude::*;
+use sage_api::{
+    Amount, CreateVault, CreateVaultResponse, GetVaults, GetVaultsResponse, ProposeVaultSpend,
+    ProposeVaultSpendResponse, SubmitVaultSpend, SubmitVaultSpendResponse, VaultRecord,
+};
+use sage_database::Vault;
+use sage_wallet::SyncCommand;
+
+use crate::{
+    ConfirmationInfo, Error, Result, Sage, json_bundle, json_spend, parse_amount, parse_public_key,
+    rust_bundle,
+};
+
+impl Sage {
+    pub async fn create_vault(&self, req: CreateVault) -> Result<CreateVaultResponse> {
+        let wallet = self.wallet()?;
+
+        let public_keys = req
+            .public_keys
+            .into_iter()
+            .map(parse_public_key)
+            .collect::<Result<Vec<_>>>()?;
+
+        if req.threshold == 0 || req.threshold as usize > public_keys.len() {
+            return Err(Error::InvalidVault(format!(
+                "The threshold must be between 1 and {}",
+                public_keys.len()
+            )));
+        }
+
+        if public_keys
+            .iter()
+            .enumerate()
+            .any(|(i, public_key)| public_keys[..i].contains(public_key))
+        {
+            return Err(Error::InvalidVault(
+                "The same public key can't be a member more than once".to_string(),
+            ));
+        }
+
+        let vault = Vault {
+            threshold: req.threshold,
+            public_keys,
+        };
+
+        let mut tx = wallet.db.tx().await?;
+        let p2_puzzle_hash = tx.insert_vault_p2_puzzle(&req.name, &vault).await?;
+        tx.commit().await?;
+
+        self.command_sender
+            .send(SyncCommand::SubscribePuzzles {
+                puzzle_hashes: vec![p2_puzzle_hash],
+            })
+            .await?;
+
+        Ok(CreateVaultResponse {
+            address: Address::new(p2_puzzle_hash, self.network()?.prefix()).encode()?,
+        })
+    }
+
+    pub async fn get_vaults(&self, _req: GetVaults) -> Result<GetVaultsResponse> {
+        let wallet = self.wallet()?;
+
+        let mut vaults = Vec::new();
+
+        for row in wallet.db.vaults().await? {
+            let balance = wallet
+                .db
+                .selectable_vault_coins(row.p2_puzzle_hash)
+                .await?
+                .iter()
+                .map(|coin| u128::from(coin.amount))
+                .sum();
+
+            vaults.push(VaultRecord {
+                address: Address::new(row.p2_puzzle_hash, self.network()?.prefix()).encode()?,
+                name: row.name,
+                threshold: row.vault.threshold,
+                public_keys: row
+                    .vault
+                    .public_keys
+                    .iter()
+                    .map(|public_key| hex::encode(public_key.to_bytes()))
+                    .collect(),
+                balance: Amount::u128(balance),
+            });
+        }
+
+        Ok(GetVaultsResponse { vaults })
+    }
+
+    pub async fn propose_vault_spend(
+        &self,
+        req: ProposeVaultSpend,
+    ) -> Result<ProposeVaultSpendResponse> {
+        let wallet = self.wallet()?;
+
+        let vault_puzzle_hash = self.parse_address(req.vault_address)?;
+        let puzzle_hash = self.parse_address(req.address)?;
+        let amount = parse_amount(req.amount)?;
+        let fee = parse_amount(req.fee)?;
+
+        let Some(vault) = wallet.db.vault(vault_puzzle_hash).await? else {
+            return Err(Error::UnknownVault);
+        };
+
+        let signers = if let Some(signers) = req.signers {
+            signers
+                .into_iter()
+                .map(parse_public_key)
+                .collect::<Result<Vec<_>>>()?
+        } else {
+            self.default_vault_signers(&vault).await?
+        };
+
+        let coin_spends = wallet
+            .propose_vault_spend(&vault, &signers, vec![(puzzle_hash, amount)], fee)
+            .await?;
+
+        Ok(ProposeVaultSpendResponse {
+            coin_spends: coin_spends.iter().map(json_spend).collect(),
+            summary: self
+                .summarize(coin_spends, ConfirmationInfo::default())
+                .await?,
+            signers: signers
+                .iter()
+                .map(|public_key| hex::encode(public_key.to_bytes()))
+                .collect(),
+        })
+    }
+
+    pub async fn submit_vault_spend(
+        &self,
+        req: SubmitVaultSpend,
+    ) -> Result<SubmitVaultSpendResponse> {
+        let spend_bundles = req
+            .spend_bundles
+            .into_iter()
+            .map(rust_bundle)
+            .collect::<Result<Vec<_>>>()?;
+
+        let Some(first) = spend_bundles.first() else {
+            return Err(Error::MismatchedVaultSpends);
+        };
+
+        if spend_bundles
+            .iter()
+            .any(|spend_bundle| spend_bundle.coin_spends != first.coin_spends)
+        {
+            return Err(Error::MismatchedVaultSpends);
+        }
+
+        let mut aggregated_signature = Signature::default();
+
+        for spend_bundle in &spend_bundles {
+            aggregated_signature += &spend_bundle.aggregated_signature;
+        }
+
+        let spend_bundle = SpendBundle::new(first.coin_spends.clone(), aggregated_signature);
+        let json_bundle = json_bundle(&spend_bundle);
+
+        self.submit(spend_bundle).await?;
+
+        Ok(SubmitVaultSpendResponse {
+            spend_bundle: json_bundle,
+        })
+    }
+
+    /// The members this wallet can sign for come first, followed by the rest in order, up to
+    /// the threshold of the vault.
+    async fn default_vault_signers(&self, vault: &Vault) -> Result<Vec<PublicKey>> {
+        let wallet = self.wallet()?;
+        let master_pk = self.keychain.extract_public_key(wallet.fingerprint)?;
+
+        let mut owned = Vec::new();
+        let mut others = Vec::new();
+
+        for &public_key in &vault.public_keys {
+            if Some(public_key) == master_pk || wallet.db.derivation(public_key).await?.is_some() {
+                owned.push(public_key);
+            } else {
+                others.push(public_key);
+            }
+        }
+
+        owned.extend(others);
+        owned.truncate(vault.threshold as usize);
+
+        Ok(owned)
+    }
+}
@@ -1,12 +1,8 @@
 use std::slice;
 
 use chia_wallet_sdk::{
-    chia::{
-        bls::{master_to_wallet_hardened, master_to_wallet_unhardened, sign},
-        puzzle_types::{DeriveSynthetic, Proof},
-    },
-    driver::P2DelegatedConditionsLayer,
-    prelude::*,
+    chia::puzzle_types::Proof, driver::P2DelegatedConditionsLayer, prelude::*,
+    types::puzzles::P2MOfNDelegateDirectArgs,
 };
 use sage_api::{
     Amount,
@@ -18,7 +14,9 @@ use sage_api::{
     },
 };
 use sage_database::{AssetFilter, CoinFilterMode, CoinSortMode, DeserializePrimitive, P2Puzzle};
-use sage_wallet::{Status, SyncCommand, Transaction, insert_transaction, submit_to_peers};
+use sage_wallet::{
+    SignatureRequest, Status, SyncCommand, Transaction, insert_transaction, submit_to_peers,
+};
 use tracing::{debug, info, warn};
 
 use crate::{
@@ -111,6 +109,10 @@ impl Sage {
                 P2Puzzle::Arbor(key) => {
                     P2DelegatedConditionsLayer::new(key).construct_puzzle(&mut ctx)?
                 }
+                P2Puzzle::Vault(vault) => ctx.curry(P2MOfNDelegateDirectArgs::new(
+                    vault.threshold as usize,
+                    vault.public_keys,
+                ))?,
             };
 
             let (puzzle, proof) = match req.kind {
@@ -180,24 +182,22 @@ impl Sage {
             return Err(Error::InvalidKey);
         };
 
-        let (_mnemonic, Some(master_sk)) =
-            self.keychain.extract_secrets(wallet.fingerprint, b"")?
-        else {
-            return Err(Error::NoSigningKey);
-        };
-
-        let secret_key = if info.is_hardened {
-            master_to_wallet_hardened(&master_sk, info.derivation_index)
-        } else {
-            master_to_wallet_unhardened(&master_sk, info.derivation_index)
-        }
-        .derive_synthetic();
-
         let decoded_message = parse_signature_message(req.message)?;
-        let signature = sign(
-            &secret_key,
-            ("Chia Signed Message", decoded_message).tree_hash(),
-        );
+
+        let signature = self
+            .signer(wallet.fingerprint)?
+            .sign(&[SignatureRequest {
+                public_key,
+                derivation: Some(info),
+                message: ("Chia Signed Message", decoded_message)
+                    .tree_hash()
+                    .as_ref()
+                    .to_vec(),
+            }])
+            .await?
+            .pop()
+            .flatten()
+            .ok_or(Error::NoSigningKey)?;
 
         Ok(SignMessageWithPublicKeyResponse {
             signature: hex::encode(signature.to_bytes()),
@@ -219,24 +219,22 @@ impl Sage {
             return Err(Error::InvalidKey);
         };
 
-        let (_mnemonic, Some(master_sk)) =
-            self.keychain.extract_secrets(wallet.fingerprint, b"")?
-        else {
-            return Err(Error::NoSigningKey);
-        };
-
-        let secret_key = if info.is_hardened {
-            master_to_wallet_hardened(&master_sk, info.derivation_index)
-        } else {
-            master_to_wallet_unhardened(&master_sk, info.derivation_index)
-        }
-        .derive_synthetic();
-
         let decoded_message = parse_signature_message(req.message)?;
-        let signature = sign(
-            &secret_key,
-            ("Chia Signed Message", decoded_message).tree_hash(),
-        );
+
+        let signature = self
+            .signer(wallet.fingerprint)?
+            .sign(&[SignatureRequest {
+                public_key,
+                derivation: Some(info),
+                message: ("Chia Signed Message", decoded_message)
+                    .tree_hash()
+                    .as_ref()
+                    .to_vec(),
+            }])
+            .await?
+            .pop()
+            .flatten()
+            .ok_or(Error::NoSigningKey)?;
 
         Ok(SignMessageByAddressResponse {
             public_key: hex::encode(public_key.to_bytes()),
@@ -15,6 +15,7 @@ use chia_wallet_sdk::{
 use hex::FromHexError;
 use sage_api::ErrorKind;
 use sage_assets::UriError;
+use sage_config::NetworkError;
 use sage_database::DatabaseError;
 use sage_keychain::KeychainError;
 use sage_wallet::{SyncCommand, WalletError};
@@ -110,8 +111,31 @@ pub enum Error {
     #[error("SQLx Migration error: {0}")]
     SqlxMigration(#[from] MigrateError),
 
-    #[error("Unknown network")]
-    UnknownNetwork,
+    #[error("Unknown network: {0}")]
+    UnknownNetwork(String),
+
+    #[error("Invalid network: {0}")]
+    Network(#[from] NetworkError),
+
+    #[error("Network {0} is in use")]
+    NetworkInUse(String),
+
+    #[error(
+        "Network {0} already has wallet data, so its genesis challenge and prefix can't change"
+    )]
+    NetworkHasWalletData(String),
+
+    #[error("Simulator error: {0}")]
+    Simulator(String),
+
+    #[error("The active network isn't a simulator")]
+    NotSimulator,
+
+    #[error("The simulator network isn't enabled")]
+    SimulatorDisabled,
+
+    #[error("Can't farm more than {0} blocks at once")]
+    TooManyBlocks(u32),
 
     #[error("Unknown fingerprint")]
     UnknownFingerprint,
@@ -209,6 +233,9 @@ pub enum Error {
     #[error("No peers are currently available")]
     NoPeers,
 
+    #[error("The wallet hasn't synced to a peak yet")]
+    NotSynced,
+
     #[error("Could not fetch NFT with id: {0}")]
     CouldNotFetchNft(Bytes32),
 
@@ -227,11 +254,32 @@ pub enum Error {
     #[error("Missing asset id")]
     MissingAssetId,
 
+    #[error("Invalid action index: {0}")]
+    InvalidActionIndex(usize),
+
+    #[error("Only one offer can be settled per transaction")]
+    MultipleOffersSettled,
+
     #[error("Database version too old")]
     DatabaseVersionTooOld,
 
     #[error("Timeout")]
     Timeout(#[from] Elapsed),
+
+    #[error("Invalid backup")]
+    InvalidBackup,
+
+    #[error("Invalid vault: {0}")]
+    InvalidVault(String),
+
+    #[error("Unknown vault")]
+    UnknownVault,
+
+    #[error("Vault spend bundles don't have the same coin spends")]
+    MismatchedVaultSpends,
+
+    #[error("Gap limit must be between 1 and {0}")]
+    InvalidGapLimit(u32),
 }
 
 impl Error {
@@ -241,7 +289,14 @@ impl Error {
             Self::NotLoggedIn => ErrorKind::Unauthorized,
             Self::Keychain(error) => match error {
                 KeychainError::Decrypt => ErrorKind::Unauthorized,
+                KeychainError::KeyNotFound => ErrorKind::NotFound,
+                KeychainError::NoSecrets
+                | KeychainError::NoMnemonic
+                | KeychainError::InvalidArchive
+                | KeychainError::FingerprintMismatch(..)
+                | KeychainError::Shamir(..) => ErrorKind::Api,
                 KeychainError::KeyExists
+                | KeychainError::UnsupportedVersion(..)
                 | KeychainError::Bincode(..)
                 | KeychainError::Encrypt
                 | KeychainError::Bls(..)
@@ -267,9 +322,10 @@ impl Error {
             | Self::Bincode(..)
             | Self::Eval(..)
             | Self::Driver(..)
-            | Self::Timeout(..) => ErrorKind::Internal,
+            | Self::Timeout(..)
+            | Self::Simulator(..) => ErrorKind::Internal,
             Self::UnknownFingerprint
-            | Self::UnknownNetwork
+            | Self::UnknownNetwork(..)
             | Self::MissingCoin(..)
             | Self::MissingCatCoin(..)
             | Self::MissingDidCoin(..)
@@ -278,10 +334,17 @@ impl Error {
             | Self::MissingDid(..)
             | Self::MissingNft(..)
             | Self::MissingOption(..)
-            | Self::MissingOffer(..) => ErrorKind::NotFound,
+            | Self::MissingOffer(..)
+            | Self::UnknownVault => ErrorKind::NotFound,
             Self::Bls(..)
             | Self::Hex(..)
             | Self::InvalidKey
+            | Self::Network(..)
+            | Self::NetworkInUse(..)
+            | Self::NetworkHasWalletData(..)
+            | Self::NotSimulator
+            | Self::SimulatorDisabled
+            | Self::TooManyBlocks(..)
             | Self::InvalidMnemonic(..)
             | Self::TryFromSlice(..)
             | Self::TryFromInt(..)
@@ -305,12 +368,19 @@ impl Error {
             | Self::Uri(..)
             | Self::IpAddrParse(..)
             | Self::NoPeers
+            | Self::NotSynced
             | Self::CouldNotFetchNft(..)
             | Self::CouldNotFetchOption(..)
             | Self::MissingAssetId
+            | Self::InvalidActionIndex(..)
+            | Self::MultipleOffersSettled
             | Self::InvalidGroup
             | Self::InvalidThemeJson
-            | Self::MissingThemeData => ErrorKind::Api,
+            | Self::MissingThemeData
+            | Self::InvalidBackup
+            | Self::InvalidVault(..)
+            | Self::MismatchedVaultSpends
+            | Self::InvalidGapLimit(..) => ErrorKind::Api,
         }
     }
 }
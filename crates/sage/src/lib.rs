@@ -8,5 +8,6 @@ mod utils;
 
 pub use error::*;
 pub use sage::*;
+pub use utils::{encode_sync_progress, json_spend};
 
 pub(crate) use utils::*;
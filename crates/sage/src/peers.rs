@@ -3,6 +3,7 @@ use std::{
     net::IpAddr,
 };
 
+use sage_wallet::PeerReputation;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
@@ -10,6 +11,47 @@ pub struct Peers {
     pub connections: HashSet<IpAddr>,
     pub user_managed: HashSet<IpAddr>,
     pub banned: HashMap<IpAddr, u64>,
+    pub reputations: HashMap<IpAddr, PeerReputationRecord>,
+}
+
+/// The layout of the peers file before reputations were persisted.
+#[derive(Deserialize)]
+struct LegacyPeers {
+    connections: HashSet<IpAddr>,
+    user_managed: HashSet<IpAddr>,
+    banned: HashMap<IpAddr, u64>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct PeerReputationRecord {
+    pub latency_ms: Option<f64>,
+    pub timeout_rate: f64,
+    pub misbehavior: f64,
+    pub misbehavior_timestamp: u64,
+}
+
+impl From<PeerReputation> for PeerReputationRecord {
+    fn from(reputation: PeerReputation) -> Self {
+        Self {
+            latency_ms: reputation.latency_ms,
+            timeout_rate: reputation.timeout_rate,
+            misbehavior: reputation.misbehavior,
+            misbehavior_timestamp: reputation.misbehavior_timestamp,
+        }
+    }
+}
+
+impl From<PeerReputationRecord> for PeerReputation {
+    fn from(record: PeerReputationRecord) -> Self {
+        // The peak lag is recalculated once the peer connects again
+        Self {
+            latency_ms: record.latency_ms,
+            timeout_rate: record.timeout_rate,
+            peak_lag: 0,
+            misbehavior: record.misbehavior,
+            misbehavior_timestamp: record.misbehavior_timestamp,
+        }
+    }
 }
 
 impl Peers {
@@ -18,6 +60,46 @@ impl Peers {
     }
 
     pub fn from_bytes(bytes: &[u8]) -> bincode::Result<Self> {
-        bincode::deserialize(bytes)
+        bincode::deserialize(bytes).or_else(|error| {
+            let Ok(legacy) = bincode::deserialize::<LegacyPeers>(bytes) else {
+                return Err(error);
+            };
+
+            Ok(Self {
+                connections: legacy.connections,
+                user_managed: legacy.user_managed,
+                banned: legacy.banned,
+                reputations: HashMap::new(),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_peers() -> bincode::Result<()> {
+        #[derive(Serialize)]
+        struct Legacy {
+            connections: HashSet<IpAddr>,
+            user_managed: HashSet<IpAddr>,
+            banned: HashMap<IpAddr, u64>,
+        }
+
+        let ip = IpAddr::from([1, 2, 3, 4]);
+
+        let bytes = bincode::serialize(&Legacy {
+            connections: HashSet::from([ip]),
+            user_managed: HashSet::new(),
+            banned: HashMap::new(),
+        })?;
+
+        let peers = Peers::from_bytes(&bytes)?;
+        assert!(peers.connections.contains(&ip));
+        assert!(peers.reputations.is_empty());
+
+        Ok(())
     }
 }
@@ -1,5 +1,6 @@
 use std::{
     fs,
+    io::Write,
     path::{Path, PathBuf},
     str::FromStr,
     sync::{Arc, Once},
@@ -7,25 +8,31 @@ use std::{
 };
 
 use chia_wallet_sdk::{
-    chia::bls::master_to_wallet_unhardened_intermediate,
+    chia::{
+        bls::master_to_wallet_unhardened_intermediate,
+        consensus::consensus_constants::ConsensusConstants,
+    },
     client::{Connector, create_rustls_connector, load_ssl_cert},
     prelude::*,
 };
 use indexmap::IndexMap;
 use sage_api::{Unit, XCH};
 use sage_config::{
-    Config, Network, NetworkList, OldConfig, OldNetwork, WalletConfig, migrate_config,
-    migrate_networks,
+    ChangePolicy, Config, Network, NetworkError, NetworkList, OldConfig, OldNetwork, SIMULATOR,
+    WalletConfig, migrate_config, migrate_networks,
 };
 use sage_database::Database;
-use sage_keychain::Keychain;
-use sage_wallet::{PeerState, SyncCommand, SyncEvent, SyncManager, SyncOptions, Timeouts, Wallet};
+use sage_keychain::{KdfParams, Keychain};
+use sage_wallet::{
+    KeychainSigner, PeerState, RebroadcastPolicy, SocksProxy, SyncCommand, SyncEvent, SyncManager,
+    SyncOptions, SyncProgressTracker, Timeouts, Wallet,
+};
 use sqlx::{
     ConnectOptions, SqlitePool,
     sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous},
 };
 use tokio::sync::{Mutex, mpsc};
-use tracing::{Level, error, info};
+use tracing::{Level, error, info, warn};
 use tracing_appender::rolling::{Builder, Rotation};
 use tracing_subscriber::{
     EnvFilter, Layer, Registry, filter::filter_fn, fmt, layer::SubscriberExt,
@@ -44,9 +51,19 @@ pub struct Sage {
     pub keychain: Keychain,
     pub wallet: Option<Arc<Wallet>>,
     pub peer_state: Arc<Mutex<PeerState>>,
+    pub sync_progress: Arc<Mutex<SyncProgressTracker>>,
     pub command_sender: mpsc::Sender<SyncCommand>,
     pub unit: Unit,
     pub test: bool,
+    #[cfg(feature = "simulator")]
+    pub simulator: Option<Arc<chia_wallet_sdk::test::PeerSimulator>>,
+    /// Whether the built-in simulator network can be used, which is only meant for local
+    /// development.
+    #[cfg(feature = "simulator")]
+    pub enable_simulator: bool,
+    /// The fingerprint and password of the logged in key, which its secrets are decrypted with
+    /// until logging out.
+    pub session_password: Option<(u32, String)>,
 }
 
 impl Sage {
@@ -59,9 +76,15 @@ impl Sage {
             keychain: Keychain::default(),
             wallet: None,
             peer_state: Arc::new(Mutex::new(PeerState::default())),
+            sync_progress: Arc::new(Mutex::new(SyncProgressTracker::default())),
             command_sender: mpsc::channel(1).0,
             unit: XCH.clone(),
             test,
+            #[cfg(feature = "simulator")]
+            simulator: None,
+            #[cfg(feature = "simulator")]
+            enable_simulator: false,
+            session_password: None,
         }
     }
 
@@ -69,9 +92,14 @@ impl Sage {
         fs::create_dir_all(&self.path)?;
 
         self.setup_keys()?;
-        self.setup_config()?;
+        let network_errors = self.setup_config()?;
+        self.setup_kdf()?;
         self.setup_logging()?;
 
+        for error in network_errors {
+            warn!("Ignoring invalid network: {error}");
+        }
+
         let receiver = self.setup_sync_manager()?;
         self.setup_peers().await?;
 
@@ -141,17 +169,40 @@ impl Sage {
         if key_path.try_exists()? {
             let data = fs::read(&key_path)?;
             self.keychain = Keychain::from_bytes(&data)?;
+
+            if self.keychain.was_migrated() {
+                // Keep the legacy keychain around, since older versions can't read the new format
+                let legacy_path = self.path.join("keys.bin.legacy");
+
+                if !legacy_path.try_exists()? {
+                    write_atomically(&legacy_path, &data)?;
+                }
+
+                write_atomically(&key_path, &self.keychain.to_bytes()?)?;
+            }
         } else {
-            fs::write(&key_path, self.keychain.to_bytes()?)?;
+            write_atomically(&key_path, &self.keychain.to_bytes()?)?;
         }
 
         Ok(())
     }
 
-    fn setup_config(&mut self) -> Result<()> {
+    fn setup_kdf(&mut self) -> Result<()> {
+        let config = self.config.keychain;
+
+        self.keychain.set_kdf_params(KdfParams {
+            memory_kib: config.kdf_memory_kib,
+            iterations: config.kdf_iterations,
+            parallelism: config.kdf_parallelism,
+            ..Default::default()
+        })?;
+
+        Ok(())
+    }
+
+    fn setup_config(&mut self) -> Result<Vec<NetworkError>> {
         let config_path = self.path.join("config.toml");
         let wallet_config_path = self.path.join("wallets.toml");
-        let network_list_path = self.path.join("networks.toml");
 
         if config_path.try_exists()? {
             let config_text = fs::read_to_string(&config_path)?;
@@ -172,6 +223,7 @@ impl Sage {
                 self.config = toml::from_str(&config_text)?;
                 let wallet_config_text = fs::read_to_string(&wallet_config_path)?;
                 self.wallet_config = toml::from_str(&wallet_config_text)?;
+                self.wallet_config.migrate_change_addresses();
             }
         } else {
             fs::write(&config_path, toml::to_string_pretty(&self.config)?)?;
@@ -181,26 +233,37 @@ impl Sage {
             )?;
         }
 
-        if network_list_path.try_exists()? {
-            let text = fs::read_to_string(&network_list_path)?;
+        let (network_list, errors) = self.load_network_list()?;
+        self.network_list = network_list;
+
+        Ok(errors)
+    }
+
+    /// Reads the network list from disk, along with the errors for any networks which were
+    /// invalid and had to be replaced with their defaults or left out.
+    pub fn load_network_list(&self) -> Result<(NetworkList, Vec<NetworkError>)> {
+        let network_list_path = self.path.join("networks.toml");
+
+        if !network_list_path.try_exists()? {
+            let network_list = NetworkList::default();
+            fs::write(&network_list_path, toml::to_string_pretty(&network_list)?)?;
+            return Ok((network_list, Vec::new()));
+        }
+
+        let text = fs::read_to_string(&network_list_path)?;
 
+        let mut network_list =
             if let Ok(old_network_list) = toml::from_str::<IndexMap<String, OldNetwork>>(&text) {
-                self.network_list = migrate_networks(old_network_list);
-                fs::write(
-                    &network_list_path,
-                    toml::to_string_pretty(&self.network_list)?,
-                )?;
+                let network_list = migrate_networks(old_network_list);
+                fs::write(&network_list_path, toml::to_string_pretty(&network_list)?)?;
+                network_list
             } else {
-                self.network_list = toml::from_str(&text)?;
-            }
-        } else {
-            fs::write(
-                &network_list_path,
-                toml::to_string_pretty(&self.network_list)?,
-            )?;
-        }
+                toml::from_str(&text)?
+            };
 
-        Ok(())
+        let errors = network_list.repair();
+
+        Ok((network_list, errors))
     }
 
     fn setup_ssl(&mut self) -> Result<Connector> {
@@ -225,6 +288,20 @@ impl Sage {
 
     fn setup_sync_manager(&mut self) -> Result<mpsc::Receiver<SyncEvent>> {
         let connector = self.setup_ssl()?;
+        let proxy = self.proxy()?;
+
+        sage_assets::set_http_proxy(
+            self.config
+                .network
+                .proxy
+                .as_ref()
+                .map(|proxy| proxy.address.as_str()),
+            self.config
+                .network
+                .proxy
+                .as_ref()
+                .is_some_and(|proxy| proxy.isolate_streams),
+        )?;
 
         let (sync_manager, command_sender, receiver) = SyncManager::new(
             SyncOptions {
@@ -238,7 +315,15 @@ impl Sage {
                     .cloned()
                     .unwrap_or_default()
                     .delta_sync(&self.wallet_config.defaults),
+                paranoid: self.config.network.paranoid_sync,
+                offline: self.config.network.offline,
+                proxy,
                 puzzle_batch_size_per_peer: 5,
+                rebroadcast: RebroadcastPolicy {
+                    interval: Duration::from_secs(self.config.network.rebroadcast.interval_seconds),
+                    max_age: Duration::from_secs(self.config.network.rebroadcast.max_age_seconds),
+                    max_retries: self.config.network.rebroadcast.max_retries,
+                },
                 timeouts: if self.test {
                     Timeouts {
                         sync_delay: Duration::from_millis(100),
@@ -246,6 +331,7 @@ impl Sage {
                         cat_delay: Duration::from_millis(100),
                         puzzle_delay: Duration::from_millis(100),
                         transaction_delay: Duration::from_millis(100),
+                        broadcast_delay: Duration::from_millis(100),
                         offer_delay: Duration::from_millis(100),
                         ..Default::default()
                     }
@@ -255,8 +341,9 @@ impl Sage {
                 testing: self.test,
             },
             self.peer_state.clone(),
+            self.sync_progress.clone(),
             self.wallet.clone(),
-            self.network().clone(),
+            self.network()?.clone(),
             connector,
         );
 
@@ -267,8 +354,14 @@ impl Sage {
     }
 
     pub async fn switch_network(&mut self) -> Result<()> {
+        let network = self.network()?.clone();
+
+        if network.simulator {
+            self.start_simulator(&network).await?;
+        }
+
         self.command_sender
-            .send(SyncCommand::SwitchNetwork(self.network().clone()))
+            .send(SyncCommand::SwitchNetwork(network))
             .await?;
 
         Ok(())
@@ -299,29 +392,45 @@ impl Sage {
         let pool = self.connect_to_database(fingerprint).await?;
         let db = Database::new(pool);
 
-        db.run_rust_migrations(self.network().ticker.clone())
-            .await?;
+        let network = self.network()?.clone();
+
+        db.run_rust_migrations(network.ticker.clone()).await?;
 
         let wallet_config = self.wallet_config().cloned().unwrap_or_default();
 
-        let wallet = Arc::new(Wallet::new(
+        let mut wallet = Wallet::new(
             db.clone(),
             fingerprint,
             intermediate_pk,
-            self.network().genesis_challenge,
-            AggSigConstants::new(self.network().agg_sig_me()),
-            wallet_config
-                .change_address
-                .as_ref()
-                .map(|address| Address::decode(address))
-                .transpose()?
-                .map(|address| address.puzzle_hash),
-        ));
+            network.genesis_challenge,
+            AggSigConstants::new(network.agg_sig_me()),
+            match &wallet_config.change_policy {
+                ChangePolicy::Fixed { address } => Some(Address::decode(address)?.puzzle_hash),
+                ChangePolicy::Reuse | ChangePolicy::Fresh => None,
+            },
+        );
+
+        // Public key only wallets, and password protected wallets that were opened without the
+        // password, can still sync, but can't derive any more hardened keys. Otherwise the secret
+        // key stays decrypted in memory until the wallet is closed, so that hardened keys can be
+        // derived as the wallet is used.
+        if let Ok((_mnemonic, Some(master_sk))) = self
+            .keychain
+            .extract_secrets(fingerprint, self.key_password(fingerprint, None))
+        {
+            wallet.hardened_keys = Some(Arc::new(KeychainSigner::new(master_sk)));
+        }
+
+        wallet.receive_hardened = wallet_config.receive_hardened;
+        wallet.gap_limit = wallet_config.gap_limit();
+        wallet.reuse_change = wallet_config.change_policy != ChangePolicy::Fresh;
+
+        let wallet = Arc::new(wallet);
 
         self.wallet = Some(wallet.clone());
         self.unit = Unit {
-            ticker: self.network().ticker.clone(),
-            precision: self.network().precision,
+            ticker: network.ticker,
+            precision: network.precision,
         };
 
         self.command_sender
@@ -341,7 +450,7 @@ impl Sage {
             fs::create_dir_all(&peer_dir)?;
         }
 
-        let peer_path = peer_dir.join(format!("{}.bin", self.network_id()));
+        let peer_path = peer_dir.join(format!("{}.bin", self.network_id()?));
 
         let peers = if peer_path.try_exists()? {
             Peers::from_bytes(&fs::read(&peer_path)?).unwrap_or_else(|error| {
@@ -354,17 +463,12 @@ impl Sage {
 
         let mut state = self.peer_state.lock().await;
 
-        for (&ip, &timestamp) in &peers.banned {
-            let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .expect("system time before epoch")
-                .as_secs();
-
-            if now >= timestamp {
-                continue;
-            }
+        for (&ip, &reputation) in &peers.reputations {
+            state.restore_reputation(ip, reputation.into());
+        }
 
-            state.ban(ip, Duration::from_secs(timestamp - now), "already banned");
+        for (&ip, &timestamp) in &peers.banned {
+            state.restore_ban(ip, timestamp);
         }
 
         for &ip in &peers.connections {
@@ -418,7 +522,22 @@ impl Sage {
             peers.banned.insert(ip, ban);
         }
 
-        let peer_path = peer_dir.join(format!("{}.bin", self.network_id()));
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before epoch")
+            .as_secs();
+
+        // Reputations of peers that are long gone are only worth keeping while they're penalized
+        for (&ip, &reputation) in state.reputations() {
+            if state.is_connected(ip)
+                || peers.banned.contains_key(&ip)
+                || reputation.current_misbehavior(now) >= 1.0
+            {
+                peers.reputations.insert(ip, reputation.into());
+            }
+        }
+
+        let peer_path = peer_dir.join(format!("{}.bin", self.network_id()?));
         fs::write(&peer_path, peers.to_bytes()?)?;
 
         Ok(())
@@ -427,7 +546,7 @@ impl Sage {
     pub fn parse_address(&self, input: String) -> Result<Bytes32> {
         let address = Address::decode(&input)?;
 
-        if address.prefix != self.network().prefix() {
+        if address.prefix != self.network()?.prefix() {
             return Err(Error::AddressPrefix(address.prefix));
         }
 
@@ -460,18 +579,16 @@ impl Sage {
     pub fn wallet_db_path(&self, fingerprint: u32) -> Result<PathBuf> {
         let path = self.path.join("wallets").join(fingerprint.to_string());
         fs::create_dir_all(&path)?;
-        let network_id = self
+        let network_override = self
             .wallet_config
             .wallets
             .iter()
-            .find_map(|wallet| {
-                if wallet.fingerprint == fingerprint {
-                    wallet.network.clone()
-                } else {
-                    None
-                }
-            })
-            .unwrap_or_else(|| self.network_id());
+            .find(|wallet| wallet.fingerprint == fingerprint)
+            .and_then(|wallet| wallet.network.clone());
+        let network_id = match network_override {
+            Some(network_id) => network_id,
+            None => self.network_id()?,
+        };
         let path = path.join(format!("{network_id}.sqlite"));
         Ok(path)
     }
@@ -485,23 +602,127 @@ impl Sage {
         })
     }
 
-    pub fn network(&self) -> &Network {
-        if let Some(wallet) = self.wallet_config()
-            && let Some(network) = &wallet.network
-        {
-            return self
-                .network_list
-                .by_name(network)
-                .expect("network not found");
+    /// Wallet databases are tied to the chain and addresses of the network they were synced
+    /// from, so those can't change once any wallet has data for the network.
+    pub fn check_network_change(&self, old: &Network, new: &Network) -> Result<()> {
+        let same_chain = old.genesis_challenge == new.genesis_challenge
+            && old.prefix() == new.prefix()
+            && old.network_id() == new.network_id();
+
+        if !same_chain && self.has_wallet_data(old)? {
+            return Err(Error::NetworkHasWalletData(old.name.clone()));
+        }
+
+        Ok(())
+    }
+
+    fn has_wallet_data(&self, network: &Network) -> Result<bool> {
+        Ok(!self.wallet_data_paths(network)?.is_empty())
+    }
+
+    /// The databases of every wallet which has synced from the network.
+    pub(crate) fn wallet_data_paths(&self, network: &Network) -> Result<Vec<PathBuf>> {
+        let wallets_path = self.path.join("wallets");
+
+        if !wallets_path.try_exists()? {
+            return Ok(Vec::new());
+        }
+
+        // Wallets with a network override use the name rather than the network id
+        let mut file_names = vec![
+            format!("{}.sqlite", network.network_id()),
+            format!("{}.sqlite", network.name),
+        ];
+        file_names.dedup();
+
+        let mut paths = Vec::new();
+
+        for entry in fs::read_dir(wallets_path)? {
+            let path = entry?.path();
+
+            for file_name in &file_names {
+                let path = path.join(file_name);
+
+                if path.try_exists()? {
+                    paths.push(path);
+                }
+            }
         }
 
-        self.network_list
-            .by_name(&self.config.network.default_network)
-            .expect("network not found")
+        Ok(paths)
     }
 
-    pub fn network_id(&self) -> String {
-        self.network().network_id()
+    /// Looks up a network by name. The built-in simulator network is only available when it's
+    /// enabled, and is never saved to the network list.
+    pub fn network_by_name(&self, name: &str) -> Option<&Network> {
+        if let Some(network) = self.network_list.by_name(name) {
+            return Some(network);
+        }
+
+        #[cfg(feature = "simulator")]
+        if self.enable_simulator && name == SIMULATOR.name {
+            return Some(&*SIMULATOR);
+        }
+
+        None
+    }
+
+    /// The consensus rules of the active network, so that spend bundles can be run the same
+    /// way its mempool would. Networks other than mainnet use the testnet11 rules.
+    pub fn consensus_constants(&self) -> Result<ConsensusConstants> {
+        let network = self.network()?;
+
+        let mut constants = if network.genesis_challenge == MAINNET_CONSTANTS.genesis_challenge {
+            MAINNET_CONSTANTS.clone()
+        } else {
+            TESTNET11_CONSTANTS.clone()
+        };
+
+        let agg_sig = AggSigConstants::new(network.agg_sig_me());
+
+        constants.genesis_challenge = network.genesis_challenge;
+        constants.agg_sig_me_additional_data = agg_sig.me();
+        constants.agg_sig_parent_additional_data = agg_sig.parent();
+        constants.agg_sig_puzzle_additional_data = agg_sig.puzzle();
+        constants.agg_sig_amount_additional_data = agg_sig.amount();
+        constants.agg_sig_puzzle_amount_additional_data = agg_sig.puzzle_amount();
+        constants.agg_sig_parent_amount_additional_data = agg_sig.parent_amount();
+        constants.agg_sig_parent_puzzle_additional_data = agg_sig.parent_puzzle();
+
+        Ok(constants)
+    }
+
+    pub fn network(&self) -> Result<&Network> {
+        let name = self
+            .wallet_config()
+            .and_then(|wallet| wallet.network.as_ref())
+            .unwrap_or(&self.config.network.default_network);
+
+        self.network_by_name(name).ok_or_else(|| {
+            if *name == SIMULATOR.name {
+                Error::SimulatorDisabled
+            } else {
+                Error::UnknownNetwork(name.clone())
+            }
+        })
+    }
+
+    pub fn proxy(&self) -> Result<Option<SocksProxy>> {
+        self.config
+            .network
+            .proxy
+            .as_ref()
+            .map(|proxy| {
+                Ok(SocksProxy {
+                    addr: proxy.address.parse()?,
+                    isolate_streams: proxy.isolate_streams,
+                })
+            })
+            .transpose()
+    }
+
+    pub fn network_id(&self) -> Result<String> {
+        Ok(self.network()?.network_id())
     }
 
     pub fn wallet(&self) -> Result<Arc<Wallet>> {
@@ -533,7 +754,58 @@ impl Sage {
     }
 
     pub fn save_keychain(&self) -> Result<()> {
-        fs::write(self.path.join("keys.bin"), self.keychain.to_bytes()?)?;
+        write_keychain(&self.path, &self.keychain)
+    }
+
+    /// Persists a modified copy of the keychain, and only then replaces the one in memory, so
+    /// that they can't get out of sync if writing it fails.
+    pub fn replace_keychain(&mut self, keychain: Keychain) -> Result<()> {
+        write_keychain(&self.path, &keychain)?;
+        self.keychain = keychain;
         Ok(())
     }
+
+    /// The password to decrypt a key's secrets with. It defaults to the session password if the
+    /// key is logged in, and otherwise to an empty password.
+    pub(crate) fn key_password<'a>(
+        &'a self,
+        fingerprint: u32,
+        password: Option<&'a str>,
+    ) -> &'a [u8] {
+        password
+            .or_else(|| {
+                self.session_password
+                    .as_ref()
+                    .filter(|(session_fingerprint, _)| *session_fingerprint == fingerprint)
+                    .map(|(_, password)| password.as_str())
+            })
+            .unwrap_or_default()
+            .as_bytes()
+    }
+}
+
+fn write_keychain(path: &Path, keychain: &Keychain) -> Result<()> {
+    write_atomically(&path.join("keys.bin"), &keychain.to_bytes()?)?;
+    Ok(())
+}
+
+/// Writes the file to a temporary path and renames it into place, so that a crash can't
+/// leave a partially written file behind.
+fn write_atomically(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let temp_path = path.with_extension("tmp");
+
+    let mut file = fs::File::create(&temp_path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&temp_path, path)?;
+
+    // Make sure the rename itself is persisted on platforms that support it
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        fs::File::open(parent)?.sync_all()?;
+    }
+
+    Ok(())
 }
@@ -3,10 +3,17 @@ mod confirmation;
 mod conversions;
 mod offer_status;
 mod offer_summary;
+mod offer_validation;
 mod parse;
+mod royalties;
+mod spend_analysis;
+mod spend_decoding;
 mod spends;
+mod transaction_simulation;
 
 pub use confirmation::*;
 pub use conversions::*;
 pub use offer_status::*;
 pub use parse::*;
+pub use royalties::*;
+pub use spend_analysis::*;
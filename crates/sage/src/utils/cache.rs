@@ -19,7 +19,7 @@ impl Sage {
             return Ok(asset);
         }
 
-        let testnet = self.network().genesis_challenge == TESTNET11_CONSTANTS.genesis_challenge;
+        let testnet = self.network()?.genesis_challenge == TESTNET11_CONSTANTS.genesis_challenge;
 
         let asset = if let Ok(Ok(asset)) =
             timeout(Duration::from_secs(5), DexieCat::fetch(asset_id, testnet)).await
@@ -79,7 +79,8 @@ impl Sage {
         }
 
         let info = if let Ok(metadata) = NftMetadata::from_clvm(allocator, nft_metadata) {
-            let testnet = self.network().genesis_challenge == TESTNET11_CONSTANTS.genesis_challenge;
+            let testnet =
+                self.network()?.genesis_challenge == TESTNET11_CONSTANTS.genesis_challenge;
 
             if let Some(hash) = metadata.data_hash
                 && let Entry::Vacant(entry) = confirmation_info.nft_data.entry(hash)
@@ -91,7 +91,7 @@ impl Sage {
                 }
             };
 
-            let address = Address::new(p2_puzzle_hash, self.network().prefix()).encode()?;
+            let address = Address::new(p2_puzzle_hash, self.network()?.prefix()).encode()?;
 
             let mut outputs = Vec::new();
 
@@ -101,7 +101,7 @@ impl Sage {
                     .receiver_custody_p2_puzzle_hash()
                     .unwrap_or(output.coin.puzzle_hash);
 
-                let address = Address::new(p2_puzzle_hash, self.network().prefix()).encode()?;
+                let address = Address::new(p2_puzzle_hash, self.network()?.prefix()).encode()?;
 
                 outputs.push(TransactionOutput {
                     coin_id: hex::encode(output.coin.coin_id()),
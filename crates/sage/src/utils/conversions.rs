@@ -3,13 +3,16 @@ use chia_wallet_sdk::{
     prelude::*,
     puzzles::{SETTLEMENT_PAYMENT_HASH, SINGLETON_LAUNCHER_HASH},
 };
-use sage_api::AddressKind;
+use sage_api::{AddressKind, SyncPhaseRecord, SyncProgressRecord};
 use sage_database::{Asset, AssetKind};
+use sage_wallet::{PhaseProgress, SyncProgress};
 
 use crate::{Result, Sage};
 
 impl Sage {
     pub fn encode_asset(&self, asset: Asset) -> Result<sage_api::Asset> {
+        let prefix = self.network()?.prefix();
+
         Ok(sage_api::Asset {
             asset_id: encode_asset_id(asset.hash, asset.kind)?,
             name: asset.name,
@@ -21,7 +24,7 @@ impl Sage {
             is_visible: asset.is_visible,
             revocation_address: asset
                 .hidden_puzzle_hash
-                .map(|puzzle_hash| Address::new(puzzle_hash, self.network().prefix()).encode())
+                .map(|puzzle_hash| Address::new(puzzle_hash, prefix).encode())
                 .transpose()?,
             kind: encode_asset_kind(asset.kind),
         })
@@ -67,3 +70,21 @@ pub fn encode_asset_kind(kind: AssetKind) -> sage_api::AssetKind {
         AssetKind::Option => sage_api::AssetKind::Option,
     }
 }
+
+pub fn encode_sync_progress(progress: SyncProgress) -> SyncProgressRecord {
+    SyncProgressRecord {
+        derivations: encode_sync_phase(progress.derivations),
+        coin_states: progress.coin_states,
+        puzzles: encode_sync_phase(progress.puzzles),
+        nft_uris: encode_sync_phase(progress.nft_uris),
+        block_timestamps: encode_sync_phase(progress.block_timestamps),
+        eta_seconds: progress.eta.map(|eta| eta.as_secs()),
+    }
+}
+
+fn encode_sync_phase(phase: PhaseProgress) -> SyncPhaseRecord {
+    SyncPhaseRecord {
+        completed: phase.completed,
+        total: phase.total,
+    }
+}
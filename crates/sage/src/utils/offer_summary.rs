@@ -11,6 +11,7 @@ use crate::ConfirmationInfo;
 use crate::StatusCoinType;
 use crate::utils::offer_status::offer_expiration;
 use crate::{Error, Result, Sage};
+use crate::{OfferRoyalties, offer_royalties};
 
 impl Sage {
     pub(crate) async fn summarize_offer(
@@ -79,9 +80,10 @@ impl Sage {
                 nft_royalty: Some(NftRoyalty {
                     royalty_address: Address::new(
                         nft.info.royalty_puzzle_hash,
-                        self.network().prefix(),
+                        self.network()?.prefix(),
                     )
                     .encode()?,
+                    royalty_puzzle_hash: hex::encode(nft.info.royalty_puzzle_hash),
                     royalty_basis_points: nft.info.royalty_basis_points,
                 }),
                 option_assets: None,
@@ -166,8 +168,12 @@ impl Sage {
                 royalty: Amount::u64(0),
                 asset: self.encode_asset(asset)?,
                 nft_royalty: Some(NftRoyalty {
-                    royalty_address: Address::new(nft.royalty_puzzle_hash, self.network().prefix())
-                        .encode()?,
+                    royalty_address: Address::new(
+                        nft.royalty_puzzle_hash,
+                        self.network()?.prefix(),
+                    )
+                    .encode()?,
+                    royalty_puzzle_hash: hex::encode(nft.royalty_puzzle_hash),
                     royalty_basis_points: nft.royalty_basis_points,
                 }),
                 option_assets: None,
@@ -201,12 +207,21 @@ impl Sage {
             });
         }
 
+        let OfferRoyalties {
+            royalties,
+            maker_royalties,
+            taker_royalties,
+        } = offer_royalties(&maker, &taker);
+
         let summary = OfferSummary {
             fee: Amount::u64(offer.offered_coins().fee),
             maker,
             taker,
             expiration_height: status.expiration_height,
             expiration_timestamp: status.expiration_timestamp,
+            royalties,
+            maker_royalties,
+            taker_royalties,
         };
 
         let mut offer_status = OfferStatus::Active;
@@ -0,0 +1,337 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chia_wallet_sdk::prelude::*;
+use itertools::Itertools;
+use sage_api::{OfferIssue, OfferIssueKind, ValidateOfferResponse};
+
+use crate::{
+    Error, MAX_TRANSACTION_COST, Result, Sage, analyze_spend, spend_bundle_cost, verify_signature,
+};
+
+/// How close to an expiration timestamp an offer needs to be before it's flagged.
+const EXPIRY_WARNING_SECONDS: u64 = 600;
+
+/// How close to an expiration height an offer needs to be before it's flagged.
+const EXPIRY_WARNING_BLOCKS: u64 = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bound {
+    After,
+    Before,
+}
+
+impl Sage {
+    pub(crate) async fn validate_offer_bundle(
+        &self,
+        spend_bundle: SpendBundle,
+    ) -> Result<ValidateOfferResponse> {
+        // Requested payments are encoded as spends of placeholder coins, which can't be checked
+        let coin_spends = spend_bundle
+            .coin_spends
+            .iter()
+            .filter(|cs| cs.coin.parent_coin_info != Bytes32::default())
+            .cloned()
+            .collect_vec();
+
+        let peer_state = self.peer_state.lock().await;
+        let peak_height = peer_state.peak().map(|(height, _)| height);
+        let peer = peer_state.acquire_peer();
+        drop(peer_state);
+
+        // Validating against the genesis rules would give misleading results
+        let Some(height) = peak_height else {
+            return Err(Error::NotSynced);
+        };
+
+        let constants = self.consensus_constants()?;
+
+        let mut allocator = Allocator::new();
+        let mut issues = Vec::new();
+        let mut spend_cost = 0;
+        let mut created_coin_ids = HashSet::new();
+        let mut assertions = Vec::new();
+
+        for coin_spend in &coin_spends {
+            let coin_id = coin_spend.coin.coin_id();
+
+            let analysis = match analyze_spend(&mut allocator, coin_spend) {
+                Ok(analysis) => analysis,
+                Err(error) => {
+                    issues.push(OfferIssue {
+                        kind: OfferIssueKind::ClvmError,
+                        coin_id: Some(hex::encode(coin_id)),
+                        message: format!("Spend failed to run: {error}"),
+                    });
+                    continue;
+                }
+            };
+
+            spend_cost += analysis.cost;
+
+            for condition in analysis.conditions {
+                match condition {
+                    Condition::CreateCoin(create_coin) => {
+                        created_coin_ids.insert(
+                            Coin::new(coin_id, create_coin.puzzle_hash, create_coin.amount)
+                                .coin_id(),
+                        );
+                    }
+                    Condition::AssertHeightAbsolute(..)
+                    | Condition::AssertHeightRelative(..)
+                    | Condition::AssertSecondsAbsolute(..)
+                    | Condition::AssertSecondsRelative(..)
+                    | Condition::AssertBeforeHeightAbsolute(..)
+                    | Condition::AssertBeforeHeightRelative(..)
+                    | Condition::AssertBeforeSecondsAbsolute(..)
+                    | Condition::AssertBeforeSecondsRelative(..) => {
+                        assertions.push((coin_id, condition));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // The placeholder spends are kept here, since the offered spends assert the
+        // announcements created by the settlement payments
+        let cost = match spend_bundle_cost(&spend_bundle, height, &constants) {
+            Ok(cost) => cost,
+            Err(error) => {
+                issues.push(OfferIssue {
+                    kind: OfferIssueKind::ClvmError,
+                    coin_id: None,
+                    message: format!("Offer failed to run: {:?}", error.1),
+                });
+                spend_cost
+            }
+        };
+
+        if cost > MAX_TRANSACTION_COST {
+            issues.push(OfferIssue {
+                kind: OfferIssueKind::CostExceeded,
+                coin_id: None,
+                message: format!(
+                    "Offer costs {cost}, which exceeds the maximum transaction cost of {MAX_TRANSACTION_COST}"
+                ),
+            });
+        }
+
+        match verify_signature(
+            &mut allocator,
+            &coin_spends,
+            &spend_bundle.aggregated_signature,
+            self.network()?.agg_sig_me(),
+        ) {
+            Ok(Some(true) | None) => {}
+            Ok(Some(false)) => {
+                issues.push(OfferIssue {
+                    kind: OfferIssueKind::InvalidSignature,
+                    coin_id: None,
+                    message: "Aggregated signature does not match the offered spends".to_string(),
+                });
+            }
+            Err(error) => {
+                issues.push(OfferIssue {
+                    kind: OfferIssueKind::ClvmError,
+                    coin_id: None,
+                    message: format!("Could not determine required signatures: {error}"),
+                });
+            }
+        }
+
+        let mut coin_states = HashMap::new();
+
+        if let Some(peer) = &peer {
+            let coin_ids = coin_spends
+                .iter()
+                .map(|cs| cs.coin.coin_id())
+                .filter(|coin_id| !created_coin_ids.contains(coin_id))
+                .collect_vec();
+
+            match peer
+                .fetch_coins(coin_ids.clone(), self.network()?.genesis_challenge)
+                .await
+            {
+                Ok(fetched) => {
+                    for coin_state in fetched {
+                        coin_states.insert(coin_state.coin.coin_id(), coin_state);
+                    }
+
+                    for coin_id in coin_ids {
+                        match coin_states.get(&coin_id) {
+                            None => issues.push(OfferIssue {
+                                kind: OfferIssueKind::CoinNotFound,
+                                coin_id: Some(hex::encode(coin_id)),
+                                message: "Offered coin does not exist on chain".to_string(),
+                            }),
+                            Some(coin_state) => {
+                                if let Some(spent_height) = coin_state.spent_height {
+                                    issues.push(OfferIssue {
+                                        kind: OfferIssueKind::CoinSpent,
+                                        coin_id: Some(hex::encode(coin_id)),
+                                        message: format!(
+                                            "Offered coin was already spent at height {spent_height}"
+                                        ),
+                                    });
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(error) => issues.push(OfferIssue {
+                    kind: OfferIssueKind::PeerError,
+                    coin_id: None,
+                    message: format!("Could not fetch the offered coins from a peer: {error}"),
+                }),
+            }
+        } else {
+            issues.push(OfferIssue {
+                kind: OfferIssueKind::NoPeers,
+                coin_id: None,
+                message: "No peers are available to check the offered coins".to_string(),
+            });
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let mut created_timestamps = HashMap::new();
+
+        for (coin_id, condition) in assertions {
+            let created_height = coin_states
+                .get(&coin_id)
+                .and_then(|coin_state| coin_state.created_height);
+
+            let created_timestamp = if let Some(created_height) = created_height
+                && let Some(peer) = &peer
+                && matches!(
+                    condition,
+                    Condition::AssertSecondsRelative(..)
+                        | Condition::AssertBeforeSecondsRelative(..)
+                ) {
+                if let Some(&timestamp) = created_timestamps.get(&created_height) {
+                    Some(timestamp)
+                } else {
+                    match peer.block_timestamp(created_height).await {
+                        Ok((_, timestamp)) => {
+                            created_timestamps.insert(created_height, timestamp);
+                            Some(timestamp)
+                        }
+                        Err(error) => {
+                            issues.push(OfferIssue {
+                                kind: OfferIssueKind::PeerError,
+                                coin_id: Some(hex::encode(coin_id)),
+                                message: format!(
+                                    "Could not fetch the timestamp of block {created_height}: {error}"
+                                ),
+                            });
+                            None
+                        }
+                    }
+                }
+            } else {
+                None
+            };
+
+            let peak = peak_height.map(u64::from);
+            let created = created_height.map(u64::from);
+
+            let (bound, required, current, margin, unit) = match condition {
+                Condition::AssertHeightAbsolute(cond) => (
+                    Bound::After,
+                    Some(u64::from(cond.height)),
+                    peak,
+                    0,
+                    "height",
+                ),
+                Condition::AssertHeightRelative(cond) => (
+                    Bound::After,
+                    created.map(|created| created + u64::from(cond.height)),
+                    peak,
+                    0,
+                    "height",
+                ),
+                Condition::AssertSecondsAbsolute(cond) => {
+                    (Bound::After, Some(cond.seconds), Some(now), 0, "timestamp")
+                }
+                Condition::AssertSecondsRelative(cond) => (
+                    Bound::After,
+                    created_timestamp.map(|created| created + cond.seconds),
+                    Some(now),
+                    0,
+                    "timestamp",
+                ),
+                Condition::AssertBeforeHeightAbsolute(cond) => (
+                    Bound::Before,
+                    Some(u64::from(cond.height)),
+                    peak,
+                    EXPIRY_WARNING_BLOCKS,
+                    "height",
+                ),
+                Condition::AssertBeforeHeightRelative(cond) => (
+                    Bound::Before,
+                    created.map(|created| created + u64::from(cond.height)),
+                    peak,
+                    EXPIRY_WARNING_BLOCKS,
+                    "height",
+                ),
+                Condition::AssertBeforeSecondsAbsolute(cond) => (
+                    Bound::Before,
+                    Some(cond.seconds),
+                    Some(now),
+                    EXPIRY_WARNING_SECONDS,
+                    "timestamp",
+                ),
+                Condition::AssertBeforeSecondsRelative(cond) => (
+                    Bound::Before,
+                    created_timestamp.map(|created| created + cond.seconds),
+                    Some(now),
+                    EXPIRY_WARNING_SECONDS,
+                    "timestamp",
+                ),
+                _ => continue,
+            };
+
+            let (Some(required), Some(current)) = (required, current) else {
+                continue;
+            };
+
+            let issue = match bound {
+                Bound::After if current < required => Some((
+                    OfferIssueKind::NotYetValid,
+                    format!("Offer can't be taken until {unit} {required} (currently {current})"),
+                )),
+                Bound::Before if current >= required => Some((
+                    OfferIssueKind::Expired,
+                    format!("Offer expired at {unit} {required} (currently {current})"),
+                )),
+                Bound::Before if required - current <= margin => Some((
+                    OfferIssueKind::ExpiresSoon,
+                    format!("Offer expires at {unit} {required} (currently {current})"),
+                )),
+                Bound::After | Bound::Before => None,
+            };
+
+            if let Some((kind, message)) = issue {
+                issues.push(OfferIssue {
+                    kind,
+                    coin_id: Some(hex::encode(coin_id)),
+                    message,
+                });
+            }
+        }
+
+        Ok(ValidateOfferResponse {
+            valid: issues
+                .iter()
+                .all(|issue| issue.kind == OfferIssueKind::ExpiresSoon),
+            cost,
+            peak_height,
+            issues,
+        })
+    }
+}
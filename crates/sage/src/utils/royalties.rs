@@ -0,0 +1,152 @@
+use sage_api::{Amount, AssetKind, NftRoyaltyBreakdown, OfferAsset, RoyaltyAmount};
+
+#[derive(Debug, Clone)]
+pub struct OfferRoyalties {
+    pub royalties: Vec<NftRoyaltyBreakdown>,
+    pub maker_royalties: Vec<RoyaltyAmount>,
+    pub taker_royalties: Vec<RoyaltyAmount>,
+}
+
+/// Breaks down the royalties owed to the creator of each NFT in an offer. Royalties for offered
+/// NFTs are paid by the taker out of the requested tokens, and royalties for requested NFTs are
+/// paid by the maker out of the offered tokens. The trade price is split evenly between the NFTs
+/// on the same side of the offer, the same way the royalty payments themselves are calculated.
+pub fn offer_royalties(maker: &[OfferAsset], taker: &[OfferAsset]) -> OfferRoyalties {
+    let mut royalties = nft_royalties(maker, taker, false);
+    royalties.extend(nft_royalties(taker, maker, true));
+
+    OfferRoyalties {
+        royalties,
+        maker_royalties: royalty_totals(maker),
+        taker_royalties: royalty_totals(taker),
+    }
+}
+
+fn nft_royalties(
+    nfts: &[OfferAsset],
+    payments: &[OfferAsset],
+    paid_by_maker: bool,
+) -> Vec<NftRoyaltyBreakdown> {
+    let nfts: Vec<_> = nfts
+        .iter()
+        .filter_map(|asset| Some((asset, asset.nft_royalty.as_ref()?)))
+        .collect();
+
+    let nft_count = nfts.len() as u64;
+
+    nfts.into_iter()
+        .map(|(nft, royalty)| NftRoyaltyBreakdown {
+            nft: nft.asset.clone(),
+            royalty_address: royalty.royalty_address.clone(),
+            royalty_puzzle_hash: royalty.royalty_puzzle_hash.clone(),
+            royalty_basis_points: royalty.royalty_basis_points,
+            paid_by_maker,
+            payments: payments
+                .iter()
+                .filter(|payment| payment.asset.kind == AssetKind::Token)
+                .filter_map(|payment| {
+                    let trade_price = payment.amount.to_u64()? / nft_count;
+                    let amount =
+                        u128::from(trade_price) * u128::from(royalty.royalty_basis_points) / 10_000;
+
+                    (amount > 0).then(|| RoyaltyAmount {
+                        asset: payment.asset.clone(),
+                        amount: Amount::u128(amount),
+                    })
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+fn royalty_totals(assets: &[OfferAsset]) -> Vec<RoyaltyAmount> {
+    assets
+        .iter()
+        .filter(|asset| asset.royalty.to_u64().is_some_and(|royalty| royalty > 0))
+        .map(|asset| RoyaltyAmount {
+            asset: asset.asset.clone(),
+            amount: asset.royalty.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use sage_api::{Asset, NftRoyalty};
+
+    use super::*;
+
+    fn asset(kind: AssetKind, asset_id: Option<&str>) -> Asset {
+        Asset {
+            asset_id: asset_id.map(ToString::to_string),
+            name: None,
+            ticker: None,
+            precision: if kind == AssetKind::Token { 12 } else { 0 },
+            icon_url: None,
+            description: None,
+            is_sensitive_content: false,
+            is_visible: true,
+            revocation_address: None,
+            kind,
+        }
+    }
+
+    fn token(amount: u64, royalty: u64) -> OfferAsset {
+        OfferAsset {
+            asset: asset(AssetKind::Token, None),
+            amount: Amount::u64(amount),
+            royalty: Amount::u64(royalty),
+            nft_royalty: None,
+            option_assets: None,
+        }
+    }
+
+    fn nft(id: &str, royalty_basis_points: u16) -> OfferAsset {
+        OfferAsset {
+            asset: asset(AssetKind::Nft, Some(id)),
+            amount: Amount::u64(1),
+            royalty: Amount::u64(0),
+            nft_royalty: Some(NftRoyalty {
+                royalty_address: format!("xch1{id}"),
+                royalty_puzzle_hash: id.to_string(),
+                royalty_basis_points,
+            }),
+            option_assets: None,
+        }
+    }
+
+    #[test]
+    fn test_offered_nft_royalties() {
+        let maker = [nft("a", 300), nft("b", 0)];
+        let taker = [token(1_000_001, 15_000)];
+
+        let royalties = offer_royalties(&maker, &taker);
+
+        assert_eq!(royalties.royalties.len(), 2);
+        assert!(!royalties.royalties[0].paid_by_maker);
+        assert_eq!(royalties.royalties[0].payments.len(), 1);
+        assert_eq!(
+            royalties.royalties[0].payments[0].amount,
+            Amount::u64(15_000)
+        );
+        assert!(royalties.royalties[1].payments.is_empty());
+
+        assert!(royalties.maker_royalties.is_empty());
+        assert_eq!(royalties.taker_royalties.len(), 1);
+        assert_eq!(royalties.taker_royalties[0].amount, Amount::u64(15_000));
+    }
+
+    #[test]
+    fn test_requested_nft_royalties() {
+        let maker = [token(500, 25)];
+        let taker = [nft("a", 500)];
+
+        let royalties = offer_royalties(&maker, &taker);
+
+        assert_eq!(royalties.royalties.len(), 1);
+        assert!(royalties.royalties[0].paid_by_maker);
+        assert_eq!(royalties.royalties[0].payments[0].amount, Amount::u64(25));
+        assert_eq!(royalties.maker_royalties[0].amount, Amount::u64(25));
+        assert!(royalties.taker_royalties.is_empty());
+    }
+}
@@ -0,0 +1,208 @@
+use chia_wallet_sdk::{
+    chia::{
+        bls::aggregate_verify,
+        consensus::{
+            consensus_constants::ConsensusConstants, owned_conditions::OwnedSpendBundleConditions,
+            spendbundle_conditions::get_conditions_from_spendbundle,
+            validation_error::ValidationErr,
+        },
+    },
+    clvmr::{ChiaDialect, MEMPOOL_MODE, reduction::Reduction, run_program},
+    prelude::*,
+};
+use sage_api::ConditionJson;
+use sage_wallet::WalletError;
+
+use crate::Result;
+
+/// The maximum cost of all spends in a block.
+pub const MAX_BLOCK_COST_CLVM: u64 = 11_000_000_000;
+
+/// The maximum cost the mempool will accept for a single spend bundle.
+pub const MAX_TRANSACTION_COST: u64 = MAX_BLOCK_COST_CLVM / 2;
+
+const COST_PER_BYTE: u64 = 12_000;
+const CREATE_COIN_COST: u64 = 1_800_000;
+const AGG_SIG_COST: u64 = 1_200_000;
+
+#[derive(Debug, Clone)]
+pub struct SpendAnalysis {
+    pub output: NodePtr,
+    pub conditions: Vec<Condition<NodePtr>>,
+    pub cost: u64,
+}
+
+/// Runs a coin spend in mempool mode and estimates the cost it would add to a block,
+/// including the execution cost, the size of the spend, and the cost of its conditions.
+pub fn analyze_spend(allocator: &mut Allocator, coin_spend: &CoinSpend) -> Result<SpendAnalysis> {
+    let puzzle = coin_spend.puzzle_reveal.to_clvm(allocator)?;
+    let solution = coin_spend.solution.to_clvm(allocator)?;
+
+    let Reduction(execution_cost, output) = run_program(
+        allocator,
+        &ChiaDialect::new(MEMPOOL_MODE),
+        puzzle,
+        solution,
+        MAX_BLOCK_COST_CLVM,
+    )?;
+
+    let conditions = Vec::<Condition<NodePtr>>::from_clvm(allocator, output)?;
+
+    let size = coin_spend.puzzle_reveal.clone().into_bytes().len()
+        + coin_spend.solution.clone().into_bytes().len();
+
+    let mut cost = execution_cost + size as u64 * COST_PER_BYTE;
+
+    for condition in &conditions {
+        match condition {
+            Condition::CreateCoin(..) => cost += CREATE_COIN_COST,
+            Condition::AggSigParent(..)
+            | Condition::AggSigPuzzle(..)
+            | Condition::AggSigAmount(..)
+            | Condition::AggSigPuzzleAmount(..)
+            | Condition::AggSigParentAmount(..)
+            | Condition::AggSigParentPuzzle(..)
+            | Condition::AggSigUnsafe(..)
+            | Condition::AggSigMe(..) => cost += AGG_SIG_COST,
+            _ => {}
+        }
+    }
+
+    Ok(SpendAnalysis {
+        output,
+        conditions,
+        cost,
+    })
+}
+
+/// Runs the spend bundle the same way the mempool does and returns its parsed conditions.
+/// Unlike [`analyze_spend`], this fails if the spends assert announcements, concurrent spends
+/// or ephemeral coins that aren't part of the bundle, spend a coin twice, or create more
+/// value than they spend. Timelocks are returned rather than checked, since they depend on
+/// the state of the chain.
+pub fn spend_bundle_conditions(
+    spend_bundle: &SpendBundle,
+    height: u32,
+    constants: &ConsensusConstants,
+) -> std::result::Result<OwnedSpendBundleConditions, ValidationErr> {
+    let mut allocator = Allocator::new();
+
+    get_conditions_from_spendbundle(
+        &mut allocator,
+        spend_bundle,
+        MAX_BLOCK_COST_CLVM,
+        height,
+        constants,
+    )
+}
+
+/// Runs the spend bundle the same way the mempool does and returns its total cost.
+pub fn spend_bundle_cost(
+    spend_bundle: &SpendBundle,
+    height: u32,
+    constants: &ConsensusConstants,
+) -> std::result::Result<u64, ValidationErr> {
+    Ok(spend_bundle_conditions(spend_bundle, height, constants)?.cost)
+}
+
+/// Verifies an aggregated signature against the BLS signatures required by the coin spends.
+/// Returns `None` if some of the required signatures can't be verified locally.
+pub fn verify_signature(
+    allocator: &mut Allocator,
+    coin_spends: &[CoinSpend],
+    signature: &Signature,
+    agg_sig_me: Bytes32,
+) -> Result<Option<bool>> {
+    let required_signatures = RequiredSignature::from_coin_spends(
+        allocator,
+        coin_spends,
+        &AggSigConstants::new(agg_sig_me),
+    )
+    .map_err(WalletError::from)?;
+
+    let mut pairs = Vec::new();
+
+    for required in required_signatures {
+        let RequiredSignature::Bls(required) = required else {
+            return Ok(None);
+        };
+
+        pairs.push((required.public_key, required.message()));
+    }
+
+    Ok(Some(aggregate_verify(signature, pairs)))
+}
+
+/// Converts the raw output of a puzzle into a list of conditions with their arguments.
+pub fn condition_list(allocator: &Allocator, output: NodePtr) -> Result<Vec<ConditionJson>> {
+    let mut conditions = Vec::new();
+
+    for condition in Vec::<NodePtr>::from_clvm(allocator, output)? {
+        if let Some(condition) = condition_json(allocator, condition)? {
+            conditions.push(condition);
+        }
+    }
+
+    Ok(conditions)
+}
+
+/// Converts a single raw condition into its opcode and arguments.
+/// Returns `None` if the condition is an empty list.
+pub fn condition_json(allocator: &Allocator, condition: NodePtr) -> Result<Option<ConditionJson>> {
+    let mut args = Vec::<NodePtr>::from_clvm(allocator, condition)?.into_iter();
+
+    let Some(opcode) = args.next() else {
+        return Ok(None);
+    };
+
+    let opcode = u16::from_clvm(allocator, opcode)?;
+
+    Ok(Some(ConditionJson {
+        opcode,
+        name: condition_name(opcode).map(ToString::to_string),
+        args: args
+            .map(|arg| Ok(hex::encode(Program::from_clvm(allocator, arg)?)))
+            .collect::<Result<_>>()?,
+    }))
+}
+
+pub fn condition_name(opcode: u16) -> Option<&'static str> {
+    Some(match opcode {
+        1 => "REMARK",
+        43 => "AGG_SIG_PARENT",
+        44 => "AGG_SIG_PUZZLE",
+        45 => "AGG_SIG_AMOUNT",
+        46 => "AGG_SIG_PUZZLE_AMOUNT",
+        47 => "AGG_SIG_PARENT_AMOUNT",
+        48 => "AGG_SIG_PARENT_PUZZLE",
+        49 => "AGG_SIG_UNSAFE",
+        50 => "AGG_SIG_ME",
+        51 => "CREATE_COIN",
+        52 => "RESERVE_FEE",
+        60 => "CREATE_COIN_ANNOUNCEMENT",
+        61 => "ASSERT_COIN_ANNOUNCEMENT",
+        62 => "CREATE_PUZZLE_ANNOUNCEMENT",
+        63 => "ASSERT_PUZZLE_ANNOUNCEMENT",
+        64 => "ASSERT_CONCURRENT_SPEND",
+        65 => "ASSERT_CONCURRENT_PUZZLE",
+        66 => "SEND_MESSAGE",
+        67 => "RECEIVE_MESSAGE",
+        70 => "ASSERT_MY_COIN_ID",
+        71 => "ASSERT_MY_PARENT_ID",
+        72 => "ASSERT_MY_PUZZLEHASH",
+        73 => "ASSERT_MY_AMOUNT",
+        74 => "ASSERT_MY_BIRTH_SECONDS",
+        75 => "ASSERT_MY_BIRTH_HEIGHT",
+        76 => "ASSERT_EPHEMERAL",
+        80 => "ASSERT_SECONDS_RELATIVE",
+        81 => "ASSERT_SECONDS_ABSOLUTE",
+        82 => "ASSERT_HEIGHT_RELATIVE",
+        83 => "ASSERT_HEIGHT_ABSOLUTE",
+        84 => "ASSERT_BEFORE_SECONDS_RELATIVE",
+        85 => "ASSERT_BEFORE_SECONDS_ABSOLUTE",
+        86 => "ASSERT_BEFORE_HEIGHT_RELATIVE",
+        87 => "ASSERT_BEFORE_HEIGHT_ABSOLUTE",
+        90 => "SOFTFORK",
+        _ => return None,
+    })
+}
@@ -0,0 +1,421 @@
+use std::collections::HashMap;
+
+use chia_wallet_sdk::{
+    driver::{CatLayer, RevocationLayer, SingletonLayer, StandardLayer},
+    prelude::*,
+    puzzles::{SETTLEMENT_PAYMENT_HASH, SINGLETON_LAUNCHER_HASH},
+};
+use sage_api::{
+    Amount, CoinJson, ConditionJson, DecodedCoinKind, DecodedCondition, DecodedOutput,
+    DecodedSpend, PuzzleLayer,
+};
+use sage_database::P2Puzzle;
+use sage_wallet::{ChildKind, CoinKind};
+
+use crate::{Result, Sage, analyze_spend, condition_json};
+
+impl Sage {
+    /// Walks the layers of a coin spend's puzzle and explains each condition it outputs.
+    /// This works for arbitrary spends, but clawback puzzles of spent coins can only be
+    /// recognized if they belong to the current wallet.
+    pub(crate) async fn decode_coin_spend(&self, coin_spend: &CoinSpend) -> Result<DecodedSpend> {
+        let coin = coin_spend.coin;
+
+        let mut allocator = Allocator::new();
+        let puzzle_ptr = coin_spend.puzzle_reveal.to_clvm(&mut allocator)?;
+        let puzzle = Puzzle::parse(&allocator, puzzle_ptr);
+
+        let (kind, asset_id) = match CoinKind::from_puzzle_cached(&allocator, puzzle)? {
+            CoinKind::Unknown => (DecodedCoinKind::Unknown, None),
+            CoinKind::Launcher => (DecodedCoinKind::Launcher, None),
+            CoinKind::Cat { info } => (DecodedCoinKind::Cat, Some(info.asset_id)),
+            CoinKind::Did { info } => (DecodedCoinKind::Did, Some(info.launcher_id)),
+            CoinKind::Nft { info, .. } => (DecodedCoinKind::Nft, Some(info.launcher_id)),
+            CoinKind::Option { info } => (DecodedCoinKind::Option, Some(info.launcher_id)),
+        };
+
+        let (mut layers, p2_puzzle) = puzzle_layers(&allocator, puzzle);
+
+        if let Some(p2_puzzle) = p2_puzzle {
+            let layer = match p2_layer(&allocator, p2_puzzle) {
+                PuzzleLayer::Unknown if self.is_clawback(p2_puzzle).await? => PuzzleLayer::Clawback,
+                layer => layer,
+            };
+
+            layers.push(layer);
+        }
+
+        let mut decoded = DecodedSpend {
+            coin_id: hex::encode(coin.coin_id()),
+            coin: CoinJson {
+                parent_coin_info: hex::encode(coin.parent_coin_info),
+                puzzle_hash: hex::encode(coin.puzzle_hash),
+                amount: Amount::u64(coin.amount),
+            },
+            kind,
+            asset_id: asset_id.map(hex::encode),
+            layers,
+            p2_puzzle_hash: p2_puzzle
+                .map(|p2_puzzle| hex::encode(Bytes32::from(p2_puzzle.curried_puzzle_hash()))),
+            conditions: Vec::new(),
+            error: None,
+        };
+
+        let analysis = match analyze_spend(&mut allocator, coin_spend) {
+            Ok(analysis) => analysis,
+            Err(error) => {
+                decoded.error = Some(format!("Spend failed to run: {error}"));
+                return Ok(decoded);
+            }
+        };
+
+        let children: HashMap<Bytes32, ChildKind> =
+            ChildKind::parse_children(coin, &coin_spend.puzzle_reveal, &coin_spend.solution)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(coin, kind)| (coin.coin_id(), kind))
+                .collect();
+
+        let Ok(conditions) = Vec::<NodePtr>::from_clvm(&allocator, analysis.output) else {
+            decoded.error = Some("Spend output is not a list of conditions".to_string());
+            return Ok(decoded);
+        };
+
+        for condition in conditions {
+            let json = match condition_json(&allocator, condition) {
+                Ok(Some(json)) => json,
+                Ok(None) => continue,
+                Err(error) => {
+                    // The opcode itself couldn't be read, so the raw condition is kept instead
+                    decoded.conditions.push(DecodedCondition {
+                        description: format!("Malformed condition: {error}"),
+                        condition: ConditionJson {
+                            opcode: 0,
+                            name: None,
+                            args: vec![hex::encode(Program::from_clvm(&allocator, condition)?)],
+                        },
+                        output: None,
+                    });
+                    continue;
+                }
+            };
+
+            let Ok(condition) = Condition::<NodePtr>::from_clvm(&allocator, condition) else {
+                decoded.conditions.push(DecodedCondition {
+                    description: format!("Malformed condition with opcode {}", json.opcode),
+                    condition: json,
+                    output: None,
+                });
+                continue;
+            };
+
+            let output = if let Condition::CreateCoin(create_coin) = &condition {
+                let child = Coin::new(coin.coin_id(), create_coin.puzzle_hash, create_coin.amount);
+                let kind = children.get(&child.coin_id()).cloned();
+                Some(self.decoded_output(&allocator, child, create_coin, kind)?)
+            } else {
+                None
+            };
+
+            decoded.conditions.push(DecodedCondition {
+                description: describe_condition(&condition, output.as_ref()),
+                condition: json,
+                output,
+            });
+        }
+
+        Ok(decoded)
+    }
+
+    async fn is_clawback(&self, p2_puzzle: Puzzle) -> Result<bool> {
+        let Ok(wallet) = self.wallet() else {
+            return Ok(false);
+        };
+
+        let puzzle_hash = p2_puzzle.curried_puzzle_hash().into();
+
+        if !wallet.db.is_p2_puzzle_hash(puzzle_hash).await? {
+            return Ok(false);
+        }
+
+        Ok(matches!(
+            wallet.db.p2_puzzle(puzzle_hash).await?,
+            P2Puzzle::Clawback(..)
+        ))
+    }
+
+    fn decoded_output(
+        &self,
+        allocator: &Allocator,
+        coin: Coin,
+        create_coin: &CreateCoin<NodePtr>,
+        child_kind: Option<ChildKind>,
+    ) -> Result<DecodedOutput> {
+        let hint = if let Memos::Some(memos) = create_coin.memos
+            && let Ok((hint, _)) = <(Bytes32, NodePtr)>::from_clvm(allocator, memos)
+        {
+            Some(hint)
+        } else {
+            None
+        };
+
+        let (kind, asset_id, clawback) = match &child_kind {
+            None | Some(ChildKind::Unknown) => (DecodedCoinKind::Unknown, None, None),
+            Some(ChildKind::Launcher) => (DecodedCoinKind::Launcher, None, None),
+            Some(ChildKind::Clawback { info }) => (DecodedCoinKind::Clawback, None, Some(*info)),
+            Some(ChildKind::Cat { info, clawback, .. }) => {
+                (DecodedCoinKind::Cat, Some(info.asset_id), *clawback)
+            }
+            Some(ChildKind::Did { info, clawback, .. }) => {
+                (DecodedCoinKind::Did, Some(info.launcher_id), *clawback)
+            }
+            Some(ChildKind::Nft { info, clawback, .. }) => {
+                (DecodedCoinKind::Nft, Some(info.launcher_id), *clawback)
+            }
+            Some(ChildKind::Option { info, clawback, .. }) => {
+                (DecodedCoinKind::Option, Some(info.launcher_id), *clawback)
+            }
+        };
+
+        // Outer puzzles wrap the recipient's puzzle, so the puzzle hash alone isn't an address
+        let recipient = child_kind
+            .as_ref()
+            .and_then(ChildKind::receiver_custody_p2_puzzle_hash)
+            .or(matches!(kind, DecodedCoinKind::Unknown).then_some(create_coin.puzzle_hash));
+
+        let prefix = self.network()?.prefix();
+
+        Ok(DecodedOutput {
+            coin_id: hex::encode(coin.coin_id()),
+            amount: Amount::u64(coin.amount),
+            puzzle_hash: hex::encode(coin.puzzle_hash),
+            kind,
+            asset_id: asset_id.map(hex::encode),
+            address: recipient
+                .map(|puzzle_hash| Address::new(puzzle_hash, prefix.clone()).encode())
+                .transpose()?,
+            clawback_address: clawback
+                .map(|clawback| Address::new(clawback.sender_puzzle_hash, prefix.clone()).encode())
+                .transpose()?,
+            clawback_timestamp: clawback.map(|clawback| clawback.seconds),
+            hint: hint.map(hex::encode),
+        })
+    }
+}
+
+fn describe_condition(condition: &Condition<NodePtr>, output: Option<&DecodedOutput>) -> String {
+    match condition {
+        Condition::CreateCoin(create_coin) => {
+            let asset = match output.map(|output| output.kind) {
+                Some(DecodedCoinKind::Cat) => "a CAT coin",
+                Some(DecodedCoinKind::Did) => "a DID",
+                Some(DecodedCoinKind::Nft) => "an NFT",
+                Some(DecodedCoinKind::Option) => "an option contract",
+                Some(DecodedCoinKind::Launcher) => "a singleton launcher",
+                Some(DecodedCoinKind::Clawback) => "a clawback coin",
+                Some(DecodedCoinKind::Unknown) | None => "a coin",
+            };
+
+            let recipient = if create_coin.puzzle_hash == SETTLEMENT_PAYMENT_HASH.into() {
+                "the offer settlement puzzle".to_string()
+            } else if let Some(address) = output.and_then(|output| output.address.as_ref()) {
+                address.clone()
+            } else {
+                format!("puzzle hash {}", hex::encode(create_coin.puzzle_hash))
+            };
+
+            let mut description = format!(
+                "Creates {asset} worth {} mojos for {recipient}",
+                create_coin.amount
+            );
+
+            if let Some(output) = output
+                && let (Some(sender), Some(seconds)) =
+                    (&output.clawback_address, output.clawback_timestamp)
+            {
+                description.push_str(&format!(
+                    ", which {sender} can claw back until timestamp {seconds}"
+                ));
+            }
+
+            description
+        }
+        Condition::ReserveFee(cond) => {
+            format!("Reserves a fee of {} mojos", cond.amount)
+        }
+        Condition::AggSigParent(cond) => agg_sig("the parent coin id", &cond.public_key),
+        Condition::AggSigPuzzle(cond) => agg_sig("the puzzle hash", &cond.public_key),
+        Condition::AggSigAmount(cond) => agg_sig("the amount", &cond.public_key),
+        Condition::AggSigPuzzleAmount(cond) => {
+            agg_sig("the puzzle hash and amount", &cond.public_key)
+        }
+        Condition::AggSigParentAmount(cond) => {
+            agg_sig("the parent coin id and amount", &cond.public_key)
+        }
+        Condition::AggSigParentPuzzle(cond) => {
+            agg_sig("the parent coin id and puzzle hash", &cond.public_key)
+        }
+        Condition::AggSigMe(cond) => agg_sig("the coin id", &cond.public_key),
+        Condition::AggSigUnsafe(cond) => format!(
+            "Requires a signature from {} on a message that isn't bound to this coin",
+            hex::encode(cond.public_key.to_bytes())
+        ),
+        Condition::CreateCoinAnnouncement(cond) => {
+            format!("Announces {} from this coin", hex::encode(&cond.message))
+        }
+        Condition::AssertCoinAnnouncement(cond) => format!(
+            "Requires coin announcement {} in the same block",
+            hex::encode(cond.announcement_id)
+        ),
+        Condition::CreatePuzzleAnnouncement(cond) => {
+            format!("Announces {} from this puzzle", hex::encode(&cond.message))
+        }
+        Condition::AssertPuzzleAnnouncement(cond) => format!(
+            "Requires puzzle announcement {} in the same block",
+            hex::encode(cond.announcement_id)
+        ),
+        Condition::AssertConcurrentSpend(cond) => format!(
+            "Requires coin {} to be spent in the same block",
+            hex::encode(cond.coin_id)
+        ),
+        Condition::AssertConcurrentPuzzle(cond) => format!(
+            "Requires a coin with puzzle hash {} to be spent in the same block",
+            hex::encode(cond.puzzle_hash)
+        ),
+        Condition::SendMessage(cond) => format!(
+            "Sends message {} with mode {}",
+            hex::encode(&cond.message),
+            cond.mode
+        ),
+        Condition::ReceiveMessage(cond) => format!(
+            "Receives message {} with mode {}",
+            hex::encode(&cond.message),
+            cond.mode
+        ),
+        Condition::AssertMyCoinId(cond) => {
+            format!("Asserts that the coin id is {}", hex::encode(cond.coin_id))
+        }
+        Condition::AssertMyParentId(cond) => format!(
+            "Asserts that the parent coin id is {}",
+            hex::encode(cond.parent_id)
+        ),
+        Condition::AssertMyPuzzleHash(cond) => format!(
+            "Asserts that the puzzle hash is {}",
+            hex::encode(cond.puzzle_hash)
+        ),
+        Condition::AssertMyAmount(cond) => {
+            format!("Asserts that the amount is {} mojos", cond.amount)
+        }
+        Condition::AssertMyBirthSeconds(cond) => {
+            format!(
+                "Asserts that the coin was created at timestamp {}",
+                cond.seconds
+            )
+        }
+        Condition::AssertMyBirthHeight(cond) => {
+            format!(
+                "Asserts that the coin was created at height {}",
+                cond.height
+            )
+        }
+        Condition::AssertEphemeral(..) => {
+            "Requires the coin to be created in the same block".to_string()
+        }
+        Condition::AssertSecondsRelative(cond) => format!(
+            "Can't be spent until {} seconds after the coin was created",
+            cond.seconds
+        ),
+        Condition::AssertSecondsAbsolute(cond) => {
+            format!("Can't be spent until timestamp {}", cond.seconds)
+        }
+        Condition::AssertHeightRelative(cond) => format!(
+            "Can't be spent until {} blocks after the coin was created",
+            cond.height
+        ),
+        Condition::AssertHeightAbsolute(cond) => {
+            format!("Can't be spent until height {}", cond.height)
+        }
+        Condition::AssertBeforeSecondsRelative(cond) => format!(
+            "Expires {} seconds after the coin was created",
+            cond.seconds
+        ),
+        Condition::AssertBeforeSecondsAbsolute(cond) => {
+            format!("Expires at timestamp {}", cond.seconds)
+        }
+        Condition::AssertBeforeHeightRelative(cond) => {
+            format!("Expires {} blocks after the coin was created", cond.height)
+        }
+        Condition::AssertBeforeHeightAbsolute(cond) => {
+            format!("Expires at height {}", cond.height)
+        }
+        Condition::Remark(..) => "Remark with no effect on consensus".to_string(),
+        Condition::Softfork(..) => "Soft fork condition".to_string(),
+        _ => "Condition that is interpreted by an outer puzzle layer".to_string(),
+    }
+}
+
+fn agg_sig(message: &str, public_key: &PublicKey) -> String {
+    format!(
+        "Requires a signature from {} on a message bound to {message}",
+        hex::encode(public_key.to_bytes())
+    )
+}
+
+/// Identifies the outer layers of a puzzle, from outermost to innermost, and returns the
+/// innermost puzzle if it's revealed.
+fn puzzle_layers(allocator: &Allocator, puzzle: Puzzle) -> (Vec<PuzzleLayer>, Option<Puzzle>) {
+    if puzzle.curried_puzzle_hash() == SINGLETON_LAUNCHER_HASH.into() {
+        return (vec![PuzzleLayer::SingletonLauncher], None);
+    }
+
+    if let Ok(Some(cat)) = CatLayer::<Puzzle>::parse_puzzle(allocator, puzzle) {
+        // The revocation layer only commits to the hash of the inner puzzle
+        if let Ok(Some(..)) = RevocationLayer::parse_puzzle(allocator, cat.inner_puzzle) {
+            return (vec![PuzzleLayer::Cat, PuzzleLayer::Revocation], None);
+        }
+
+        return (vec![PuzzleLayer::Cat], Some(cat.inner_puzzle));
+    }
+
+    if let Ok(Some((_, p2_puzzle))) = NftInfo::parse(allocator, puzzle) {
+        return (
+            vec![
+                PuzzleLayer::Singleton,
+                PuzzleLayer::NftState,
+                PuzzleLayer::NftOwnership,
+            ],
+            Some(p2_puzzle),
+        );
+    }
+
+    if let Ok(Some((_, p2_puzzle))) = DidInfo::parse(allocator, puzzle) {
+        return (
+            vec![PuzzleLayer::Singleton, PuzzleLayer::Did],
+            Some(p2_puzzle),
+        );
+    }
+
+    if let Ok(Some((_, p2_puzzle))) = OptionInfo::parse(allocator, puzzle) {
+        return (
+            vec![PuzzleLayer::Singleton, PuzzleLayer::OptionContract],
+            Some(p2_puzzle),
+        );
+    }
+
+    if let Ok(Some(singleton)) = SingletonLayer::<Puzzle>::parse_puzzle(allocator, puzzle) {
+        return (vec![PuzzleLayer::Singleton], Some(singleton.inner_puzzle));
+    }
+
+    (Vec::new(), Some(puzzle))
+}
+
+/// Identifies the innermost puzzle, which controls who can spend the coin.
+fn p2_layer(allocator: &Allocator, p2_puzzle: Puzzle) -> PuzzleLayer {
+    if p2_puzzle.curried_puzzle_hash() == SETTLEMENT_PAYMENT_HASH.into() {
+        PuzzleLayer::Settlement
+    } else if let Ok(Some(..)) = StandardLayer::parse_puzzle(allocator, p2_puzzle) {
+        PuzzleLayer::Standard
+    } else {
+        PuzzleLayer::Unknown
+    }
+}
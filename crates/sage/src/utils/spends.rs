@@ -1,5 +1,10 @@
 use chia_wallet_sdk::prelude::*;
-use sage_wallet::{SyncCommand, Transaction, insert_transaction};
+use sage_config::SignerConfig;
+use sage_wallet::{
+    KeychainSigner, RemoteSigner, Signer, SyncCommand, Transaction, insert_transaction,
+    queue_transaction,
+};
+use tracing::info;
 
 use crate::{Error, Result, Sage};
 
@@ -11,17 +16,11 @@ impl Sage {
     ) -> Result<SpendBundle> {
         let wallet = self.wallet()?;
 
-        let (_mnemonic, Some(master_sk)) =
-            self.keychain.extract_secrets(wallet.fingerprint, b"")?
-        else {
-            return Err(Error::NoSigningKey);
-        };
-
         let spend_bundle = wallet
             .sign_transaction(
                 SpendBundle::new(coin_spends, Signature::default()),
-                &AggSigConstants::new(self.network().agg_sig_me()),
-                master_sk,
+                &AggSigConstants::new(self.network()?.agg_sig_me()),
+                &*self.signer(wallet.fingerprint)?,
                 partial,
             )
             .await?;
@@ -29,14 +28,52 @@ impl Sage {
         Ok(spend_bundle)
     }
 
+    /// The signer configured for the wallet, which defaults to the keys in the keychain.
+    pub(crate) fn signer(&self, fingerprint: u32) -> Result<Box<dyn Signer>> {
+        let signer = self
+            .wallet_config
+            .wallets
+            .iter()
+            .find(|wallet| wallet.fingerprint == fingerprint)
+            .map(|wallet| wallet.signer.clone())
+            .unwrap_or_default();
+
+        match signer {
+            SignerConfig::Keychain => {
+                let (_mnemonic, Some(master_sk)) = self
+                    .keychain
+                    .extract_secrets(fingerprint, self.key_password(fingerprint, None))?
+                else {
+                    return Err(Error::NoSigningKey);
+                };
+
+                Ok(Box::new(KeychainSigner::new(master_sk)))
+            }
+            SignerConfig::Remote { address } => {
+                Ok(Box::new(RemoteSigner::new(&address, fingerprint)?))
+            }
+        }
+    }
+
     pub(crate) async fn submit(&self, spend_bundle: SpendBundle) -> Result<()> {
         let wallet = self.wallet()?;
-        let peer = self
-            .peer_state
-            .lock()
-            .await
-            .acquire_peer()
-            .ok_or(Error::NoPeers)?;
+
+        // While offline the transaction can't be validated against the chain yet, so it's
+        // queued and broadcast once the wallet is back online
+        if self.config.network.offline {
+            info!(
+                "Queueing transaction {} to broadcast later",
+                spend_bundle.name()
+            );
+
+            queue_transaction(&wallet.db, &spend_bundle).await?;
+
+            return Ok(());
+        }
+
+        let Some(peer) = self.peer_state.lock().await.acquire_peer() else {
+            return Err(Error::NoPeers);
+        };
 
         let subscriptions = insert_transaction(
             &wallet.db,
@@ -0,0 +1,555 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chia_wallet_sdk::prelude::*;
+use indexmap::IndexMap;
+use itertools::Itertools;
+use sage_api::{Amount, BalanceChange, SimulateTransactionResponse, SimulatedSpend};
+use sage_database::{Asset, AssetKind};
+use sage_wallet::{ChildKind, CoinKind, Transaction, WalletError};
+
+use crate::{
+    ConfirmationInfo, Error, MAX_TRANSACTION_COST, Result, Sage, analyze_spend, condition_list,
+    spend_bundle_conditions, verify_signature,
+};
+
+#[derive(Debug, Clone)]
+enum AssetSource {
+    Xch,
+    Cat {
+        asset_id: Bytes32,
+        hidden_puzzle_hash: Option<Bytes32>,
+    },
+    Nft {
+        launcher_id: Bytes32,
+        metadata: Program,
+    },
+    Singleton {
+        launcher_id: Bytes32,
+        kind: AssetKind,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct AssetDelta {
+    source: AssetSource,
+    received: u128,
+    spent: u128,
+}
+
+impl Sage {
+    pub(crate) async fn simulate_coin_spends(
+        &self,
+        coin_spends: Vec<CoinSpend>,
+        signature: Option<Signature>,
+    ) -> Result<SimulateTransactionResponse> {
+        let peer_state = self.peer_state.lock().await;
+        let peak_height = peer_state.peak().map(|(height, _)| height);
+        let peer = peer_state.acquire_peer();
+        drop(peer_state);
+
+        // Simulating against the genesis rules would give misleading results
+        let Some(peak_height) = peak_height else {
+            return Err(Error::NotSynced);
+        };
+
+        let mut allocator = Allocator::new();
+        let mut spends = Vec::with_capacity(coin_spends.len());
+        let mut errors = Vec::new();
+        let mut estimated_cost = 0;
+        let mut created_coin_ids = HashSet::new();
+
+        for coin_spend in &coin_spends {
+            let coin_id = coin_spend.coin.coin_id();
+
+            let mut spend = SimulatedSpend {
+                coin_id: hex::encode(coin_id),
+                cost: 0,
+                conditions: Vec::new(),
+                errors: Vec::new(),
+            };
+
+            match analyze_spend(&mut allocator, coin_spend) {
+                Ok(analysis) => {
+                    estimated_cost += analysis.cost;
+                    spend.cost = analysis.cost;
+
+                    match condition_list(&allocator, analysis.output) {
+                        Ok(conditions) => spend.conditions = conditions,
+                        Err(error) => spend
+                            .errors
+                            .push(format!("Conditions could not be parsed: {error}")),
+                    }
+
+                    for condition in analysis.conditions {
+                        if let Condition::CreateCoin(create_coin) = condition {
+                            created_coin_ids.insert(
+                                Coin::new(coin_id, create_coin.puzzle_hash, create_coin.amount)
+                                    .coin_id(),
+                            );
+                        }
+                    }
+                }
+                Err(error) => {
+                    spend.errors.push(format!("Spend failed to run: {error}"));
+                }
+            }
+
+            spends.push(spend);
+        }
+
+        // The per spend costs are estimates, but the bundle is run the same way the mempool
+        // does, which also checks duplicate spends, announcements, concurrent spends, ephemeral
+        // coins and reserved fees
+        let spend_bundle = SpendBundle::new(coin_spends.clone(), signature.unwrap_or_default());
+
+        let conditions =
+            match spend_bundle_conditions(&spend_bundle, peak_height, &self.consensus_constants()?)
+            {
+                Ok(conditions) => Some(conditions),
+                Err(error) => {
+                    errors.push(format!("Transaction failed to run: {:?}", error.1));
+                    None
+                }
+            };
+
+        let cost = conditions
+            .as_ref()
+            .map_or(estimated_cost, |conditions| conditions.cost);
+
+        let fee = conditions.as_ref().and_then(|conditions| {
+            conditions
+                .removal_amount
+                .checked_sub(conditions.addition_amount)
+        });
+
+        let spend_indices: HashMap<Bytes32, usize> = coin_spends
+            .iter()
+            .enumerate()
+            .map(|(index, coin_spend)| (coin_spend.coin.coin_id(), index))
+            .collect();
+
+        let mut coin_states = HashMap::new();
+        let mut created_timestamps = HashMap::new();
+
+        if let Some(peer) = &peer {
+            // Coins created in this transaction are ephemeral, so they don't exist on chain yet
+            let input_coin_ids = spend_indices
+                .keys()
+                .copied()
+                .filter(|coin_id| !created_coin_ids.contains(coin_id))
+                .collect_vec();
+
+            match peer
+                .fetch_coins(input_coin_ids.clone(), self.network()?.genesis_challenge)
+                .await
+            {
+                Ok(fetched) => {
+                    for coin_state in fetched {
+                        coin_states.insert(coin_state.coin.coin_id(), coin_state);
+                    }
+
+                    for coin_id in &input_coin_ids {
+                        let index = spend_indices[coin_id];
+
+                        match coin_states.get(coin_id) {
+                            None => spends[index]
+                                .errors
+                                .push("Coin does not exist on chain".to_string()),
+                            Some(coin_state) => {
+                                if let Some(spent_height) = coin_state.spent_height {
+                                    spends[index].errors.push(format!(
+                                        "Coin was already spent at height {spent_height}"
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                }
+                Err(error) => {
+                    errors.push(format!(
+                        "Could not fetch the input coins from a peer: {error}"
+                    ));
+                }
+            }
+
+            for spend in conditions.iter().flat_map(|conditions| &conditions.spends) {
+                if spend.seconds_relative.is_none() && spend.before_seconds_relative.is_none() {
+                    continue;
+                }
+
+                let Some(created_height) = coin_states
+                    .get(&spend.coin_id)
+                    .and_then(|coin_state| coin_state.created_height)
+                else {
+                    continue;
+                };
+
+                if created_timestamps.contains_key(&created_height) {
+                    continue;
+                }
+
+                match peer.block_timestamp(created_height).await {
+                    Ok((_, timestamp)) => {
+                        created_timestamps.insert(created_height, timestamp);
+                    }
+                    Err(error) => errors.push(format!(
+                        "Could not fetch the timestamp of block {created_height}: {error}"
+                    )),
+                }
+            }
+        } else {
+            errors.push("No peers are available to check the input coins".to_string());
+        }
+
+        // Timelocks aren't checked by the run, since they depend on the state of the chain
+        if let Some(conditions) = &conditions {
+            let peak_height = u64::from(peak_height);
+
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+
+            errors.extend(
+                [
+                    timelock_error(
+                        "Transaction",
+                        Timelock::After,
+                        Some(u64::from(conditions.height_absolute)),
+                        peak_height,
+                        "height",
+                    ),
+                    timelock_error(
+                        "Transaction",
+                        Timelock::After,
+                        Some(conditions.seconds_absolute),
+                        now,
+                        "timestamp",
+                    ),
+                    timelock_error(
+                        "Transaction",
+                        Timelock::Before,
+                        conditions.before_height_absolute.map(u64::from),
+                        peak_height,
+                        "height",
+                    ),
+                    timelock_error(
+                        "Transaction",
+                        Timelock::Before,
+                        conditions.before_seconds_absolute,
+                        now,
+                        "timestamp",
+                    ),
+                ]
+                .into_iter()
+                .flatten(),
+            );
+
+            for spend in &conditions.spends {
+                let Some(&index) = spend_indices.get(&spend.coin_id) else {
+                    continue;
+                };
+
+                let created_height = coin_states
+                    .get(&spend.coin_id)
+                    .and_then(|coin_state| coin_state.created_height);
+                let created_timestamp =
+                    created_height.and_then(|height| created_timestamps.get(&height).copied());
+                let created_height = created_height.map(u64::from);
+
+                spends[index].errors.extend(
+                    [
+                        timelock_error(
+                            "Coin",
+                            Timelock::After,
+                            relative(created_height, spend.height_relative.map(u64::from)),
+                            peak_height,
+                            "height",
+                        ),
+                        timelock_error(
+                            "Coin",
+                            Timelock::After,
+                            relative(created_timestamp, spend.seconds_relative),
+                            now,
+                            "timestamp",
+                        ),
+                        timelock_error(
+                            "Coin",
+                            Timelock::Before,
+                            relative(created_height, spend.before_height_relative.map(u64::from)),
+                            peak_height,
+                            "height",
+                        ),
+                        timelock_error(
+                            "Coin",
+                            Timelock::Before,
+                            relative(created_timestamp, spend.before_seconds_relative),
+                            now,
+                            "timestamp",
+                        ),
+                    ]
+                    .into_iter()
+                    .flatten(),
+                );
+            }
+        }
+
+        if cost > MAX_TRANSACTION_COST {
+            errors.push(format!(
+                "Transaction costs {cost}, which exceeds the maximum transaction cost of {MAX_TRANSACTION_COST}"
+            ));
+        }
+
+        if let Some(signature) = signature {
+            match verify_signature(
+                &mut allocator,
+                &coin_spends,
+                &signature,
+                self.network()?.agg_sig_me(),
+            ) {
+                Ok(Some(true) | None) => {}
+                Ok(Some(false)) => {
+                    errors.push("Aggregated signature does not match the coin spends".to_string());
+                }
+                Err(error) => {
+                    errors.push(format!("Could not determine required signatures: {error}"));
+                }
+            }
+        }
+
+        // Spends that fail to run can't be parsed, so there's nothing meaningful to summarize
+        let (summary, balance_changes) = match Transaction::from_coin_spends(coin_spends.clone()) {
+            Ok(transaction) => (
+                Some(
+                    self.summarize(coin_spends, ConfirmationInfo::default())
+                        .await?,
+                ),
+                self.balance_changes(transaction).await?,
+            ),
+            Err(_) => (None, Vec::new()),
+        };
+
+        Ok(SimulateTransactionResponse {
+            valid: errors.is_empty() && spends.iter().all(|spend| spend.errors.is_empty()),
+            cost,
+            fee: Amount::u128(fee.unwrap_or(0)),
+            spends,
+            errors,
+            balance_changes,
+            summary,
+        })
+    }
+
+    async fn balance_changes(&self, transaction: Transaction) -> Result<Vec<BalanceChange>> {
+        let wallet = self.wallet()?;
+
+        let mut deltas = IndexMap::<Bytes32, AssetDelta>::new();
+
+        for input in transaction.inputs {
+            let coin = input.coin_spend.coin;
+
+            let spent = match input.kind {
+                CoinKind::Launcher => None,
+                CoinKind::Unknown => Some((Bytes32::default(), coin.puzzle_hash, AssetSource::Xch)),
+                CoinKind::Cat { info } => Some((
+                    info.asset_id,
+                    info.p2_puzzle_hash,
+                    AssetSource::Cat {
+                        asset_id: info.asset_id,
+                        hidden_puzzle_hash: info.hidden_puzzle_hash,
+                    },
+                )),
+                CoinKind::Nft { info, .. } => Some((
+                    info.launcher_id,
+                    info.p2_puzzle_hash,
+                    AssetSource::Nft {
+                        launcher_id: info.launcher_id,
+                        metadata: info.metadata,
+                    },
+                )),
+                CoinKind::Did { info, .. } => Some((
+                    info.launcher_id,
+                    info.p2_puzzle_hash,
+                    AssetSource::Singleton {
+                        launcher_id: info.launcher_id,
+                        kind: AssetKind::Did,
+                    },
+                )),
+                CoinKind::Option { info, .. } => Some((
+                    info.launcher_id,
+                    info.p2_puzzle_hash,
+                    AssetSource::Singleton {
+                        launcher_id: info.launcher_id,
+                        kind: AssetKind::Option,
+                    },
+                )),
+            };
+
+            if let Some((hash, p2_puzzle_hash, source)) = spent
+                && wallet.db.is_p2_puzzle_hash(p2_puzzle_hash).await?
+            {
+                deltas
+                    .entry(hash)
+                    .or_insert(AssetDelta {
+                        source,
+                        received: 0,
+                        spent: 0,
+                    })
+                    .spent += u128::from(coin.amount);
+            }
+
+            for output in input.outputs {
+                let p2_puzzle_hash = output
+                    .kind
+                    .receiver_custody_p2_puzzle_hash()
+                    .unwrap_or(output.coin.puzzle_hash);
+
+                if !wallet.db.is_p2_puzzle_hash(p2_puzzle_hash).await? {
+                    continue;
+                }
+
+                let (hash, source) = match output.kind {
+                    ChildKind::Launcher => continue,
+                    ChildKind::Unknown | ChildKind::Clawback { .. } => {
+                        (Bytes32::default(), AssetSource::Xch)
+                    }
+                    ChildKind::Cat { info, .. } => (
+                        info.asset_id,
+                        AssetSource::Cat {
+                            asset_id: info.asset_id,
+                            hidden_puzzle_hash: info.hidden_puzzle_hash,
+                        },
+                    ),
+                    ChildKind::Nft { info, .. } => (
+                        info.launcher_id,
+                        AssetSource::Nft {
+                            launcher_id: info.launcher_id,
+                            metadata: info.metadata,
+                        },
+                    ),
+                    ChildKind::Did { info, .. } => (
+                        info.launcher_id,
+                        AssetSource::Singleton {
+                            launcher_id: info.launcher_id,
+                            kind: AssetKind::Did,
+                        },
+                    ),
+                    ChildKind::Option { info, .. } => (
+                        info.launcher_id,
+                        AssetSource::Singleton {
+                            launcher_id: info.launcher_id,
+                            kind: AssetKind::Option,
+                        },
+                    ),
+                };
+
+                deltas
+                    .entry(hash)
+                    .or_insert(AssetDelta {
+                        source,
+                        received: 0,
+                        spent: 0,
+                    })
+                    .received += u128::from(output.coin.amount);
+            }
+        }
+
+        let mut balance_changes = Vec::with_capacity(deltas.len());
+
+        for delta in deltas.into_values() {
+            let asset = self.resolve_asset(delta.source).await?;
+
+            balance_changes.push(BalanceChange {
+                asset: self.encode_asset(asset)?,
+                received: Amount::u128(delta.received),
+                spent: Amount::u128(delta.spent),
+            });
+        }
+
+        Ok(balance_changes)
+    }
+
+    async fn resolve_asset(&self, source: AssetSource) -> Result<Asset> {
+        let wallet = self.wallet()?;
+
+        Ok(match source {
+            AssetSource::Xch => {
+                let Some(asset) = wallet.db.asset(Bytes32::default()).await? else {
+                    return Err(Error::Wallet(WalletError::MissingAsset(Bytes32::default())));
+                };
+
+                asset
+            }
+            AssetSource::Cat {
+                asset_id,
+                hidden_puzzle_hash,
+            } => self.cache_cat(asset_id, hidden_puzzle_hash).await?,
+            AssetSource::Nft {
+                launcher_id,
+                metadata,
+            } => {
+                let mut allocator = Allocator::new();
+                let metadata = metadata.to_clvm(&mut allocator)?;
+
+                self.cache_nft(
+                    &allocator,
+                    launcher_id,
+                    metadata,
+                    &mut ConfirmationInfo::default(),
+                )
+                .await?
+            }
+            AssetSource::Singleton { launcher_id, kind } => {
+                wallet.db.asset(launcher_id).await?.unwrap_or(Asset {
+                    hash: launcher_id,
+                    name: None,
+                    ticker: None,
+                    precision: 1,
+                    icon_url: None,
+                    description: None,
+                    is_sensitive_content: false,
+                    is_visible: true,
+                    hidden_puzzle_hash: None,
+                    kind,
+                })
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Timelock {
+    After,
+    Before,
+}
+
+/// Adds a relative timelock to the height or timestamp the coin was created at, if both are known.
+fn relative(created: Option<u64>, offset: Option<u64>) -> Option<u64> {
+    created
+        .zip(offset)
+        .map(|(created, offset)| created.saturating_add(offset))
+}
+
+/// Checks a height or time lock against the current peak and time, if it can be evaluated.
+fn timelock_error(
+    subject: &str,
+    timelock: Timelock,
+    required: Option<u64>,
+    current: u64,
+    unit: &str,
+) -> Option<String> {
+    let required = required?;
+
+    match timelock {
+        Timelock::Before if current >= required => Some(format!(
+            "{subject} can't be spent at or after {unit} {required} (currently {current})"
+        )),
+        Timelock::After if current < required => Some(format!(
+            "{subject} can't be spent until {unit} {required} (currently {current})"
+        )),
+        _ => None,
+    }
+}
@@ -0,0 +1,11 @@
+/*
+ * Headers that have been linked to the rest of the verified header chain by their previous
+ * header hash. Coin states can't claim to be created or spent in a block that isn't a
+ * transaction block, so once a block has been verified this is used to catch peers lying
+ * about the height of a coin state.
+ */
+ALTER TABLE blocks ADD COLUMN prev_header_hash BLOB;
+ALTER TABLE blocks ADD COLUMN is_transaction_block BOOLEAN;
+ALTER TABLE blocks ADD COLUMN is_verified BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX idx_verified_blocks ON blocks(is_verified, height);
@@ -0,0 +1,27 @@
+/*
+ * Signed transactions that were created while offline. They're broadcast in the order they
+ * were queued once the wallet reconnects, but a transaction that spends coins created by
+ * another pending transaction waits until its parent has been confirmed.
+ */
+CREATE TABLE broadcast_queue (
+  id INTEGER NOT NULL PRIMARY KEY,
+  hash BLOB NOT NULL UNIQUE,
+  aggregated_signature BLOB NOT NULL,
+  queued_timestamp INTEGER NOT NULL
+);
+
+CREATE TABLE broadcast_queue_spends (
+  id INTEGER NOT NULL PRIMARY KEY,
+  broadcast_id INTEGER NOT NULL,
+  coin_hash BLOB NOT NULL,
+  parent_coin_hash BLOB NOT NULL,
+  puzzle_hash BLOB NOT NULL,
+  amount BLOB NOT NULL,
+  puzzle_reveal BLOB NOT NULL,
+  solution BLOB NOT NULL,
+  seq INTEGER NOT NULL,
+  FOREIGN KEY (broadcast_id) REFERENCES broadcast_queue(id) ON DELETE CASCADE,
+  UNIQUE(broadcast_id, coin_hash)
+);
+
+CREATE INDEX idx_broadcast_queue_spends_parent ON broadcast_queue_spends(parent_coin_hash);
@@ -0,0 +1,8 @@
+/*
+ * Tracks how long each transaction has been pending, and how many resubmissions no peer
+ * acknowledged, so that transactions which will never be confirmed can be given up on.
+ */
+ALTER TABLE mempool_items ADD COLUMN created_timestamp INTEGER;
+ALTER TABLE mempool_items ADD COLUMN failed_submissions INTEGER NOT NULL DEFAULT 0;
+
+UPDATE mempool_items SET created_timestamp = COALESCE(submitted_timestamp, unixepoch());
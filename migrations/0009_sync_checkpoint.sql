@@ -0,0 +1,18 @@
+/*
+ * The height that the wallet's subscriptions are known to be synced up to, so that switching
+ * back to the wallet can resume from there instead of from the start of the chain. Only custody
+ * p2 puzzles up to the recorded id were synced at the time, so any added later are synced in full.
+ */
+CREATE TABLE sync_checkpoint (
+  id INTEGER NOT NULL PRIMARY KEY CHECK (id = 0),
+  height INTEGER NOT NULL,
+  header_hash BLOB NOT NULL,
+  p2_puzzle_id INTEGER NOT NULL
+);
+
+INSERT INTO sync_checkpoint (id, height, header_hash, p2_puzzle_id)
+SELECT 0, height, header_hash, (SELECT COALESCE(MAX(id), 0) FROM p2_puzzles)
+FROM blocks
+WHERE header_hash IS NOT NULL AND is_peak = TRUE
+ORDER BY height DESC
+LIMIT 1;
@@ -0,0 +1,44 @@
+/*
+ * P2 vault is a p2 puzzle with kind = 4, which is spent once a threshold of its member keys
+ * have signed. Coins in a vault can't be spent by the wallet alone, so they aren't selectable.
+ */
+CREATE TABLE p2_vaults (
+  id INTEGER NOT NULL PRIMARY KEY,
+  p2_puzzle_id INTEGER NOT NULL UNIQUE,
+  name TEXT NOT NULL,
+  threshold INTEGER NOT NULL,
+  FOREIGN KEY (p2_puzzle_id) REFERENCES p2_puzzles(id) ON DELETE CASCADE
+);
+
+CREATE TABLE p2_vault_keys (
+  id INTEGER NOT NULL PRIMARY KEY,
+  p2_vault_id INTEGER NOT NULL,
+  key_index INTEGER NOT NULL,
+  key BLOB NOT NULL,
+  FOREIGN KEY (p2_vault_id) REFERENCES p2_vaults(id) ON DELETE CASCADE,
+  UNIQUE(p2_vault_id, key_index)
+);
+
+DROP VIEW selectable_coins;
+
+CREATE VIEW selectable_coins AS
+SELECT *
+FROM wallet_coins
+WHERE 1=1
+  AND created_height IS NOT NULL
+  AND spent_height IS NULL
+  AND mempool_item_hash IS NULL
+  AND offer_hash IS NULL
+  AND p2_puzzle_kind != 4
+  AND NOT EXISTS (
+    SELECT 1 FROM mempool_coins
+    WHERE mempool_coins.coin_id = wallet_coins.coin_id
+  )
+  AND (
+    clawback_expiration_seconds IS NULL
+    OR (clawback_receiver_p2_puzzle_id IS NOT NULL AND unixepoch() >= clawback_expiration_seconds)
+  )
+  AND (
+    option_expiration_seconds IS NULL
+    OR (option_creator_p2_puzzle_id IS NOT NULL AND unixepoch() >= option_expiration_seconds)
+  );
@@ -0,0 +1,5 @@
+/*
+ * Derivations which have been handed out as a new address, or used as change, before any coins
+ * were received. They count as used so that the same address isn't handed out twice.
+ */
+ALTER TABLE public_keys ADD COLUMN is_reserved BOOLEAN NOT NULL DEFAULT FALSE;
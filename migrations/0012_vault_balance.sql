@@ -0,0 +1,21 @@
+/*
+ * Coins in a vault aren't counted towards the wallet's balance, since the wallet can't spend
+ * them alone. They're only listed by the vault itself.
+ */
+DROP VIEW owned_coins;
+
+CREATE VIEW owned_coins AS
+SELECT *
+FROM wallet_coins
+WHERE 1=1
+  AND spent_height IS NULL
+  AND mempool_item_hash IS NULL
+  AND p2_puzzle_kind != 4
+  AND (
+    clawback_expiration_seconds IS NULL
+    OR (clawback_receiver_p2_puzzle_id IS NOT NULL AND unixepoch() >= clawback_expiration_seconds)
+  )
+  AND (
+    option_expiration_seconds IS NULL
+    OR (option_creator_p2_puzzle_id IS NOT NULL AND unixepoch() >= option_expiration_seconds)
+  );
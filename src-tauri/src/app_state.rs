@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use sage::{Result, Sage};
+use sage::{Result, Sage, encode_sync_progress};
 use sage_api::SyncEvent as ApiEvent;
 #[cfg(not(mobile))]
 use sage_apps::{AppsHostState, process_sage_network_change};
@@ -44,9 +44,13 @@ pub async fn initialize(app_handle: AppHandle, sage: &mut Sage) -> Result<()> {
                 | SyncEvent::TransactionUpdated { .. }
                 | SyncEvent::OfferUpdated { .. } => ApiEvent::CoinState,
                 SyncEvent::PuzzleBatchSynced => ApiEvent::PuzzleBatchSynced,
+                SyncEvent::Progress(progress) => ApiEvent::Progress {
+                    progress: encode_sync_progress(progress),
+                },
                 SyncEvent::CatInfo => ApiEvent::CatInfo,
                 SyncEvent::DidInfo => ApiEvent::DidInfo,
                 SyncEvent::NftData => ApiEvent::NftData,
+                SyncEvent::Reorg { fork_height } => ApiEvent::Reorg { fork_height },
                 SyncEvent::NetworkChanged { .. } => continue,
             };
             if app_handle.emit("sync-event", event).is_err() {
@@ -81,7 +81,7 @@ pub async fn validate_address(state: State<'_, AppState>, address: String) -> Re
     let Some(address) = Address::decode(&address).ok() else {
         return Ok(false);
     };
-    Ok(address.prefix == state.network().prefix())
+    Ok(address.prefix == state.network()?.prefix())
 }
 
 #[command]
@@ -43,6 +43,12 @@ macro_rules! sage_commands {
             commands::get_key,
             commands::get_wallet_address,
             commands::get_secret_key,
+            commands::change_key_password,
+            commands::verify_key_password,
+            commands::export_backup,
+            commands::import_backup,
+            commands::export_key_shares,
+            commands::inspect_key_shares,
             commands::send_xch,
             commands::bulk_send_xch,
             commands::combine,
@@ -66,13 +72,22 @@ macro_rules! sage_commands {
             commands::create_transaction,
             commands::sign_coin_spends,
             commands::view_coin_spends,
+            commands::simulate_transaction,
+            commands::decode_coin_spends,
             commands::submit_transaction,
+            commands::create_vault,
+            commands::get_vaults,
+            commands::propose_vault_spend,
+            commands::submit_vault_spend,
+            commands::farm_blocks,
+            commands::mint_to_address,
             commands::get_sync_status,
             commands::get_version,
             commands::get_database_stats,
             commands::perform_database_maintenance,
             commands::check_address,
             commands::get_derivations,
+            commands::get_derivation_gap,
             commands::get_are_coins_spendable,
             commands::get_spendable_coin_count,
             commands::get_coins_by_ids,
@@ -99,6 +114,7 @@ macro_rules! sage_commands {
             commands::take_offer,
             commands::combine_offers,
             commands::view_offer,
+            commands::validate_offer,
             commands::import_offer,
             commands::get_offers,
             commands::get_offers_for_asset,
@@ -108,6 +124,9 @@ macro_rules! sage_commands {
             commands::cancel_offers,
             commands::network_config,
             commands::set_discover_peers,
+            commands::set_paranoid_sync,
+            commands::set_proxy,
+            commands::set_offline,
             commands::set_target_peers,
             commands::set_network,
             commands::set_network_override,
@@ -115,9 +134,16 @@ macro_rules! sage_commands {
             commands::default_wallet_config,
             commands::get_networks,
             commands::get_network,
+            commands::add_network,
+            commands::update_network,
+            commands::remove_network,
+            commands::reload_networks,
             commands::set_delta_sync,
             commands::set_delta_sync_override,
             commands::set_change_address,
+            commands::set_receive_hardened,
+            commands::set_change_policy,
+            commands::set_gap_limit,
             commands::update_cat,
             commands::resync_cat,
             commands::update_did,
@@ -126,6 +152,7 @@ macro_rules! sage_commands {
             commands::update_nft_collection,
             commands::redownload_nft,
             commands::increase_derivation_index,
+            commands::get_new_address,
             commands::get_peers,
             commands::get_user_theme,
             commands::get_user_themes,